@@ -4,7 +4,7 @@ use crate::config::{CsvConfig, NullRepresentation};
 use crate::errors::{CsvError, CsvResult, RowLengthMismatchKind};
 use crate::schema::{ColumnType, CsvSchema};
 use edi_ir::document::DocumentMetadata;
-use edi_ir::{Document, Node, NodeType, Value};
+use edi_ir::{Document, Node, NodeType, Value, parse_decimal};
 use std::io::Read;
 use tracing::{debug, trace, warn};
 
@@ -248,9 +248,18 @@ impl CsvReader {
             ColumnType::Integer => value.parse::<i64>().map(Value::Integer).map_err(|_| {
                 CsvError::conversion(line, column, format!("'{}' is not a valid integer", value))
             }),
-            ColumnType::Decimal => value.parse::<f64>().map(Value::Decimal).map_err(|_| {
-                CsvError::conversion(line, column, format!("'{}' is not a valid decimal", value))
-            }),
+            ColumnType::Decimal => self
+                .schema
+                .as_ref()
+                .and_then(|schema| schema.parse_decimal(value))
+                .map(Value::Decimal)
+                .ok_or_else(|| {
+                    CsvError::conversion(
+                        line,
+                        column,
+                        format!("'{}' is not a valid decimal", value),
+                    )
+                }),
             ColumnType::Boolean => self
                 .parse_boolean(value)
                 .map(Value::Boolean)
@@ -275,8 +284,8 @@ impl CsvReader {
             return Value::Integer(int_val);
         }
         // Then decimal
-        if let Ok(float_val) = value.parse::<f64>() {
-            return Value::Decimal(float_val);
+        if let Some(decimal_val) = parse_decimal(value) {
+            return Value::Decimal(decimal_val);
         }
         // Then boolean
         if let Some(bool_val) = self.parse_boolean(value) {
@@ -502,13 +511,15 @@ fn parse_value_with_schema(
                         format!("'{}' is not a valid integer", value),
                     )
                 }),
-                ColumnType::Decimal => value.parse::<f64>().map(Value::Decimal).map_err(|_| {
-                    CsvError::conversion(
-                        line,
-                        column,
-                        format!("'{}' is not a valid decimal", value),
-                    )
-                }),
+                ColumnType::Decimal => {
+                    s.parse_decimal(value).map(Value::Decimal).ok_or_else(|| {
+                        CsvError::conversion(
+                            line,
+                            column,
+                            format!("'{}' is not a valid decimal", value),
+                        )
+                    })
+                }
                 ColumnType::Boolean => parse_boolean(value).map(Value::Boolean).ok_or_else(|| {
                     CsvError::conversion(
                         line,
@@ -527,8 +538,8 @@ fn parse_value_with_schema(
     // Default type inference
     if let Ok(int_val) = value.parse::<i64>() {
         Ok(Value::Integer(int_val))
-    } else if let Ok(float_val) = value.parse::<f64>() {
-        Ok(Value::Decimal(float_val))
+    } else if let Some(decimal_val) = parse_decimal(value) {
+        Ok(Value::Decimal(decimal_val))
     } else if let Some(bool_val) = parse_boolean(value) {
         Ok(Value::Boolean(bool_val))
     } else {
@@ -713,6 +724,41 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_read_decimal_keeps_scale_and_accepts_decimal_comma() {
+        let schema = CsvSchema::new()
+            .with_header()
+            .with_delimiter(';')
+            .with_decimal_comma()
+            .add_column(ColumnDef::new("amount").with_type(ColumnType::Decimal))
+            .add_column(ColumnDef::new("price").with_type(ColumnType::Decimal));
+
+        let data = "amount;price\n1234,50;0.10";
+        let reader = CsvReader::new().with_schema(schema);
+        let doc = reader.read_to_ir(Cursor::new(data)).unwrap();
+
+        let record = &doc.root.children[0];
+        let text = |index: usize| record.children[index].value.as_ref().unwrap().as_string();
+        assert_eq!(text(0), Some("1234.50".to_string()));
+        assert_eq!(text(1), Some("0.10".to_string()));
+    }
+
+    #[test]
+    fn test_read_decimal_rejects_comma_without_opt_in() {
+        let schema = CsvSchema::new()
+            .with_header()
+            .add_column(ColumnDef::new("amount").with_type(ColumnType::Decimal));
+
+        let data = "amount\n\"1,234\"";
+        let reader = CsvReader::new().with_schema(schema);
+        let error = reader.read_to_ir(Cursor::new(data)).unwrap_err();
+
+        assert!(
+            error.to_string().contains("'1,234' is not a valid decimal"),
+            "{error}"
+        );
+    }
+
     #[test]
     fn test_read_iter_streaming() {
         let data = "name,age\nJohn,30\nJane,25\nBob,35";
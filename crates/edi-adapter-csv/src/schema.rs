@@ -1,5 +1,6 @@
 //! CSV schema definitions
 
+use edi_ir::{Decimal, parse_decimal, parse_edi_decimal};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -20,6 +21,10 @@ pub struct CsvSchema {
     pub field_mappings: HashMap<String, usize>,
     /// Null value representation
     pub null_value: Option<String>,
+    /// Whether decimal columns may use a comma as decimal mark when read
+    /// (default: full stop only)
+    #[serde(default)]
+    pub decimal_comma: bool,
 }
 
 /// Definition of a CSV column
@@ -90,6 +95,7 @@ impl Default for CsvSchema {
             quote_char: '"',
             field_mappings: HashMap::new(),
             null_value: None,
+            decimal_comma: false,
         }
     }
 }
@@ -138,6 +144,21 @@ impl CsvSchema {
         self
     }
 
+    /// Accept a comma as decimal mark in decimal columns
+    pub fn with_decimal_comma(mut self) -> Self {
+        self.decimal_comma = true;
+        self
+    }
+
+    /// Parse the text of a decimal column
+    pub fn parse_decimal(&self, value: &str) -> Option<Decimal> {
+        if self.decimal_comma {
+            parse_edi_decimal(value)
+        } else {
+            parse_decimal(value)
+        }
+    }
+
     /// Add a column definition
     pub fn add_column(mut self, column: ColumnDef) -> Self {
         let index = self.columns.len();
@@ -211,11 +211,12 @@ impl CsvWriter {
         match value {
             Some(Value::String(s)) => Ok(s.clone()),
             Some(Value::Integer(i)) => Ok(i.to_string()),
-            Some(Value::Decimal(d)) => Ok(self.format_decimal(*d)),
+            Some(Value::Decimal(d)) => Ok(d.to_plain_string()),
             Some(Value::Boolean(b)) => Ok(b.to_string()),
             Some(Value::Date(d)) => Ok(d.clone()),
             Some(Value::Time(t)) => Ok(t.clone()),
             Some(Value::DateTime(dt)) => Ok(dt.clone()),
+            Some(Value::Period(p)) => Ok(p.clone()),
             Some(Value::Binary(_)) => {
                 warn!("Binary value cannot be serialized to CSV, using empty string");
                 Ok(self.null_to_string())
@@ -233,28 +234,6 @@ impl CsvWriter {
         }
     }
 
-    fn format_decimal(&self, value: f64) -> String {
-        // Check if we have precision/scale from schema
-        if let Some(_schema) = &self.schema {
-            // Use schema-defined precision if available
-            // For now, just format to avoid unnecessary trailing zeros
-            if value.fract() == 0.0 {
-                format!("{:.0}", value)
-            } else {
-                // Trim trailing zeros
-                let s = format!("{}", value);
-                s.trim_end_matches('0').trim_end_matches('.').to_string()
-            }
-        } else {
-            // Default formatting
-            if value.fract() == 0.0 {
-                format!("{:.0}", value)
-            } else {
-                format!("{}", value)
-            }
-        }
-    }
-
     /// Write multiple records incrementally (streaming write)
     ///
     /// This method returns a CsvRecordWriter that can be used to write
@@ -383,11 +362,12 @@ impl<W: Write> CsvRecordWriter<W> {
         match value {
             Some(Value::String(s)) => Ok(s.clone()),
             Some(Value::Integer(i)) => Ok(i.to_string()),
-            Some(Value::Decimal(d)) => Ok(self.format_decimal(*d)),
+            Some(Value::Decimal(d)) => Ok(d.to_plain_string()),
             Some(Value::Boolean(b)) => Ok(b.to_string()),
             Some(Value::Date(d)) => Ok(d.clone()),
             Some(Value::Time(t)) => Ok(t.clone()),
             Some(Value::DateTime(dt)) => Ok(dt.clone()),
+            Some(Value::Period(p)) => Ok(p.clone()),
             Some(Value::Binary(_)) => {
                 warn!("Binary value cannot be serialized to CSV");
                 Ok(self.null_to_string())
@@ -396,14 +376,6 @@ impl<W: Write> CsvRecordWriter<W> {
         }
     }
 
-    fn format_decimal(&self, value: f64) -> String {
-        if value.fract() == 0.0 {
-            format!("{:.0}", value)
-        } else {
-            format!("{}", value)
-        }
-    }
-
     fn null_to_string(&self) -> String {
         match &self.config.null_representation {
             NullRepresentation::EmptyString => String::new(),
@@ -633,7 +605,7 @@ mod tests {
         record.add_child(Node::with_value(
             "decimal_field",
             NodeType::Field,
-            Value::Decimal(99.99),
+            Value::Decimal("99.99".parse().unwrap()),
         ));
         record.add_child(Node::with_value(
             "bool_field",
@@ -706,17 +678,22 @@ mod tests {
         record.add_child(Node::with_value(
             "int_decimal",
             NodeType::Field,
-            Value::Decimal(100.0),
+            Value::Decimal("100".parse().unwrap()),
         ));
         record.add_child(Node::with_value(
             "frac_decimal",
             NodeType::Field,
-            Value::Decimal(99.99),
+            Value::Decimal("99.99".parse().unwrap()),
         ));
         record.add_child(Node::with_value(
             "many_decimals",
             NodeType::Field,
-            Value::Decimal(1.123456),
+            Value::Decimal("1.123456".parse().unwrap()),
+        ));
+        record.add_child(Node::with_value(
+            "scaled_decimal",
+            NodeType::Field,
+            Value::Decimal("12.50".parse().unwrap()),
         ));
         root.add_child(record);
 
@@ -729,6 +706,8 @@ mod tests {
         let result = String::from_utf8(output).unwrap();
         assert!(result.contains("100"));
         assert!(result.contains("99.99"));
+        assert!(result.contains("1.123456"));
+        assert!(result.contains("12.50"));
     }
 
     #[test]
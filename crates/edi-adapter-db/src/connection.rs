@@ -5,6 +5,7 @@ use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
 
+use edi_ir::{Decimal, parse_decimal};
 use libsql::{Builder, Connection as LibsqlConnection, Database, Transaction, params_from_iter};
 use tokio::sync::{RwLock, Semaphore};
 
@@ -1046,7 +1047,7 @@ fn libsql_value_to_db(value: libsql::Value) -> DbValue {
     match value {
         libsql::Value::Null => DbValue::Null,
         libsql::Value::Integer(value) => DbValue::Integer(value),
-        libsql::Value::Real(value) => DbValue::Decimal(value),
+        libsql::Value::Real(value) => DbValue::Decimal(real_to_decimal(value)),
         libsql::Value::Text(value) => DbValue::String(value),
         libsql::Value::Blob(value) => DbValue::Blob(value),
     }
@@ -1070,8 +1071,20 @@ fn libsql_value_to_db_typed(
                 ),
             }),
         (libsql::Value::Integer(value), ColumnType::Integer) => Ok(DbValue::Integer(value)),
-        (libsql::Value::Real(value), ColumnType::Decimal) => Ok(DbValue::Decimal(value)),
-        (libsql::Value::Integer(value), ColumnType::Decimal) => Ok(DbValue::Decimal(value as f64)),
+        (libsql::Value::Text(value), ColumnType::Decimal) => parse_decimal(&value)
+            .map(DbValue::Decimal)
+            .ok_or_else(|| Error::Schema {
+                details: format!(
+                    "Invalid decimal value for '{}.{}': '{value}'",
+                    table, column
+                ),
+            }),
+        (libsql::Value::Real(value), ColumnType::Decimal) => {
+            Ok(DbValue::Decimal(real_to_decimal(value)))
+        }
+        (libsql::Value::Integer(value), ColumnType::Decimal) => {
+            Ok(DbValue::Decimal(Decimal::from(value)))
+        }
         (libsql::Value::Integer(value), ColumnType::Boolean) => Ok(DbValue::Boolean(value != 0)),
         (libsql::Value::Text(value), ColumnType::Boolean) => match value.as_str() {
             "true" | "TRUE" | "1" => Ok(DbValue::Boolean(true)),
@@ -1092,12 +1105,18 @@ fn libsql_value_to_db_typed(
     }
 }
 
+/// Decimal with the shortest digits that round-trip `value`, as written by
+/// tables created with a REAL column
+fn real_to_decimal(value: f64) -> Decimal {
+    parse_decimal(&value.to_string()).unwrap_or_default()
+}
+
 fn db_value_to_libsql(value: &DbValue) -> libsql::Value {
     match value {
         DbValue::String(value) => libsql::Value::Text(value.clone()),
         DbValue::Blob(value) => libsql::Value::Blob(value.clone()),
         DbValue::Integer(value) => libsql::Value::Integer(*value),
-        DbValue::Decimal(value) => libsql::Value::Real(*value),
+        DbValue::Decimal(value) => libsql::Value::Text(value.to_plain_string()),
         DbValue::Boolean(value) => libsql::Value::Integer(if *value { 1 } else { 0 }),
         DbValue::Null => libsql::Value::Null,
     }
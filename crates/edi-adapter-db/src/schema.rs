@@ -2,6 +2,7 @@
 
 use std::collections::{BTreeMap, HashMap};
 
+use edi_ir::Decimal;
 use serde::{Deserialize, Serialize};
 
 use crate::sql::quote_identifier;
@@ -13,7 +14,7 @@ pub enum DbValue {
     String(String),
     Blob(Vec<u8>),
     Integer(i64),
-    Decimal(f64),
+    Decimal(Decimal),
     Boolean(bool),
     Null,
 }
@@ -232,7 +233,10 @@ fn column_type_sql(column_type: ColumnType) -> &'static str {
     match column_type {
         ColumnType::String => "TEXT",
         ColumnType::Integer => "INTEGER",
-        ColumnType::Decimal => "REAL",
+        // Stored as text: REAL and NUMERIC affinity round through binary
+        // floating point and drop the scale of the value. Tables created with
+        // REAL columns are still read; see CHANGELOG.md for migrating them.
+        ColumnType::Decimal => "TEXT",
         ColumnType::Boolean => "BOOLEAN",
     }
 }
@@ -318,7 +322,10 @@ mod tests {
         let mut row = Row::new();
         row.insert("id".to_string(), DbValue::Integer(1));
         row.insert("order_no".to_string(), DbValue::String("PO-1".to_string()));
-        row.insert("total".to_string(), DbValue::Decimal(42.5));
+        row.insert(
+            "total".to_string(),
+            DbValue::Decimal("42.50".parse().unwrap()),
+        );
         row.insert("is_priority".to_string(), DbValue::Boolean(false));
 
         assert!(schema.validate_row(&row).is_ok());
@@ -304,9 +304,11 @@ fn validate_row_for_mode(
 
 fn ir_value_to_db(value: Value) -> DbValue {
     match value {
-        Value::String(value) | Value::Date(value) | Value::Time(value) | Value::DateTime(value) => {
-            DbValue::String(value)
-        }
+        Value::String(value)
+        | Value::Date(value)
+        | Value::Time(value)
+        | Value::DateTime(value)
+        | Value::Period(value) => DbValue::String(value),
         Value::Integer(value) => DbValue::Integer(value),
         Value::Decimal(value) => DbValue::Decimal(value),
         Value::Boolean(value) => DbValue::Boolean(value),
@@ -95,7 +95,10 @@ async fn test_read_with_schema_typed_values() {
     let mut row = Row::new();
     row.insert("id".to_string(), DbValue::Integer(1));
     row.insert("is_priority".to_string(), DbValue::Boolean(true));
-    row.insert("total".to_string(), DbValue::Decimal(12.5));
+    row.insert(
+        "total".to_string(),
+        DbValue::Decimal("12.50".parse().unwrap()),
+    );
     writer.insert("flags", row).await.unwrap();
 
     let rows = reader
@@ -104,7 +107,10 @@ async fn test_read_with_schema_typed_values() {
         .unwrap();
     assert_eq!(rows.len(), 1);
     assert_eq!(rows[0].get("is_priority"), Some(&DbValue::Boolean(true)));
-    assert_eq!(rows[0].get("total"), Some(&DbValue::Decimal(12.5)));
+    match rows[0].get("total") {
+        Some(DbValue::Decimal(total)) => assert_eq!(total.to_plain_string(), "12.50"),
+        other => panic!("expected decimal total, found {other:?}"),
+    }
 }
 
 #[tokio::test]
@@ -0,0 +1,344 @@
+//! APERAK application error and acknowledgement generation
+//!
+//! Where CONTRL reports syntax and service errors, an APERAK reports that a
+//! syntactically correct message failed business validation, e.g. an unknown
+//! GTIN or a missing order reference. This module builds an EANCOM D96A
+//! APERAK for a received message from the errors business validation found in
+//! it: every error becomes an ERC segment with an application error code,
+//! followed by an FTX segment describing it.
+
+use std::collections::HashMap;
+
+use crate::contrl::{composite, segment, simple, unh, unt};
+use crate::parser::{Element, Segment};
+use edi_ir::document::DocumentMetadata;
+use edi_ir::{Document, Node, NodeType, PartyInfo, Value};
+
+/// Maximum length of a free text component (data element 4440)
+const FREE_TEXT_LENGTH: usize = 70;
+
+/// Maximum number of free text components in FTX (C108)
+const FREE_TEXT_COMPONENTS: usize = 5;
+
+/// Business validation error to report in an APERAK
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApplicationIssue {
+    /// Issue code the application error code is looked up by (e.g.
+    /// "CODELIST_VIOLATION")
+    pub code: Option<String>,
+    /// Description sent in FTX unless the error code has fixed free text
+    pub message: String,
+}
+
+impl ApplicationIssue {
+    /// Create an issue without an issue code
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            code: None,
+            message: message.into(),
+        }
+    }
+
+    /// Set the issue code
+    #[must_use]
+    pub fn with_code(mut self, code: impl Into<String>) -> Self {
+        self.code = Some(code.into());
+        self
+    }
+}
+
+/// Application error code (data element 9321) and optional fixed free text
+/// reported for a validation issue code
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApplicationErrorCode {
+    /// Code value as transmitted in ERC
+    pub code: String,
+    /// Free text sent in FTX instead of the validation message
+    pub free_text: Option<String>,
+}
+
+impl ApplicationErrorCode {
+    /// Create an error code reporting the validation message as free text
+    pub fn new(code: impl Into<String>) -> Self {
+        Self {
+            code: code.into(),
+            free_text: None,
+        }
+    }
+
+    /// Report `text` in FTX instead of the validation message
+    #[must_use]
+    pub fn with_free_text(mut self, text: impl Into<String>) -> Self {
+        self.free_text = Some(text.into());
+        self
+    }
+}
+
+/// Generator for APERAK application error messages
+#[derive(Debug, Clone)]
+pub struct AperakGenerator {
+    message_ref: String,
+    error_codes: HashMap<String, ApplicationErrorCode>,
+    default_error_code: ApplicationErrorCode,
+    issued_at: Option<chrono::NaiveDateTime>,
+}
+
+impl AperakGenerator {
+    /// Create a generator whose APERAK message uses `message_ref` in
+    /// UNH/BGM/UNT
+    pub fn new(message_ref: impl Into<String>) -> Self {
+        Self {
+            message_ref: message_ref.into(),
+            error_codes: HashMap::new(),
+            default_error_code: ApplicationErrorCode::new("ZZZ"),
+            issued_at: None,
+        }
+    }
+
+    /// Report validation issues with code `issue_code` (e.g.
+    /// "CODELIST_VIOLATION") as `error_code`
+    #[must_use]
+    pub fn with_error_code(
+        mut self,
+        issue_code: impl Into<String>,
+        error_code: ApplicationErrorCode,
+    ) -> Self {
+        self.error_codes.insert(issue_code.into(), error_code);
+        self
+    }
+
+    /// Error code for issues without a configured mapping ("ZZZ", mutually
+    /// defined, by default)
+    #[must_use]
+    pub fn with_default_error_code(mut self, error_code: ApplicationErrorCode) -> Self {
+        self.default_error_code = error_code;
+        self
+    }
+
+    /// Use a fixed message date instead of the current UTC time
+    #[must_use]
+    pub fn with_issued_at(mut self, issued_at: chrono::NaiveDateTime) -> Self {
+        self.issued_at = Some(issued_at);
+        self
+    }
+
+    /// Build the APERAK answering `source` with `errors`.
+    ///
+    /// The APERAK references the source message by its UNH reference and
+    /// goes back to its sender: the parties of the source interchange are
+    /// swapped into NAD+MS and NAD+MR. Without errors the message is
+    /// acknowledged (BGM message function 29) instead of rejected (27).
+    pub fn generate(&self, source: &Document, errors: &[ApplicationIssue]) -> Document {
+        let errors = self.application_errors(errors);
+        let function = if errors.is_empty() { "29" } else { "27" };
+        let source_ref = source_message_ref(source);
+        let issued_at = self
+            .issued_at
+            .unwrap_or_else(|| chrono::Utc::now().naive_utc());
+
+        let mut segments = vec![
+            segment(
+                "BGM",
+                vec![simple("305"), simple(&self.message_ref), simple(function)],
+            ),
+            segment(
+                "DTM",
+                vec![composite(&[
+                    "137",
+                    &issued_at.format("%Y%m%d%H%M").to_string(),
+                    "203",
+                ])],
+            ),
+        ];
+        if let Some(source_ref) = &source_ref {
+            segments.push(segment("RFF", vec![composite(&["ACW", source_ref])]));
+        }
+        if let Some(interchange) = &source.metadata.interchange {
+            segments.push(party("MS", &interchange.recipient));
+            segments.push(party("MR", &interchange.sender));
+        }
+        for (error_code, text) in &errors {
+            segments.push(segment("ERC", vec![simple(error_code)]));
+            segments.push(free_text(text));
+        }
+
+        let mut root = Node::new("MESSAGE", NodeType::Message);
+        root.add_child(unh(&self.message_ref, &["APERAK", "D", "96A", "UN", "EAN002"]).to_node());
+        for segment in &segments {
+            root.add_child(segment.to_node());
+        }
+        root.add_child(unt(segments.len() + 2, &self.message_ref).to_node());
+
+        let metadata = DocumentMetadata {
+            doc_type: Some("APERAK".to_string()),
+            version: Some("D_96A".to_string()),
+            interchange_ref: source.metadata.interchange_ref.clone(),
+            partner_id: source
+                .metadata
+                .interchange
+                .as_ref()
+                .map(|interchange| interchange.sender.id.clone())
+                .or_else(|| source.metadata.partner_id.clone()),
+            message_refs: vec![self.message_ref.clone()],
+            ..Default::default()
+        };
+        Document::with_metadata(root, metadata)
+    }
+
+    /// ERC code and free text of every issue in `errors`
+    fn application_errors(&self, errors: &[ApplicationIssue]) -> Vec<(String, String)> {
+        errors
+            .iter()
+            .map(|issue| {
+                let error_code = issue
+                    .code
+                    .as_deref()
+                    .and_then(|code| self.error_codes.get(code))
+                    .unwrap_or(&self.default_error_code);
+                let text = error_code.free_text.as_deref().unwrap_or(&issue.message);
+                (error_code.code.clone(), text.to_string())
+            })
+            .collect()
+    }
+}
+
+/// UNH reference of the source message, from metadata or its UNH segment
+fn source_message_ref(source: &Document) -> Option<String> {
+    if let Some(message_ref) = source.metadata.message_refs.first() {
+        return Some(message_ref.clone());
+    }
+    let unh = source
+        .root
+        .children
+        .iter()
+        .find(|child| child.node_type == NodeType::Segment && child.name == "UNH")?;
+    unh.children
+        .first()
+        .and_then(|element| element.value.as_ref())
+        .and_then(Value::as_string)
+}
+
+/// NAD segment for a party, with EAN code list agency for GLNs
+fn party(qualifier: &str, party: &PartyInfo) -> Segment {
+    let identification = if party.qualifier.as_deref() == Some("14") {
+        composite(&[&party.id, "", "9"])
+    } else {
+        simple(&party.id)
+    };
+    segment("NAD", vec![simple(qualifier), identification])
+}
+
+/// FTX+AAO with `text` split into free text components
+fn free_text(text: &str) -> Segment {
+    let chars: Vec<char> = text.chars().collect();
+    let components: Vec<Vec<u8>> = chars
+        .chunks(FREE_TEXT_LENGTH)
+        .take(FREE_TEXT_COMPONENTS)
+        .map(|chunk| chunk.iter().collect::<String>().into_bytes())
+        .collect();
+    segment(
+        "FTX",
+        vec![
+            simple("AAO"),
+            simple(""),
+            simple(""),
+            Element::Composite(components),
+        ],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EdifactParser, EdifactSerializer};
+
+    const ORDERS: &[u8] = b"UNB+UNOC:3+5412345000013:14+4012345000016:14+260312:0930+42'\n\
+        UNH+ORD7+ORDERS:D:96A:UN:EAN008'\nBGM+220+PO123+9'\n\
+        LIN+1++4000862141404:SRS'\nUNT+4+ORD7'\nUNZ+1+42'";
+
+    fn issued_at() -> chrono::NaiveDateTime {
+        chrono::NaiveDate::from_ymd_opt(2026, 3, 13)
+            .unwrap()
+            .and_hms_opt(10, 15, 0)
+            .unwrap()
+    }
+
+    fn source() -> Document {
+        EdifactParser::new()
+            .parse(ORDERS, "orders.edi")
+            .unwrap()
+            .remove(0)
+    }
+
+    fn serialize(document: &Document) -> String {
+        EdifactSerializer::new()
+            .serialize_document(document)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_aperak_reports_mapped_errors() {
+        let errors = [
+            ApplicationIssue::new("Unknown GTIN 4000862141404").with_code("CODELIST_VIOLATION"),
+            ApplicationIssue::new("Required segment RFF+ON missing").with_code("MISSING_REQUIRED"),
+        ];
+
+        let aperak = AperakGenerator::new("APE1")
+            .with_error_code("CODELIST_VIOLATION", ApplicationErrorCode::new("12"))
+            .with_error_code(
+                "MISSING_REQUIRED",
+                ApplicationErrorCode::new("13").with_free_text("Order reference missing"),
+            )
+            .with_issued_at(issued_at())
+            .generate(&source(), &errors);
+
+        assert_eq!(
+            serialize(&aperak),
+            "UNH+APE1+APERAK:D:96A:UN:EAN002'\n\
+             BGM+305+APE1+27'\n\
+             DTM+137:202603131015:203'\n\
+             RFF+ACW:ORD7'\n\
+             NAD+MS+4012345000016::9'\n\
+             NAD+MR+5412345000013::9'\n\
+             ERC+12'\n\
+             FTX+AAO+++Unknown GTIN 4000862141404'\n\
+             ERC+13'\n\
+             FTX+AAO+++Order reference missing'\n\
+             UNT+11+APE1'"
+        );
+        assert_eq!(aperak.metadata.doc_type.as_deref(), Some("APERAK"));
+        assert_eq!(aperak.metadata.partner_id.as_deref(), Some("5412345000013"));
+    }
+
+    #[test]
+    fn test_aperak_uses_default_code_and_splits_long_text() {
+        let errors = [ApplicationIssue::new("x".repeat(150))];
+
+        let aperak = AperakGenerator::new("APE2")
+            .with_default_error_code(ApplicationErrorCode::new("7"))
+            .with_issued_at(issued_at())
+            .generate(&source(), &errors);
+        let text = serialize(&aperak);
+
+        assert!(text.contains("ERC+7'"), "{text}");
+        let ftx = format!(
+            "FTX+AAO+++{}:{}:{}'",
+            "x".repeat(70),
+            "x".repeat(70),
+            "x".repeat(10)
+        );
+        assert!(text.contains(&ftx), "{text}");
+    }
+
+    #[test]
+    fn test_aperak_acknowledges_message_without_errors() {
+        let aperak = AperakGenerator::new("APE3")
+            .with_issued_at(issued_at())
+            .generate(&source(), &[]);
+        let text = serialize(&aperak);
+
+        assert!(text.contains("BGM+305+APE3+29'"), "{text}");
+        assert!(!text.contains("ERC"), "{text}");
+        assert!(text.ends_with("UNT+7+APE3'"), "{text}");
+    }
+}
@@ -0,0 +1,511 @@
+//! Zero-copy parsing into borrowed IR trees
+//!
+//! [`EdifactParser::parse_borrowed`] builds [`BorrowedDocument`]s whose
+//! segment tags and element values are slices of the input text, and whose
+//! element and component names are interned. Only values containing release
+//! characters are copied, to drop the release characters. This keeps large
+//! batches (e.g. inventory reports with hundreds of thousands of lines) from
+//! allocating a string per element; convert a message with
+//! [`BorrowedDocument::to_document`] when it has to be mapped, validated or
+//! edited.
+//!
+//! The input must already be UTF-8 text: values are not transcoded from the
+//! interchange character set, and characters outside its repertoire are not
+//! reported. Lossless mode and error recovery are only available through
+//! [`EdifactParser::parse_with_warnings`].
+
+use std::borrow::Cow;
+
+use edi_ir::document::DocumentMetadata;
+use edi_ir::{
+    BorrowedDocument, BorrowedNode, InterchangeInfo, NodeType, SourceSpan, component_name,
+    element_name, repetition_name,
+};
+use tracing::warn;
+
+use crate::envelopes;
+use crate::parser::{EdifactParser, LineCursor, Segment, number_segments, span_until};
+use crate::syntax::Separators;
+use crate::{Error, Result};
+
+/// Component values of the element occurrence being read, with their spans
+type Components<'a> = Vec<(Cow<'a, str>, SourceSpan)>;
+
+impl EdifactParser {
+    /// Parse all messages of `data` into trees that borrow from it.
+    ///
+    /// Messages are grouped and numbered like [`Self::parse`], and
+    /// [`BorrowedDocument::to_document`] yields the documents it returns for
+    /// input in the interchange's character set.
+    pub fn parse_borrowed<'a>(&self, data: &'a str) -> Result<Vec<BorrowedDocument<'a>>> {
+        let mut scanner = SegmentScanner::new(data)?;
+        let mut documents = Vec::new();
+        let mut message = Vec::new();
+        let mut interchange = None;
+
+        while let Some(segment) = scanner.next_segment()? {
+            match segment.name.as_ref() {
+                "UNB" => {
+                    // Without UNA, syntax version 4 implies the default
+                    // repetition separator
+                    if !scanner.has_una && syntax_version(&segment).is_some_and(|v| v >= 4) {
+                        scanner.separators = Separators::for_syntax_version(4);
+                    }
+                    interchange = match envelopes::parse_unb(&Segment::from_node(
+                        &segment.to_node(),
+                    )) {
+                        Ok(unb) => Some(InterchangeInfo::from(&unb)),
+                        Err(error) => {
+                            warn!(
+                                %error,
+                                "Interchange header could not be read; messages carry no interchange metadata"
+                            );
+                            None
+                        }
+                    };
+                }
+                "UNG" | "UNE" => {}
+                "UNH" => message.push(segment),
+                "UNT" if !message.is_empty() => {
+                    message.push(segment);
+                    let segments = std::mem::take(&mut message);
+                    documents.push(self.build_borrowed(segments, interchange.as_ref()));
+                }
+                "UNZ" => {
+                    message.clear();
+                    interchange = None;
+                }
+                _ if !message.is_empty() => message.push(segment),
+                _ => {}
+            }
+        }
+
+        if !message.is_empty() {
+            let document = self.build_borrowed(message, interchange.as_ref());
+            warn!(
+                message_ref = document
+                    .metadata
+                    .message_refs
+                    .first()
+                    .map_or("unknown", String::as_str),
+                "Message is missing its UNT trailer"
+            );
+            documents.push(document);
+        }
+
+        Ok(documents)
+    }
+
+    /// Build a message from its segments, UNH first
+    fn build_borrowed<'a>(
+        &self,
+        segments: Vec<BorrowedNode<'a>>,
+        interchange: Option<&InterchangeInfo>,
+    ) -> BorrowedDocument<'a> {
+        let (message_type, version, message_ref) = message_info(&segments[0]);
+
+        let mut root = BorrowedNode::new("MESSAGE", NodeType::Message);
+        root.children = self.group_segments(message_type.as_deref(), segments);
+        number_segments(&mut root, &mut 0);
+
+        let mut metadata = DocumentMetadata {
+            doc_type: message_type,
+            version,
+            ..Default::default()
+        };
+        metadata.message_refs.extend(message_ref);
+        Self::attach_interchange(&mut metadata, interchange);
+
+        BorrowedDocument::with_metadata(root, metadata)
+    }
+}
+
+/// Message type, `version_release` and message reference from a UNH segment
+fn message_info(unh: &BorrowedNode<'_>) -> (Option<String>, Option<String>, Option<String>) {
+    let message_ref = unh
+        .find_child("e1")
+        .and_then(BorrowedNode::text)
+        .map(str::to_string);
+
+    let identifier = unh
+        .find_child("e2")
+        .filter(|element| !element.children.is_empty());
+    let component = |name: &str| {
+        identifier
+            .and_then(|element| element.find_child(name))
+            .and_then(BorrowedNode::text)
+    };
+    let version = match (component("c2"), component("c3")) {
+        (Some(version), Some(release)) => Some(format!("{version}_{release}")),
+        (version, _) => version.map(str::to_string),
+    };
+
+    (component("c1").map(str::to_string), version, message_ref)
+}
+
+/// Syntax version number from the first UNB element
+fn syntax_version(unb: &BorrowedNode<'_>) -> Option<u8> {
+    unb.find_child("e1")?.find_child("c2")?.text()?.parse().ok()
+}
+
+/// Reads segments as borrowed nodes, one at a time
+struct SegmentScanner<'a> {
+    data: &'a str,
+    pos: usize,
+    separators: Separators,
+    has_una: bool,
+    cursor: LineCursor,
+}
+
+impl<'a> SegmentScanner<'a> {
+    /// Start scanning `data`, taking the separators from a leading UNA
+    fn new(data: &'a str) -> Result<Self> {
+        let una = data.as_bytes().get(..9).and_then(Separators::from_una);
+        let separators = una.unwrap_or_default();
+        let ascii = [
+            separators.component,
+            separators.element,
+            separators.release,
+            separators.segment,
+        ];
+        if !ascii.iter().chain(&separators.repetition).all(u8::is_ascii) {
+            return Err(Error::Syntax(
+                "Borrowed parsing requires ASCII separators".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            data,
+            pos: if una.is_some() { 9 } else { 0 },
+            separators,
+            has_una: una.is_some(),
+            cursor: LineCursor::new(),
+        })
+    }
+
+    /// Parse the next segment, with the same element layout and spans as
+    /// [`SegmentParser::next_segment`](crate::parser::SegmentParser::next_segment)
+    fn next_segment(&mut self) -> Result<Option<BorrowedNode<'a>>> {
+        let bytes = self.data.as_bytes();
+        while matches!(bytes.get(self.pos), Some(b' ' | b'\n' | b'\r' | b'\t')) {
+            self.pos += 1;
+        }
+        if self.pos >= bytes.len() {
+            return Ok(None);
+        }
+
+        let start = self.pos;
+        let segment_span = self.cursor.span_at(bytes, start);
+        let is_tag = bytes
+            .get(start..start + 3)
+            .is_some_and(|tag| tag.iter().all(u8::is_ascii_uppercase));
+        if !is_tag {
+            return Err(parse_error(
+                segment_span,
+                "Expected segment tag (3 characters)",
+            ));
+        }
+        self.pos += 3;
+
+        let post_tag = self.cursor.span_at(bytes, self.pos);
+        match bytes.get(self.pos) {
+            None => {
+                return Err(parse_error(
+                    post_tag,
+                    "Unexpected end of input after segment tag",
+                ));
+            }
+            Some(&next) if next != self.separators.element && next != self.separators.segment => {
+                return Err(parse_error(
+                    post_tag,
+                    "Expected element separator or segment terminator after segment tag",
+                ));
+            }
+            Some(&next) if next == self.separators.element => self.pos += 1,
+            Some(_) => {}
+        }
+
+        let mut segment = BorrowedNode::new(&self.data[start..start + 3], NodeType::Segment);
+        let mut components = Components::new();
+        let mut occurrences = Vec::new();
+        let mut element_start = segment_span;
+        let mut occurrence_start = segment_span;
+
+        loop {
+            let value_start = self.cursor.span_at(bytes, self.pos);
+            if components.is_empty() {
+                occurrence_start = value_start;
+                if occurrences.is_empty() {
+                    element_start = value_start;
+                }
+            }
+            let (value, delimiter) = self.read_value();
+            let value_end = self.pos - usize::from(delimiter.is_some());
+            let value_span = span_until(value_start, value_end);
+            let element = element_name(segment.children.len() + 1);
+
+            // An empty value before anything else of the element is an
+            // empty simple element; a component separator after it starts a
+            // composite with an empty first component
+            if value.is_empty() && components.is_empty() && occurrences.is_empty() {
+                let delimiter = delimiter.filter(|&d| Some(d) != self.separators.repetition);
+                if let Some(delimiter) = delimiter {
+                    if delimiter == self.separators.segment && segment.children.is_empty() {
+                        break;
+                    }
+                    let mut empty = BorrowedNode::with_value(element, NodeType::Element, value);
+                    empty.set_span(value_span);
+                    segment.add_child(empty);
+                    if delimiter == self.separators.component {
+                        components.push((Cow::Borrowed(""), value_span));
+                    }
+                    if delimiter == self.separators.segment {
+                        break;
+                    }
+                    continue;
+                }
+            }
+
+            components.push((value, value_span));
+            match delimiter {
+                Some(d) if d == self.separators.component => {}
+                Some(d) if Some(d) == self.separators.repetition => {
+                    let name = repetition_name(occurrences.len() + 1);
+                    let span = span_until(occurrence_start, value_end);
+                    occurrences.push(occurrence(name, span, &mut components));
+                }
+                _ => {
+                    let occurrence_span = span_until(occurrence_start, value_end);
+                    segment.add_child(if occurrences.is_empty() {
+                        occurrence(element, occurrence_span, &mut components)
+                    } else {
+                        let name = repetition_name(occurrences.len() + 1);
+                        occurrences.push(occurrence(name, occurrence_span, &mut components));
+                        let mut repeated = BorrowedNode::new(element, NodeType::Element);
+                        repeated.children = std::mem::take(&mut occurrences);
+                        repeated.set_span(span_until(element_start, value_end));
+                        repeated
+                    });
+                    if delimiter != Some(self.separators.element) {
+                        break;
+                    }
+                }
+            }
+        }
+
+        segment.set_span(span_until(segment_span, self.pos));
+        Ok(Some(segment))
+    }
+
+    /// Read a value up to the next delimiter, which is consumed and returned
+    fn read_value(&mut self) -> (Cow<'a, str>, Option<u8>) {
+        let bytes = self.data.as_bytes();
+        let start = self.pos;
+        let mut released = false;
+        while let Some(&byte) = bytes.get(self.pos) {
+            if byte == self.separators.release {
+                released = true;
+                self.pos += 2;
+            } else if byte == self.separators.component
+                || byte == self.separators.element
+                || byte == self.separators.segment
+                || Some(byte) == self.separators.repetition
+            {
+                let value = self.unescape(&self.data[start..self.pos], released);
+                self.pos += 1;
+                return (value, Some(byte));
+            } else {
+                self.pos += 1;
+            }
+        }
+        self.pos = bytes.len();
+        (self.unescape(&self.data[start..], released), None)
+    }
+
+    /// `value` without its release characters
+    fn unescape(&self, value: &'a str, released: bool) -> Cow<'a, str> {
+        if !released {
+            return Cow::Borrowed(value);
+        }
+        let release = char::from(self.separators.release);
+        let mut unescaped = String::with_capacity(value.len());
+        let mut chars = value.chars();
+        while let Some(c) = chars.next() {
+            if c == release {
+                unescaped.extend(chars.next());
+            } else {
+                unescaped.push(c);
+            }
+        }
+        Cow::Owned(unescaped)
+    }
+}
+
+/// Simple or composite element occurrence from the collected components
+fn occurrence<'a>(
+    name: Cow<'a, str>,
+    span: SourceSpan,
+    components: &mut Components<'a>,
+) -> BorrowedNode<'a> {
+    let mut node = if components.len() == 1 {
+        let (value, _) = components.remove(0);
+        BorrowedNode::with_value(name, NodeType::Element, value)
+    } else {
+        let mut node = BorrowedNode::new(name, NodeType::Element);
+        for (j, (value, span)) in components.drain(..).enumerate() {
+            let mut component =
+                BorrowedNode::with_value(component_name(j + 1), NodeType::Component, value);
+            component.set_span(span);
+            node.add_child(component);
+        }
+        node
+    };
+    node.set_span(span);
+    node
+}
+
+fn parse_error(at: SourceSpan, message: &str) -> Error {
+    Error::Parse {
+        line: at.line,
+        column: at.column,
+        message: message.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::groups::{BranchingDiagram, SegmentGroup};
+    use edi_ir::{Document, Node};
+
+    const INVRPT: &str = "UNA:+.? '\
+UNB+UNOC:3+SENDER:14+RECEIVER:14+240101:1200+REF1'\n\
+UNH+1+INVRPT:D:96A:UN:EAN008'\n\
+BGM+35+INV?+1+9'\n\
+LIN+1++4000862141404:SRS'\n\
+QTY+145:10:PCE'\n\
+LIN+2++4000862141411:SRS'\n\
+QTY+145:::7'\n\
+UNT+7+1'\n\
+UNZ+1+REF1'";
+
+    fn parse_both<'a>(
+        parser: &EdifactParser,
+        data: &'a str,
+    ) -> (Vec<Document>, Vec<BorrowedDocument<'a>>) {
+        let owned = parser.parse(data.as_bytes(), "test").unwrap();
+        let borrowed = parser.parse_borrowed(data).unwrap();
+        (owned, borrowed)
+    }
+
+    fn assert_same_tree(owned: &Node, converted: &Node) {
+        assert_eq!(owned.name, converted.name);
+        assert_eq!(owned.node_type, converted.node_type);
+        assert_eq!(owned.value, converted.value, "value of {}", owned.name);
+        assert_eq!(owned.span, converted.span, "span of {}", owned.name);
+        assert_eq!(owned.children.len(), converted.children.len());
+        for (owned, converted) in owned.children.iter().zip(&converted.children) {
+            assert_same_tree(owned, converted);
+        }
+    }
+
+    #[test]
+    fn test_borrowed_documents_match_owned_parse() {
+        let diagram = BranchingDiagram::new()
+            .with_segment("UNH")
+            .with_segment("BGM")
+            .with_group(SegmentGroup::new("SG9", "LIN").with_segment("QTY"))
+            .with_segment("UNT");
+        for parser in [
+            EdifactParser::new(),
+            EdifactParser::new().with_branching_diagram("INVRPT", diagram),
+        ] {
+            let (owned, borrowed) = parse_both(&parser, INVRPT);
+            assert_eq!(owned.len(), 1);
+            assert_eq!(borrowed.len(), 1);
+
+            let converted = borrowed[0].to_document();
+            assert!(owned[0].diff(&converted).is_empty());
+            assert_same_tree(&owned[0].root, &converted.root);
+            assert_eq!(converted.metadata.doc_type.as_deref(), Some("INVRPT"));
+            assert_eq!(converted.metadata.version.as_deref(), Some("D_96A"));
+            assert_eq!(converted.metadata.message_refs, vec!["1".to_string()]);
+            assert_eq!(converted.metadata.interchange_ref.as_deref(), Some("REF1"));
+            assert_eq!(converted.metadata.partner_id.as_deref(), Some("SENDER"));
+        }
+    }
+
+    #[test]
+    fn test_values_borrow_from_input_unless_released() {
+        let parser = EdifactParser::new();
+        let documents = parser.parse_borrowed(INVRPT).unwrap();
+        let root = &documents[0].root;
+
+        let bgm = root.find_child("BGM").unwrap();
+        assert!(matches!(bgm.name, Cow::Borrowed(_)));
+        let released = bgm.find_child("e2").unwrap();
+        assert_eq!(released.text(), Some("INV+1"));
+        assert!(matches!(released.value, Some(Cow::Owned(_))));
+
+        let qty = root.find_child("QTY").unwrap();
+        let quantity = qty.find_child("e1").unwrap().find_child("c2").unwrap();
+        assert_eq!(quantity.text(), Some("10"));
+        assert!(matches!(quantity.value, Some(Cow::Borrowed(_))));
+
+        let span = quantity.span.unwrap();
+        assert_eq!(&INVRPT[span.offset..span.end()], "10");
+        assert_eq!((span.line, span.segment_index), (5, Some(4)));
+    }
+
+    #[test]
+    fn test_repetitions_and_line_item_groups_match_owned_parse() {
+        let data = "UNB+UNOC:4+SENDER+RECEIVER+240101:1200+REF2'\
+UNH+7+ORDERS:D:01B:UN'\
+BGM+220+PO1'\
+LIN+1++A:SRV*B:SRV'\
+FTX+AAI+++TEXT ONE*TEXT TWO'\
+LIN+2'\
+CNT+2:2'\
+UNT+7+7'\
+UNZ+1+REF2'";
+        let (owned, borrowed) = parse_both(&EdifactParser::new(), data);
+
+        assert_same_tree(&owned[0].root, &borrowed[0].to_document().root);
+        let names: Vec<_> = borrowed[0]
+            .root
+            .children
+            .iter()
+            .map(|node| node.name.as_ref())
+            .collect();
+        assert_eq!(
+            names,
+            vec!["UNH", "BGM", "LINE_ITEM", "LINE_ITEM", "CNT", "UNT"]
+        );
+        let lin = borrowed[0].root.children[2].find_child("LIN").unwrap();
+        let repeated = lin.find_child("e3").unwrap();
+        assert_eq!(repeated.children.len(), 2);
+        assert_eq!(repeated.children[1].name, "r2");
+    }
+
+    #[test]
+    fn test_message_without_unt_is_kept() {
+        let documents = EdifactParser::new()
+            .parse_borrowed("UNH+1+INVRPT:D:96A:UN'BGM+35'")
+            .unwrap();
+        assert_eq!(documents.len(), 1);
+        assert_eq!(documents[0].root.children.len(), 2);
+    }
+
+    #[test]
+    fn test_malformed_segment_is_reported() {
+        let result = EdifactParser::new().parse_borrowed("UNH+1+INVRPT:D:96A:UN'\nbgm+35'");
+        assert!(matches!(
+            result,
+            Err(Error::Parse {
+                line: 2,
+                column: 1,
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,353 @@
+//! EDIFACT character sets (syntax identifiers UNOA–UNOY)
+//!
+//! The first component of the UNB syntax identifier declares the character
+//! repertoire and encoding of the interchange. This module decodes element
+//! data into Rust strings, checks the restricted UNOA/UNOB repertoires and
+//! encodes serialized output back into the declared character set.
+
+use crate::{Error, Result};
+use encoding_rs::Encoding;
+
+/// Punctuation allowed by the level A and level B repertoires
+const BASIC_PUNCTUATION: &str = " .,-()/='+:?!\"%&*;<>";
+
+/// ISO 8859-9 code points that differ from ISO 8859-1
+const LATIN5_OVERRIDES: [(u8, char); 6] = [
+    (0xD0, '\u{011E}'),
+    (0xDD, '\u{0130}'),
+    (0xDE, '\u{015E}'),
+    (0xF0, '\u{011F}'),
+    (0xFD, '\u{0131}'),
+    (0xFE, '\u{015F}'),
+];
+
+/// Character set declared by an EDIFACT syntax identifier
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Charset {
+    /// Level A: upper case letters, digits and basic punctuation
+    Unoa,
+    /// Level B: level A plus lower case letters
+    Unob,
+    /// ISO 8859-1 (Latin alphabet No. 1)
+    Unoc,
+    /// ISO 8859-2 (Latin alphabet No. 2)
+    Unod,
+    /// ISO 8859-5 (Latin/Cyrillic)
+    Unoe,
+    /// ISO 8859-7 (Latin/Greek)
+    Unof,
+    /// ISO 8859-3 (Latin alphabet No. 3)
+    Unog,
+    /// ISO 8859-4 (Latin alphabet No. 4)
+    Unoh,
+    /// ISO 8859-6 (Latin/Arabic)
+    Unoi,
+    /// ISO 8859-8 (Latin/Hebrew)
+    Unoj,
+    /// ISO 8859-9 (Latin alphabet No. 5)
+    Unok,
+    /// ISO 10646-1 encoded as UTF-8 (syntax version 4 partial code extension)
+    Unow,
+    /// ISO 10646-1 encoded as UTF-8
+    Unoy,
+}
+
+/// Result of decoding element data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decoded {
+    /// Decoded text; undecodable bytes become U+FFFD
+    pub text: String,
+    /// `true` when some bytes were not valid in the declared character set
+    pub had_errors: bool,
+}
+
+impl Charset {
+    /// Look up the character set for a syntax identifier (e.g. "UNOC").
+    ///
+    /// Returns `None` for unknown identifiers and for UNOX, whose ISO 2022
+    /// code extension techniques are not supported.
+    pub fn from_syntax_identifier(identifier: &str) -> Option<Self> {
+        match identifier {
+            "UNOA" => Some(Self::Unoa),
+            "UNOB" => Some(Self::Unob),
+            "UNOC" => Some(Self::Unoc),
+            "UNOD" => Some(Self::Unod),
+            "UNOE" => Some(Self::Unoe),
+            "UNOF" => Some(Self::Unof),
+            "UNOG" => Some(Self::Unog),
+            "UNOH" => Some(Self::Unoh),
+            "UNOI" => Some(Self::Unoi),
+            "UNOJ" => Some(Self::Unoj),
+            "UNOK" => Some(Self::Unok),
+            "UNOW" => Some(Self::Unow),
+            "UNOY" => Some(Self::Unoy),
+            _ => None,
+        }
+    }
+
+    /// Syntax identifier for this character set
+    pub fn syntax_identifier(&self) -> &'static str {
+        match self {
+            Self::Unoa => "UNOA",
+            Self::Unob => "UNOB",
+            Self::Unoc => "UNOC",
+            Self::Unod => "UNOD",
+            Self::Unoe => "UNOE",
+            Self::Unof => "UNOF",
+            Self::Unog => "UNOG",
+            Self::Unoh => "UNOH",
+            Self::Unoi => "UNOI",
+            Self::Unoj => "UNOJ",
+            Self::Unok => "UNOK",
+            Self::Unow => "UNOW",
+            Self::Unoy => "UNOY",
+        }
+    }
+
+    /// Returns `true` if `ch` belongs to the character repertoire
+    pub fn allows(&self, ch: char) -> bool {
+        match self {
+            Self::Unoa => ch.is_ascii_uppercase() || ch.is_ascii_digit() || is_basic(ch),
+            Self::Unob => ch.is_ascii_alphanumeric() || is_basic(ch),
+            Self::Unoc => u32::from(ch) <= 0xFF,
+            Self::Unok => encode_latin5(ch).is_some(),
+            Self::Unow | Self::Unoy => ch != char::REPLACEMENT_CHARACTER,
+            _ => self
+                .encoding()
+                .is_some_and(|encoding| !encoding.encode(ch.encode_utf8(&mut [0; 4])).2),
+        }
+    }
+
+    /// Characters of `text` outside the repertoire, in order of appearance
+    pub fn invalid_characters(&self, text: &str) -> Vec<char> {
+        text.chars().filter(|ch| !self.allows(*ch)).collect()
+    }
+
+    /// Decode element data from this character set
+    pub fn decode(&self, bytes: &[u8]) -> Decoded {
+        match self {
+            Self::Unoc => Decoded {
+                text: bytes.iter().map(|&byte| char::from(byte)).collect(),
+                had_errors: false,
+            },
+            Self::Unok => Decoded {
+                text: bytes.iter().map(|&byte| decode_latin5(byte)).collect(),
+                had_errors: false,
+            },
+            _ => match self.encoding() {
+                Some(encoding) => {
+                    let (text, had_errors) = encoding.decode_without_bom_handling(bytes);
+                    Decoded {
+                        text: text.into_owned(),
+                        had_errors,
+                    }
+                }
+                None => {
+                    // UNOA/UNOB are 7-bit; anything else is reported by the
+                    // repertoire check rather than rejected here.
+                    let text = String::from_utf8_lossy(bytes);
+                    Decoded {
+                        had_errors: text.contains(char::REPLACEMENT_CHARACTER),
+                        text: text.into_owned(),
+                    }
+                }
+            },
+        }
+    }
+
+    /// Encode text into this character set.
+    ///
+    /// Line breaks are accepted in every character set, since they separate
+    /// segments rather than carry data.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Charset`] naming the first character that is not part
+    /// of the repertoire.
+    pub fn encode(&self, text: &str) -> Result<Vec<u8>> {
+        let invalid = self.invalid_characters(text);
+        if let Some(ch) = invalid.iter().find(|ch| !matches!(ch, '\r' | '\n')) {
+            return Err(Error::Charset(format!(
+                "Character '{}' (U+{:04X}) cannot be represented in {}",
+                ch,
+                u32::from(*ch),
+                self.syntax_identifier()
+            )));
+        }
+
+        let bytes = match self {
+            Self::Unoa | Self::Unob | Self::Unow | Self::Unoy => text.as_bytes().to_vec(),
+            Self::Unoc => text
+                .chars()
+                .filter_map(|ch| u8::try_from(u32::from(ch)).ok())
+                .collect(),
+            Self::Unok => text.chars().filter_map(encode_latin5).collect(),
+            _ => self
+                .encoding()
+                .map(|encoding| encoding.encode(text).0.into_owned())
+                .unwrap_or_default(),
+        };
+        Ok(bytes)
+    }
+
+    /// Encode text as the parser read it.
+    ///
+    /// UNOA and UNOB data is read without decoding and characters outside
+    /// their repertoires are only reported as warnings, so it is written back
+    /// unchecked; the other character sets are encoded as by [`Self::encode`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::Charset`] naming the first character the character
+    /// set cannot encode.
+    pub(crate) fn encode_as_read(&self, text: &str) -> Result<Vec<u8>> {
+        match self {
+            Self::Unoa | Self::Unob => Ok(text.as_bytes().to_vec()),
+            _ => self.encode(text),
+        }
+    }
+
+    /// `encoding_rs` codec for the ISO 8859 parts and UTF-8
+    fn encoding(&self) -> Option<&'static Encoding> {
+        match self {
+            Self::Unod => Some(encoding_rs::ISO_8859_2),
+            Self::Unoe => Some(encoding_rs::ISO_8859_5),
+            Self::Unof => Some(encoding_rs::ISO_8859_7),
+            Self::Unog => Some(encoding_rs::ISO_8859_3),
+            Self::Unoh => Some(encoding_rs::ISO_8859_4),
+            Self::Unoi => Some(encoding_rs::ISO_8859_6),
+            Self::Unoj => Some(encoding_rs::ISO_8859_8),
+            Self::Unow | Self::Unoy => Some(encoding_rs::UTF_8),
+            Self::Unoa | Self::Unob | Self::Unoc | Self::Unok => None,
+        }
+    }
+}
+
+impl std::fmt::Display for Charset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.syntax_identifier())
+    }
+}
+
+fn is_basic(ch: char) -> bool {
+    BASIC_PUNCTUATION.contains(ch)
+}
+
+fn decode_latin5(byte: u8) -> char {
+    LATIN5_OVERRIDES
+        .iter()
+        .find(|(code, _)| *code == byte)
+        .map_or_else(|| char::from(byte), |(_, ch)| *ch)
+}
+
+fn encode_latin5(ch: char) -> Option<u8> {
+    if let Some((code, _)) = LATIN5_OVERRIDES.iter().find(|(_, mapped)| *mapped == ch) {
+        return Some(*code);
+    }
+    let byte = u8::try_from(u32::from(ch)).ok()?;
+    if LATIN5_OVERRIDES.iter().any(|(code, _)| *code == byte) {
+        None
+    } else {
+        Some(byte)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_syntax_identifier() {
+        assert_eq!(Charset::from_syntax_identifier("UNOC"), Some(Charset::Unoc));
+        assert_eq!(Charset::from_syntax_identifier("UNOY"), Some(Charset::Unoy));
+        assert_eq!(Charset::from_syntax_identifier("UNOX"), None);
+        assert_eq!(Charset::Unok.to_string(), "UNOK");
+    }
+
+    #[test]
+    fn test_level_a_and_b_repertoires() {
+        assert!(
+            Charset::Unoa
+                .invalid_characters("ACME GMBH (DE)")
+                .is_empty()
+        );
+        assert_eq!(
+            Charset::Unoa.invalid_characters("Acme"),
+            vec!['c', 'm', 'e']
+        );
+        assert!(Charset::Unob.invalid_characters("Acme Corp.").is_empty());
+        assert_eq!(
+            Charset::Unob.invalid_characters("M\u{fc}ller"),
+            vec!['\u{fc}']
+        );
+        assert_eq!(Charset::Unob.invalid_characters("a@b"), vec!['@']);
+    }
+
+    #[test]
+    fn test_decode_latin1() {
+        let decoded = Charset::Unoc.decode(b"M\xfcller Stra\xdfe");
+        assert_eq!(decoded.text, "M\u{fc}ller Stra\u{df}e");
+        assert!(!decoded.had_errors);
+    }
+
+    #[test]
+    fn test_decode_iso_8859_parts() {
+        assert_eq!(
+            Charset::Unod.decode(b"\xb3\xf3d\xbc").text,
+            "\u{142}\u{f3}d\u{17a}"
+        );
+        assert_eq!(
+            Charset::Unoe.decode(b"\xbc\xde\xe1\xda\xd2\xd0").text,
+            "Москва"
+        );
+        assert_eq!(Charset::Unof.decode(b"\xc1\xe8\xe7\xed\xdc").text, "Αθηνά");
+        assert_eq!(Charset::Unok.decode(b"\xddstanbul").text, "\u{130}stanbul");
+
+        let decoded = Charset::Unog.decode(b"\xa5");
+        assert!(decoded.had_errors);
+    }
+
+    #[test]
+    fn test_decode_utf8_reports_malformed_bytes() {
+        assert_eq!(Charset::Unoy.decode("Zoë".as_bytes()).text, "Zoë");
+        assert!(Charset::Unoy.decode(b"Zo\xeb").had_errors);
+    }
+
+    #[test]
+    fn test_encode_round_trips() {
+        for (charset, text) in [
+            (Charset::Unoc, "M\u{fc}ller"),
+            (Charset::Unod, "\u{141}\u{f3}d\u{17a}"),
+            (Charset::Unoe, "Москва"),
+            (Charset::Unok, "\u{130}stanbul \u{15f}"),
+            (Charset::Unoy, "東京"),
+        ] {
+            let bytes = charset.encode(text).unwrap();
+            assert_eq!(charset.decode(&bytes).text, text, "{charset}");
+        }
+        assert_eq!(Charset::Unoc.encode("M\u{fc}ller").unwrap(), b"M\xfcller");
+    }
+
+    #[test]
+    fn test_encode_rejects_characters_outside_repertoire() {
+        let error = Charset::Unoc.encode("Москва").unwrap_err();
+        assert!(error.to_string().contains("U+041C"));
+        assert!(Charset::Unoa.encode("abc").is_err());
+        assert!(Charset::Unok.encode("\u{d0}").is_err());
+    }
+
+    #[test]
+    fn test_encode_as_read_keeps_level_a_and_b_data() {
+        assert_eq!(Charset::Unoa.encode_as_read("Acme").unwrap(), b"Acme");
+        assert!(Charset::Unoc.encode_as_read("Москва").is_err());
+    }
+
+    #[test]
+    fn test_encode_accepts_line_breaks() {
+        assert_eq!(
+            Charset::Unoa.encode("UNH'\r\nBGM'\n").unwrap(),
+            b"UNH'\r\nBGM'\n"
+        );
+        assert_eq!(Charset::Unob.encode("a'\n").unwrap(), b"a'\n");
+    }
+}
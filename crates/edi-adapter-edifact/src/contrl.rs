@@ -0,0 +1,647 @@
+//! CONTRL syntax and service report generation
+//!
+//! A CONTRL message acknowledges a received interchange: UCI reports on the
+//! interchange, UCF on each functional group and UCM on each message. Errors in
+//! user data segments are pointed at with UCS (segment position) and UCD
+//! (element and component position). This module builds a CONTRL D:3 message
+//! from the envelope validation report of a parsed interchange.
+
+use crate::envelopes::{
+    EdifactDocument, EnvelopeType, ErrorKind, GroupEnvelope, InterchangeEnvelope, MessageEnvelope,
+    PartyId, ValidationError, ValidationReport,
+};
+use crate::parser::{Element, Segment};
+use crate::{Error, Result};
+use edi_ir::document::DocumentMetadata;
+use edi_ir::{Document, Node, NodeType, Position};
+
+/// Action code (data element 0083)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActionCode {
+    /// This level and all lower levels rejected
+    Rejected,
+    /// This level acknowledged, lower levels acknowledged if not explicitly
+    /// rejected
+    Acknowledged,
+}
+
+impl ActionCode {
+    /// Code value as transmitted
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::Rejected => "4",
+            Self::Acknowledged => "7",
+        }
+    }
+}
+
+/// Syntax error code (data element 0085)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyntaxErrorCode {
+    /// Syntax version or level not supported
+    UnsupportedSyntaxVersion,
+    /// Invalid value
+    InvalidValue,
+    /// Missing
+    Missing,
+    /// Value not supported in this position
+    ValueNotSupported,
+    /// Not supported in this position
+    NotSupportedInPosition,
+    /// Too many constituents
+    TooManyConstituents,
+    /// Unspecified error
+    Unspecified,
+    /// Invalid character(s)
+    InvalidCharacters,
+    /// Control reference in header and trailer do not match
+    ReferencesDoNotMatch,
+    /// Control count does not match number of instances received
+    ControlCountMismatch,
+    /// Functional groups and messages mixed
+    GroupsAndMessagesMixed,
+    /// Too many repetitions
+    TooManyRepetitions,
+    /// Data element too long
+    DataElementTooLong,
+    /// Data element too short
+    DataElementTooShort,
+}
+
+impl SyntaxErrorCode {
+    /// Code value as transmitted
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::UnsupportedSyntaxVersion => "2",
+            Self::InvalidValue => "12",
+            Self::Missing => "13",
+            Self::ValueNotSupported => "14",
+            Self::NotSupportedInPosition => "15",
+            Self::TooManyConstituents => "16",
+            Self::Unspecified => "18",
+            Self::InvalidCharacters => "21",
+            Self::ReferencesDoNotMatch => "28",
+            Self::ControlCountMismatch => "29",
+            Self::GroupsAndMessagesMixed => "30",
+            Self::TooManyRepetitions => "35",
+            Self::DataElementTooLong => "39",
+            Self::DataElementTooShort => "40",
+        }
+    }
+}
+
+impl From<ErrorKind> for SyntaxErrorCode {
+    fn from(kind: ErrorKind) -> Self {
+        match kind {
+            ErrorKind::MissingHeader | ErrorKind::MissingTrailer => Self::Missing,
+            ErrorKind::MismatchedControlReference => Self::ReferencesDoNotMatch,
+            ErrorKind::IncorrectSegmentCount | ErrorKind::IncorrectMessageCount => {
+                Self::ControlCountMismatch
+            }
+            ErrorKind::InvalidSyntaxIdentifier => Self::UnsupportedSyntaxVersion,
+            ErrorKind::InvalidDateTime => Self::InvalidValue,
+            ErrorKind::InvalidMessageType => Self::ValueNotSupported,
+            ErrorKind::OrphanedMessage | ErrorKind::OrphanedSegment => Self::NotSupportedInPosition,
+            ErrorKind::UngroupedMessage => Self::GroupsAndMessagesMixed,
+        }
+    }
+}
+
+/// Error in a user data segment of a message, reported with UCS/UCD
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SegmentError {
+    /// Reference of the message containing the segment (UNH 0062)
+    pub message_ref: String,
+    /// Position of the segment in the message, UNH being 1
+    pub segment_position: usize,
+    /// Position of the erroneous element (1-based); `None` reports the
+    /// segment as a whole
+    pub element_position: Option<usize>,
+    /// Position of the erroneous component within the element (1-based)
+    pub component_position: Option<usize>,
+    /// Syntax error code
+    pub code: SyntaxErrorCode,
+}
+
+impl SegmentError {
+    /// Create an error for the whole segment
+    pub fn new(
+        message_ref: impl Into<String>,
+        segment_position: usize,
+        code: SyntaxErrorCode,
+    ) -> Self {
+        Self {
+            message_ref: message_ref.into(),
+            segment_position,
+            element_position: None,
+            component_position: None,
+            code,
+        }
+    }
+
+    /// Point the error at an element and optionally a component
+    pub fn at_element(
+        mut self,
+        element_position: usize,
+        component_position: Option<usize>,
+    ) -> Self {
+        self.element_position = Some(element_position);
+        self.component_position = component_position;
+        self
+    }
+}
+
+/// Generator for CONTRL syntax and service report messages
+#[derive(Debug, Clone)]
+pub struct ContrlGenerator {
+    message_ref: String,
+    segment_errors: Vec<SegmentError>,
+}
+
+impl ContrlGenerator {
+    /// Create a generator whose CONTRL message uses `message_ref` in UNH/UNT
+    pub fn new(message_ref: impl Into<String>) -> Self {
+        Self {
+            message_ref: message_ref.into(),
+            segment_errors: Vec::new(),
+        }
+    }
+
+    /// Report errors found in user data segments (e.g. by schema validation)
+    pub fn with_segment_errors(mut self, errors: impl IntoIterator<Item = SegmentError>) -> Self {
+        self.segment_errors.extend(errors);
+        self
+    }
+
+    /// Build the CONTRL message acknowledging `document`.
+    ///
+    /// Envelope errors in `report` reject the interchange, group or message
+    /// they belong to; segment errors reject their message. A rejected
+    /// interchange is reported with UCI only, since all lower levels are
+    /// rejected with it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `document` has no interchange to acknowledge.
+    pub fn generate(
+        &self,
+        document: &EdifactDocument,
+        report: &ValidationReport,
+    ) -> Result<Document> {
+        let interchange = document.interchange.as_ref().ok_or_else(|| {
+            Error::Envelope("Cannot acknowledge a document without interchange".to_string())
+        })?;
+
+        let mut segments = vec![uci(interchange, report)];
+        if first_error(report, EnvelopeType::Interchange, None).is_none() {
+            for message in &interchange.messages {
+                segments.extend(self.message_response(message, report));
+            }
+            for group in &interchange.groups {
+                segments.extend(self.group_response(group, report));
+            }
+        }
+
+        let mut root = Node::new("MESSAGE", NodeType::Message);
+        root.add_child(unh(&self.message_ref, &["CONTRL", "D", "3", "UN"]).to_node());
+        for segment in &segments {
+            root.add_child(segment.to_node());
+        }
+        root.add_child(unt(segments.len() + 2, &self.message_ref).to_node());
+
+        let metadata = DocumentMetadata {
+            doc_type: Some("CONTRL".to_string()),
+            version: Some("D_3".to_string()),
+            interchange_ref: Some(interchange.unb.control_ref.clone()),
+            partner_id: Some(interchange.unb.sender.id.clone()),
+            message_refs: vec![self.message_ref.clone()],
+            ..Default::default()
+        };
+        Ok(Document::with_metadata(root, metadata))
+    }
+
+    fn group_response(&self, group: &GroupEnvelope, report: &ValidationReport) -> Vec<Segment> {
+        let error = first_error(report, EnvelopeType::Group, Some(&group.ung.control_ref));
+        let mut elements = vec![
+            simple(&group.ung.control_ref),
+            party(&group.ung.sender),
+            party(&group.ung.recipient),
+        ];
+        elements.extend(response(error));
+
+        let mut segments = vec![segment("UCF", elements)];
+        if error.is_none() {
+            for message in &group.messages {
+                segments.extend(self.message_response(message, report));
+            }
+        }
+        segments
+    }
+
+    fn message_response(
+        &self,
+        message: &MessageEnvelope,
+        report: &ValidationReport,
+    ) -> Vec<Segment> {
+        let message_ref = &message.unh.message_ref;
+        let error = first_error(report, EnvelopeType::Message, Some(message_ref));
+        let segment_errors: Vec<&SegmentError> = self
+            .segment_errors
+            .iter()
+            .filter(|error| &error.message_ref == message_ref)
+            .collect();
+
+        let message_type = &message.unh.message_type;
+        let mut identifier = vec![
+            message_type.message_type.as_bytes().to_vec(),
+            message_type.version.as_bytes().to_vec(),
+            message_type.release.as_bytes().to_vec(),
+            message_type.agency.as_bytes().to_vec(),
+        ];
+        if let Some(ref code) = message_type.association_code {
+            identifier.push(code.as_bytes().to_vec());
+        }
+
+        let mut elements = vec![simple(message_ref), Element::Composite(identifier)];
+        if error.is_none() && !segment_errors.is_empty() {
+            elements.push(simple(ActionCode::Rejected.code()));
+        } else {
+            elements.extend(response(error));
+        }
+
+        let mut segments = vec![segment("UCM", elements)];
+        segments.extend(segment_pointers(&segment_errors));
+        segments
+    }
+}
+
+/// Interchange response (UCI)
+fn uci(interchange: &InterchangeEnvelope, report: &ValidationReport) -> Segment {
+    let error = first_error(report, EnvelopeType::Interchange, None);
+    let mut elements = vec![
+        simple(&interchange.unb.control_ref),
+        party(&interchange.unb.sender),
+        party(&interchange.unb.receiver),
+    ];
+    elements.extend(response(error));
+    segment("UCI", elements)
+}
+
+/// UCS/UCD segments for the segment errors of one message, ordered by
+/// segment position.
+///
+/// Each erroneous segment gets one UCS, carrying the code of the first
+/// error reported for the segment as a whole, followed by a UCD per element
+/// error.
+fn segment_pointers(errors: &[&SegmentError]) -> Vec<Segment> {
+    let mut errors = errors.to_vec();
+    errors.sort_by_key(|error| error.segment_position);
+
+    let mut segments = Vec::new();
+    for position_errors in errors.chunk_by(|a, b| a.segment_position == b.segment_position) {
+        let mut elements = vec![simple(&position_errors[0].segment_position.to_string())];
+        if let Some(error) = position_errors
+            .iter()
+            .find(|error| error.element_position.is_none())
+        {
+            elements.push(simple(error.code.code()));
+        }
+        segments.push(segment("UCS", elements));
+
+        for error in position_errors {
+            let Some(element_position) = error.element_position else {
+                continue;
+            };
+            let mut pointer = vec![element_position.to_string().into_bytes()];
+            if let Some(component_position) = error.component_position {
+                pointer.push(component_position.to_string().into_bytes());
+            }
+            segments.push(segment(
+                "UCD",
+                vec![simple(error.code.code()), Element::Composite(pointer)],
+            ));
+        }
+    }
+    segments
+}
+
+/// First error reported for an envelope level, optionally limited to the
+/// envelope with `control_ref`
+fn first_error<'a>(
+    report: &'a ValidationReport,
+    envelope_type: EnvelopeType,
+    control_ref: Option<&str>,
+) -> Option<&'a ValidationError> {
+    report.errors.iter().find(|error| {
+        error.envelope_type == envelope_type
+            && (control_ref.is_none() || error.control_ref.as_deref() == control_ref)
+    })
+}
+
+/// Action code, syntax error code, erroneous service segment tag and
+/// element pointer for a response segment (UCI/UCF/UCM)
+fn response(error: Option<&ValidationError>) -> Vec<Element> {
+    let Some(error) = error else {
+        return vec![simple(ActionCode::Acknowledged.code())];
+    };
+
+    let code = SyntaxErrorCode::from(error.error_kind);
+    let mut elements = vec![simple(ActionCode::Rejected.code()), simple(code.code())];
+    if let Some((tag, element_position)) = service_segment(error.envelope_type, error.error_kind) {
+        elements.push(simple(tag));
+        if let Some(position) = element_position {
+            elements.push(simple(&position.to_string()));
+        }
+    }
+    elements
+}
+
+/// Service segment tag and element position an envelope error points at
+fn service_segment(
+    envelope_type: EnvelopeType,
+    kind: ErrorKind,
+) -> Option<(&'static str, Option<usize>)> {
+    match (envelope_type, kind) {
+        (EnvelopeType::Interchange, ErrorKind::MissingHeader) => Some(("UNB", None)),
+        (EnvelopeType::Interchange, ErrorKind::InvalidSyntaxIdentifier) => Some(("UNB", Some(1))),
+        (EnvelopeType::Interchange, ErrorKind::InvalidDateTime) => Some(("UNB", Some(4))),
+        (EnvelopeType::Interchange, ErrorKind::MissingTrailer) => Some(("UNZ", None)),
+        (EnvelopeType::Interchange, ErrorKind::IncorrectMessageCount) => Some(("UNZ", Some(1))),
+        (EnvelopeType::Interchange, ErrorKind::MismatchedControlReference) => {
+            Some(("UNZ", Some(2)))
+        }
+        (EnvelopeType::Group, ErrorKind::MissingHeader) => Some(("UNG", None)),
+        (EnvelopeType::Group, ErrorKind::MissingTrailer) => Some(("UNE", None)),
+        (EnvelopeType::Group, ErrorKind::IncorrectMessageCount) => Some(("UNE", Some(1))),
+        (EnvelopeType::Group, ErrorKind::MismatchedControlReference) => Some(("UNE", Some(2))),
+        (EnvelopeType::Message, ErrorKind::MissingHeader) => Some(("UNH", None)),
+        (EnvelopeType::Message, ErrorKind::InvalidMessageType) => Some(("UNH", Some(2))),
+        (EnvelopeType::Message, ErrorKind::MissingTrailer) => Some(("UNT", None)),
+        (EnvelopeType::Message, ErrorKind::IncorrectSegmentCount) => Some(("UNT", Some(1))),
+        (EnvelopeType::Message, ErrorKind::MismatchedControlReference) => Some(("UNT", Some(2))),
+        _ => None,
+    }
+}
+
+/// UNH segment for a generated message of type `message_type` (S009
+/// components, e.g. `["CONTRL", "D", "3", "UN"]`)
+pub(crate) fn unh(message_ref: &str, message_type: &[&str]) -> Segment {
+    segment("UNH", vec![simple(message_ref), composite(message_type)])
+}
+
+/// UNT segment counting `segment_count` segments including UNH and UNT
+pub(crate) fn unt(segment_count: usize, message_ref: &str) -> Segment {
+    segment(
+        "UNT",
+        vec![simple(&segment_count.to_string()), simple(message_ref)],
+    )
+}
+
+fn party(party: &PartyId) -> Element {
+    let mut comps = vec![party.id.as_bytes().to_vec()];
+    if let Some(ref qualifier) = party.qualifier {
+        comps.push(qualifier.as_bytes().to_vec());
+    }
+    Element::Composite(comps)
+}
+
+pub(crate) fn simple(value: &str) -> Element {
+    Element::Simple(value.as_bytes().to_vec())
+}
+
+pub(crate) fn composite(values: &[&str]) -> Element {
+    Element::Composite(
+        values
+            .iter()
+            .map(|value| value.as_bytes().to_vec())
+            .collect(),
+    )
+}
+
+pub(crate) fn segment(tag: &str, elements: Vec<Element>) -> Segment {
+    Segment {
+        tag: tag.to_string(),
+        elements,
+        position: Position::default(),
+        raw: None,
+        spans: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EdifactSerializer;
+    use crate::envelopes::{
+        EnvelopeValidator, parse_unb, parse_une, parse_ung, parse_unh, parse_unt, parse_unz,
+    };
+    use crate::parser::SegmentParser;
+
+    /// Build the envelope structure of an interchange from raw EDIFACT
+    fn edifact_document(data: &[u8]) -> EdifactDocument {
+        let mut parser = SegmentParser::new(data, "test");
+        let mut interchange: Option<InterchangeEnvelope> = None;
+        let mut group: Option<GroupEnvelope> = None;
+        let mut message: Option<MessageEnvelope> = None;
+
+        while let Some(segment) = parser.next_segment() {
+            let segment = segment.unwrap();
+            match segment.tag.as_str() {
+                "UNB" => {
+                    interchange = Some(InterchangeEnvelope {
+                        unb: parse_unb(&segment).unwrap(),
+                        unz: None,
+                        messages: Vec::new(),
+                        groups: Vec::new(),
+                    });
+                }
+                "UNG" => {
+                    group = Some(GroupEnvelope {
+                        ung: parse_ung(&segment).unwrap(),
+                        une: None,
+                        messages: Vec::new(),
+                    });
+                }
+                "UNH" => {
+                    message = Some(MessageEnvelope {
+                        unh: parse_unh(&segment).unwrap(),
+                        unt: None,
+                        segments: Vec::new(),
+                    });
+                }
+                "UNT" => {
+                    let mut finished = message.take().unwrap();
+                    finished.unt = Some(parse_unt(&segment).unwrap());
+                    match group.as_mut() {
+                        Some(group) => group.messages.push(finished),
+                        None => interchange.as_mut().unwrap().messages.push(finished),
+                    }
+                }
+                "UNE" => {
+                    let mut finished = group.take().unwrap();
+                    finished.une = Some(parse_une(&segment).unwrap());
+                    interchange.as_mut().unwrap().groups.push(finished);
+                }
+                "UNZ" => {
+                    interchange.as_mut().unwrap().unz = Some(parse_unz(&segment).unwrap());
+                }
+                _ => message.as_mut().unwrap().segments.push(segment),
+            }
+        }
+
+        EdifactDocument {
+            una: None,
+            interchange,
+        }
+    }
+
+    fn contrl(generator: &ContrlGenerator, data: &[u8]) -> Vec<String> {
+        let document = edifact_document(data);
+        let report = EnvelopeValidator::new().validate(&document).unwrap();
+        let contrl = generator.generate(&document, &report).unwrap();
+        EdifactSerializer::new()
+            .serialize_document(&contrl)
+            .unwrap()
+            .lines()
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn test_acknowledges_valid_interchange() {
+        let data = b"UNB+UNOA:3+SENDER:14+RECEIVER:14+200101:1200+REF1'\
+UNH+1+ORDERS:D:96A:UN:EAN008'BGM+220+PO1+9'UNT+3+1'\
+UNH+2+ORDERS:D:96A:UN'BGM+220+PO2+9'UNT+3+2'UNZ+2+REF1'";
+
+        let lines = contrl(&ContrlGenerator::new("ACK1"), data);
+        assert_eq!(
+            lines,
+            vec![
+                "UNH+ACK1+CONTRL:D:3:UN'",
+                "UCI+REF1+SENDER:14+RECEIVER:14+7'",
+                "UCM+1+ORDERS:D:96A:UN:EAN008+7'",
+                "UCM+2+ORDERS:D:96A:UN+7'",
+                "UNT+5+ACK1'",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rejects_message_with_envelope_error() {
+        let data = b"UNB+UNOA:3+SENDER+RECEIVER+200101:1200+REF1'\
+UNH+1+ORDERS:D:96A:UN'BGM+220+PO1+9'UNT+3+1'\
+UNH+2+ORDERS:D:96A:UN'BGM+220+PO2+9'UNT+9+2'UNZ+2+REF1'";
+
+        let lines = contrl(&ContrlGenerator::new("ACK1"), data);
+        assert_eq!(lines[1], "UCI+REF1+SENDER+RECEIVER+7'");
+        assert_eq!(lines[2], "UCM+1+ORDERS:D:96A:UN+7'");
+        assert_eq!(lines[3], "UCM+2+ORDERS:D:96A:UN+4+29+UNT+1'");
+    }
+
+    #[test]
+    fn test_rejects_interchange_with_control_reference_mismatch() {
+        let data = b"UNB+UNOA:3+SENDER+RECEIVER+200101:1200+REF1'\
+UNH+1+ORDERS:D:96A:UN'BGM+220+PO1+9'UNT+3+1'UNZ+1+REF2'";
+
+        let lines = contrl(&ContrlGenerator::new("ACK1"), data);
+        assert_eq!(
+            lines,
+            vec![
+                "UNH+ACK1+CONTRL:D:3:UN'",
+                "UCI+REF1+SENDER+RECEIVER+4+28+UNZ+2'",
+                "UNT+3+ACK1'",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_points_at_erroneous_segments_and_elements() {
+        let data = b"UNB+UNOA:3+SENDER+RECEIVER+200101:1200+REF1'\
+UNH+1+ORDERS:D:96A:UN'BGM+220+PO1+9'DTM+137:2020:102'NAD+XX'UNT+5+1'UNZ+1+REF1'";
+        let generator = ContrlGenerator::new("ACK1").with_segment_errors(vec![
+            SegmentError::new("1", 4, SyntaxErrorCode::ValueNotSupported).at_element(1, None),
+            SegmentError::new("1", 3, SyntaxErrorCode::DataElementTooShort).at_element(1, Some(2)),
+            SegmentError::new("1", 4, SyntaxErrorCode::Missing),
+        ]);
+
+        let lines = contrl(&generator, data);
+        assert_eq!(
+            lines,
+            vec![
+                "UNH+ACK1+CONTRL:D:3:UN'",
+                "UCI+REF1+SENDER+RECEIVER+7'",
+                "UCM+1+ORDERS:D:96A:UN+4'",
+                "UCS+3'",
+                "UCD+40+1:2'",
+                "UCS+4+13'",
+                "UCD+14+1'",
+                "UNT+8+ACK1'",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reports_functional_groups() {
+        let data = b"UNB+UNOA:3+SENDER+RECEIVER+200101:1200+REF1'\
+UNG+INVOIC+APP1:ZZ+APP2:ZZ+200101:1200+G1+UN+D:96A'\
+UNH+1+INVOIC:D:96A:UN'BGM+380+INV1+9'UNT+3+1'UNE+2+G1'\
+UNG+DESADV+APP1+APP2+200101:1200+G2+UN+D:96A'\
+UNH+2+DESADV:D:96A:UN'BGM+351+DES1+9'UNT+3+2'UNE+1+G2'UNZ+2+REF1'";
+
+        let lines = contrl(&ContrlGenerator::new("ACK1"), data);
+        assert_eq!(
+            lines,
+            vec![
+                "UNH+ACK1+CONTRL:D:3:UN'",
+                "UCI+REF1+SENDER+RECEIVER+7'",
+                "UCF+G1+APP1:ZZ+APP2:ZZ+4+29+UNE+1'",
+                "UCF+G2+APP1+APP2+7'",
+                "UCM+2+DESADV:D:96A:UN+7'",
+                "UNT+6+ACK1'",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_contrl_document_metadata_and_missing_interchange() {
+        let generator = ContrlGenerator::new("ACK1");
+        let data = b"UNB+UNOA:3+SENDER+RECEIVER+200101:1200+REF1'UNZ+0+REF1'";
+        let document = edifact_document(data);
+        let contrl = generator
+            .generate(&document, &ValidationReport::new())
+            .unwrap();
+        assert_eq!(contrl.metadata.doc_type.as_deref(), Some("CONTRL"));
+        assert_eq!(contrl.metadata.interchange_ref.as_deref(), Some("REF1"));
+        assert_eq!(contrl.metadata.message_refs, vec!["ACK1"]);
+
+        assert!(
+            generator
+                .generate(&EdifactDocument::new(), &ValidationReport::new())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_syntax_error_codes_for_envelope_errors() {
+        assert_eq!(
+            SyntaxErrorCode::from(ErrorKind::MissingTrailer).code(),
+            "13"
+        );
+        assert_eq!(
+            SyntaxErrorCode::from(ErrorKind::MismatchedControlReference).code(),
+            "28"
+        );
+        assert_eq!(
+            SyntaxErrorCode::from(ErrorKind::IncorrectSegmentCount).code(),
+            "29"
+        );
+        assert_eq!(
+            SyntaxErrorCode::from(ErrorKind::InvalidSyntaxIdentifier).code(),
+            "2"
+        );
+        assert_eq!(
+            SyntaxErrorCode::from(ErrorKind::UngroupedMessage).code(),
+            "30"
+        );
+    }
+}
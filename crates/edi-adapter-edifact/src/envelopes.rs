@@ -1,4 +1,4 @@
-//! EDIFACT envelope handling (UNB/UNZ, UNH/UNT, UNA)
+//! EDIFACT envelope handling (UNB/UNZ, UNG/UNE, UNH/UNT, UNA)
 //!
 //! This module provides parsing, generation, and validation of EDIFACT envelope segments.
 
@@ -18,7 +18,40 @@ pub struct InterchangeEnvelope {
     pub unb: UnbSegment,
     /// UNZ segment (interchange trailer) - None until parsed/generated
     pub unz: Option<UnzSegment>,
-    /// Messages within this interchange
+    /// Messages directly within this interchange (not in a functional group)
+    pub messages: Vec<MessageEnvelope>,
+    /// Functional groups within this interchange
+    pub groups: Vec<GroupEnvelope>,
+}
+
+impl InterchangeEnvelope {
+    /// Number of entries UNZ is expected to count: functional groups when the
+    /// interchange uses them, otherwise messages
+    pub fn control_count(&self) -> usize {
+        if self.groups.is_empty() {
+            self.messages.len()
+        } else {
+            self.groups.len()
+        }
+    }
+
+    /// All messages in the interchange, including those inside functional
+    /// groups, in order of appearance
+    pub fn all_messages(&self) -> impl Iterator<Item = &MessageEnvelope> {
+        self.messages
+            .iter()
+            .chain(self.groups.iter().flat_map(|group| group.messages.iter()))
+    }
+}
+
+/// Functional group envelope (UNG/UNE) containing messages
+#[derive(Debug, Clone)]
+pub struct GroupEnvelope {
+    /// UNG segment (functional group header)
+    pub ung: UngSegment,
+    /// UNE segment (functional group trailer) - None until parsed/generated
+    pub une: Option<UneSegment>,
+    /// Messages within this functional group
     pub messages: Vec<MessageEnvelope>,
 }
 
@@ -44,6 +77,15 @@ pub struct SyntaxIdentifier {
     pub service_code_list: Option<String>,
     /// Character encoding (optional, Coded character set)
     pub encoding: Option<String>,
+    /// Syntax release number (optional, syntax version 4, e.g. "01")
+    pub release: Option<String>,
+}
+
+impl SyntaxIdentifier {
+    /// Numeric syntax version, if the version field is a number
+    pub fn version_number(&self) -> Option<u8> {
+        self.version.parse().ok()
+    }
 }
 
 /// Party identifier (sender or receiver)
@@ -62,14 +104,35 @@ pub struct PartyId {
 /// Date and time for interchange
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub struct DateTime {
-    /// Date in YYMMDD or CCYYMMDD format
+    /// Date in YYMMDD (syntax versions 1-3) or CCYYMMDD (version 4) format
     pub date: String,
-    /// Time in HHMM or HHMMSS format
+    /// Time in HHMM format
     pub time: String,
 }
 
+impl DateTime {
+    /// Returns `true` if the date and time use the format required by the
+    /// syntax version: YYMMDD up to version 3, CCYYMMDD from version 4
+    pub fn is_valid_for_version(&self, version: u8) -> bool {
+        let date_len = if version >= 4 { 8 } else { 6 };
+        let is_digits = |value: &str, len: usize| {
+            value.len() == len && value.bytes().all(|b| b.is_ascii_digit())
+        };
+        is_digits(&self.date, date_len) && is_digits(&self.time, 4)
+    }
+}
+
+/// Recipient reference/password (S005)
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct RecipientReference {
+    /// Recipient reference/password
+    pub reference: String,
+    /// Recipient reference/password qualifier (optional)
+    pub qualifier: Option<String>,
+}
+
 /// UNB - Interchange Header segment
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct UnbSegment {
     /// Syntax identifier
     pub syntax_identifier: SyntaxIdentifier,
@@ -81,6 +144,8 @@ pub struct UnbSegment {
     pub datetime: DateTime,
     /// Interchange control reference
     pub control_ref: String,
+    /// Recipient reference/password (optional, S005)
+    pub recipient_ref: Option<RecipientReference>,
     /// Application reference (optional)
     pub application_ref: Option<String>,
     /// Processing priority code (optional, e.g., "A" for highest priority)
@@ -93,6 +158,24 @@ pub struct UnbSegment {
     pub test_indicator: Option<String>,
 }
 
+impl From<&UnbSegment> for edi_ir::InterchangeInfo {
+    fn from(unb: &UnbSegment) -> Self {
+        let party = |party: &PartyId| edi_ir::PartyInfo {
+            id: party.id.clone(),
+            qualifier: party.qualifier.clone(),
+        };
+        Self {
+            sender: party(&unb.sender),
+            recipient: party(&unb.receiver),
+            preparation_date: unb.datetime.date.clone(),
+            preparation_time: unb.datetime.time.clone(),
+            control_ref: unb.control_ref.clone(),
+            application_ref: unb.application_ref.clone(),
+            test_indicator: unb.test_indicator.clone(),
+        }
+    }
+}
+
 /// UNZ - Interchange Trailer segment
 #[derive(Debug, Clone)]
 pub struct UnzSegment {
@@ -102,6 +185,47 @@ pub struct UnzSegment {
     pub control_ref: String,
 }
 
+/// Message version (S008 composite in UNG)
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct MessageVersion {
+    /// Message version number (e.g., "D")
+    pub version: String,
+    /// Message release number (e.g., "96A")
+    pub release: String,
+    /// Association assigned code (optional)
+    pub association_code: Option<String>,
+}
+
+/// UNG - Functional Group Header segment
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UngSegment {
+    /// Message group identification (e.g., "INVOIC")
+    pub group_id: String,
+    /// Application sender identification
+    pub sender: PartyId,
+    /// Application recipient identification
+    pub recipient: PartyId,
+    /// Date and time of preparation
+    pub datetime: DateTime,
+    /// Group reference number
+    pub control_ref: String,
+    /// Controlling agency (optional, e.g., "UN")
+    pub agency: Option<String>,
+    /// Message version (optional)
+    pub message_version: Option<MessageVersion>,
+    /// Application password (optional)
+    pub password: Option<String>,
+}
+
+/// UNE - Functional Group Trailer segment
+#[derive(Debug, Clone)]
+pub struct UneSegment {
+    /// Count of messages in the functional group
+    pub message_count: usize,
+    /// Group reference number (must match UNG)
+    pub control_ref: String,
+}
+
 /// Message type identifier (composite in UNH)
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct MessageTypeIdentifier {
@@ -117,6 +241,33 @@ pub struct MessageTypeIdentifier {
     pub association_code: Option<String>,
 }
 
+impl std::str::FromStr for MessageTypeIdentifier {
+    type Err = Error;
+
+    /// Parse the S009 notation `TYPE:VERSION:RELEASE:AGENCY[:ASSOCIATION]`
+    /// (e.g. "ORDERS:D:96A:UN:EAN008")
+    fn from_str(s: &str) -> Result<Self> {
+        let parts: Vec<&str> = s.split(':').collect();
+        if parts.len() < 4 || parts.len() > 5 || parts[..4].iter().any(|p| p.is_empty()) {
+            return Err(Error::Envelope(format!(
+                "Message type identifier '{}' must be TYPE:VERSION:RELEASE:AGENCY[:ASSOCIATION]",
+                s
+            )));
+        }
+
+        Ok(Self {
+            message_type: parts[0].to_string(),
+            version: parts[1].to_string(),
+            release: parts[2].to_string(),
+            agency: parts[3].to_string(),
+            association_code: parts
+                .get(4)
+                .filter(|code| !code.is_empty())
+                .map(|code| code.to_string()),
+        })
+    }
+}
+
 /// UNH - Message Header segment
 #[derive(Debug, Clone)]
 pub struct UnhSegment {
@@ -156,7 +307,7 @@ pub struct UnaSegment {
     pub decimal_point: u8,
     /// Release indicator
     pub release_character: u8,
-    /// Reserved (space character)
+    /// Repetition separator (syntax version 4), or reserved space
     pub reserved: u8,
     /// Segment terminator
     pub segment_terminator: u8,
@@ -183,6 +334,7 @@ impl UnaSegment {
             element: self.element_separator,
             decimal: self.decimal_point,
             release: self.release_character,
+            repetition: (self.reserved != b' ').then_some(self.reserved),
             segment: self.segment_terminator,
         }
     }
@@ -194,7 +346,7 @@ impl UnaSegment {
             element_separator: sep.element,
             decimal_point: sep.decimal,
             release_character: sep.release,
-            reserved: b' ',
+            reserved: sep.repetition.unwrap_or(b' '),
             segment_terminator: sep.segment,
         }
     }
@@ -222,6 +374,7 @@ impl Default for SyntaxIdentifier {
             version: "3".to_string(),
             service_code_list: None,
             encoding: None,
+            release: None,
         }
     }
 }
@@ -274,31 +427,29 @@ pub fn parse_unb(segment: &Segment) -> Result<UnbSegment> {
     // Parse control reference (element 4 - simple)
     let control_ref = parse_simple_string(&segment.elements[4], "control reference")?;
 
-    // Parse optional fields
-    let application_ref = segment
-        .elements
-        .get(5)
-        .and_then(|e| parse_simple_string(e, "application reference").ok());
-
-    let priority = segment
-        .elements
-        .get(6)
-        .and_then(|e| parse_simple_string(e, "priority").ok());
-
-    let ack_request = segment
-        .elements
-        .get(7)
-        .and_then(|e| parse_simple_string(e, "ack request").ok());
+    // Parse optional fields. Syntax version 4 follows the ISO 9735-2 layout
+    // with the recipient reference (S005) before the application reference;
+    // earlier versions are read without S005.
+    let (recipient_ref, offset) = if syntax_identifier.version_number() >= Some(4) {
+        let recipient_ref = segment.elements.get(5).and_then(parse_recipient_reference);
+        (recipient_ref, 6)
+    } else {
+        (None, 5)
+    };
 
-    let comms_agreement_id = segment
-        .elements
-        .get(8)
-        .and_then(|e| parse_simple_string(e, "comms agreement").ok());
+    let optional = |index: usize, field_name: &str| {
+        segment
+            .elements
+            .get(offset + index)
+            .and_then(|e| parse_simple_string(e, field_name).ok())
+            .filter(|value| !value.is_empty())
+    };
 
-    let test_indicator = segment
-        .elements
-        .get(9)
-        .and_then(|e| parse_simple_string(e, "test indicator").ok());
+    let application_ref = optional(0, "application reference");
+    let priority = optional(1, "priority");
+    let ack_request = optional(2, "ack request");
+    let comms_agreement_id = optional(3, "comms agreement");
+    let test_indicator = optional(4, "test indicator");
 
     Ok(UnbSegment {
         syntax_identifier,
@@ -306,6 +457,7 @@ pub fn parse_unb(segment: &Segment) -> Result<UnbSegment> {
         receiver,
         datetime,
         control_ref,
+        recipient_ref,
         application_ref,
         priority,
         ack_request,
@@ -338,6 +490,76 @@ pub fn parse_unz(segment: &Segment) -> Result<UnzSegment> {
     })
 }
 
+/// Parse a UNG (Functional Group Header) segment
+pub fn parse_ung(segment: &Segment) -> Result<UngSegment> {
+    if segment.tag != "UNG" {
+        return Err(Error::Envelope(format!(
+            "Expected UNG segment, got {}",
+            segment.tag
+        )));
+    }
+
+    if segment.elements.len() < 5 {
+        return Err(Error::Envelope(
+            "UNG segment must have at least 5 elements".to_string(),
+        ));
+    }
+
+    let group_id = parse_simple_string(&segment.elements[0], "message group identification")?;
+    let sender = parse_party_id(&segment.elements[1])?;
+    let recipient = parse_party_id(&segment.elements[2])?;
+    let datetime = parse_datetime(&segment.elements[3])?;
+    let control_ref = parse_simple_string(&segment.elements[4], "group reference")?;
+
+    // Parse optional fields
+    let agency = segment
+        .elements
+        .get(5)
+        .and_then(|e| parse_simple_string(e, "controlling agency").ok())
+        .filter(|v| !v.is_empty());
+    let message_version = segment.elements.get(6).and_then(parse_message_version);
+    let password = segment
+        .elements
+        .get(7)
+        .and_then(|e| parse_simple_string(e, "application password").ok())
+        .filter(|v| !v.is_empty());
+
+    Ok(UngSegment {
+        group_id,
+        sender,
+        recipient,
+        datetime,
+        control_ref,
+        agency,
+        message_version,
+        password,
+    })
+}
+
+/// Parse a UNE (Functional Group Trailer) segment
+pub fn parse_une(segment: &Segment) -> Result<UneSegment> {
+    if segment.tag != "UNE" {
+        return Err(Error::Envelope(format!(
+            "Expected UNE segment, got {}",
+            segment.tag
+        )));
+    }
+
+    if segment.elements.len() < 2 {
+        return Err(Error::Envelope(
+            "UNE segment must have at least 2 elements".to_string(),
+        ));
+    }
+
+    let message_count = parse_simple_usize(&segment.elements[0], "message count")?;
+    let control_ref = parse_simple_string(&segment.elements[1], "group reference")?;
+
+    Ok(UneSegment {
+        message_count,
+        control_ref,
+    })
+}
+
 /// Parse a UNH (Message Header) segment
 pub fn parse_unh(segment: &Segment) -> Result<UnhSegment> {
     if segment.tag != "UNH" {
@@ -477,15 +699,20 @@ fn parse_syntax_identifier(element: &Element) -> Result<SyntaxIdentifier> {
                 .get(3)
                 .filter(|v| !v.is_empty())
                 .map(|v| String::from_utf8_lossy(v).to_string());
+            let release = comps
+                .get(4)
+                .filter(|v| !v.is_empty())
+                .map(|v| String::from_utf8_lossy(v).to_string());
 
             Ok(SyntaxIdentifier {
                 identifier,
                 version,
                 service_code_list,
                 encoding,
+                release,
             })
         }
-        Element::Simple(_) => {
+        Element::Simple(_) | Element::Repeated(_) => {
             // Simple element not valid for syntax identifier
             Err(Error::Envelope(
                 "Syntax identifier must be composite".to_string(),
@@ -533,9 +760,47 @@ fn parse_party_id(element: &Element) -> Result<PartyId> {
                 internal_qualifier: None,
             })
         }
+        Element::Repeated(_) => Err(Error::Envelope("Party ID must not repeat".to_string())),
     }
 }
 
+fn parse_recipient_reference(element: &Element) -> Option<RecipientReference> {
+    let (reference, qualifier) = match element {
+        Element::Simple(val) => (val, None),
+        Element::Composite(comps) => (comps.first()?, comps.get(1)),
+        Element::Repeated(_) => return None,
+    };
+    if reference.is_empty() {
+        return None;
+    }
+    Some(RecipientReference {
+        reference: String::from_utf8_lossy(reference).to_string(),
+        qualifier: qualifier
+            .filter(|v| !v.is_empty())
+            .map(|v| String::from_utf8_lossy(v).to_string()),
+    })
+}
+
+fn parse_message_version(element: &Element) -> Option<MessageVersion> {
+    let comps = match element {
+        Element::Composite(comps) => comps.as_slice(),
+        Element::Simple(val) if !val.is_empty() => std::slice::from_ref(val),
+        _ => return None,
+    };
+    let component = |index: usize| {
+        comps
+            .get(index)
+            .filter(|v| !v.is_empty())
+            .map(|v| String::from_utf8_lossy(v).to_string())
+    };
+
+    Some(MessageVersion {
+        version: component(0).unwrap_or_default(),
+        release: component(1).unwrap_or_default(),
+        association_code: component(2),
+    })
+}
+
 fn parse_datetime(element: &Element) -> Result<DateTime> {
     match element {
         Element::Composite(comps) => {
@@ -550,7 +815,9 @@ fn parse_datetime(element: &Element) -> Result<DateTime> {
                 time: String::from_utf8_lossy(&comps[1]).to_string(),
             })
         }
-        Element::Simple(_) => Err(Error::Envelope("DateTime must be composite".to_string())),
+        Element::Simple(_) | Element::Repeated(_) => {
+            Err(Error::Envelope("DateTime must be composite".to_string()))
+        }
     }
 }
 
@@ -581,7 +848,7 @@ fn parse_message_type_identifier(element: &Element) -> Result<MessageTypeIdentif
                 association_code,
             })
         }
-        Element::Simple(_) => Err(Error::Envelope(
+        Element::Simple(_) | Element::Repeated(_) => Err(Error::Envelope(
             "Message type identifier must be composite".to_string(),
         )),
     }
@@ -619,15 +886,14 @@ pub fn generate_unb(unb: &UnbSegment, separators: &crate::syntax::Separators) ->
         unb.syntax_identifier.identifier.as_bytes().to_vec(),
         unb.syntax_identifier.version.as_bytes().to_vec(),
     ];
-    if let Some(ref scl) = unb.syntax_identifier.service_code_list {
-        syntax_comps.push(scl.as_bytes().to_vec());
-        if let Some(ref enc) = unb.syntax_identifier.encoding {
-            syntax_comps.push(enc.as_bytes().to_vec());
-        }
-    } else if let Some(ref enc) = unb.syntax_identifier.encoding {
-        syntax_comps.push(vec![]); // Empty service code list
-        syntax_comps.push(enc.as_bytes().to_vec());
-    }
+    let optional_syntax = [
+        &unb.syntax_identifier.service_code_list,
+        &unb.syntax_identifier.encoding,
+        &unb.syntax_identifier.release,
+    ];
+    push_optional(&mut syntax_comps, &optional_syntax, Vec::new, |value| {
+        value.as_bytes().to_vec()
+    });
     elements.push(Element::Composite(syntax_comps));
 
     // Sender (composite)
@@ -665,36 +931,55 @@ pub fn generate_unb(unb: &UnbSegment, separators: &crate::syntax::Separators) ->
     // Control reference
     elements.push(Element::Simple(unb.control_ref.as_bytes().to_vec()));
 
-    // Optional fields
-    if let Some(ref ar) = unb.application_ref {
-        elements.push(Element::Simple(ar.as_bytes().to_vec()));
-    } else {
-        return create_segment("UNB", elements, separators);
-    }
-
-    if let Some(ref p) = unb.priority {
-        elements.push(Element::Simple(p.as_bytes().to_vec()));
-    } else {
-        return create_segment("UNB", elements, separators);
-    }
-
-    if let Some(ref ar) = unb.ack_request {
-        elements.push(Element::Simple(ar.as_bytes().to_vec()));
-    } else {
-        return create_segment("UNB", elements, separators);
-    }
+    // Optional fields; gaps before a present field are left empty
+    let recipient_ref = unb.recipient_ref.as_ref().map(|recipient| {
+        let mut comps = vec![recipient.reference.as_bytes().to_vec()];
+        if let Some(ref qualifier) = recipient.qualifier {
+            comps.push(qualifier.as_bytes().to_vec());
+        }
+        Element::Composite(comps)
+    });
+    let simple = |value: &Option<String>| {
+        value
+            .as_ref()
+            .map(|value| Element::Simple(value.as_bytes().to_vec()))
+    };
+    let mut optional = Vec::new();
+    if unb.syntax_identifier.version_number() >= Some(4) {
+        optional.push(recipient_ref);
+    }
+    optional.extend([
+        simple(&unb.application_ref),
+        simple(&unb.priority),
+        simple(&unb.ack_request),
+        simple(&unb.comms_agreement_id),
+        simple(&unb.test_indicator),
+    ]);
+    let optional: Vec<&Option<Element>> = optional.iter().collect();
+    push_optional(
+        &mut elements,
+        &optional,
+        || Element::Simple(Vec::new()),
+        Clone::clone,
+    );
 
-    if let Some(ref cai) = unb.comms_agreement_id {
-        elements.push(Element::Simple(cai.as_bytes().to_vec()));
-    } else {
-        return create_segment("UNB", elements, separators);
-    }
+    create_segment("UNB", elements, separators)
+}
 
-    if let Some(ref ti) = unb.test_indicator {
-        elements.push(Element::Simple(ti.as_bytes().to_vec()));
+/// Append optional trailing values, filling gaps before the last present
+/// value with `empty()`
+fn push_optional<T, V>(
+    target: &mut Vec<T>,
+    values: &[&Option<V>],
+    empty: impl Fn() -> T,
+    convert: impl Fn(&V) -> T,
+) {
+    let Some(last) = values.iter().rposition(|value| value.is_some()) else {
+        return;
+    };
+    for value in &values[..=last] {
+        target.push(value.as_ref().map_or_else(&empty, &convert));
     }
-
-    create_segment("UNB", elements, separators)
 }
 
 /// Generate a UNZ segment from UnzSegment
@@ -706,6 +991,65 @@ pub fn generate_unz(unz: &UnzSegment, separators: &crate::syntax::Separators) ->
     create_segment("UNZ", elements, separators)
 }
 
+/// Generate a UNG segment from UngSegment
+pub fn generate_ung(ung: &UngSegment, separators: &crate::syntax::Separators) -> Segment {
+    let party = |party: &PartyId| {
+        let mut comps = vec![party.id.as_bytes().to_vec()];
+        if let Some(ref q) = party.qualifier {
+            comps.push(q.as_bytes().to_vec());
+        }
+        Element::Composite(comps)
+    };
+
+    let mut elements = vec![
+        Element::Simple(ung.group_id.as_bytes().to_vec()),
+        party(&ung.sender),
+        party(&ung.recipient),
+        Element::Composite(vec![
+            ung.datetime.date.as_bytes().to_vec(),
+            ung.datetime.time.as_bytes().to_vec(),
+        ]),
+        Element::Simple(ung.control_ref.as_bytes().to_vec()),
+    ];
+
+    // Optional fields; gaps before a present field are left empty
+    let agency = ung
+        .agency
+        .as_ref()
+        .map(|agency| Element::Simple(agency.as_bytes().to_vec()));
+    let message_version = ung.message_version.as_ref().map(|version| {
+        let mut comps = vec![
+            version.version.as_bytes().to_vec(),
+            version.release.as_bytes().to_vec(),
+        ];
+        if let Some(ref ac) = version.association_code {
+            comps.push(ac.as_bytes().to_vec());
+        }
+        Element::Composite(comps)
+    });
+    let password = ung
+        .password
+        .as_ref()
+        .map(|password| Element::Simple(password.as_bytes().to_vec()));
+    push_optional(
+        &mut elements,
+        &[&agency, &message_version, &password],
+        || Element::Simple(Vec::new()),
+        Clone::clone,
+    );
+
+    create_segment("UNG", elements, separators)
+}
+
+/// Generate a UNE segment from UneSegment
+pub fn generate_une(une: &UneSegment, separators: &crate::syntax::Separators) -> Segment {
+    let elements = vec![
+        Element::Simple(une.message_count.to_string().as_bytes().to_vec()),
+        Element::Simple(une.control_ref.as_bytes().to_vec()),
+    ];
+    create_segment("UNE", elements, separators)
+}
+
 /// Generate a UNH segment from UnhSegment
 pub fn generate_unh(unh: &UnhSegment, separators: &crate::syntax::Separators) -> Segment {
     let mut elements = Vec::new();
@@ -776,6 +1120,8 @@ fn create_segment(
         tag: tag.to_string(),
         elements,
         position: Position::default(),
+        raw: None,
+        spans: None,
     }
 }
 
@@ -799,17 +1145,23 @@ pub fn validate_interchange(interchange: &InterchangeEnvelope) -> Result<()> {
         )));
     }
 
-    // Check message count
-    if unz.message_count != interchange.messages.len() {
+    // Check message (or functional group) count
+    if unz.message_count != interchange.control_count() {
         return Err(Error::Envelope(format!(
             "Message count mismatch: UNZ says {}, actual count is {}",
             unz.message_count,
-            interchange.messages.len()
+            interchange.control_count()
         )));
     }
 
+    // Validate each functional group
+    for (i, group) in interchange.groups.iter().enumerate() {
+        validate_group(group)
+            .map_err(|e| Error::Envelope(format!("Group {} validation failed: {}", i + 1, e)))?;
+    }
+
     // Validate each message
-    for (i, message) in interchange.messages.iter().enumerate() {
+    for (i, message) in interchange.all_messages().enumerate() {
         validate_message(message)
             .map_err(|e| Error::Envelope(format!("Message {} validation failed: {}", i + 1, e)))?;
     }
@@ -817,6 +1169,34 @@ pub fn validate_interchange(interchange: &InterchangeEnvelope) -> Result<()> {
     Ok(())
 }
 
+/// Validate a functional group envelope
+pub fn validate_group(group: &GroupEnvelope) -> Result<()> {
+    // Check UNE exists
+    let une = group
+        .une
+        .as_ref()
+        .ok_or_else(|| Error::Envelope("Missing UNE segment".to_string()))?;
+
+    // Check group reference matching
+    if une.control_ref != group.ung.control_ref {
+        return Err(Error::Envelope(format!(
+            "Group reference mismatch: UNG='{}', UNE='{}'",
+            group.ung.control_ref, une.control_ref
+        )));
+    }
+
+    // Check message count
+    if une.message_count != group.messages.len() {
+        return Err(Error::Envelope(format!(
+            "Message count mismatch: UNE says {}, actual count is {}",
+            une.message_count,
+            group.messages.len()
+        )));
+    }
+
+    Ok(())
+}
+
 /// Validate a message envelope
 pub fn validate_message(message: &MessageEnvelope) -> Result<()> {
     // Check UNT exists
@@ -1000,6 +1380,8 @@ impl ControlNumberGenerator for MemoryControlNumberGenerator {
 pub enum EnvelopeType {
     /// Interchange envelope (UNB/UNZ)
     Interchange,
+    /// Functional group envelope (UNG/UNE)
+    Group,
     /// Message envelope (UNH/UNT)
     Message,
 }
@@ -1008,6 +1390,7 @@ impl std::fmt::Display for EnvelopeType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             EnvelopeType::Interchange => write!(f, "Interchange"),
+            EnvelopeType::Group => write!(f, "Group"),
             EnvelopeType::Message => write!(f, "Message"),
         }
     }
@@ -1028,10 +1411,14 @@ pub enum ErrorKind {
     IncorrectMessageCount,
     /// Invalid syntax identifier in UNB
     InvalidSyntaxIdentifier,
+    /// UNB date/time not in the format required by the syntax version
+    InvalidDateTime,
     /// Invalid message type in UNH
     InvalidMessageType,
     /// Message outside of interchange
     OrphanedMessage,
+    /// Message outside of a functional group in an interchange that uses them
+    UngroupedMessage,
     /// Segment outside of message
     OrphanedSegment,
 }
@@ -1045,8 +1432,10 @@ impl std::fmt::Display for ErrorKind {
             ErrorKind::IncorrectSegmentCount => write!(f, "incorrect segment count"),
             ErrorKind::IncorrectMessageCount => write!(f, "incorrect message count"),
             ErrorKind::InvalidSyntaxIdentifier => write!(f, "invalid syntax identifier"),
+            ErrorKind::InvalidDateTime => write!(f, "invalid date/time"),
             ErrorKind::InvalidMessageType => write!(f, "invalid message type"),
             ErrorKind::OrphanedMessage => write!(f, "message outside of interchange"),
+            ErrorKind::UngroupedMessage => write!(f, "message outside of functional group"),
             ErrorKind::OrphanedSegment => write!(f, "segment outside of message"),
         }
     }
@@ -1063,10 +1452,21 @@ pub struct ValidationError {
     pub position: Position,
     /// Kind of error
     pub error_kind: ErrorKind,
-    /// Expected value (if applicable)
-    pub expected: Option<String>,
-    /// Actual value (if applicable)
-    pub actual: Option<String>,
+    /// Expected and actual values (if applicable), boxed to keep the error
+    /// small enough to return by value
+    pub mismatch: Option<Box<ValueMismatch>>,
+    /// Control reference of the envelope the error was found in (if known);
+    /// boxed to keep the error small enough to return by value
+    pub control_ref: Option<Box<str>>,
+}
+
+/// Expected and actual values of an envelope mismatch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValueMismatch {
+    /// Expected value
+    pub expected: String,
+    /// Actual value
+    pub actual: String,
 }
 
 impl ValidationError {
@@ -1082,15 +1482,37 @@ impl ValidationError {
             envelope_type,
             position,
             error_kind,
-            expected: None,
-            actual: None,
+            mismatch: None,
+            control_ref: None,
         }
     }
 
     /// Add expected and actual values
     pub fn with_values(mut self, expected: impl Into<String>, actual: impl Into<String>) -> Self {
-        self.expected = Some(expected.into());
-        self.actual = Some(actual.into());
+        self.mismatch = Some(Box::new(ValueMismatch {
+            expected: expected.into(),
+            actual: actual.into(),
+        }));
+        self
+    }
+
+    /// Expected value (if applicable)
+    pub fn expected(&self) -> Option<&str> {
+        self.mismatch
+            .as_deref()
+            .map(|mismatch| mismatch.expected.as_str())
+    }
+
+    /// Actual value (if applicable)
+    pub fn actual(&self) -> Option<&str> {
+        self.mismatch
+            .as_deref()
+            .map(|mismatch| mismatch.actual.as_str())
+    }
+
+    /// Set the control reference of the envelope the error was found in
+    pub fn with_control_ref(mut self, control_ref: impl Into<String>) -> Self {
+        self.control_ref = Some(control_ref.into().into_boxed_str());
         self
     }
 }
@@ -1102,8 +1524,12 @@ impl std::fmt::Display for ValidationError {
             "{} validation error at line {}: {} (kind: {:?})",
             self.envelope_type, self.position.line, self.message, self.error_kind
         )?;
-        if let (Some(expected), Some(actual)) = (&self.expected, &self.actual) {
-            write!(f, " [expected: {}, actual: {}]", expected, actual)?;
+        if let Some(mismatch) = &self.mismatch {
+            write!(
+                f,
+                " [expected: {}, actual: {}]",
+                mismatch.expected, mismatch.actual
+            )?;
         }
         Ok(())
     }
@@ -1222,7 +1648,7 @@ impl EdifactDocument {
     pub fn message_count(&self) -> usize {
         self.interchange
             .as_ref()
-            .map(|i| i.messages.len())
+            .map(|i| i.all_messages().count())
             .unwrap_or(0)
     }
 }
@@ -1236,7 +1662,8 @@ impl Default for EdifactDocument {
 /// Validator for EDIFACT envelope pairings
 ///
 /// Validates that:
-/// - UNB/UNZ pairs match (control reference, message count)
+/// - UNB/UNZ pairs match (control reference, message or group count)
+/// - UNG/UNE pairs match (group reference, message count)
 /// - UNH/UNT pairs match (message reference, segment count)
 /// - Messages are properly nested within interchanges
 /// - Segments are properly nested within messages
@@ -1269,21 +1696,40 @@ impl EnvelopeValidator {
         if let Some(ref interchange) = document.interchange {
             match self.validate_interchange(interchange) {
                 Ok(()) => {}
-                Err(e) => report.add_error(e),
+                Err(e) => report.add_error(e.with_control_ref(&interchange.unb.control_ref)),
+            }
+            if let Some(warning) = self.validate_datetime(&interchange.unb) {
+                report.add_warning(warning);
+            }
+
+            // Validate each functional group
+            for (idx, group) in interchange.groups.iter().enumerate() {
+                if let Err(e) = self.validate_group(group) {
+                    let error = ValidationError {
+                        message: format!("Group {} validation failed: {}", idx + 1, e.message),
+                        envelope_type: EnvelopeType::Group,
+                        ..e
+                    }
+                    .with_control_ref(&group.ung.control_ref);
+                    report.add_error(error);
+                }
             }
 
             // Validate each message
-            for (idx, message) in interchange.messages.iter().enumerate() {
+            for (idx, message) in interchange.all_messages().enumerate() {
                 match self.validate_message(message) {
                     Ok(()) => {}
                     Err(e) => {
-                        let error = ValidationError::new(
-                            format!("Message {} validation failed: {}", idx + 1, e.message),
-                            EnvelopeType::Message,
-                            e.position,
-                            e.error_kind,
-                        )
-                        .with_values(e.expected.unwrap_or_default(), e.actual.unwrap_or_default());
+                        let error = ValidationError {
+                            message: format!(
+                                "Message {} validation failed: {}",
+                                idx + 1,
+                                e.message
+                            ),
+                            envelope_type: EnvelopeType::Message,
+                            ..e
+                        }
+                        .with_control_ref(&message.unh.message_ref);
                         report.add_error(error);
                     }
                 }
@@ -1305,7 +1751,8 @@ impl EnvelopeValidator {
     /// Checks:
     /// - UNZ exists
     /// - Control references match
-    /// - Message count matches actual
+    /// - Message count (or functional group count) matches actual
+    /// - Messages are not mixed with functional groups
     pub fn validate_interchange(
         &self,
         interchange: &InterchangeEnvelope,
@@ -1334,22 +1781,37 @@ impl EnvelopeValidator {
             .with_values(&interchange.unb.control_ref, &unz.control_ref));
         }
 
-        // Check message count
-        if unz.message_count != interchange.messages.len() {
+        // Messages must either all be grouped or none of them
+        if !interchange.groups.is_empty() && !interchange.messages.is_empty() {
             return Err(ValidationError::new(
                 format!(
-                    "Message count mismatch: UNZ declares {}, actual count is {}",
-                    unz.message_count,
+                    "Interchange mixes functional groups with {} ungrouped message(s)",
                     interchange.messages.len()
                 ),
                 EnvelopeType::Interchange,
                 Position::default(),
+                ErrorKind::UngroupedMessage,
+            ));
+        }
+
+        // Check message count (group count when functional groups are used)
+        let actual_count = interchange.control_count();
+        if unz.message_count != actual_count {
+            let counted = if interchange.groups.is_empty() {
+                "Message"
+            } else {
+                "Group"
+            };
+            return Err(ValidationError::new(
+                format!(
+                    "{} count mismatch: UNZ declares {}, actual count is {}",
+                    counted, unz.message_count, actual_count
+                ),
+                EnvelopeType::Interchange,
+                Position::default(),
                 ErrorKind::IncorrectMessageCount,
             )
-            .with_values(
-                unz.message_count.to_string(),
-                interchange.messages.len().to_string(),
-            ));
+            .with_values(unz.message_count.to_string(), actual_count.to_string()));
         }
 
         // Validate syntax identifier
@@ -1358,6 +1820,64 @@ impl EnvelopeValidator {
         Ok(())
     }
 
+    /// Validate UNG/UNE pairing for a functional group
+    ///
+    /// Checks:
+    /// - UNE exists
+    /// - Group references match
+    /// - Message count matches actual
+    pub fn validate_group(
+        &self,
+        group: &GroupEnvelope,
+    ) -> std::result::Result<(), ValidationError> {
+        // Check UNE exists
+        let une = group.une.as_ref().ok_or_else(|| {
+            ValidationError::new(
+                format!(
+                    "Missing UNE segment for group '{}' (functional group trailer)",
+                    group.ung.control_ref
+                ),
+                EnvelopeType::Group,
+                Position::default(),
+                ErrorKind::MissingTrailer,
+            )
+        })?;
+
+        // Check group reference matching
+        if une.control_ref != group.ung.control_ref {
+            return Err(ValidationError::new(
+                format!(
+                    "Group reference mismatch: UNG='{}', UNE='{}'",
+                    group.ung.control_ref, une.control_ref
+                ),
+                EnvelopeType::Group,
+                Position::default(),
+                ErrorKind::MismatchedControlReference,
+            )
+            .with_values(&group.ung.control_ref, &une.control_ref));
+        }
+
+        // Check message count
+        if une.message_count != group.messages.len() {
+            return Err(ValidationError::new(
+                format!(
+                    "Message count mismatch: UNE declares {}, actual count is {}",
+                    une.message_count,
+                    group.messages.len()
+                ),
+                EnvelopeType::Group,
+                Position::default(),
+                ErrorKind::IncorrectMessageCount,
+            )
+            .with_values(
+                une.message_count.to_string(),
+                group.messages.len().to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
     /// Validate UNH/UNT pairing for a message
     ///
     /// Checks:
@@ -1429,8 +1949,21 @@ impl EnvelopeValidator {
         header: &Segment,
         trailer: &Segment,
     ) -> std::result::Result<(), Error> {
-        // Extract control reference from header (UNB.0020 or UNH.0062)
+        // Extract control reference from header (UNB.0020, UNG.0048 or UNH.0062)
         let header_ref = match header.tag.as_str() {
+            "UNG" => header
+                .elements
+                .get(4)
+                .ok_or_else(|| Error::Envelope("UNG missing group reference element".to_string()))
+                .and_then(|e| match e {
+                    Element::Simple(v) => Ok(String::from_utf8_lossy(v).to_string()),
+                    Element::Composite(c) if !c.is_empty() => {
+                        Ok(String::from_utf8_lossy(&c[0]).to_string())
+                    }
+                    _ => Err(Error::Envelope(
+                        "UNG group reference has unexpected format".to_string(),
+                    )),
+                }),
             "UNB" => header
                 .elements
                 .get(4)
@@ -1463,8 +1996,21 @@ impl EnvelopeValidator {
             ))),
         }?;
 
-        // Extract control reference from trailer (UNZ.0036 or UNT.0062)
+        // Extract control reference from trailer (UNZ.0020, UNE.0048 or UNT.0062)
         let trailer_ref = match trailer.tag.as_str() {
+            "UNE" => trailer
+                .elements
+                .get(1)
+                .ok_or_else(|| Error::Envelope("UNE missing group reference element".to_string()))
+                .and_then(|e| match e {
+                    Element::Simple(v) => Ok(String::from_utf8_lossy(v).to_string()),
+                    Element::Composite(c) if !c.is_empty() => {
+                        Ok(String::from_utf8_lossy(&c[0]).to_string())
+                    }
+                    _ => Err(Error::Envelope(
+                        "UNE group reference has unexpected format".to_string(),
+                    )),
+                }),
             "UNZ" => trailer
                 .elements
                 .get(1)
@@ -1564,6 +2110,7 @@ impl EnvelopeValidator {
     ) -> std::result::Result<(), ValidationError> {
         let valid_identifiers = [
             "UNOA", "UNOB", "UNOC", "UNOD", "UNOE", "UNOF", "UNOG", "UNOH", "UNOI", "UNOJ", "UNOK",
+            "UNOW", "UNOX", "UNOY",
         ];
 
         if !valid_identifiers.contains(&syntax.identifier.as_str()) {
@@ -1590,6 +2137,32 @@ impl EnvelopeValidator {
         Ok(())
     }
 
+    /// Check the UNB date/time format for the declared syntax version.
+    ///
+    /// A date in the other version's format is still readable, so it is
+    /// reported as a warning rather than failing the interchange.
+    fn validate_datetime(&self, unb: &UnbSegment) -> Option<ValidationWarning> {
+        let version = unb.syntax_identifier.version_number()?;
+
+        // Only check the format of a date that was actually provided
+        if unb.datetime.date.is_empty() || unb.datetime.is_valid_for_version(version) {
+            return None;
+        }
+        let expected = if version >= 4 {
+            "CCYYMMDD:HHMM"
+        } else {
+            "YYMMDD:HHMM"
+        };
+        Some(ValidationWarning::new(
+            format!(
+                "Invalid date/time '{}:{}' for syntax version {}, expected {}",
+                unb.datetime.date, unb.datetime.time, version, expected
+            ),
+            EnvelopeType::Interchange,
+            Position::default(),
+        ))
+    }
+
     /// Validate message type identifier
     fn validate_message_type(
         &self,
@@ -1706,6 +2279,108 @@ mod tests {
         assert_eq!(unb.test_indicator, Some("1".to_string()));
     }
 
+    #[test]
+    fn test_parse_unb_version_4_layout() {
+        let data = b"UNB+UNOY:4:::01+SENDER:14+RECEIVER:14+20240131:0930+REF1+PASSWORD:AA+APPREF+A+1+AGREEMENT+1'";
+        let mut parser = SegmentParser::new(data, "test");
+        let segment = parser.next_segment().unwrap().unwrap();
+
+        let unb = parse_unb(&segment).unwrap();
+        assert_eq!(unb.syntax_identifier.version_number(), Some(4));
+        assert_eq!(unb.syntax_identifier.release, Some("01".to_string()));
+        assert_eq!(unb.datetime.date, "20240131");
+        assert_eq!(
+            unb.recipient_ref,
+            Some(RecipientReference {
+                reference: "PASSWORD".to_string(),
+                qualifier: Some("AA".to_string()),
+            })
+        );
+        assert_eq!(unb.application_ref, Some("APPREF".to_string()));
+        assert_eq!(unb.priority, Some("A".to_string()));
+        assert_eq!(unb.ack_request, Some("1".to_string()));
+        assert_eq!(unb.comms_agreement_id, Some("AGREEMENT".to_string()));
+        assert_eq!(unb.test_indicator, Some("1".to_string()));
+    }
+
+    #[test]
+    fn test_unb_version_4_round_trip() {
+        let data = "UNB+UNOC:4:::01+SENDER:14+RECEIVER:14+20240131:0930+REF1++APPREF++1'";
+        let mut parser = SegmentParser::new(data.as_bytes(), "test");
+        let unb = parse_unb(&parser.next_segment().unwrap().unwrap()).unwrap();
+        assert_eq!(unb.recipient_ref, None);
+        assert_eq!(unb.priority, None);
+
+        let generated = generate_unb(&unb, &crate::syntax::Separators::for_syntax_version(4));
+        let mut root = edi_ir::Node::new("ROOT", edi_ir::NodeType::Root);
+        root.add_child(generated.to_node());
+        let serialized = crate::EdifactSerializer::new()
+            .serialize_document(&edi_ir::Document::new(root))
+            .unwrap();
+        assert_eq!(serialized, data);
+
+        let mut parser = SegmentParser::new(serialized.as_bytes(), "test");
+        let reparsed = parse_unb(&parser.next_segment().unwrap().unwrap()).unwrap();
+        assert_eq!(reparsed, unb);
+    }
+
+    #[test]
+    fn test_validate_unb_date_format_per_syntax_version() {
+        let validator = EnvelopeValidator::new();
+        let mut unb = UnbSegment {
+            syntax_identifier: SyntaxIdentifier {
+                identifier: "UNOC".to_string(),
+                version: "4".to_string(),
+                ..SyntaxIdentifier::default()
+            },
+            sender: PartyId::default(),
+            receiver: PartyId::default(),
+            datetime: DateTime {
+                date: "240131".to_string(),
+                time: "0930".to_string(),
+            },
+            control_ref: "1".to_string(),
+            recipient_ref: None,
+            application_ref: None,
+            priority: None,
+            ack_request: None,
+            comms_agreement_id: None,
+            test_indicator: None,
+        };
+        let interchange = |unb: &UnbSegment| EdifactDocument {
+            una: None,
+            interchange: Some(InterchangeEnvelope {
+                unb: unb.clone(),
+                unz: Some(UnzSegment {
+                    message_count: 0,
+                    control_ref: "1".to_string(),
+                }),
+                messages: vec![],
+                groups: Vec::new(),
+            }),
+        };
+
+        let report = validator.validate(&interchange(&unb)).unwrap();
+        assert!(report.is_valid, "{:?}", report.errors);
+        assert_eq!(report.warning_count(), 1);
+        assert!(
+            report.warnings[0]
+                .message
+                .contains("expected CCYYMMDD:HHMM")
+        );
+
+        unb.datetime.date = "20240131".to_string();
+        let report = validator.validate(&interchange(&unb)).unwrap();
+        assert!(report.is_valid);
+        assert_eq!(report.warning_count(), 0);
+
+        unb.syntax_identifier.version = "3".to_string();
+        let report = validator.validate(&interchange(&unb)).unwrap();
+        assert!(report.is_valid);
+        assert_eq!(report.warning_count(), 1);
+        assert!(report.warnings[0].message.contains("expected YYMMDD:HHMM"));
+    }
+
     #[test]
     fn test_parse_unz() {
         let data = b"UNZ+5+12345'";
@@ -1717,6 +2392,68 @@ mod tests {
         assert_eq!(unz.control_ref, "12345");
     }
 
+    #[test]
+    fn test_parse_ung_and_une() {
+        let data =
+            b"UNG+INVOIC+SENDER:14+RECEIVER:14+200101:1200+G1+UN+D:96A:EAN008+SECRET'UNE+2+G1'";
+        let mut parser = SegmentParser::new(data, "test");
+
+        let ung = parse_ung(&parser.next_segment().unwrap().unwrap()).unwrap();
+        assert_eq!(ung.group_id, "INVOIC");
+        assert_eq!(ung.sender.id, "SENDER");
+        assert_eq!(ung.recipient.qualifier, Some("14".to_string()));
+        assert_eq!(ung.datetime.date, "200101");
+        assert_eq!(ung.control_ref, "G1");
+        assert_eq!(ung.agency, Some("UN".to_string()));
+        assert_eq!(
+            ung.message_version,
+            Some(MessageVersion {
+                version: "D".to_string(),
+                release: "96A".to_string(),
+                association_code: Some("EAN008".to_string()),
+            })
+        );
+        assert_eq!(ung.password, Some("SECRET".to_string()));
+
+        let une = parse_une(&parser.next_segment().unwrap().unwrap()).unwrap();
+        assert_eq!(une.message_count, 2);
+        assert_eq!(une.control_ref, "G1");
+    }
+
+    #[test]
+    fn test_ung_une_round_trip() {
+        let data = "UNG+DESADV+SENDER+RECEIVER:14+200101:1200+G2++D:96A'UNE+1+G2'";
+        let mut parser = SegmentParser::new(data.as_bytes(), "test");
+        let ung = parse_ung(&parser.next_segment().unwrap().unwrap()).unwrap();
+        let une = parse_une(&parser.next_segment().unwrap().unwrap()).unwrap();
+        assert_eq!(ung.agency, None);
+        assert_eq!(ung.password, None);
+
+        let separators = crate::syntax::Separators::default();
+        let mut root = edi_ir::Node::new("GROUP", edi_ir::NodeType::FunctionalGroup);
+        root.add_child(generate_ung(&ung, &separators).to_node());
+        root.add_child(generate_une(&une, &separators).to_node());
+        let serialized = crate::EdifactSerializer::new()
+            .serialize_document(&edi_ir::Document::new(root))
+            .unwrap();
+        assert_eq!(serialized.replace('\n', ""), data);
+
+        let mut parser = SegmentParser::new(serialized.as_bytes(), "test");
+        assert_eq!(
+            parse_ung(&parser.next_segment().unwrap().unwrap()).unwrap(),
+            ung
+        );
+    }
+
+    #[test]
+    fn test_parse_une_error_wrong_tag() {
+        let data = b"UNZ+1+G1'";
+        let mut parser = SegmentParser::new(data, "test");
+        let segment = parser.next_segment().unwrap().unwrap();
+        assert!(parse_une(&segment).is_err());
+        assert!(parse_ung(&segment).is_err());
+    }
+
     #[test]
     fn test_parse_unh() {
         let data = b"UNH+1+ORDERS:D:96A:UN'";
@@ -1744,6 +2481,20 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_message_type_identifier_from_str() {
+        let message_type: MessageTypeIdentifier = "ORDERS:D:96A:UN:EAN008".parse().unwrap();
+        assert_eq!(message_type.message_type, "ORDERS");
+        assert_eq!(message_type.release, "96A");
+        assert_eq!(message_type.association_code, Some("EAN008".to_string()));
+
+        let message_type: MessageTypeIdentifier = "INVOIC:D:01B:UN".parse().unwrap();
+        assert_eq!(message_type.association_code, None);
+
+        assert!("ORDERS:D:96A".parse::<MessageTypeIdentifier>().is_err());
+        assert!("ORDERS::96A:UN".parse::<MessageTypeIdentifier>().is_err());
+    }
+
     #[test]
     fn test_parse_unt() {
         let data = b"UNT+15+1'";
@@ -1813,6 +2564,7 @@ mod tests {
                 time: "1200".to_string(),
             },
             control_ref: "12345".to_string(),
+            recipient_ref: None,
             application_ref: None,
             priority: None,
             ack_request: None,
@@ -1834,6 +2586,7 @@ mod tests {
             receiver: PartyId::default(),
             datetime: DateTime::default(),
             control_ref: "12345".to_string(),
+            recipient_ref: None,
             application_ref: Some("APP".to_string()),
             priority: None,
             ack_request: None,
@@ -1898,6 +2651,7 @@ mod tests {
                 receiver: PartyId::default(),
                 datetime: DateTime::default(),
                 control_ref: "12345".to_string(),
+                recipient_ref: None,
                 application_ref: None,
                 priority: None,
                 ack_request: None,
@@ -1927,10 +2681,13 @@ mod tests {
                         tag: "BGM".to_string(),
                         elements: vec![],
                         position: Position::default(),
+                        raw: None,
+                        spans: None,
                     };
                     3
                 ],
             }],
+            groups: Vec::new(),
         };
 
         assert!(validate_interchange(&interchange).is_ok());
@@ -1945,6 +2702,7 @@ mod tests {
                 receiver: PartyId::default(),
                 datetime: DateTime::default(),
                 control_ref: "12345".to_string(),
+                recipient_ref: None,
                 application_ref: None,
                 priority: None,
                 ack_request: None,
@@ -1956,6 +2714,7 @@ mod tests {
                 control_ref: "54321".to_string(),
             }),
             messages: vec![],
+            groups: Vec::new(),
         };
 
         let result = validate_interchange(&interchange);
@@ -1998,6 +2757,7 @@ mod tests {
                 receiver: PartyId::default(),
                 datetime: DateTime::default(),
                 control_ref: "12345".to_string(),
+                recipient_ref: None,
                 application_ref: None,
                 priority: None,
                 ack_request: None,
@@ -2024,6 +2784,7 @@ mod tests {
                 }),
                 segments: vec![],
             }],
+            groups: Vec::new(),
         };
 
         let result = validate_interchange(&interchange);
@@ -2053,6 +2814,8 @@ mod tests {
                     tag: "BGM".to_string(),
                     elements: vec![],
                     position: Position::default(),
+                    raw: None,
+                    spans: None,
                 };
                 3
             ],
@@ -2073,6 +2836,7 @@ mod tests {
                 receiver: PartyId::default(),
                 datetime: DateTime::default(),
                 control_ref: "12345".to_string(),
+                recipient_ref: None,
                 application_ref: None,
                 priority: None,
                 ack_request: None,
@@ -2081,6 +2845,7 @@ mod tests {
             },
             unz: None,
             messages: vec![],
+            groups: Vec::new(),
         };
 
         let result = validate_interchange(&interchange);
@@ -2177,6 +2942,7 @@ mod tests {
                 receiver: PartyId::default(),
                 datetime: DateTime::default(),
                 control_ref: "12345".to_string(),
+                recipient_ref: None,
                 application_ref: None,
                 priority: None,
                 ack_request: None,
@@ -2207,6 +2973,8 @@ mod tests {
                             tag: "BGM".to_string(),
                             elements: vec![],
                             position: Position::default(),
+                            raw: None,
+                            spans: None,
                         };
                         3
                     ],
@@ -2233,6 +3001,8 @@ mod tests {
                             tag: "BGM".to_string(),
                             elements: vec![],
                             position: Position::default(),
+                            raw: None,
+                            spans: None,
                         };
                         2
                     ],
@@ -2259,11 +3029,14 @@ mod tests {
                             tag: "BGM".to_string(),
                             elements: vec![],
                             position: Position::default(),
+                            raw: None,
+                            spans: None,
                         };
                         4
                     ],
                 },
             ],
+            groups: Vec::new(),
         };
 
         assert!(validate_interchange(&interchange).is_ok());
@@ -2312,6 +3085,7 @@ mod tests {
             element: b'=',
             decimal: b'_',
             release: b'#',
+            repetition: None,
             segment: b'~',
         };
         let una = UnaSegment::from_separators(sep);
@@ -2369,6 +3143,7 @@ mod tests {
                 version: "3".to_string(),
                 service_code_list: None,
                 encoding: None,
+                release: None,
             },
             sender: PartyId {
                 id: "SENDER".to_string(),
@@ -2387,6 +3162,7 @@ mod tests {
                 time: "1200".to_string(),
             },
             control_ref: "00000000000001".to_string(),
+            recipient_ref: None,
             application_ref: None,
             priority: None,
             ack_request: None,
@@ -2419,6 +3195,7 @@ mod tests {
                 receiver: PartyId::default(),
                 datetime: DateTime::default(),
                 control_ref: "12345".to_string(),
+                recipient_ref: None,
                 application_ref: None,
                 priority: None,
                 ack_request: None,
@@ -2448,10 +3225,13 @@ mod tests {
                         tag: "BGM".to_string(),
                         elements: vec![],
                         position: Position::default(),
+                        raw: None,
+                        spans: None,
                     };
                     3
                 ],
             }],
+            groups: Vec::new(),
         };
 
         assert!(validator.validate_interchange(&interchange).is_ok());
@@ -2468,6 +3248,7 @@ mod tests {
                 receiver: PartyId::default(),
                 datetime: DateTime::default(),
                 control_ref: "12345".to_string(),
+                recipient_ref: None,
                 application_ref: None,
                 priority: None,
                 ack_request: None,
@@ -2479,6 +3260,7 @@ mod tests {
                 control_ref: "54321".to_string(),
             }),
             messages: vec![],
+            groups: Vec::new(),
         };
 
         let result = validator.validate_interchange(&interchange);
@@ -2486,8 +3268,8 @@ mod tests {
 
         let error = result.unwrap_err();
         assert_eq!(error.error_kind, ErrorKind::MismatchedControlReference);
-        assert_eq!(error.expected, Some("12345".to_string()));
-        assert_eq!(error.actual, Some("54321".to_string()));
+        assert_eq!(error.expected(), Some("12345"));
+        assert_eq!(error.actual(), Some("54321"));
     }
 
     #[test]
@@ -2501,6 +3283,7 @@ mod tests {
                 receiver: PartyId::default(),
                 datetime: DateTime::default(),
                 control_ref: "12345".to_string(),
+                recipient_ref: None,
                 application_ref: None,
                 priority: None,
                 ack_request: None,
@@ -2509,6 +3292,7 @@ mod tests {
             },
             unz: None,
             messages: vec![],
+            groups: Vec::new(),
         };
 
         let result = validator.validate_interchange(&interchange);
@@ -2529,6 +3313,7 @@ mod tests {
                 receiver: PartyId::default(),
                 datetime: DateTime::default(),
                 control_ref: "12345".to_string(),
+                recipient_ref: None,
                 application_ref: None,
                 priority: None,
                 ack_request: None,
@@ -2555,6 +3340,7 @@ mod tests {
                 }),
                 segments: vec![],
             }],
+            groups: Vec::new(),
         };
 
         let result = validator.validate_interchange(&interchange);
@@ -2562,8 +3348,8 @@ mod tests {
 
         let error = result.unwrap_err();
         assert_eq!(error.error_kind, ErrorKind::IncorrectMessageCount);
-        assert_eq!(error.expected, Some("5".to_string()));
-        assert_eq!(error.actual, Some("1".to_string()));
+        assert_eq!(error.expected(), Some("5"));
+        assert_eq!(error.actual(), Some("1"));
     }
 
     #[test]
@@ -2589,6 +3375,8 @@ mod tests {
                     tag: "BGM".to_string(),
                     elements: vec![],
                     position: Position::default(),
+                    raw: None,
+                    spans: None,
                 };
                 3
             ],
@@ -2623,8 +3411,8 @@ mod tests {
 
         let error = result.unwrap_err();
         assert_eq!(error.error_kind, ErrorKind::MismatchedControlReference);
-        assert_eq!(error.expected, Some("MSG001".to_string()));
-        assert_eq!(error.actual, Some("MSG002".to_string()));
+        assert_eq!(error.expected(), Some("MSG001"));
+        assert_eq!(error.actual(), Some("MSG002"));
     }
 
     #[test]
@@ -2650,6 +3438,8 @@ mod tests {
                     tag: "BGM".to_string(),
                     elements: vec![],
                     position: Position::default(),
+                    raw: None,
+                    spans: None,
                 };
                 3
             ],
@@ -2661,8 +3451,8 @@ mod tests {
         let error = result.unwrap_err();
         assert_eq!(error.error_kind, ErrorKind::IncorrectSegmentCount);
         // Expected 10, actual is 5 (3 segments + UNH + UNT)
-        assert_eq!(error.expected, Some("10".to_string()));
-        assert_eq!(error.actual, Some("5".to_string()));
+        assert_eq!(error.expected(), Some("10"));
+        assert_eq!(error.actual(), Some("5"));
     }
 
     #[test]
@@ -2690,6 +3480,142 @@ mod tests {
         assert_eq!(error.error_kind, ErrorKind::MissingTrailer);
     }
 
+    fn grouped_interchange(
+        une_count: usize,
+        une_ref: &str,
+        unz_count: usize,
+    ) -> InterchangeEnvelope {
+        let message = |message_ref: &str| MessageEnvelope {
+            unh: UnhSegment {
+                message_ref: message_ref.to_string(),
+                message_type: MessageTypeIdentifier::default(),
+                common_access_ref: None,
+                transfer_status: None,
+                subset_id: None,
+                implementation_id: None,
+                scenario_id: None,
+            },
+            unt: Some(UntSegment {
+                segment_count: 2,
+                message_ref: message_ref.to_string(),
+            }),
+            segments: vec![],
+        };
+        let group =
+            |control_ref: &str, count: usize, trailer_ref: &str, refs: &[&str]| GroupEnvelope {
+                ung: UngSegment {
+                    group_id: "ORDERS".to_string(),
+                    sender: PartyId::default(),
+                    recipient: PartyId::default(),
+                    datetime: DateTime::default(),
+                    control_ref: control_ref.to_string(),
+                    agency: None,
+                    message_version: None,
+                    password: None,
+                },
+                une: Some(UneSegment {
+                    message_count: count,
+                    control_ref: trailer_ref.to_string(),
+                }),
+                messages: refs
+                    .iter()
+                    .map(|message_ref| message(message_ref))
+                    .collect(),
+            };
+
+        InterchangeEnvelope {
+            unb: UnbSegment {
+                syntax_identifier: SyntaxIdentifier::default(),
+                sender: PartyId::default(),
+                receiver: PartyId::default(),
+                datetime: DateTime::default(),
+                control_ref: "12345".to_string(),
+                recipient_ref: None,
+                application_ref: None,
+                priority: None,
+                ack_request: None,
+                comms_agreement_id: None,
+                test_indicator: None,
+            },
+            unz: Some(UnzSegment {
+                message_count: unz_count,
+                control_ref: "12345".to_string(),
+            }),
+            messages: vec![],
+            groups: vec![
+                group("G1", une_count, une_ref, &["1", "2"]),
+                group("G2", 1, "G2", &["3"]),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_valid_functional_groups() {
+        let validator = EnvelopeValidator::new();
+        let interchange = grouped_interchange(2, "G1", 2);
+
+        assert_eq!(interchange.control_count(), 2);
+        assert_eq!(interchange.all_messages().count(), 3);
+        assert!(validator.validate_interchange(&interchange).is_ok());
+        assert!(validate_interchange(&interchange).is_ok());
+
+        let document = EdifactDocument {
+            una: None,
+            interchange: Some(interchange),
+        };
+        assert_eq!(document.message_count(), 3);
+        assert!(validator.validate(&document).unwrap().is_valid);
+    }
+
+    #[test]
+    fn test_functional_group_errors() {
+        let validator = EnvelopeValidator::new();
+
+        // UNZ counts groups, not messages
+        let error = validator
+            .validate_interchange(&grouped_interchange(2, "G1", 3))
+            .unwrap_err();
+        assert_eq!(error.error_kind, ErrorKind::IncorrectMessageCount);
+        assert_eq!(error.actual(), Some("2"));
+
+        let interchange = grouped_interchange(2, "GX", 2);
+        let error = validator
+            .validate_group(&interchange.groups[0])
+            .unwrap_err();
+        assert_eq!(error.envelope_type, EnvelopeType::Group);
+        assert_eq!(error.error_kind, ErrorKind::MismatchedControlReference);
+        assert!(validate_interchange(&interchange).is_err());
+
+        let interchange = grouped_interchange(1, "G1", 2);
+        let error = validator
+            .validate_group(&interchange.groups[0])
+            .unwrap_err();
+        assert_eq!(error.error_kind, ErrorKind::IncorrectMessageCount);
+
+        let mut interchange = grouped_interchange(2, "G1", 2);
+        interchange.groups[1].une = None;
+        let report = validator
+            .validate(&EdifactDocument {
+                una: None,
+                interchange: Some(interchange),
+            })
+            .unwrap();
+        assert_eq!(report.error_count(), 1);
+        assert_eq!(report.errors[0].error_kind, ErrorKind::MissingTrailer);
+        assert_eq!(report.errors[0].envelope_type, EnvelopeType::Group);
+    }
+
+    #[test]
+    fn test_ungrouped_message_in_grouped_interchange() {
+        let validator = EnvelopeValidator::new();
+        let mut interchange = grouped_interchange(2, "G1", 2);
+        let stray = interchange.groups[1].messages[0].clone();
+        interchange.messages.push(stray);
+
+        let error = validator.validate_interchange(&interchange).unwrap_err();
+        assert_eq!(error.error_kind, ErrorKind::UngroupedMessage);
+    }
+
     #[test]
     fn test_full_document_validation() {
         let validator = EnvelopeValidator::new();
@@ -2703,6 +3629,7 @@ mod tests {
                     receiver: PartyId::default(),
                     datetime: DateTime::default(),
                     control_ref: "INTERCHANGE001".to_string(),
+                    recipient_ref: None,
                     application_ref: None,
                     priority: None,
                     ack_request: None,
@@ -2733,6 +3660,8 @@ mod tests {
                                 tag: "BGM".to_string(),
                                 elements: vec![],
                                 position: Position::default(),
+                                raw: None,
+                                spans: None,
                             };
                             3
                         ],
@@ -2759,11 +3688,14 @@ mod tests {
                                 tag: "BGM".to_string(),
                                 elements: vec![],
                                 position: Position::default(),
+                                raw: None,
+                                spans: None,
                             };
                             2
                         ],
                     },
                 ],
+                groups: Vec::new(),
             }),
         };
 
@@ -2786,6 +3718,7 @@ mod tests {
                     receiver: PartyId::default(),
                     datetime: DateTime::default(),
                     control_ref: "INTERCHANGE001".to_string(),
+                    recipient_ref: None,
                     application_ref: None,
                     priority: None,
                     ack_request: None,
@@ -2815,10 +3748,13 @@ mod tests {
                             tag: "BGM".to_string(),
                             elements: vec![],
                             position: Position::default(),
+                            raw: None,
+                            spans: None,
                         };
                         3
                     ],
                 }],
+                groups: Vec::new(),
             }),
         };
 
@@ -2856,6 +3792,8 @@ mod tests {
                 Element::Simple(b"REF123".to_vec()),
             ],
             position: Position::default(),
+            raw: None,
+            spans: None,
         };
 
         let unz = Segment {
@@ -2865,11 +3803,30 @@ mod tests {
                 Element::Simple(b"REF123".to_vec()),
             ],
             position: Position::default(),
+            raw: None,
+            spans: None,
         };
 
         assert!(validator.check_control_reference(&unb, &unz).is_ok());
     }
 
+    #[test]
+    fn test_check_control_reference_ung_une() {
+        let validator = EnvelopeValidator::new();
+        let data = b"UNG+INVOIC+SENDER+RECEIVER+200101:1200+G1'UNE+1+G1'UNE+1+G2'";
+        let mut parser = SegmentParser::new(data, "test");
+        let ung = parser.next_segment().unwrap().unwrap();
+        let une = parser.next_segment().unwrap().unwrap();
+        let mismatched = parser.next_segment().unwrap().unwrap();
+
+        assert!(validator.check_control_reference(&ung, &une).is_ok());
+        assert!(
+            validator
+                .check_control_reference(&ung, &mismatched)
+                .is_err()
+        );
+    }
+
     #[test]
     fn test_check_control_reference_mismatch() {
         let validator = EnvelopeValidator::new();
@@ -2884,6 +3841,8 @@ mod tests {
                 Element::Simple(b"REF123".to_vec()),
             ],
             position: Position::default(),
+            raw: None,
+            spans: None,
         };
 
         let unz = Segment {
@@ -2893,6 +3852,8 @@ mod tests {
                 Element::Simple(b"WRONG".to_vec()),
             ],
             position: Position::default(),
+            raw: None,
+            spans: None,
         };
 
         let result = validator.check_control_reference(&unb, &unz);
@@ -2916,6 +3877,8 @@ mod tests {
                 ]),
             ],
             position: Position::default(),
+            raw: None,
+            spans: None,
         };
 
         let unt = Segment {
@@ -2925,6 +3888,8 @@ mod tests {
                 Element::Simple(b"MSG001".to_vec()),
             ],
             position: Position::default(),
+            raw: None,
+            spans: None,
         };
 
         assert!(validator.check_control_reference(&unh, &unt).is_ok());
@@ -2950,6 +3915,8 @@ mod tests {
                     tag: "BGM".to_string(),
                     elements: vec![],
                     position: Position::default(),
+                    raw: None,
+                    spans: None,
                 };
                 3
             ],
@@ -2963,6 +3930,8 @@ mod tests {
                 Element::Simple(b"1".to_vec()),
             ],
             position: Position::default(),
+            raw: None,
+            spans: None,
         };
 
         assert!(
@@ -2992,6 +3961,8 @@ mod tests {
                     tag: "BGM".to_string(),
                     elements: vec![],
                     position: Position::default(),
+                    raw: None,
+                    spans: None,
                 };
                 3
             ],
@@ -3005,6 +3976,8 @@ mod tests {
                 Element::Simple(b"1".to_vec()),
             ],
             position: Position::default(),
+            raw: None,
+            spans: None,
         };
 
         let result = validator.check_segment_count(&message, &unt_segment);
@@ -3023,11 +3996,13 @@ mod tests {
                     version: "3".to_string(),
                     service_code_list: None,
                     encoding: None,
+                    release: None,
                 },
                 sender: PartyId::default(),
                 receiver: PartyId::default(),
                 datetime: DateTime::default(),
                 control_ref: "12345".to_string(),
+                recipient_ref: None,
                 application_ref: None,
                 priority: None,
                 ack_request: None,
@@ -3039,6 +4014,7 @@ mod tests {
                 control_ref: "12345".to_string(),
             }),
             messages: vec![],
+            groups: Vec::new(),
         };
 
         let result = validator.validate_interchange(&interchange);
@@ -3094,6 +4070,7 @@ mod tests {
                     receiver: PartyId::default(),
                     datetime: DateTime::default(),
                     control_ref: "12345".to_string(),
+                    recipient_ref: None,
                     application_ref: None,
                     priority: None,
                     ack_request: None,
@@ -3138,6 +4115,7 @@ mod tests {
                         segments: vec![],
                     },
                 ],
+                groups: Vec::new(),
             }),
         };
 
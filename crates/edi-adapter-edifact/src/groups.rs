@@ -0,0 +1,459 @@
+//! Segment group hierarchy (branching diagram) support
+//!
+//! An EDIFACT message definition describes its segments as a branching
+//! diagram: an ordered list of segments and nested segment groups, where each
+//! group is opened by its trigger segment. This module holds that diagram and
+//! nests a flat list of parsed segments into `SegmentGroup` nodes accordingly.
+
+use edi_ir::{BorrowedNode, Node, NodeType};
+use edi_schema::{Schema, SchemaEntry, SegmentGroupDefinition};
+
+/// An entry in a branching diagram: either a segment or a nested group
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiagramEntry {
+    /// A segment identified by its tag
+    Segment(String),
+    /// A nested segment group
+    Group(SegmentGroup),
+}
+
+impl DiagramEntry {
+    /// Returns `true` when a segment with `tag` starts or belongs to this entry
+    fn accepts(&self, tag: &str) -> bool {
+        match self {
+            Self::Segment(entry_tag) => entry_tag == tag,
+            Self::Group(group) => group.trigger() == Some(tag),
+        }
+    }
+}
+
+/// A segment group definition (e.g. `SG2` triggered by `NAD`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SegmentGroup {
+    /// Group identifier, used as the node name (e.g. "SG2")
+    pub id: String,
+    /// Ordered entries; the first entry is the trigger segment
+    pub entries: Vec<DiagramEntry>,
+}
+
+impl SegmentGroup {
+    /// Create a new group opened by `trigger`
+    pub fn new(id: impl Into<String>, trigger: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            entries: vec![DiagramEntry::Segment(trigger.into())],
+        }
+    }
+
+    /// Append a segment to the group
+    pub fn with_segment(mut self, tag: impl Into<String>) -> Self {
+        self.entries.push(DiagramEntry::Segment(tag.into()));
+        self
+    }
+
+    /// Append a nested group to the group
+    pub fn with_group(mut self, group: SegmentGroup) -> Self {
+        self.entries.push(DiagramEntry::Group(group));
+        self
+    }
+
+    /// Tag of the trigger segment that opens this group
+    pub fn trigger(&self) -> Option<&str> {
+        match self.entries.first() {
+            Some(DiagramEntry::Segment(tag)) => Some(tag),
+            _ => None,
+        }
+    }
+}
+
+/// Branching diagram for a message type
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BranchingDiagram {
+    /// Ordered message-level entries
+    pub entries: Vec<DiagramEntry>,
+}
+
+impl BranchingDiagram {
+    /// Create an empty diagram
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a message-level segment
+    pub fn with_segment(mut self, tag: impl Into<String>) -> Self {
+        self.entries.push(DiagramEntry::Segment(tag.into()));
+        self
+    }
+
+    /// Append a message-level group
+    pub fn with_group(mut self, group: SegmentGroup) -> Self {
+        self.entries.push(DiagramEntry::Group(group));
+        self
+    }
+
+    /// Find a group definition by id anywhere in the diagram
+    pub fn find_group(&self, id: &str) -> Option<&SegmentGroup> {
+        fn find<'a>(entries: &'a [DiagramEntry], id: &str) -> Option<&'a SegmentGroup> {
+            entries.iter().find_map(|entry| match entry {
+                DiagramEntry::Group(group) if group.id == id => Some(group),
+                DiagramEntry::Group(group) => find(&group.entries, id),
+                DiagramEntry::Segment(_) => None,
+            })
+        }
+        find(&self.entries, id)
+    }
+
+    /// Nest message segment nodes into segment group nodes.
+    ///
+    /// Segments are matched against the diagram from the innermost open group
+    /// outwards; a segment that only fits an enclosing level closes the groups
+    /// above it. Segments the diagram does not know are kept in the innermost
+    /// open group, and `UNT` always closes every group.
+    pub(crate) fn nest<N: MessageNode>(&self, segments: impl IntoIterator<Item = N>) -> Vec<N> {
+        let mut stack = vec![Frame {
+            entries: &self.entries,
+            position: 0,
+            is_group: false,
+            node: N::new_node("MESSAGE", NodeType::Message),
+        }];
+
+        for segment in segments {
+            if segment.tag() == "UNT" {
+                close_frames(&mut stack, 1);
+                stack[0].node.push_child(segment);
+                continue;
+            }
+
+            let matched = (0..stack.len())
+                .rev()
+                .find_map(|depth| stack[depth].find(segment.tag()).map(|index| (depth, index)));
+
+            let Some((depth, index)) = matched else {
+                if let Some(frame) = stack.last_mut() {
+                    frame.node.push_child(segment);
+                }
+                continue;
+            };
+
+            close_frames(&mut stack, depth + 1);
+            let frame = &mut stack[depth];
+            frame.position = index;
+            match &frame.entries[index] {
+                DiagramEntry::Segment(_) => {
+                    frame.node.push_child(segment);
+                }
+                DiagramEntry::Group(group) => {
+                    let mut node = N::new_node(&group.id, NodeType::SegmentGroup);
+                    node.push_child(segment);
+                    stack.push(Frame {
+                        entries: &group.entries,
+                        position: 0,
+                        is_group: true,
+                        node,
+                    });
+                }
+            }
+        }
+
+        close_frames(&mut stack, 1);
+        stack
+            .pop()
+            .map(|root| root.node.into_children())
+            .unwrap_or_default()
+    }
+}
+
+impl From<&Schema> for BranchingDiagram {
+    /// Build the diagram from the schema's segments and groups in position order
+    fn from(schema: &Schema) -> Self {
+        Self {
+            entries: schema
+                .entries()
+                .into_iter()
+                .map(DiagramEntry::from)
+                .collect(),
+        }
+    }
+}
+
+impl From<SchemaEntry<'_>> for DiagramEntry {
+    fn from(entry: SchemaEntry<'_>) -> Self {
+        match entry {
+            SchemaEntry::Segment(segment) => Self::Segment(segment.tag.clone()),
+            SchemaEntry::Group(group) => Self::Group(SegmentGroup::from(group)),
+        }
+    }
+}
+
+impl From<&SegmentGroupDefinition> for SegmentGroup {
+    fn from(definition: &SegmentGroupDefinition) -> Self {
+        let mut group = Self::new(&definition.id, &definition.trigger);
+        group.entries.extend(
+            definition
+                .entries()
+                .into_iter()
+                .filter(|entry| {
+                    !matches!(entry, SchemaEntry::Segment(segment) if segment.tag == definition.trigger)
+                })
+                .map(DiagramEntry::from),
+        );
+        group
+    }
+}
+
+/// Owned or borrowed IR node that message segments are grouped into
+pub(crate) trait MessageNode: Sized {
+    /// Create a node without value
+    fn new_node(name: &str, node_type: NodeType) -> Self;
+
+    /// Node name; the tag of a segment node
+    fn tag(&self) -> &str;
+
+    /// Node type
+    fn node_type(&self) -> NodeType;
+
+    /// Append a child node
+    fn push_child(&mut self, child: Self);
+
+    /// The child nodes
+    fn children_mut(&mut self) -> &mut [Self];
+
+    /// Take the child nodes
+    fn into_children(self) -> Vec<Self>;
+
+    /// Record the index of the enclosing segment on the node's spans
+    fn set_segment_index(&mut self, segment_index: usize);
+}
+
+impl MessageNode for Node {
+    fn new_node(name: &str, node_type: NodeType) -> Self {
+        Node::new(name, node_type)
+    }
+
+    fn tag(&self) -> &str {
+        &self.name
+    }
+
+    fn node_type(&self) -> NodeType {
+        self.node_type
+    }
+
+    fn push_child(&mut self, child: Self) {
+        self.add_child(child);
+    }
+
+    fn children_mut(&mut self) -> &mut [Self] {
+        &mut self.children
+    }
+
+    fn into_children(self) -> Vec<Self> {
+        self.children
+    }
+
+    fn set_segment_index(&mut self, segment_index: usize) {
+        Self::set_segment_index(self, segment_index);
+    }
+}
+
+impl MessageNode for BorrowedNode<'_> {
+    fn new_node(name: &str, node_type: NodeType) -> Self {
+        BorrowedNode::new(name.to_string(), node_type)
+    }
+
+    fn tag(&self) -> &str {
+        &self.name
+    }
+
+    fn node_type(&self) -> NodeType {
+        self.node_type
+    }
+
+    fn push_child(&mut self, child: Self) {
+        self.add_child(child);
+    }
+
+    fn children_mut(&mut self) -> &mut [Self] {
+        &mut self.children
+    }
+
+    fn into_children(self) -> Vec<Self> {
+        self.children
+    }
+
+    fn set_segment_index(&mut self, segment_index: usize) {
+        Self::set_segment_index(self, segment_index);
+    }
+}
+
+/// An open level while nesting: the message itself or a group instance
+struct Frame<'d, N> {
+    entries: &'d [DiagramEntry],
+    position: usize,
+    is_group: bool,
+    node: N,
+}
+
+impl<N> Frame<'_, N> {
+    /// Index of the first entry at or after the current position accepting
+    /// `tag`. A group's trigger is never matched again inside the same
+    /// instance; a repeated trigger opens a new instance one level up.
+    fn find(&self, tag: &str) -> Option<usize> {
+        let start = if self.is_group {
+            self.position.max(1)
+        } else {
+            self.position
+        };
+        self.entries
+            .get(start..)?
+            .iter()
+            .position(|entry| entry.accepts(tag))
+            .map(|offset| start + offset)
+    }
+}
+
+fn close_frames<N: MessageNode>(stack: &mut Vec<Frame<'_, N>>, keep: usize) {
+    while stack.len() > keep {
+        if let Some(frame) = stack.pop() {
+            if let Some(parent) = stack.last_mut() {
+                parent.node.push_child(frame.node);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{Segment, SegmentParser};
+
+    fn segments(data: &[u8]) -> Vec<Segment> {
+        let mut parser = SegmentParser::new(data, "test");
+        let mut segments = Vec::new();
+        while let Some(segment) = parser.next_segment() {
+            segments.push(segment.unwrap());
+        }
+        segments
+    }
+
+    fn orders_diagram() -> BranchingDiagram {
+        BranchingDiagram::new()
+            .with_segment("UNH")
+            .with_segment("BGM")
+            .with_segment("DTM")
+            .with_group(
+                SegmentGroup::new("SG2", "NAD")
+                    .with_group(SegmentGroup::new("SG3", "RFF").with_segment("DTM"))
+                    .with_group(SegmentGroup::new("SG5", "CTA").with_segment("COM")),
+            )
+            .with_group(SegmentGroup::new("SG7", "CUX"))
+            .with_group(
+                SegmentGroup::new("SG25", "LIN")
+                    .with_segment("PIA")
+                    .with_segment("QTY")
+                    .with_group(SegmentGroup::new("SG28", "PRI")),
+            )
+            .with_segment("UNS")
+            .with_segment("CNT")
+            .with_segment("UNT")
+    }
+
+    fn names(nodes: &[Node]) -> Vec<&str> {
+        nodes.iter().map(|node| node.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_nest_orders_groups() {
+        let data = b"UNH+1+ORDERS:D:96A:UN'\
+BGM+220+PO1+9'\
+DTM+137:20200101:102'\
+NAD+BY+111::9'\
+RFF+VA:123'\
+CTA+PD'\
+COM+123:TE'\
+NAD+SU+222::9'\
+CUX+2:EUR:9'\
+LIN+1++A:EN'\
+QTY+21:10'\
+PRI+AAA:1.5'\
+LIN+2++B:EN'\
+QTY+21:5'\
+UNS+S'\
+CNT+2:2'\
+UNT+16+1'";
+
+        let nodes = orders_diagram().nest(segments(data).iter().map(Segment::to_node));
+        assert_eq!(
+            names(&nodes),
+            vec![
+                "UNH", "BGM", "DTM", "SG2", "SG2", "SG7", "SG25", "SG25", "UNS", "CNT", "UNT"
+            ]
+        );
+
+        let buyer = &nodes[3];
+        assert_eq!(buyer.node_type, NodeType::SegmentGroup);
+        assert_eq!(names(&buyer.children), vec!["NAD", "SG3", "SG5"]);
+        assert_eq!(names(&buyer.children[2].children), vec!["CTA", "COM"]);
+        assert_eq!(names(&nodes[4].children), vec!["NAD"]);
+
+        let first_line = &nodes[6];
+        assert_eq!(names(&first_line.children), vec!["LIN", "QTY", "SG28"]);
+        assert_eq!(names(&nodes[7].children), vec!["LIN", "QTY"]);
+    }
+
+    #[test]
+    fn test_nest_keeps_unknown_segment_in_open_group() {
+        let data = b"UNH+1+ORDERS:D:96A:UN'NAD+BY+111::9'FTX+AAI+++NOTE'UNT+4+1'";
+
+        let nodes = orders_diagram().nest(segments(data).iter().map(Segment::to_node));
+        assert_eq!(names(&nodes), vec!["UNH", "SG2", "UNT"]);
+        assert_eq!(names(&nodes[1].children), vec!["NAD", "FTX"]);
+    }
+
+    #[test]
+    fn test_diagram_from_schema() {
+        use edi_schema::SegmentDefinition;
+
+        let schema = Schema::new("ORDERS", "D96A")
+            .with_segments(vec![
+                SegmentDefinition::new("UNH").position(10),
+                SegmentDefinition::new("BGM").position(20),
+                SegmentDefinition::new("UNS").position(2000),
+            ])
+            .with_groups(vec![
+                SegmentGroupDefinition::new("SG2", "NAD")
+                    .position(80)
+                    .with_segments(vec![SegmentDefinition::new("NAD")])
+                    .with_groups(vec![
+                        SegmentGroupDefinition::new("SG5", "CTA")
+                            .with_segments(vec![SegmentDefinition::new("COM")]),
+                    ]),
+            ]);
+
+        let diagram = BranchingDiagram::from(&schema);
+        assert_eq!(diagram.entries.len(), 4);
+        assert!(matches!(&diagram.entries[2], DiagramEntry::Group(group) if group.id == "SG2"));
+
+        let sg2 = diagram.find_group("SG2").unwrap();
+        assert_eq!(sg2.trigger(), Some("NAD"));
+        assert_eq!(sg2.entries.len(), 2);
+
+        let sg5 = diagram.find_group("SG5").unwrap();
+        assert_eq!(
+            sg5.entries,
+            vec![
+                DiagramEntry::Segment("CTA".to_string()),
+                DiagramEntry::Segment("COM".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_find_group() {
+        let diagram = orders_diagram();
+        assert_eq!(
+            diagram.find_group("SG28").and_then(SegmentGroup::trigger),
+            Some("PRI")
+        );
+        assert!(diagram.find_group("SG99").is_none());
+    }
+}
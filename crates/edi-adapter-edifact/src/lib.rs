@@ -5,17 +5,34 @@
 //! This crate provides streaming parsing and serialization of EDIFACT
 //! documents with support for EANCOM variants.
 
+pub mod aperak;
+pub mod borrowed;
+pub mod charset;
+pub mod contrl;
 pub mod envelopes;
+pub mod groups;
 pub mod parser;
+pub mod reader;
 pub mod serializer;
+pub mod split;
 pub mod syntax;
+pub mod writer;
 
+pub use aperak::{AperakGenerator, ApplicationErrorCode, ApplicationIssue};
+pub use charset::Charset;
+pub use contrl::ContrlGenerator;
 pub use envelopes::{
     EdifactDocument, EnvelopeType, EnvelopeValidator, ErrorKind, ValidationError, ValidationReport,
     ValidationWarning,
 };
-pub use parser::EdifactParser;
-pub use serializer::EdifactSerializer;
+pub use groups::{BranchingDiagram, DiagramEntry, SegmentGroup};
+pub use parser::{EdifactParser, ParseFailure, RawSegment};
+#[cfg(feature = "async")]
+pub use reader::AsyncEdifactReader;
+pub use reader::{EdifactReader, SegmentReader};
+pub use serializer::{EdifactSerializer, SegmentSuffix};
+pub use split::{InterchangeMerger, InterchangePart, InterchangeSplitter, SplitKey};
+pub use writer::{InterchangeWriter, PartnerProfile};
 
 use thiserror::Error;
 
@@ -38,6 +55,9 @@ pub enum Error {
     #[error("Syntax error: {0}")]
     Syntax(String),
 
+    #[error("Character set error: {0}")]
+    Charset(String),
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
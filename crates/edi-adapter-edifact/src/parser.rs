@@ -4,12 +4,32 @@
 //! messages one at a time, supporting large batch files without loading
 //! everything into memory.
 
+use crate::charset::Charset;
+use crate::envelopes;
+use crate::groups::{BranchingDiagram, MessageNode};
 use crate::syntax::{Separators, SyntaxBuffer};
 use crate::{Error, Result};
 use edi_ir::document::DocumentMetadata;
-use edi_ir::{Document, Node, NodeType, Position, Value};
+use edi_ir::{Document, InterchangeInfo, Node, NodeType, Position, SourceSpan, Value};
+use edi_schema::Schema;
+use std::collections::{HashMap, VecDeque};
+use std::ops::Range;
 use tracing::warn;
 
+/// Node attribute holding a segment's source text in lossless mode
+pub const RAW_SEGMENT_ATTRIBUTE: &str = "raw_segment";
+/// Node attribute holding the whitespace before a segment in lossless mode
+pub const RAW_PREFIX_ATTRIBUTE: &str = "raw_prefix";
+/// Interchange root attribute holding the UNA service string advice that
+/// preceded it
+pub const RAW_UNA_ATTRIBUTE: &str = "raw_una";
+/// Message root attribute holding the UNA of the enclosing input, used to
+/// render modified segments with the source separators
+pub const RAW_SEPARATORS_ATTRIBUTE: &str = "raw_separators";
+/// Interchange root attribute holding the whitespace after the last segment
+/// of the input
+pub const RAW_SUFFIX_ATTRIBUTE: &str = "raw_suffix";
+
 /// A parsed EDIFACT segment
 #[derive(Debug, Clone)]
 pub struct Segment {
@@ -19,6 +39,39 @@ pub struct Segment {
     pub elements: Vec<Element>,
     /// Position in source
     pub position: Position,
+    /// Source text, recorded when the segment parser runs in lossless mode
+    pub raw: Option<RawSegment>,
+    /// Source spans of the segment and its elements, set for parsed segments
+    pub spans: Option<SegmentSpans>,
+}
+
+/// Source spans of a parsed segment, attached to its IR nodes by
+/// [`Segment::to_node`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SegmentSpans {
+    /// Segment text from the tag through the segment terminator
+    pub segment: SourceSpan,
+    /// One span per element, in the order of [`Segment::elements`]
+    pub elements: Vec<ElementSpan>,
+}
+
+/// Source span of an element value, shaped like the [`Element`] it belongs to
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ElementSpan {
+    /// Element text without the surrounding separators
+    pub span: SourceSpan,
+    /// Component spans of a composite, or occurrence spans of a repeated
+    /// element; empty for a simple element
+    pub parts: Vec<ElementSpan>,
+}
+
+/// Source text of a segment, kept to reproduce the input byte for byte
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RawSegment {
+    /// Whitespace between the previous segment and this one
+    pub prefix: Vec<u8>,
+    /// Segment text from the tag through the segment terminator
+    pub text: Vec<u8>,
 }
 
 /// A data element (simple or composite)
@@ -28,11 +81,208 @@ pub enum Element {
     Simple(Vec<u8>),
     /// Composite element (multiple components)
     Composite(Vec<Vec<u8>>),
+    /// Repeated element (syntax version 4); each occurrence is simple or
+    /// composite
+    Repeated(Vec<Element>),
+}
+
+impl Element {
+    /// Build a simple or composite occurrence from the collected components
+    fn from_components(components: &mut Vec<Vec<u8>>) -> Self {
+        if components.len() == 1 {
+            Element::Simple(components.pop().unwrap_or_default())
+        } else {
+            Element::Composite(std::mem::take(components))
+        }
+    }
+
+    /// Finish an element, wrapping it with earlier occurrences if repeated
+    fn finish(components: &mut Vec<Vec<u8>>, occurrences: &mut Vec<Element>) -> Self {
+        let element = Self::from_components(components);
+        if occurrences.is_empty() {
+            element
+        } else {
+            occurrences.push(element);
+            Element::Repeated(std::mem::take(occurrences))
+        }
+    }
+
+    /// Occurrences of this element; a non-repeated element has one
+    pub fn occurrences(&self) -> &[Element] {
+        match self {
+            Element::Repeated(occurrences) => occurrences,
+            _ => std::slice::from_ref(self),
+        }
+    }
+
+    /// Convert this element to an IR node named `name`.
+    ///
+    /// Components become `c1..cn` children; a repeated element gets one
+    /// `Element` child per occurrence, named `r1..rn`.
+    pub fn to_node(&self, name: String) -> Node {
+        match self {
+            Element::Simple(value) => Node::with_value(
+                name,
+                NodeType::Element,
+                Value::String(String::from_utf8_lossy(value).to_string()),
+            ),
+            Element::Composite(components) => {
+                let mut node = Node::new(name, NodeType::Element);
+                for (j, comp) in components.iter().enumerate() {
+                    node.add_child(Node::with_value(
+                        format!("c{}", j + 1),
+                        NodeType::Component,
+                        Value::String(String::from_utf8_lossy(comp).to_string()),
+                    ));
+                }
+                node
+            }
+            Element::Repeated(occurrences) => {
+                let mut node = Node::new(name, NodeType::Element);
+                for (k, occurrence) in occurrences.iter().enumerate() {
+                    node.add_child(occurrence.to_node(format!("r{}", k + 1)));
+                }
+                node
+            }
+        }
+    }
+
+    /// Visit every value buffer with its path below `path`
+    fn for_each_value_mut(&mut self, path: &str, visit: &mut impl FnMut(&mut Vec<u8>, String)) {
+        match self {
+            Element::Simple(value) => visit(value, path.to_string()),
+            Element::Composite(components) => {
+                for (j, component) in components.iter_mut().enumerate() {
+                    visit(component, format!("{}/c{}", path, j + 1));
+                }
+            }
+            Element::Repeated(occurrences) => {
+                for (k, occurrence) in occurrences.iter_mut().enumerate() {
+                    occurrence.for_each_value_mut(&format!("{}/r{}", path, k + 1), visit);
+                }
+            }
+        }
+    }
+}
+
+impl SegmentSpans {
+    /// Visit the segment span and every element, occurrence and component
+    /// span
+    pub fn for_each_span_mut(&mut self, visit: &mut impl FnMut(&mut SourceSpan)) {
+        visit(&mut self.segment);
+        for element in &mut self.elements {
+            element.for_each_span_mut(visit);
+        }
+    }
+}
+
+impl ElementSpan {
+    fn leaf(span: SourceSpan) -> Self {
+        Self {
+            span,
+            parts: Vec::new(),
+        }
+    }
+
+    /// Mirror of [`Element::from_components`]
+    fn occurrence(span: SourceSpan, components: &mut Vec<ElementSpan>) -> Self {
+        if components.len() == 1 {
+            components.clear();
+            Self::leaf(span)
+        } else {
+            Self {
+                span,
+                parts: std::mem::take(components),
+            }
+        }
+    }
+
+    /// Mirror of [`Element::finish`]
+    fn finish(
+        span: SourceSpan,
+        occurrence: SourceSpan,
+        components: &mut Vec<ElementSpan>,
+        occurrences: &mut Vec<ElementSpan>,
+    ) -> Self {
+        let element = Self::occurrence(occurrence, components);
+        if occurrences.is_empty() {
+            element
+        } else {
+            occurrences.push(element);
+            Self {
+                span,
+                parts: std::mem::take(occurrences),
+            }
+        }
+    }
+
+    fn for_each_span_mut(&mut self, visit: &mut impl FnMut(&mut SourceSpan)) {
+        visit(&mut self.span);
+        for part in &mut self.parts {
+            part.for_each_span_mut(visit);
+        }
+    }
+
+    /// Set the spans on `node`, built by [`Element::to_node`], and its
+    /// children
+    pub fn apply(&self, node: &mut Node) {
+        node.set_span(self.span);
+        for (part, child) in self.parts.iter().zip(&mut node.children) {
+            part.apply(child);
+        }
+    }
+}
+
+/// Incremental line and column tracking for the spans of one segment.
+///
+/// Counts `\r\n` as a single line break, like [`SyntaxBuffer::line_column`],
+/// without rescanning the input from its start for every value.
+pub(crate) struct LineCursor {
+    offset: usize,
+    line: usize,
+    column: usize,
+}
+
+impl LineCursor {
+    /// Cursor at the start of the input
+    pub(crate) fn new() -> Self {
+        Self {
+            offset: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+
+    /// Zero-length span at `offset`, which must not precede earlier offsets
+    pub(crate) fn span_at(&mut self, data: &[u8], offset: usize) -> SourceSpan {
+        for index in self.offset..offset.min(data.len()) {
+            match data[index] {
+                b'\n' if index > 0 && data[index - 1] == b'\r' => {}
+                b'\n' | b'\r' => {
+                    self.line += 1;
+                    self.column = 1;
+                }
+                _ => self.column += 1,
+            }
+        }
+        self.offset = self.offset.max(offset);
+        SourceSpan::new(offset, 0, self.line, self.column)
+    }
+}
+
+/// `start` extended to end at `end`
+pub(crate) fn span_until(start: SourceSpan, end: usize) -> SourceSpan {
+    SourceSpan {
+        length: end - start.offset,
+        ..start
+    }
 }
 
 /// Non-fatal parser warning emitted while parsing EDIFACT data.
 #[derive(Debug, Clone)]
 pub struct ParseWarning {
+    /// Machine-readable warning code (e.g. `MISSING_UNT`).
+    pub code: &'static str,
     /// Warning message describing what happened.
     pub message: String,
     /// Position in the source where the warning occurred.
@@ -44,11 +294,85 @@ pub struct ParseWarning {
 impl ParseWarning {
     fn missing_unt(position: Position, message_ref: Option<String>) -> Self {
         Self {
+            code: "MISSING_UNT",
             message: "Created partial message at EOF because UNT segment is missing".to_string(),
             position,
             message_ref,
         }
     }
+
+    fn invalid_unb(position: Position, error: &Error) -> Self {
+        Self {
+            code: "INVALID_UNB",
+            message: format!(
+                "Interchange header could not be read; messages carry no interchange metadata: {error}"
+            ),
+            position,
+            message_ref: None,
+        }
+    }
+
+    fn unsupported_charset(position: Position, identifier: &str) -> Self {
+        Self {
+            code: "UNSUPPORTED_CHARSET",
+            message: format!(
+                "Syntax identifier '{identifier}' is not supported; element data is read as UTF-8"
+            ),
+            position,
+            message_ref: None,
+        }
+    }
+
+    fn charset_decode_error(
+        position: Position,
+        message_ref: Option<String>,
+        path: &str,
+        charset: Charset,
+    ) -> Self {
+        Self {
+            code: "CHARSET_DECODE_ERROR",
+            message: format!("Data in '{path}' is not valid {charset} and was replaced"),
+            position,
+            message_ref,
+        }
+    }
+
+    fn outside_repertoire(
+        position: Position,
+        message_ref: Option<String>,
+        path: &str,
+        charset: Charset,
+        invalid: &[char],
+    ) -> Self {
+        let characters = invalid
+            .iter()
+            .map(|ch| format!("'{ch}' (U+{:04X})", u32::from(*ch)))
+            .collect::<Vec<_>>()
+            .join(", ");
+        Self {
+            code: "CHARACTER_OUTSIDE_REPERTOIRE",
+            message: format!(
+                "Data in '{path}' contains characters outside the {charset} repertoire: {characters}"
+            ),
+            position,
+            message_ref,
+        }
+    }
+}
+
+/// A message skipped by the error-recovering parser.
+#[derive(Debug, Clone)]
+pub struct ParseFailure {
+    /// Message reference from UNH when the malformed segment was inside a
+    /// message.
+    pub message_ref: Option<String>,
+    /// Source bytes of the damaged message, from its UNH (or the malformed
+    /// segment outside a message) up to the segment parsing resumed at.
+    pub range: Range<usize>,
+    /// Position of the malformed segment.
+    pub position: Position,
+    /// Parse error for the malformed segment.
+    pub message: String,
 }
 
 /// Parse output that includes parsed documents and non-fatal warnings.
@@ -56,38 +380,458 @@ impl ParseWarning {
 pub struct ParseOutcome {
     /// Parsed EDIFACT messages.
     pub documents: Vec<Document>,
+    /// Parsed interchanges: an `Interchange` root holding the UNB/UNZ
+    /// segments and the messages, grouped in `FunctionalGroup` nodes when the
+    /// interchange uses UNG/UNE. Messages outside any UNB are only part of
+    /// `documents`.
+    pub interchanges: Vec<Document>,
     /// Non-fatal warnings collected during parsing.
     pub warnings: Vec<ParseWarning>,
+    /// Messages skipped because of malformed segments; only populated with
+    /// [`EdifactParser::with_error_recovery`].
+    pub failures: Vec<ParseFailure>,
+}
+
+/// Builds the interchange → functional group → message node hierarchy while
+/// messages are parsed
+#[derive(Debug, Default)]
+struct EnvelopeTree {
+    interchange: Option<Node>,
+    info: Option<InterchangeInfo>,
+    group: Option<Node>,
+    finished: Vec<Document>,
+    una_recorded: bool,
+}
+
+impl EnvelopeTree {
+    fn open_interchange(&mut self, unb: &Segment, info: Option<&InterchangeInfo>) {
+        self.close_interchange(None);
+        let mut interchange = Node::new("INTERCHANGE", NodeType::Interchange);
+        interchange.add_child(unb.to_node());
+        self.interchange = Some(interchange);
+        self.info = info.cloned();
+    }
+
+    /// Attach the UNA text to the first interchange only, so that the
+    /// interchanges of one input serialize back to that input in sequence
+    fn record_una(&mut self, una: Option<Separators>) {
+        if std::mem::replace(&mut self.una_recorded, true) {
+            return;
+        }
+        if let (Some(una), Some(interchange)) = (una, self.interchange.as_mut()) {
+            interchange.set_attribute(
+                RAW_UNA_ATTRIBUTE,
+                String::from_utf8_lossy(&una.to_una()).into_owned(),
+            );
+        }
+    }
+
+    fn open_group(&mut self, ung: &Segment) {
+        self.close_group(None);
+        let mut group = Node::new("GROUP", NodeType::FunctionalGroup);
+        group.add_child(ung.to_node());
+        self.group = Some(group);
+    }
+
+    fn add_message(&mut self, message: &Node) {
+        if let Some(parent) = self.group.as_mut().or(self.interchange.as_mut()) {
+            parent.add_child(message.clone());
+        }
+    }
+
+    fn close_group(&mut self, une: Option<&Segment>) {
+        let Some(mut group) = self.group.take() else {
+            return;
+        };
+        if let Some(une) = une {
+            group.add_child(une.to_node());
+        }
+        if let Some(interchange) = self.interchange.as_mut() {
+            interchange.add_child(group);
+        }
+    }
+
+    fn close_interchange(&mut self, unz: Option<&Segment>) {
+        self.close_group(None);
+        let Some(mut interchange) = self.interchange.take() else {
+            return;
+        };
+        if let Some(unz) = unz {
+            interchange.add_child(unz.to_node());
+        }
+        let mut document = Document::new(interchange);
+        EdifactParser::attach_interchange(&mut document.metadata, self.info.take().as_ref());
+        self.finished.push(document);
+    }
+
+    fn finish(mut self, suffix: Option<&[u8]>) -> Vec<Document> {
+        self.close_interchange(None);
+        if let (Some(suffix), Some(last)) = (suffix, self.finished.last_mut()) {
+            last.root.set_attribute(
+                RAW_SUFFIX_ATTRIBUTE,
+                String::from_utf8_lossy(suffix).into_owned(),
+            );
+        }
+        self.finished
+    }
+}
+
+/// Groups a stream of segments into message documents.
+///
+/// Shared by [`EdifactParser::parse_with_warnings`] and the streaming
+/// readers; completed messages are queued in `documents` as soon as their UNT
+/// is seen.
+#[derive(Debug)]
+pub(crate) struct MessageAssembler {
+    pub(crate) documents: VecDeque<Document>,
+    pub(crate) warnings: Vec<ParseWarning>,
+    pub(crate) failures: Vec<ParseFailure>,
+    failure: Option<ParseFailure>,
+    current_segments: Vec<Segment>,
+    interchange: Option<InterchangeInfo>,
+    charset: Option<Charset>,
+    message_ref: Option<String>,
+    tree: Option<EnvelopeTree>,
+    raw_una: Option<String>,
+}
+
+impl MessageAssembler {
+    /// Create an assembler; `build_interchanges` keeps whole interchanges in
+    /// memory for [`ParseOutcome::interchanges`]
+    pub(crate) fn new(build_interchanges: bool) -> Self {
+        Self {
+            documents: VecDeque::new(),
+            warnings: Vec::new(),
+            failures: Vec::new(),
+            failure: None,
+            current_segments: Vec::new(),
+            interchange: None,
+            charset: None,
+            message_ref: None,
+            tree: build_interchanges.then(EnvelopeTree::default),
+            raw_una: None,
+        }
+    }
+
+    /// Add the next segment; `una` holds the separators of the input's UNA.
+    /// Returns the separators to use from now on when a UNB without
+    /// preceding UNA declares syntax version 4.
+    pub(crate) fn push(
+        &mut self,
+        parser: &EdifactParser,
+        mut segment: Segment,
+        una: Option<Separators>,
+    ) -> Option<Separators> {
+        if let Some(mut failure) = self.failure.take() {
+            // Drop the rest of a damaged message up to the next envelope
+            // boundary
+            if !matches!(segment.tag.as_str(), "UNH" | "UNB" | "UNG" | "UNE" | "UNZ") {
+                self.failure = Some(failure);
+                return None;
+            }
+            failure.range.end = segment.start_offset();
+            self.failures.push(failure);
+        }
+        if parser.lossless && self.raw_una.is_none() {
+            self.raw_una = una.map(|una| String::from_utf8_lossy(&una.to_una()).into_owned());
+        }
+
+        if let Some(charset) = self.charset {
+            self.warnings
+                .extend(segment.decode_charset(charset, self.message_ref.as_deref()));
+        }
+
+        let mut separators = None;
+        match segment.tag.as_str() {
+            "UNB" => {
+                // Interchange header
+                self.interchange = match envelopes::parse_unb(&segment) {
+                    Ok(unb) => Some(InterchangeInfo::from(&unb)),
+                    Err(error) => {
+                        self.warnings
+                            .push(ParseWarning::invalid_unb(segment.position.clone(), &error));
+                        None
+                    }
+                };
+                if let Some((identifier, version)) = EdifactParser::syntax_identifier(&segment) {
+                    // Without UNA, syntax version 4 implies the default
+                    // repetition separator
+                    if una.is_none() && version.is_some_and(|v| v >= 4) {
+                        separators = Some(Separators::for_syntax_version(4));
+                    }
+                    self.charset = Charset::from_syntax_identifier(&identifier);
+                    if self.charset.is_none() {
+                        self.warnings.push(ParseWarning::unsupported_charset(
+                            segment.position.clone(),
+                            &identifier,
+                        ));
+                    }
+                }
+                if let Some(tree) = self.tree.as_mut() {
+                    tree.open_interchange(&segment, self.interchange.as_ref());
+                    if parser.lossless {
+                        tree.record_una(una);
+                    }
+                }
+                self.current_segments.push(segment);
+            }
+            "UNG" => {
+                // Functional group header
+                if let Some(tree) = self.tree.as_mut() {
+                    tree.open_group(&segment);
+                }
+            }
+            "UNE" => {
+                // Functional group trailer
+                if let Some(tree) = self.tree.as_mut() {
+                    tree.close_group(Some(&segment));
+                }
+            }
+            "UNH" => {
+                // Message header - start new message
+                self.message_ref = match segment.elements.first() {
+                    Some(Element::Simple(value)) => {
+                        Some(String::from_utf8_lossy(value).to_string())
+                    }
+                    _ => None,
+                };
+                self.current_segments.push(segment);
+            }
+            "UNT" => {
+                // Message trailer - complete message
+                self.current_segments.push(segment);
+                self.message_ref = None;
+                // Create document from collected segments
+                if let Some(doc) = parser.build_message(&self.current_segments) {
+                    self.complete(doc);
+                }
+                // Keep UNB for next message if in batch
+                let unb = self.current_segments.drain(..).find(|s| s.tag == "UNB");
+                if let Some(unb) = unb {
+                    self.current_segments.push(unb);
+                }
+            }
+            "UNZ" => {
+                // Interchange trailer - end of interchange
+                if let Some(tree) = self.tree.as_mut() {
+                    tree.close_interchange(Some(&segment));
+                }
+                // Clear for potential next interchange
+                self.current_segments.clear();
+                self.interchange = None;
+                self.charset = None;
+            }
+            _ => {
+                self.current_segments.push(segment);
+            }
+        }
+        separators
+    }
+
+    /// Record a malformed segment starting at byte `start` and skip the
+    /// message it belongs to
+    pub(crate) fn fail(&mut self, error: &Error, start: usize) {
+        if self.failure.is_some() {
+            return;
+        }
+        let (line, column, message) = match error {
+            Error::Parse {
+                line,
+                column,
+                message,
+            } => (*line, *column, message.clone()),
+            other => (0, 0, other.to_string()),
+        };
+
+        let unh = self
+            .current_segments
+            .iter()
+            .position(|segment| segment.tag == "UNH");
+        let message_start = match unh {
+            Some(index) => {
+                let message_start = self.current_segments[index].start_offset();
+                self.current_segments.truncate(index);
+                message_start
+            }
+            None => start,
+        };
+        self.failure = Some(ParseFailure {
+            message_ref: self.message_ref.take(),
+            range: message_start..message_start,
+            position: Position::new(line, column, start, 0),
+            message,
+        });
+    }
+
+    /// Flush the segments left at end of input; `eof_position` is reported
+    /// when the last message has no UNT
+    pub(crate) fn finish(&mut self, parser: &EdifactParser, eof_position: Position) {
+        if let Some(mut failure) = self.failure.take() {
+            failure.range.end = eof_position.offset;
+            self.failures.push(failure);
+        }
+        if self.current_segments.is_empty() {
+            return;
+        }
+
+        let segments = std::mem::take(&mut self.current_segments);
+        let has_unh = segments.iter().any(|segment| segment.tag == "UNH");
+        let has_unt = segments.iter().any(|segment| segment.tag == "UNT");
+
+        // Handle case where input doesn't end with UNT (malformed)
+        if has_unh && !has_unt {
+            if let Some(doc) = parser.build_partial_message(&segments) {
+                let message_ref = doc.metadata.message_refs.first().cloned();
+                self.complete(doc);
+                self.warnings
+                    .push(ParseWarning::missing_unt(eof_position, message_ref));
+            }
+        } else if let Some(doc) = parser.build_message(&segments) {
+            self.complete(doc);
+        }
+    }
+
+    fn complete(&mut self, mut doc: Document) {
+        EdifactParser::attach_interchange(&mut doc.metadata, self.interchange.as_ref());
+        if let Some(una) = &self.raw_una {
+            doc.root
+                .set_attribute(RAW_SEPARATORS_ATTRIBUTE, una.clone());
+        }
+        if let Some(tree) = self.tree.as_mut() {
+            tree.add_message(&doc.root);
+        }
+        self.documents.push_back(doc);
+    }
+
+    /// Collect the parse results; `suffix` is the whitespace after the last
+    /// segment, recorded on the last interchange in lossless mode
+    pub(crate) fn into_outcome(self, suffix: Option<&[u8]>) -> ParseOutcome {
+        ParseOutcome {
+            documents: self.documents.into(),
+            interchanges: self
+                .tree
+                .map(|tree| tree.finish(suffix))
+                .unwrap_or_default(),
+            warnings: self.warnings,
+            failures: self.failures,
+        }
+    }
 }
 
 impl Segment {
+    /// Transcode element data from `charset` to UTF-8.
+    ///
+    /// Returns a warning for every element or component whose bytes are not
+    /// valid in the character set or whose characters fall outside its
+    /// repertoire.
+    pub fn decode_charset(
+        &mut self,
+        charset: Charset,
+        message_ref: Option<&str>,
+    ) -> Vec<ParseWarning> {
+        let mut warnings = Vec::new();
+        let position = self.position.clone();
+        let tag = self.tag.clone();
+        let mut decode = |bytes: &mut Vec<u8>, path: String| {
+            let decoded = charset.decode(bytes);
+            if decoded.had_errors {
+                warnings.push(ParseWarning::charset_decode_error(
+                    position.clone(),
+                    message_ref.map(ToOwned::to_owned),
+                    &path,
+                    charset,
+                ));
+            }
+            let mut invalid: Vec<char> = Vec::new();
+            for ch in charset.invalid_characters(&decoded.text) {
+                if ch != char::REPLACEMENT_CHARACTER && !invalid.contains(&ch) {
+                    invalid.push(ch);
+                }
+            }
+            if !invalid.is_empty() {
+                warnings.push(ParseWarning::outside_repertoire(
+                    position.clone(),
+                    message_ref.map(ToOwned::to_owned),
+                    &path,
+                    charset,
+                    &invalid,
+                ));
+            }
+            *bytes = decoded.text.into_bytes();
+        };
+
+        for (i, element) in self.elements.iter_mut().enumerate() {
+            element.for_each_value_mut(&format!("{}/e{}", tag, i + 1), &mut decode);
+        }
+        if let Some(raw) = self.raw.as_mut() {
+            raw.text = charset.decode(&raw.text).text.into_bytes();
+        }
+
+        warnings
+    }
+
+    /// Rebuild a segment from its IR node, the inverse of [`Self::to_node`].
+    ///
+    /// Elements with `Element` children become repeated elements, elements
+    /// with other children composites.
+    pub fn from_node(node: &Node) -> Self {
+        fn value(node: &Node) -> Vec<u8> {
+            node.value
+                .as_ref()
+                .and_then(Value::as_string)
+                .unwrap_or_default()
+                .into_bytes()
+        }
+        fn element(node: &Node) -> Element {
+            if node.children.is_empty() {
+                Element::Simple(value(node))
+            } else if node
+                .children
+                .iter()
+                .all(|child| child.node_type == NodeType::Element)
+            {
+                Element::Repeated(node.children.iter().map(element).collect())
+            } else {
+                Element::Composite(node.children.iter().map(value).collect())
+            }
+        }
+
+        Self {
+            tag: node.name.clone(),
+            elements: node
+                .children
+                .iter()
+                .filter(|child| child.node_type == NodeType::Element)
+                .map(element)
+                .collect(),
+            position: Position::default(),
+            raw: None,
+            spans: None,
+        }
+    }
+
+    /// Offset of the segment tag in the source
+    fn start_offset(&self) -> usize {
+        self.position.offset.saturating_sub(self.tag.len())
+    }
+
     /// Convert this segment to an IR Node
     pub fn to_node(&self) -> Node {
         let mut node = Node::new(&self.tag, NodeType::Segment);
-        node.set_attribute("source_line", self.position.line.to_string());
-        node.set_attribute("source_column", self.position.column.to_string());
+        if let Some(raw) = &self.raw {
+            node.set_attribute(RAW_SEGMENT_ATTRIBUTE, String::from_utf8_lossy(&raw.text));
+            node.set_attribute(RAW_PREFIX_ATTRIBUTE, String::from_utf8_lossy(&raw.prefix));
+        }
+        if let Some(spans) = &self.spans {
+            node.set_span(spans.segment);
+        }
 
         for (i, element) in self.elements.iter().enumerate() {
-            let elem_node = match element {
-                Element::Simple(value) => Node::with_value(
-                    format!("e{}", i + 1),
-                    NodeType::Element,
-                    Value::String(String::from_utf8_lossy(value).to_string()),
-                ),
-                Element::Composite(components) => {
-                    let mut n = Node::new(format!("e{}", i + 1), NodeType::Element);
-                    for (j, comp) in components.iter().enumerate() {
-                        let comp_node = Node::with_value(
-                            format!("c{}", j + 1),
-                            NodeType::Component,
-                            Value::String(String::from_utf8_lossy(comp).to_string()),
-                        );
-                        n.add_child(comp_node);
-                    }
-                    n
-                }
-            };
-            node.add_child(elem_node);
+            let mut child = element.to_node(format!("e{}", i + 1));
+            if let Some(span) = self.spans.as_ref().and_then(|spans| spans.elements.get(i)) {
+                span.apply(&mut child);
+            }
+            node.add_child(child);
         }
 
         node
@@ -98,6 +842,8 @@ impl Segment {
 pub struct SegmentParser<'a> {
     buffer: SyntaxBuffer<'a>,
     _source_name: String,
+    record_raw: bool,
+    segment_start: usize,
 }
 
 impl<'a> SegmentParser<'a> {
@@ -106,9 +852,16 @@ impl<'a> SegmentParser<'a> {
         Self {
             buffer: SyntaxBuffer::new(data),
             _source_name: source_name.into(),
+            record_raw: false,
+            segment_start: 0,
         }
     }
 
+    /// Record the source text of every segment in [`Segment::raw`]
+    pub fn set_record_raw(&mut self, record_raw: bool) {
+        self.record_raw = record_raw;
+    }
+
     /// Check if input starts with UNA and parse separators
     pub fn parse_una(&mut self) -> Option<Separators> {
         // Check for UNA at the start (must be at position 0)
@@ -127,10 +880,23 @@ impl<'a> SegmentParser<'a> {
         None
     }
 
+    /// Separators currently used for parsing
+    pub fn separators(&self) -> Separators {
+        self.buffer.separators
+    }
+
+    /// Replace the separators used for the following segments
+    pub fn set_separators(&mut self, separators: Separators) {
+        self.buffer.set_separators(separators);
+    }
+
     /// Parse the next segment
     pub fn next_segment(&mut self) -> Option<Result<Segment>> {
         // Skip any whitespace/newlines between segments
+        let prefix_start = self.buffer.position();
         self.skip_whitespace();
+        let start = self.buffer.position();
+        self.segment_start = start;
 
         if self.buffer.is_empty() {
             return None;
@@ -174,32 +940,38 @@ impl<'a> SegmentParser<'a> {
 
         let tag = String::from_utf8_lossy(&tag_bytes).to_string();
 
+        let mut cursor = LineCursor {
+            offset: start,
+            line: tag_line,
+            column: tag_column,
+        };
+        let segment_span = cursor.span_at(self.buffer.data, start);
+
         // UNA is special - no elements
         if tag == "UNA" {
             return Some(Ok(Segment {
                 tag,
                 elements: vec![],
                 position,
+                raw: self.raw_since(prefix_start, start),
+                spans: Some(SegmentSpans {
+                    segment: span_until(segment_span, self.buffer.position()),
+                    elements: Vec::new(),
+                }),
             }));
         }
 
         // Parse elements until segment terminator
         let mut elements = Vec::new();
         let mut components = Vec::new();
-        let push_simple_element =
-            |components: &mut Vec<Vec<u8>>, elements: &mut Vec<Element>| -> Result<()> {
-                match components.pop() {
-                    Some(component) => {
-                        elements.push(Element::Simple(component));
-                        Ok(())
-                    }
-                    None => Err(Error::Parse {
-                        line: post_tag_line,
-                        column: post_tag_column,
-                        message: "Missing component for simple element".to_string(),
-                    }),
-                }
-            };
+        let mut occurrences = Vec::new();
+
+        // Spans mirroring `elements`, `components` and `occurrences`
+        let mut element_spans = Vec::new();
+        let mut component_spans = Vec::new();
+        let mut occurrence_spans = Vec::new();
+        let mut element_start = segment_span;
+        let mut occurrence_start = segment_span;
 
         // Skip the element separator immediately after the tag (if present)
         if self.buffer.peek() == Some(self.buffer.separators.element) {
@@ -207,23 +979,35 @@ impl<'a> SegmentParser<'a> {
         }
 
         loop {
+            let value_start = cursor.span_at(self.buffer.data, self.buffer.position());
+            if components.is_empty() {
+                occurrence_start = value_start;
+                if occurrences.is_empty() {
+                    element_start = value_start;
+                }
+            }
             let (value, delimiter) = self.buffer.read_until_delimiter();
+            let value_end = self.buffer.position() - usize::from(delimiter.is_some());
+            let value_span = span_until(value_start, value_end);
 
             // Handle empty values - when we get an empty value with a delimiter,
             // we need to determine if it's an empty element or empty component
-            if value.is_empty() && components.is_empty() {
+            if value.is_empty() && components.is_empty() && occurrences.is_empty() {
                 if let Some(d) = delimiter {
                     if d == self.buffer.separators.element {
                         // Empty element followed by element separator
                         elements.push(Element::Simple(Vec::new()));
+                        element_spans.push(ElementSpan::leaf(value_span));
                         continue;
                     } else if d == self.buffer.separators.component {
                         // Empty element followed by component separator
                         // This means we have an empty simple element, then a composite starts
                         // Add the empty simple element first
                         elements.push(Element::Simple(Vec::new()));
+                        element_spans.push(ElementSpan::leaf(value_span));
                         // Now start the composite with an empty first component
                         components.push(Vec::new());
+                        component_spans.push(ElementSpan::leaf(value_span));
                         continue;
                     } else if d == self.buffer.separators.segment {
                         // Empty segment or trailing empty element
@@ -233,6 +1017,7 @@ impl<'a> SegmentParser<'a> {
                         }
                         // Trailing empty element before terminator
                         elements.push(Element::Simple(Vec::new()));
+                        element_spans.push(ElementSpan::leaf(value_span));
                         break;
                     }
                 }
@@ -242,43 +1027,47 @@ impl<'a> SegmentParser<'a> {
                 Some(d) if d == self.buffer.separators.component => {
                     // Component separator - add current value as a component
                     components.push(value);
+                    component_spans.push(ElementSpan::leaf(value_span));
                 }
-                Some(d) if d == self.buffer.separators.element => {
-                    // Element separator - finish current element
+                Some(d) if Some(d) == self.buffer.separators.repetition => {
+                    // Repetition separator - finish the current occurrence
                     components.push(value);
-                    if components.len() == 1 {
-                        // Simple element (single component)
-                        if let Err(err) = push_simple_element(&mut components, &mut elements) {
-                            return Some(Err(err));
-                        }
-                    } else {
-                        // Composite element (multiple components)
-                        elements.push(Element::Composite(std::mem::take(&mut components)));
-                    }
-                    components.clear();
+                    component_spans.push(ElementSpan::leaf(value_span));
+                    occurrences.push(Element::from_components(&mut components));
+                    occurrence_spans.push(ElementSpan::occurrence(
+                        span_until(occurrence_start, value_end),
+                        &mut component_spans,
+                    ));
                 }
-                Some(d) if d == self.buffer.separators.segment => {
-                    // Segment terminator - finish last element
+                Some(d)
+                    if d == self.buffer.separators.element
+                        || d == self.buffer.separators.segment =>
+                {
+                    // Element separator or segment terminator - finish current element
                     components.push(value);
-                    if components.len() == 1 {
-                        if let Err(err) = push_simple_element(&mut components, &mut elements) {
-                            return Some(Err(err));
-                        }
-                    } else {
-                        elements.push(Element::Composite(components));
+                    component_spans.push(ElementSpan::leaf(value_span));
+                    elements.push(Element::finish(&mut components, &mut occurrences));
+                    element_spans.push(ElementSpan::finish(
+                        span_until(element_start, value_end),
+                        span_until(occurrence_start, value_end),
+                        &mut component_spans,
+                        &mut occurrence_spans,
+                    ));
+                    if d == self.buffer.separators.segment {
+                        break;
                     }
-                    break;
                 }
                 None => {
                     // End of input without segment terminator
                     components.push(value);
-                    if components.len() == 1 {
-                        if let Err(err) = push_simple_element(&mut components, &mut elements) {
-                            return Some(Err(err));
-                        }
-                    } else {
-                        elements.push(Element::Composite(components));
-                    }
+                    component_spans.push(ElementSpan::leaf(value_span));
+                    elements.push(Element::finish(&mut components, &mut occurrences));
+                    element_spans.push(ElementSpan::finish(
+                        span_until(element_start, value_end),
+                        span_until(occurrence_start, value_end),
+                        &mut component_spans,
+                        &mut occurrence_spans,
+                    ));
                     break;
                 }
                 _ => {
@@ -295,9 +1084,55 @@ impl<'a> SegmentParser<'a> {
             tag,
             elements,
             position,
+            raw: self.raw_since(prefix_start, start),
+            spans: Some(SegmentSpans {
+                segment: span_until(segment_span, self.buffer.position()),
+                elements: element_spans,
+            }),
         }))
     }
 
+    /// Source text between `prefix_start` and the current position, split
+    /// at `start` where the segment tag begins
+    fn raw_since(&self, prefix_start: usize, start: usize) -> Option<RawSegment> {
+        self.record_raw.then(|| RawSegment {
+            prefix: self.buffer.data[prefix_start..start].to_vec(),
+            text: self.buffer.data[start..self.buffer.position()].to_vec(),
+        })
+    }
+
+    /// Skip the malformed segment returned by the last call to
+    /// [`Self::next_segment`], up to its next unreleased segment terminator or
+    /// up to the next line starting with UNH, whichever comes first.
+    ///
+    /// Returns the offset the malformed segment starts at.
+    pub fn resync(&mut self) -> usize {
+        let start = self.segment_start;
+        let data = self.buffer.data;
+        let separators = self.buffer.separators;
+        let mut index = start;
+        while index < data.len() {
+            let byte = data[index];
+            if byte == separators.release {
+                index += 2;
+                continue;
+            }
+            if byte == separators.segment {
+                index += 1;
+                break;
+            }
+            if index > start
+                && matches!(data[index - 1], b'\n' | b'\r')
+                && data[index..].starts_with(b"UNH")
+            {
+                break;
+            }
+            index += 1;
+        }
+        self.buffer.pos = index.min(data.len());
+        start
+    }
+
     fn skip_whitespace(&mut self) {
         while let Some(b) = self.buffer.peek() {
             if b == b' ' || b == b'\n' || b == b'\r' || b == b'\t' {
@@ -321,19 +1156,91 @@ impl<'a> SegmentParser<'a> {
 }
 
 /// Streaming EDIFACT parser that yields messages one at a time
-pub struct EdifactParser;
+#[derive(Debug, Clone)]
+pub struct EdifactParser {
+    diagrams: HashMap<String, BranchingDiagram>,
+    lossless: bool,
+    recover: bool,
+}
 
 impl EdifactParser {
     /// Create a new EDIFACT parser
     pub fn new() -> Self {
-        Self
+        Self {
+            diagrams: HashMap::new(),
+            lossless: false,
+            recover: false,
+        }
+    }
+
+    /// Keep parsing after a malformed segment instead of failing.
+    ///
+    /// The parser resynchronises at the next segment terminator (or the next
+    /// line starting with UNH), skips the rest of the damaged message and
+    /// records it in [`ParseOutcome::failures`] with its source byte range.
+    pub fn with_error_recovery(mut self, recover: bool) -> Self {
+        self.recover = recover;
+        self
+    }
+
+    /// Whether malformed segments are skipped instead of failing the parse
+    pub fn recovers_from_errors(&self) -> bool {
+        self.recover
+    }
+
+    /// Record the source text of every segment, the UNA and the whitespace
+    /// between segments as node attributes, so that
+    /// [`EdifactSerializer::with_lossless`](crate::EdifactSerializer::with_lossless)
+    /// can reproduce the input byte for byte.
+    pub fn with_lossless(mut self, lossless: bool) -> Self {
+        self.lossless = lossless;
+        self
+    }
+
+    /// Whether segment source text is recorded
+    pub fn is_lossless(&self) -> bool {
+        self.lossless
+    }
+
+    /// Register the branching diagram used to nest segment groups for
+    /// `message_type` (e.g. "ORDERS"). Messages without a registered diagram
+    /// keep the built-in `LINE_ITEM` grouping or stay flat.
+    pub fn with_branching_diagram(
+        mut self,
+        message_type: impl Into<String>,
+        diagram: BranchingDiagram,
+    ) -> Self {
+        self.diagrams.insert(message_type.into(), diagram);
+        self
+    }
+
+    /// Register the segment group hierarchy described by `schema` for
+    /// `message_type`.
+    pub fn with_schema(self, message_type: impl Into<String>, schema: &Schema) -> Self {
+        self.with_branching_diagram(message_type, BranchingDiagram::from(schema))
+    }
+
+    /// Branching diagram registered for `message_type`, if any
+    pub fn branching_diagram(&self, message_type: &str) -> Option<&BranchingDiagram> {
+        self.diagrams.get(message_type)
     }
 
     /// Parse a complete EDIFACT document and return all messages
     pub fn parse(&self, data: &[u8], source_name: impl Into<String>) -> Result<Vec<Document>> {
         let source_name = source_name.into();
-        let outcome = self.parse_with_warnings(data, &source_name)?;
+        let outcome = self.parse_outcome(data, &source_name, false)?;
 
+        for failure in &outcome.failures {
+            warn!(
+                source = %source_name,
+                message_ref = failure.message_ref.as_deref().unwrap_or("unknown"),
+                line = failure.position.line,
+                column = failure.position.column,
+                offset = failure.position.offset,
+                "Skipped damaged message: {}",
+                failure.message
+            );
+        }
         for warning in &outcome.warnings {
             warn!(
                 source = %source_name,
@@ -355,86 +1262,57 @@ impl EdifactParser {
         data: &[u8],
         source_name: impl Into<String>,
     ) -> Result<ParseOutcome> {
-        let source_name = source_name.into();
-        let mut documents = Vec::new();
-        let mut warnings = Vec::new();
+        self.parse_outcome(data, &source_name.into(), true)
+    }
 
+    /// Parse `data`; `build_interchanges` also assembles
+    /// [`ParseOutcome::interchanges`], which copies every message
+    fn parse_outcome(
+        &self,
+        data: &[u8],
+        source_name: &str,
+        build_interchanges: bool,
+    ) -> Result<ParseOutcome> {
         // Parse segments
-        let mut parser = SegmentParser::new(data, &source_name);
+        let mut parser = SegmentParser::new(data, source_name);
 
-        // Check for UNA
-        let _una = parser.parse_una();
+        parser.set_record_raw(self.lossless);
 
-        let mut current_segments: Vec<Segment> = Vec::new();
-        let mut _interchange_ref: Option<String> = None;
-        let mut _message_count = 0;
+        // Check for UNA
+        let una = parser.parse_una();
 
+        let mut assembler = MessageAssembler::new(build_interchanges);
+        let mut end = data.len() - parser.remaining().len();
         while let Some(result) = parser.next_segment() {
-            let segment = result?;
-
-            match segment.tag.as_str() {
-                "UNB" => {
-                    // Interchange header
-                    if let Some(Element::Simple(_sender)) = segment.elements.get(1) {
-                        if let Some(Element::Simple(id)) = segment.elements.get(4) {
-                            _interchange_ref = Some(String::from_utf8_lossy(id).to_string());
-                        }
-                    }
-                    current_segments.push(segment);
-                }
-                "UNH" => {
-                    // Message header - start new message
-                    _message_count += 1;
-                    current_segments.push(segment);
-                }
-                "UNT" => {
-                    // Message trailer - complete message
-                    current_segments.push(segment);
-                    // Create document from collected segments
-                    if let Some(doc) = self.build_message(&current_segments) {
-                        documents.push(doc);
-                    }
-                    // Keep UNB for next message if in batch
-                    let unb = current_segments.drain(..).find(|s| s.tag == "UNB");
-                    if let Some(unb) = unb {
-                        current_segments.push(unb);
-                    }
-                }
-                "UNZ" => {
-                    // Interchange trailer - end of interchange
-                    current_segments.push(segment);
-                    // Clear for potential next interchange
-                    current_segments.clear();
-                    _interchange_ref = None;
-                }
-                _ => {
-                    current_segments.push(segment);
+            let segment = match result {
+                Ok(segment) => segment,
+                Err(error @ Error::Parse { .. }) if self.recover => {
+                    let start = parser.resync();
+                    assembler.fail(&error, start);
+                    end = data.len() - parser.remaining().len();
+                    continue;
                 }
+                Err(error) => return Err(error),
+            };
+            end = data.len() - parser.remaining().len();
+            if let Some(separators) = assembler.push(self, segment, una) {
+                parser.set_separators(separators);
             }
         }
+        assembler.finish(self, parser.current_position());
 
-        let eof_position = parser.current_position();
-
-        // Handle case where file doesn't end with UNT (malformed)
-        if !current_segments.is_empty() {
-            let has_unh = current_segments.iter().any(|segment| segment.tag == "UNH");
-            let has_unt = current_segments.iter().any(|segment| segment.tag == "UNT");
-
-            if has_unh && !has_unt {
-                if let Some(doc) = self.build_partial_message(&current_segments) {
-                    let message_ref = doc.metadata.message_refs.first().cloned();
-                    documents.push(doc);
-                    warnings.push(ParseWarning::missing_unt(eof_position, message_ref));
-                }
-            } else if let Some(doc) = self.build_message(&current_segments) {
-                documents.push(doc);
-            }
-        }
+        let suffix = self.lossless.then(|| &data[end..]);
+        Ok(assembler.into_outcome(suffix))
+    }
 
-        Ok(ParseOutcome {
-            documents,
-            warnings,
-        })
+    /// Parse a complete EDIFACT document and return one document per
+    /// interchange, preserving UNB/UNZ and UNG/UNE envelopes.
+    pub fn parse_interchanges(
+        &self,
+        data: &[u8],
+        source_name: impl Into<String>,
+    ) -> Result<Vec<Document>> {
+        Ok(self.parse_with_warnings(data, source_name)?.interchanges)
     }
 
     /// Parse a single message from a byte slice
@@ -480,18 +1358,15 @@ impl EdifactParser {
         // Build document root
         let mut root = Node::new("MESSAGE", NodeType::Message);
 
-        let children = if Self::needs_line_item_grouping(message_type.as_deref()) {
-            Self::group_line_items(message_segments)
-        } else {
-            message_segments
-                .iter()
-                .map(Segment::to_node)
-                .collect::<Vec<_>>()
-        };
+        let children = self.group_segments(
+            message_type.as_deref(),
+            message_segments.iter().map(Segment::to_node),
+        );
 
         for child in children {
             root.add_child(child);
         }
+        number_segments(&mut root, &mut 0);
 
         let mut metadata = DocumentMetadata {
             doc_type: message_type,
@@ -505,6 +1380,38 @@ impl EdifactParser {
         Some(Document::with_metadata(root, metadata))
     }
 
+    /// Nest the segment nodes of a message with the branching diagram
+    /// registered for `message_type`, into `LINE_ITEM` groups, or not at all
+    pub(crate) fn group_segments<N: MessageNode>(
+        &self,
+        message_type: Option<&str>,
+        segments: impl IntoIterator<Item = N>,
+    ) -> Vec<N> {
+        if let Some(diagram) =
+            message_type.and_then(|message_type| self.branching_diagram(message_type))
+        {
+            diagram.nest(segments)
+        } else if Self::needs_line_item_grouping(message_type) {
+            Self::group_line_items(segments)
+        } else {
+            segments.into_iter().collect()
+        }
+    }
+
+    /// Record the enclosing interchange in the document metadata; the
+    /// sender identification doubles as the partner id
+    pub(crate) fn attach_interchange(
+        metadata: &mut DocumentMetadata,
+        interchange: Option<&InterchangeInfo>,
+    ) {
+        let Some(interchange) = interchange else {
+            return;
+        };
+        metadata.interchange_ref = Some(interchange.control_ref.clone());
+        metadata.partner_id = Some(interchange.sender.id.clone());
+        metadata.interchange = Some(interchange.clone());
+    }
+
     /// Returns `true` for message types whose LIN loops should be wrapped in
     /// `LINE_ITEM` segment groups. Add new line-item message types to the
     /// `LINE_ITEM_MESSAGE_TYPES` array.
@@ -517,6 +1424,18 @@ impl EdifactParser {
         matches!(tag, "UNS" | "CNT" | "UNT")
     }
 
+    /// Syntax identifier (e.g. "UNOC") and version number from the first
+    /// UNB element
+    fn syntax_identifier(unb: &Segment) -> Option<(String, Option<u8>)> {
+        let (identifier, version) = match unb.elements.first()? {
+            Element::Simple(value) => (value, None),
+            Element::Composite(components) => (components.first()?, components.get(1)),
+            Element::Repeated(_) => return None,
+        };
+        let version = version.and_then(|v| String::from_utf8_lossy(v).parse().ok());
+        Some((String::from_utf8_lossy(identifier).to_string(), version))
+    }
+
     fn message_info(segments: &[Segment]) -> (Option<String>, Option<String>, Option<String>) {
         let mut message_type = None;
         let mut version = None;
@@ -551,32 +1470,32 @@ impl EdifactParser {
         (message_type, version, message_ref)
     }
 
-    fn group_line_items(segments: &[Segment]) -> Vec<Node> {
+    fn group_line_items<N: MessageNode>(segments: impl IntoIterator<Item = N>) -> Vec<N> {
         let mut children = Vec::new();
-        let mut current_group: Option<Node> = None;
+        let mut current_group: Option<N> = None;
 
         for segment in segments {
-            match segment.tag.as_str() {
+            match segment.tag() {
                 "LIN" => {
                     if let Some(group) = current_group.take() {
                         children.push(group);
                     }
 
-                    let mut group = Node::new("LINE_ITEM", NodeType::SegmentGroup);
-                    group.add_child(segment.to_node());
+                    let mut group = N::new_node("LINE_ITEM", NodeType::SegmentGroup);
+                    group.push_child(segment);
                     current_group = Some(group);
                 }
                 tag if Self::is_line_item_group_boundary(tag) => {
                     if let Some(group) = current_group.take() {
                         children.push(group);
                     }
-                    children.push(segment.to_node());
+                    children.push(segment);
                 }
                 _ => {
                     if let Some(group) = current_group.as_mut() {
-                        group.add_child(segment.to_node());
+                        group.push_child(segment);
                     } else {
-                        children.push(segment.to_node());
+                        children.push(segment);
                     }
                 }
             }
@@ -590,6 +1509,19 @@ impl EdifactParser {
     }
 }
 
+/// Record on every segment below `node` its index within the message, UNH
+/// being 1, in document order
+pub(crate) fn number_segments<N: MessageNode>(node: &mut N, count: &mut usize) {
+    for child in node.children_mut() {
+        if child.node_type() == NodeType::Segment {
+            *count += 1;
+            child.set_segment_index(*count);
+        } else {
+            number_segments(child, count);
+        }
+    }
+}
+
 impl Default for EdifactParser {
     fn default() -> Self {
         Self::new()
@@ -681,11 +1613,80 @@ UNT+5+1'";
                 Element::Simple(b"9".to_vec()),
             ],
             position: Position::new(1, 1, 0, 0),
+            raw: None,
+            spans: None,
         };
 
         let node = segment.to_node();
         assert_eq!(node.name, "BGM");
         assert_eq!(node.children.len(), 3);
+        assert!(node.span.is_none());
+    }
+
+    #[test]
+    fn test_segment_spans_cover_elements_and_components() {
+        let data = b"UNA:+.? '\r\nBGM+220+PO?+1:X+A*B:C'";
+        let mut parser = SegmentParser::new(data, "test");
+        let separators = parser.parse_una().unwrap();
+        parser.set_separators(Separators {
+            repetition: Some(b'*'),
+            ..separators
+        });
+        let node = parser.next_segment().unwrap().unwrap().to_node();
+
+        let segment = node.span.unwrap();
+        assert_eq!((segment.line, segment.column), (2, 1));
+        assert_eq!(segment.slice(data), Some(&b"BGM+220+PO?+1:X+A*B:C'"[..]));
+
+        let e2 = &node.children[1];
+        assert_eq!(e2.span.unwrap().slice(data), Some(&b"PO?+1:X"[..]));
+        let c1 = e2.children[0].span.unwrap();
+        assert_eq!(c1.slice(data), Some(&b"PO?+1"[..]));
+        assert_eq!((c1.offset, c1.length, c1.line, c1.column), (19, 5, 2, 9));
+        assert_eq!(e2.children[1].span.unwrap().column, 15);
+
+        let e3 = &node.children[2];
+        assert_eq!(e3.span.unwrap().slice(data), Some(&b"A*B:C"[..]));
+        assert_eq!(e3.children[0].span.unwrap().slice(data), Some(&b"A"[..]));
+        let r2 = &e3.children[1];
+        assert_eq!(r2.span.unwrap().slice(data), Some(&b"B:C"[..]));
+        assert_eq!(r2.children[1].span.unwrap().slice(data), Some(&b"C"[..]));
+    }
+
+    #[test]
+    fn test_message_segments_carry_segment_index() {
+        let data = b"UNH+1+ORDERS:D:96A:UN'BGM+220+PO1'DTM+137:20240101:102'UNT+4+1'";
+        let docs = EdifactParser::new().parse(data, "test").unwrap();
+        let root = &docs[0].root;
+
+        let dtm = root.find_child("DTM").unwrap();
+        assert_eq!(dtm.span.unwrap().segment_index, Some(3));
+        assert_eq!(
+            dtm.children[0].children[1].span.unwrap().segment_index,
+            Some(3)
+        );
+        assert_eq!(
+            dtm.children[0].children[1].span.unwrap().slice(data),
+            Some(&b"20240101"[..])
+        );
+        assert!(!dtm.attributes.contains_key("source_line"));
+    }
+
+    #[test]
+    fn test_segment_from_node_inverts_to_node() {
+        let data =
+            b"UNA:+.?*'UNB+UNOC:4+SENDER:14+RECEIVER+20200101:1200+1'\nFTX+AAI+++A:B*C++D?+E'";
+        let mut parser = SegmentParser::new(data, "test");
+        parser.parse_una();
+        while let Some(segment) = parser.next_segment() {
+            let segment = segment.unwrap();
+            let rebuilt = Segment::from_node(&segment.to_node());
+            assert_eq!(rebuilt.tag, segment.tag);
+            assert_eq!(
+                format!("{:?}", rebuilt.elements),
+                format!("{:?}", segment.elements)
+            );
+        }
     }
 
     #[test]
@@ -883,6 +1884,47 @@ UNT+7+1'";
         );
     }
 
+    #[test]
+    fn test_branching_diagram_replaces_line_item_grouping() {
+        use crate::groups::SegmentGroup;
+
+        let data = b"UNH+1+ORDERS:D:96A:UN'\
+BGM+220+PO123+9'\
+NAD+BY+111::9'\
+CTA+PD'\
+LIN+1++123456789:EN'\
+QTY+21:10'\
+UNS+S'\
+UNT+8+1'";
+
+        let diagram = BranchingDiagram::new()
+            .with_segment("BGM")
+            .with_group(SegmentGroup::new("SG2", "NAD").with_group(SegmentGroup::new("SG5", "CTA")))
+            .with_group(SegmentGroup::new("SG25", "LIN").with_segment("QTY"))
+            .with_segment("UNS");
+        let parser = EdifactParser::new().with_branching_diagram("ORDERS", diagram);
+
+        let doc = parser.parse_message(data, "test").unwrap();
+        let names: Vec<&str> = doc
+            .root
+            .children
+            .iter()
+            .map(|node| node.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["UNH", "BGM", "SG2", "SG25", "UNS", "UNT"]);
+        assert_eq!(doc.root.children[2].children[1].name, "SG5");
+        assert_eq!(doc.root.children[3].children.len(), 2);
+
+        let fallback = EdifactParser::new().parse_message(data, "test").unwrap();
+        assert!(
+            fallback
+                .root
+                .children
+                .iter()
+                .any(|node| node.name == "LINE_ITEM")
+        );
+    }
+
     #[test]
     fn test_empty_element_handling() {
         // Test NAD+BY++12345 - empty qualifier between party code and GLN
@@ -1125,6 +2167,7 @@ UNT+7+1'";
 
         assert_eq!(outcome.warnings.len(), 1);
         let warning = &outcome.warnings[0];
+        assert_eq!(warning.code, "MISSING_UNT");
         assert!(warning.message.contains("UNT"));
         assert_eq!(warning.message_ref.as_deref(), Some("1"));
         assert_eq!(warning.position.line, 1);
@@ -1134,4 +2177,351 @@ UNT+7+1'";
         let docs = parser.parse(data, "test").unwrap();
         assert_eq!(docs.len(), 1);
     }
+
+    #[test]
+    fn test_unreadable_unb_is_reported() {
+        let data = b"UNB+UNOC:3+SENDER'UNH+1+ORDERS:D:96A:UN'BGM+220+12345+9'UNT+3+1'UNZ+1+1'";
+        let outcome = EdifactParser::new()
+            .parse_with_warnings(data, "test")
+            .unwrap();
+
+        assert_eq!(outcome.documents.len(), 1);
+        assert!(outcome.documents[0].metadata.interchange.is_none());
+        assert_eq!(outcome.warnings.len(), 1);
+        let warning = &outcome.warnings[0];
+        assert_eq!(warning.code, "INVALID_UNB");
+        assert!(warning.message.contains("at least 5 elements"));
+        assert_eq!(warning.position.line, 1);
+    }
+
+    fn nad_values(document: &Document) -> Vec<String> {
+        let nad = document
+            .root
+            .children
+            .iter()
+            .find(|node| node.name == "NAD")
+            .expect("NAD segment");
+        nad.children
+            .iter()
+            .map(|element| {
+                element
+                    .value
+                    .as_ref()
+                    .and_then(Value::as_string)
+                    .unwrap_or_default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_decode_element_data_per_syntax_identifier() {
+        let parser = EdifactParser::new();
+
+        let latin1 = b"UNB+UNOC:3+S+R+200101:1200+1'UNH+1+INVOIC:D:96A:UN'\
+NAD+BY+++M\xfcller+K\xf6ln'UNT+3+1'UNZ+1+1'";
+        let outcome = parser.parse_with_warnings(latin1, "test").unwrap();
+        assert!(outcome.warnings.is_empty());
+        assert_eq!(nad_values(&outcome.documents[0])[3..], ["Müller", "Köln"]);
+
+        let cyrillic = b"UNB+UNOE:3+S+R+200101:1200+1'UNH+1+INVOIC:D:96A:UN'\
+NAD+BY+++\xbc\xde\xe1\xda\xd2\xd0'UNT+3+1'UNZ+1+1'";
+        let outcome = parser.parse_with_warnings(cyrillic, "test").unwrap();
+        assert_eq!(nad_values(&outcome.documents[0])[3], "Москва");
+
+        let utf8 = "UNB+UNOY:4+S+R+20200101:1200+1'UNH+1+INVOIC:D:96A:UN'\
+NAD+BY+++東京'UNT+3+1'UNZ+1+1'";
+        let outcome = parser.parse_with_warnings(utf8.as_bytes(), "test").unwrap();
+        assert!(outcome.warnings.is_empty());
+        assert_eq!(nad_values(&outcome.documents[0])[3], "東京");
+    }
+
+    #[test]
+    fn test_characters_outside_repertoire_are_reported() {
+        let data = b"UNB+UNOA:3+S+R+200101:1200+1'UNH+7+INVOIC:D:96A:UN'\
+NAD+BY+123::9++Acme'UNT+3+7'UNZ+1+1'";
+        let outcome = EdifactParser::new()
+            .parse_with_warnings(data, "test")
+            .unwrap();
+
+        assert_eq!(outcome.warnings.len(), 1);
+        let warning = &outcome.warnings[0];
+        assert_eq!(warning.code, "CHARACTER_OUTSIDE_REPERTOIRE");
+        assert_eq!(warning.message_ref.as_deref(), Some("7"));
+        assert!(warning.message.contains("'NAD/e4'"));
+        assert!(warning.message.contains("UNOA"));
+        assert!(
+            warning
+                .message
+                .contains("'c' (U+0063), 'm' (U+006D), 'e' (U+0065)")
+        );
+    }
+
+    #[test]
+    fn test_malformed_and_unsupported_charsets_are_reported() {
+        let malformed = b"UNB+UNOY:4+S+R+20200101:1200+1'UNH+1+INVOIC:D:96A:UN'\
+NAD+BY+++Zo\xeb'UNT+3+1'UNZ+1+1'";
+        let outcome = EdifactParser::new()
+            .parse_with_warnings(malformed, "test")
+            .unwrap();
+        assert_eq!(outcome.warnings.len(), 1);
+        assert_eq!(outcome.warnings[0].code, "CHARSET_DECODE_ERROR");
+        assert!(outcome.warnings[0].message.contains("'NAD/e4'"));
+
+        let unsupported = b"UNB+UNOX:4+S+R+20200101:1200+1'UNH+1+INVOIC:D:96A:UN'\
+UNT+2+1'UNZ+1+1'";
+        let outcome = EdifactParser::new()
+            .parse_with_warnings(unsupported, "test")
+            .unwrap();
+        assert_eq!(outcome.warnings.len(), 1);
+        assert_eq!(outcome.warnings[0].code, "UNSUPPORTED_CHARSET");
+    }
+
+    #[test]
+    fn test_parse_repeated_elements_with_una_repetition_separator() {
+        let data = b"UNA:+.?*'NAD+BY+123::9*456::9+A?*B'";
+        let mut parser = SegmentParser::new(data, "test");
+        assert_eq!(
+            parser.parse_una().and_then(|sep| sep.repetition),
+            Some(b'*')
+        );
+
+        let segment = parser.next_segment().unwrap().unwrap();
+        assert_eq!(segment.elements.len(), 3);
+        match &segment.elements[1] {
+            Element::Repeated(occurrences) => {
+                assert_eq!(occurrences.len(), 2);
+                assert!(matches!(&occurrences[1], Element::Composite(comps) if comps[0] == b"456"));
+            }
+            other => panic!("Expected repeated element, got {other:?}"),
+        }
+        assert!(matches!(&segment.elements[2], Element::Simple(value) if value == b"A*B"));
+        assert_eq!(segment.elements[0].occurrences().len(), 1);
+
+        let node = segment.to_node();
+        let repeated = &node.children[1];
+        assert_eq!(repeated.node_type, NodeType::Element);
+        assert_eq!(
+            repeated
+                .children
+                .iter()
+                .map(|child| child.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["r1", "r2"]
+        );
+        assert_eq!(repeated.children[1].children[0].name, "c1");
+    }
+
+    #[test]
+    fn test_syntax_version_4_defaults_repetition_separator_without_una() {
+        let data = b"UNB+UNOC:4+S+R+20240131:0930+1'UNH+1+ORDERS:D:01B:UN'\
+FTX+AAI+++LINE 1*LINE 2'UNT+3+1'UNZ+1+1'";
+        let docs = EdifactParser::new().parse(data, "test").unwrap();
+        let ftx = docs[0]
+            .root
+            .children
+            .iter()
+            .find(|node| node.name == "FTX")
+            .unwrap();
+        let text = &ftx.children[3];
+        assert_eq!(text.children.len(), 2);
+        assert_eq!(
+            text.children[1].value.as_ref().and_then(Value::as_string),
+            Some("LINE 2".to_string())
+        );
+
+        let data = b"UNB+UNOC:3+S+R+240131:0930+1'UNH+1+ORDERS:D:96A:UN'\
+FTX+AAI+++5*6'UNT+3+1'UNZ+1+1'";
+        let docs = EdifactParser::new().parse(data, "test").unwrap();
+        let ftx = &docs[0].root.children[1];
+        assert_eq!(
+            ftx.children[3].value.as_ref().and_then(Value::as_string),
+            Some("5*6".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_interchanges_without_functional_groups() {
+        let data = b"UNB+UNOA:3+S+R+200101:1200+1'UNH+1+ORDERS:D:96A:UN'BGM+220+PO1'UNT+3+1'\
+UNZ+1+1'UNB+UNOA:3+S+R+200101:1200+2'UNH+1+INVOIC:D:96A:UN'UNT+2+1'UNZ+1+2'\
+UNH+9+DESADV:D:96A:UN'UNT+2+9'";
+        let parser = EdifactParser::new();
+
+        let interchanges = parser.parse_interchanges(data, "test").unwrap();
+        assert_eq!(interchanges.len(), 2);
+        let names: Vec<&str> = interchanges[0]
+            .root
+            .children
+            .iter()
+            .map(|node| node.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["UNB", "MESSAGE", "UNZ"]);
+        assert_eq!(
+            interchanges[1].root.children[1].node_type,
+            NodeType::Message
+        );
+
+        // The trailing message without UNB is still returned on its own
+        assert_eq!(parser.parse(data, "test").unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_messages_carry_interchange_metadata() {
+        let data =
+            b"UNB+UNOA:3+5412345000013:14+4012345000016:14:INT+200101:1200+REF42+ORDERS++++1'\
+UNH+1+ORDERS:D:96A:UN'BGM+220+PO1'UNT+3+1'UNH+2+ORDERS:D:96A:UN'BGM+220+PO2'UNT+3+2'UNZ+2+REF42'";
+        let outcome = EdifactParser::new()
+            .parse_with_warnings(data, "test")
+            .unwrap();
+
+        assert_eq!(outcome.documents.len(), 2);
+        for document in &outcome.documents {
+            let metadata = &document.metadata;
+            assert_eq!(metadata.interchange_ref.as_deref(), Some("REF42"));
+            assert_eq!(metadata.partner_id.as_deref(), Some("5412345000013"));
+
+            let interchange = metadata.interchange.as_ref().unwrap();
+            assert_eq!(interchange.sender.qualifier.as_deref(), Some("14"));
+            assert_eq!(interchange.recipient.id, "4012345000016");
+            assert_eq!(interchange.preparation_date, "200101");
+            assert_eq!(interchange.preparation_time, "1200");
+            assert_eq!(interchange.application_ref.as_deref(), Some("ORDERS"));
+            assert!(interchange.is_test());
+        }
+
+        let interchange = &outcome.interchanges[0];
+        assert_eq!(
+            interchange.metadata.interchange_ref.as_deref(),
+            Some("REF42")
+        );
+        assert_eq!(
+            interchange.metadata.interchange,
+            outcome.documents[0].metadata.interchange
+        );
+    }
+
+    #[test]
+    fn test_messages_without_interchange_have_no_interchange_metadata() {
+        let data = b"UNH+1+ORDERS:D:96A:UN'BGM+220+PO1'UNT+3+1'";
+        let docs = EdifactParser::new().parse(data, "test").unwrap();
+
+        assert!(docs[0].metadata.interchange.is_none());
+        assert!(docs[0].metadata.interchange_ref.is_none());
+        assert!(docs[0].metadata.partner_id.is_none());
+    }
+
+    #[test]
+    fn test_lossless_mode_records_source_text() {
+        let data = b"UNA:+.? '\nUNB+UNOA:3+SENDER+RECEIVER+200101:1200+1'\n  UNH+1+ORDERS:D:96A:UN'\nBGM+220+PO?+1++'\nUNT+3+1'\nUNZ+1+1'\n\n";
+        let outcome = EdifactParser::new()
+            .with_lossless(true)
+            .parse_with_warnings(data, "test")
+            .unwrap();
+
+        let message = &outcome.documents[0].root;
+        assert_eq!(message.attributes[RAW_SEPARATORS_ATTRIBUTE], "UNA:+.? '");
+        let unh = message.find_child("UNH").unwrap();
+        assert_eq!(unh.attributes[RAW_PREFIX_ATTRIBUTE], "\n  ");
+        let bgm = message.find_child("BGM").unwrap();
+        assert_eq!(bgm.attributes[RAW_SEGMENT_ATTRIBUTE], "BGM+220+PO?+1++'");
+        assert_eq!(bgm.attributes[RAW_PREFIX_ATTRIBUTE], "\n");
+
+        let interchange = &outcome.interchanges[0].root;
+        assert_eq!(interchange.attributes[RAW_UNA_ATTRIBUTE], "UNA:+.? '");
+        assert_eq!(interchange.attributes[RAW_SUFFIX_ATTRIBUTE], "\n\n");
+    }
+
+    #[test]
+    fn test_default_mode_records_no_source_text() {
+        let data = b"UNA:+.? '\nUNB+UNOA:3+SENDER+RECEIVER+200101:1200+1'\nUNH+1+ORDERS:D:96A:UN'\nUNT+2+1'\nUNZ+1+1'";
+        let outcome = EdifactParser::new()
+            .parse_with_warnings(data, "test")
+            .unwrap();
+        let unh = outcome.documents[0].root.find_child("UNH").unwrap();
+        assert!(!unh.attributes.contains_key(RAW_SEGMENT_ATTRIBUTE));
+        assert!(
+            !outcome.interchanges[0]
+                .root
+                .attributes
+                .contains_key(RAW_UNA_ATTRIBUTE)
+        );
+    }
+
+    const DAMAGED_INTERCHANGE: &str = "UNB+UNOA:3+SENDER+RECEIVER+200101:1200+1'\n\
+        UNH+1+ORDERS:D:96A:UN'\nBGM+220+PO1'\nUNT+3+1'\n\
+        UNH+2+ORDERS:D:96A:UN'\nBGM220+PO2'\nDTM+137:20200101:102'\nUNT+4+2'\n\
+        UNH+3+ORDERS:D:96A:UN'\nBGM+220+PO3'\nUNT+3+3'\n\
+        UNZ+3+1'";
+
+    fn message_refs(documents: &[Document]) -> Vec<String> {
+        documents
+            .iter()
+            .flat_map(|document| document.metadata.message_refs.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_error_recovery_skips_only_the_damaged_message() {
+        let data = DAMAGED_INTERCHANGE.as_bytes();
+        assert!(EdifactParser::new().parse(data, "test").is_err());
+
+        let outcome = EdifactParser::new()
+            .with_error_recovery(true)
+            .parse_with_warnings(data, "test")
+            .unwrap();
+        assert_eq!(message_refs(&outcome.documents), ["1", "3"]);
+
+        assert_eq!(outcome.failures.len(), 1);
+        let failure = &outcome.failures[0];
+        assert_eq!(failure.message_ref.as_deref(), Some("2"));
+        assert_eq!(failure.position.line, 6);
+        assert!(failure.message.contains("Expected element separator"));
+        assert_eq!(
+            &DAMAGED_INTERCHANGE[failure.range.clone()],
+            "UNH+2+ORDERS:D:96A:UN'\nBGM220+PO2'\nDTM+137:20200101:102'\nUNT+4+2'\n"
+        );
+
+        // The interchange keeps its envelope and the intact messages
+        let interchange = &outcome.interchanges[0].root;
+        let children = interchange
+            .children
+            .iter()
+            .map(|child| child.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(children, ["UNB", "MESSAGE", "MESSAGE", "UNZ"]);
+    }
+
+    #[test]
+    fn test_error_recovery_resyncs_at_next_unh_without_terminator() {
+        let data = "UNH+1+ORDERS:D:96A:UN'\nBGM+220+PO1'\nUNT+3+1'\n\
+            UNH+2+ORDERS:D:96A:UN'\nBGM220+PO2\n\
+            UNH+3+ORDERS:D:96A:UN'\nBGM+220+PO3'\nUNT+3+3'";
+        let outcome = EdifactParser::new()
+            .with_error_recovery(true)
+            .parse_with_warnings(data.as_bytes(), "test")
+            .unwrap();
+
+        assert_eq!(message_refs(&outcome.documents), ["1", "3"]);
+        assert_eq!(outcome.failures.len(), 1);
+        assert_eq!(
+            &data[outcome.failures[0].range.clone()],
+            "UNH+2+ORDERS:D:96A:UN'\nBGM220+PO2\n"
+        );
+    }
+
+    #[test]
+    fn test_error_recovery_records_damage_at_end_of_input() {
+        let data = "UNH+1+ORDERS:D:96A:UN'\nBGM+220+PO1'\nUNT+3+1'\nUNH+2+ORDERS:D:96A:UN'\nBGM220";
+        let outcome = EdifactParser::new()
+            .with_error_recovery(true)
+            .parse_with_warnings(data.as_bytes(), "test")
+            .unwrap();
+
+        assert_eq!(message_refs(&outcome.documents), ["1"]);
+        assert!(outcome.warnings.is_empty(), "{:?}", outcome.warnings);
+        let failure = &outcome.failures[0];
+        assert_eq!(
+            &data[failure.range.clone()],
+            "UNH+2+ORDERS:D:96A:UN'\nBGM220"
+        );
+    }
 }
@@ -0,0 +1,688 @@
+//! Streaming EDIFACT readers
+//!
+//! [`EdifactReader`] pulls segments from any [`BufRead`] source and yields
+//! one message [`Document`] at a time, so memory use is bounded by the
+//! largest message rather than the size of the interchange. Segments may
+//! span buffer boundaries; positions are reported as byte offsets, lines and
+//! columns of the whole input. With the `async` feature,
+//! [`AsyncEdifactReader`] does the same over a tokio `AsyncBufRead`.
+
+use std::io::{BufRead, BufReader, Read};
+
+use edi_ir::{Document, Position};
+
+use crate::parser::{
+    EdifactParser, MessageAssembler, ParseFailure, ParseWarning, Segment, SegmentParser,
+};
+use crate::syntax::Separators;
+use crate::{Error, Result};
+
+/// Where the splitter is within the input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    /// At the start of input, checking for a UNA service string advice
+    Start,
+    /// Inside the fixed-length UNA segment
+    Una,
+    /// Reading segments
+    Segments,
+}
+
+/// Line and column tracking with the same rules as
+/// [`crate::syntax::SyntaxBuffer::line_column`]: LF, CR and CR LF each end a
+/// line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Cursor {
+    offset: usize,
+    line: usize,
+    column: usize,
+    after_cr: bool,
+}
+
+impl Cursor {
+    fn advance(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            match byte {
+                b'\n' if self.after_cr => {}
+                b'\n' | b'\r' => {
+                    self.line += 1;
+                    self.column = 1;
+                }
+                _ => self.column += 1,
+            }
+            self.after_cr = byte == b'\r';
+        }
+        self.offset += bytes.len();
+    }
+
+    /// Translate a line/column relative to this cursor to absolute values
+    fn rebase(&self, line: usize, column: usize) -> (usize, usize) {
+        if line <= 1 {
+            (self.line, self.column + column.saturating_sub(1))
+        } else {
+            (self.line + line - 1, column)
+        }
+    }
+}
+
+impl Default for Cursor {
+    fn default() -> Self {
+        Self {
+            offset: 0,
+            line: 1,
+            column: 1,
+            after_cr: false,
+        }
+    }
+}
+
+/// Splits buffered input into raw segments, honouring release characters
+/// across buffer boundaries
+#[derive(Debug)]
+struct SegmentSplitter {
+    source_name: String,
+    separators: Separators,
+    una: Option<Separators>,
+    phase: Phase,
+    raw: Vec<u8>,
+    released: bool,
+    cursor: Cursor,
+    record_raw: bool,
+    segment_start: usize,
+}
+
+impl SegmentSplitter {
+    fn new(source_name: String) -> Self {
+        Self {
+            source_name,
+            separators: Separators::default(),
+            una: None,
+            phase: Phase::Start,
+            raw: Vec::new(),
+            released: false,
+            cursor: Cursor::default(),
+            record_raw: false,
+            segment_start: 0,
+        }
+    }
+
+    /// Scan `chunk`, buffering bytes up to the next segment terminator.
+    ///
+    /// Returns the number of bytes consumed and whether a complete segment
+    /// is buffered.
+    fn scan(&mut self, chunk: &[u8]) -> (usize, bool) {
+        for (index, &byte) in chunk.iter().enumerate() {
+            self.raw.push(byte);
+            let complete = match self.phase {
+                Phase::Start if self.raw.len() <= 3 && b"UNA".starts_with(&self.raw) => {
+                    if self.raw.len() == 3 {
+                        self.phase = Phase::Una;
+                    }
+                    false
+                }
+                Phase::Start => {
+                    // Bytes before this one matched "UNA" and are letters
+                    self.phase = Phase::Segments;
+                    self.is_terminator(byte)
+                }
+                Phase::Una => {
+                    if self.raw.len() == 9 {
+                        self.finish_una();
+                    }
+                    false
+                }
+                Phase::Segments => self.is_terminator(byte),
+            };
+            if complete {
+                return (index + 1, true);
+            }
+        }
+        (chunk.len(), false)
+    }
+
+    fn is_terminator(&mut self, byte: u8) -> bool {
+        if self.released {
+            self.released = false;
+            false
+        } else if byte == self.separators.release {
+            self.released = true;
+            false
+        } else {
+            byte == self.separators.segment
+        }
+    }
+
+    fn finish_una(&mut self) {
+        if let Some(separators) = Separators::from_una(&self.raw) {
+            self.separators = separators;
+            self.una = Some(separators);
+            self.cursor.advance(&self.raw);
+            self.raw.clear();
+        }
+        // A malformed UNA is parsed as an ordinary segment and reported there
+        self.phase = Phase::Segments;
+    }
+
+    /// Parse the buffered segment, if any, with absolute positions
+    fn take_segment(&mut self) -> Option<Result<Segment>> {
+        let raw = std::mem::take(&mut self.raw);
+        self.released = false;
+        self.phase = Phase::Segments;
+
+        let mut parser = SegmentParser::new(&raw, self.source_name.as_str());
+        parser.set_separators(self.separators);
+        parser.set_record_raw(self.record_raw);
+        let result = parser.next_segment().map(|result| {
+            result
+                .map(|mut segment| {
+                    let (line, column) = self
+                        .cursor
+                        .rebase(segment.position.line, segment.position.column);
+                    segment.position = Position::new(
+                        line,
+                        column,
+                        self.cursor.offset + segment.position.offset,
+                        segment.position.length,
+                    );
+                    if let Some(spans) = &mut segment.spans {
+                        spans.for_each_span_mut(&mut |span| {
+                            (span.line, span.column) = self.cursor.rebase(span.line, span.column);
+                            span.offset += self.cursor.offset;
+                        });
+                    }
+                    segment
+                })
+                .map_err(|error| match error {
+                    Error::Parse {
+                        line,
+                        column,
+                        message,
+                    } => {
+                        let (line, column) = self.cursor.rebase(line, column);
+                        Error::Parse {
+                            line,
+                            column,
+                            message,
+                        }
+                    }
+                    other => other,
+                })
+        });
+        self.segment_start = self.cursor.offset
+            + raw
+                .iter()
+                .take_while(|byte| byte.is_ascii_whitespace())
+                .count();
+        self.cursor.advance(&raw);
+        result
+    }
+
+    fn position(&self) -> Position {
+        Position::new(self.cursor.line, self.cursor.column, self.cursor.offset, 0)
+    }
+}
+
+/// Pull-based reader that yields EDIFACT segments from a [`BufRead`] source
+pub struct SegmentReader<R> {
+    reader: R,
+    splitter: SegmentSplitter,
+}
+
+impl<R: BufRead> SegmentReader<R> {
+    /// Create a segment reader over `reader`
+    pub fn new(reader: R, source_name: impl Into<String>) -> Self {
+        Self {
+            reader,
+            splitter: SegmentSplitter::new(source_name.into()),
+        }
+    }
+
+    /// Read the next segment; a leading UNA is applied and not returned
+    pub fn next_segment(&mut self) -> Option<Result<Segment>> {
+        loop {
+            let chunk = match self.reader.fill_buf() {
+                Ok(chunk) => chunk,
+                Err(error) if error.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(error) => return Some(Err(error.into())),
+            };
+            if chunk.is_empty() {
+                return self.splitter.take_segment();
+            }
+
+            let (consumed, complete) = self.splitter.scan(chunk);
+            self.reader.consume(consumed);
+            if complete {
+                if let Some(segment) = self.splitter.take_segment() {
+                    return Some(segment);
+                }
+            }
+        }
+    }
+
+    /// Separators read from the UNA segment, if the input started with one
+    pub fn una(&self) -> Option<Separators> {
+        self.splitter.una
+    }
+
+    /// Separators currently used for reading
+    pub fn separators(&self) -> Separators {
+        self.splitter.separators
+    }
+
+    /// Replace the separators used for the following segments
+    pub fn set_separators(&mut self, separators: Separators) {
+        self.splitter.separators = separators;
+    }
+
+    /// Position after the last consumed segment
+    pub fn current_position(&self) -> Position {
+        self.splitter.position()
+    }
+
+    /// Record the source text of every segment in [`Segment::raw`]
+    pub fn set_record_raw(&mut self, record_raw: bool) {
+        self.splitter.record_raw = record_raw;
+    }
+}
+
+impl<R: BufRead> Iterator for SegmentReader<R> {
+    type Item = Result<Segment>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_segment()
+    }
+}
+
+/// Pull-based reader that yields one EDIFACT message [`Document`] at a time.
+///
+/// Messages carry the same metadata as those returned by
+/// [`EdifactParser::parse`]; interchange documents are not built, since
+/// that would keep whole interchanges in memory.
+pub struct EdifactReader<R> {
+    segments: SegmentReader<R>,
+    parser: EdifactParser,
+    assembler: MessageAssembler,
+    finished: bool,
+}
+
+impl<R: BufRead> EdifactReader<R> {
+    /// Create a message reader over `reader`
+    pub fn new(reader: R, source_name: impl Into<String>) -> Self {
+        Self {
+            segments: SegmentReader::new(reader, source_name),
+            parser: EdifactParser::new(),
+            assembler: MessageAssembler::new(false),
+            finished: false,
+        }
+    }
+
+    /// Build messages with `parser`, e.g. to apply registered branching
+    /// diagrams
+    #[must_use]
+    pub fn with_parser(mut self, parser: EdifactParser) -> Self {
+        self.segments.set_record_raw(parser.is_lossless());
+        self.parser = parser;
+        self
+    }
+
+    /// Read the next message.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if reading fails or a segment is malformed; the
+    /// reader yields no further messages after an error.
+    pub fn next_message(&mut self) -> Option<Result<Document>> {
+        loop {
+            if let Some(document) = self.assembler.documents.pop_front() {
+                return Some(Ok(document));
+            }
+            if self.finished {
+                return None;
+            }
+
+            match self.segments.next_segment() {
+                Some(Ok(segment)) => {
+                    let una = self.segments.una();
+                    if let Some(separators) = self.assembler.push(&self.parser, segment, una) {
+                        self.segments.set_separators(separators);
+                    }
+                }
+                Some(Err(error @ Error::Parse { .. })) if self.parser.recovers_from_errors() => {
+                    self.assembler
+                        .fail(&error, self.segments.splitter.segment_start);
+                }
+                Some(Err(error)) => {
+                    self.finished = true;
+                    return Some(Err(error));
+                }
+                None => {
+                    self.finished = true;
+                    let position = self.segments.current_position();
+                    self.assembler.finish(&self.parser, position);
+                }
+            }
+        }
+    }
+
+    /// Take the messages skipped so far by an error-recovering parser
+    pub fn take_failures(&mut self) -> Vec<ParseFailure> {
+        std::mem::take(&mut self.assembler.failures)
+    }
+
+    /// Take the non-fatal warnings collected so far
+    pub fn take_warnings(&mut self) -> Vec<ParseWarning> {
+        std::mem::take(&mut self.assembler.warnings)
+    }
+}
+
+impl<R: Read> EdifactReader<BufReader<R>> {
+    /// Create a message reader over an unbuffered [`Read`] source
+    pub fn from_reader(reader: R, source_name: impl Into<String>) -> Self {
+        Self::new(BufReader::new(reader), source_name)
+    }
+}
+
+impl<R: BufRead> Iterator for EdifactReader<R> {
+    type Item = Result<Document>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_message()
+    }
+}
+
+#[cfg(feature = "async")]
+pub use self::async_reader::AsyncEdifactReader;
+
+#[cfg(feature = "async")]
+mod async_reader {
+    use std::pin::Pin;
+
+    use edi_ir::Document;
+    use tokio::io::{AsyncBufRead, AsyncBufReadExt};
+
+    use super::SegmentSplitter;
+    use crate::parser::{EdifactParser, MessageAssembler, ParseFailure, ParseWarning, Segment};
+    use crate::{Error, Result};
+
+    /// Message reader over a tokio [`AsyncBufRead`] source, with the same
+    /// behaviour as [`super::EdifactReader`]
+    pub struct AsyncEdifactReader<R> {
+        reader: R,
+        splitter: SegmentSplitter,
+        parser: EdifactParser,
+        assembler: MessageAssembler,
+        finished: bool,
+    }
+
+    impl<R: AsyncBufRead + Unpin> AsyncEdifactReader<R> {
+        /// Create a message reader over `reader`
+        pub fn new(reader: R, source_name: impl Into<String>) -> Self {
+            Self {
+                reader,
+                splitter: SegmentSplitter::new(source_name.into()),
+                parser: EdifactParser::new(),
+                assembler: MessageAssembler::new(false),
+                finished: false,
+            }
+        }
+
+        /// Build messages with `parser`, e.g. to apply registered branching
+        /// diagrams
+        #[must_use]
+        pub fn with_parser(mut self, parser: EdifactParser) -> Self {
+            self.splitter.record_raw = parser.is_lossless();
+            self.parser = parser;
+            self
+        }
+
+        /// Read the next message.
+        ///
+        /// # Errors
+        ///
+        /// Returns an error if reading fails or a segment is malformed; the
+        /// reader yields no further messages after an error.
+        pub async fn next_message(&mut self) -> Option<Result<Document>> {
+            loop {
+                if let Some(document) = self.assembler.documents.pop_front() {
+                    return Some(Ok(document));
+                }
+                if self.finished {
+                    return None;
+                }
+
+                match self.next_segment().await {
+                    Some(Ok(segment)) => {
+                        let una = self.splitter.una;
+                        if let Some(separators) = self.assembler.push(&self.parser, segment, una) {
+                            self.splitter.separators = separators;
+                        }
+                    }
+                    Some(Err(error @ Error::Parse { .. }))
+                        if self.parser.recovers_from_errors() =>
+                    {
+                        self.assembler.fail(&error, self.splitter.segment_start);
+                    }
+                    Some(Err(error)) => {
+                        self.finished = true;
+                        return Some(Err(error));
+                    }
+                    None => {
+                        self.finished = true;
+                        let position = self.splitter.position();
+                        self.assembler.finish(&self.parser, position);
+                    }
+                }
+            }
+        }
+
+        /// Take the messages skipped so far by an error-recovering parser
+        pub fn take_failures(&mut self) -> Vec<ParseFailure> {
+            std::mem::take(&mut self.assembler.failures)
+        }
+
+        /// Take the non-fatal warnings collected so far
+        pub fn take_warnings(&mut self) -> Vec<ParseWarning> {
+            std::mem::take(&mut self.assembler.warnings)
+        }
+
+        async fn next_segment(&mut self) -> Option<Result<Segment>> {
+            loop {
+                let chunk = match self.reader.fill_buf().await {
+                    Ok(chunk) => chunk,
+                    Err(error) => return Some(Err(error.into())),
+                };
+                if chunk.is_empty() {
+                    return self.splitter.take_segment();
+                }
+
+                let (consumed, complete) = self.splitter.scan(chunk);
+                Pin::new(&mut self.reader).consume(consumed);
+                if complete {
+                    if let Some(segment) = self.splitter.take_segment() {
+                        return Some(segment);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor as IoCursor;
+
+    const INTERCHANGE: &[u8] = b"UNA:+.? '\n\
+        UNB+UNOC:3+SENDER:14+RECEIVER:14+260312:0930+REF1'\n\
+        UNH+1+ORDERS:D:96A:UN:EAN008'\n\
+        BGM+220+PO?+1'\n\
+        UNT+3+1'\n\
+        UNH+2+ORDERS:D:96A:UN:EAN008'\n\
+        BGM+220+PO2'\n\
+        UNT+3+2'\n\
+        UNZ+2+REF1'\n";
+
+    fn read_all<R: BufRead>(mut reader: EdifactReader<R>) -> Vec<Document> {
+        let mut documents = Vec::new();
+        while let Some(document) = reader.next_message() {
+            documents.push(document.unwrap());
+        }
+        documents
+    }
+
+    fn tree(document: &Document) -> serde_json::Value {
+        serde_json::to_value(&document.root).unwrap()
+    }
+
+    fn bgm_number(document: &Document) -> String {
+        let bgm = document.root.find_child("BGM").unwrap();
+        bgm.children[1]
+            .value
+            .as_ref()
+            .and_then(edi_ir::Value::as_string)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_reader_yields_same_messages_as_parser() {
+        let expected = EdifactParser::new().parse(INTERCHANGE, "slice").unwrap();
+        let documents = read_all(EdifactReader::new(IoCursor::new(INTERCHANGE), "stream"));
+
+        assert_eq!(documents.len(), 2);
+        assert_eq!(bgm_number(&documents[0]), "PO+1");
+        assert_eq!(bgm_number(&documents[1]), "PO2");
+        for (document, expected) in documents.iter().zip(&expected) {
+            assert_eq!(tree(document), tree(expected));
+            assert_eq!(
+                document.metadata.message_refs,
+                expected.metadata.message_refs
+            );
+            assert_eq!(document.metadata.interchange, expected.metadata.interchange);
+        }
+    }
+
+    #[test]
+    fn test_lossless_reader_records_same_source_text_as_parser() {
+        let parser = EdifactParser::new().with_lossless(true);
+        let expected = parser.parse(INTERCHANGE, "slice").unwrap();
+        let reader = BufReader::with_capacity(2, INTERCHANGE);
+        let documents = read_all(EdifactReader::new(reader, "stream").with_parser(parser));
+
+        assert_eq!(documents.len(), 2);
+        for (document, expected) in documents.iter().zip(&expected) {
+            assert_eq!(tree(document), tree(expected));
+        }
+        let bgm = documents[0].root.find_child("BGM").unwrap();
+        assert_eq!(bgm.attributes["raw_segment"], "BGM+220+PO?+1'");
+        assert_eq!(bgm.attributes["raw_prefix"], "\n");
+    }
+
+    #[test]
+    fn test_segments_cross_buffer_boundaries_with_exact_positions() {
+        let expected = EdifactParser::new().parse(INTERCHANGE, "slice").unwrap();
+
+        // Buffers of one and two bytes split every segment, the UNA and the
+        // release character sequences
+        for capacity in [1, 2, 7] {
+            let reader = BufReader::with_capacity(capacity, INTERCHANGE);
+            let documents = read_all(EdifactReader::new(reader, "stream"));
+            assert_eq!(documents.len(), 2, "capacity {capacity}");
+            assert_eq!(
+                tree(&documents[0]),
+                tree(&expected[0]),
+                "capacity {capacity}"
+            );
+            assert_eq!(
+                tree(&documents[1]),
+                tree(&expected[1]),
+                "capacity {capacity}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_segment_reader_reports_absolute_offsets() {
+        let mut parser = SegmentParser::new(INTERCHANGE, "slice");
+        parser.parse_una();
+        let mut expected = Vec::new();
+        while let Some(segment) = parser.next_segment() {
+            let position = segment.unwrap().position;
+            expected.push((position.line, position.column, position.offset));
+        }
+
+        let reader = SegmentReader::new(BufReader::with_capacity(3, INTERCHANGE), "stream");
+        let positions: Vec<(usize, usize, usize)> = reader
+            .map(|segment| {
+                let position = segment.unwrap().position;
+                (position.line, position.column, position.offset)
+            })
+            .collect();
+
+        assert_eq!(positions, expected);
+        let (line, column, offset) = positions[1];
+        assert_eq!((line, column), (3, 4));
+        assert_eq!(&INTERCHANGE[offset - 3..offset], b"UNH");
+    }
+
+    #[test]
+    fn test_reader_reports_missing_unt_at_end_of_input() {
+        let data = b"UNH+1+ORDERS:D:96A:UN'BGM+220+PO1'";
+        let mut reader = EdifactReader::from_reader(&data[..], "stream");
+
+        let document = reader.next_message().unwrap().unwrap();
+        assert_eq!(document.metadata.message_refs, vec!["1".to_string()]);
+        assert!(reader.next_message().is_none());
+
+        let warnings = reader.take_warnings();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].code, "MISSING_UNT");
+        assert_eq!(warnings[0].position.offset, data.len());
+    }
+
+    #[test]
+    fn test_reader_stops_after_malformed_segment() {
+        let data = b"UNH+1+ORDERS:D:96A:UN'\n12X+1'UNT+2+1'";
+        let mut reader = EdifactReader::new(BufReader::with_capacity(4, &data[..]), "stream");
+
+        match reader.next_message() {
+            Some(Err(Error::Parse { line, column, .. })) => assert_eq!((line, column), (2, 1)),
+            other => panic!("expected parse error, got {other:?}"),
+        }
+        assert!(reader.next_message().is_none());
+    }
+
+    #[test]
+    fn test_recovering_reader_skips_damaged_message() {
+        let data = b"UNH+1+ORDERS:D:96A:UN'\nBGM220+PO1'\nUNT+3+1'\n\
+            UNH+2+ORDERS:D:96A:UN'\nBGM+220+PO2'\nUNT+3+2'\n";
+        let parser = EdifactParser::new().with_error_recovery(true);
+        let mut reader = EdifactReader::new(BufReader::with_capacity(4, &data[..]), "stream")
+            .with_parser(parser);
+
+        let documents = std::iter::from_fn(|| reader.next_message())
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(documents.len(), 1);
+        assert_eq!(bgm_number(&documents[0]), "PO2");
+
+        let failures = reader.take_failures();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].message_ref.as_deref(), Some("1"));
+        assert_eq!(failures[0].range, 0..44);
+        assert_eq!(failures[0].position.offset, 23);
+    }
+
+    #[cfg(feature = "async")]
+    #[tokio::test]
+    async fn test_async_reader_yields_messages() {
+        let reader = tokio::io::BufReader::with_capacity(5, INTERCHANGE);
+        let mut reader = AsyncEdifactReader::new(reader, "async");
+
+        let mut numbers = Vec::new();
+        while let Some(document) = reader.next_message().await {
+            numbers.push(bgm_number(&document.unwrap()));
+        }
+        assert_eq!(numbers, vec!["PO+1".to_string(), "PO2".to_string()]);
+        assert!(reader.take_warnings().is_empty());
+    }
+}
@@ -4,16 +4,127 @@ use std::collections::BTreeMap;
 
 use edi_ir::{Document, Node, NodeType, Value};
 
+use crate::charset::Charset;
+use crate::parser::{
+    RAW_PREFIX_ATTRIBUTE, RAW_SEGMENT_ATTRIBUTE, RAW_SEPARATORS_ATTRIBUTE, RAW_SUFFIX_ATTRIBUTE,
+    RAW_UNA_ATTRIBUTE, SegmentParser,
+};
+use crate::syntax::{DEFAULT_REPETITION_SEPARATOR, Separators};
 use crate::{Error, Result};
 
+/// Line layout written after segment terminators
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SegmentSuffix {
+    /// Segments follow each other on a single line
+    None,
+    /// Line feed between segments
+    #[default]
+    LineFeed,
+    /// CR LF after every segment terminator, including the last
+    CrLf,
+}
+
 /// Serializer for EDIFACT documents.
-pub struct EdifactSerializer;
+#[derive(Debug, Clone)]
+pub struct EdifactSerializer {
+    charset: Option<Charset>,
+    separators: Separators,
+    una: bool,
+    segment_suffix: SegmentSuffix,
+    trim_trailing_empty: bool,
+    lossless: bool,
+}
 
 impl EdifactSerializer {
     /// Create a new EDIFACT serializer.
     #[must_use]
     pub fn new() -> Self {
-        Self
+        Self {
+            charset: None,
+            separators: Separators::default(),
+            una: false,
+            segment_suffix: SegmentSuffix::default(),
+            trim_trailing_empty: true,
+            lossless: false,
+        }
+    }
+
+    /// Encode output in `charset` regardless of the document's UNB syntax
+    /// identifier.
+    #[must_use]
+    pub fn with_charset(mut self, charset: Charset) -> Self {
+        self.charset = Some(charset);
+        self
+    }
+
+    /// Write segments with `separators`.
+    ///
+    /// Values are escaped with the configured release character, and numeric
+    /// values use the configured decimal mark. Without a repetition separator,
+    /// documents declaring syntax version 4 or holding repeated elements use
+    /// `*`.
+    #[must_use]
+    pub fn with_separators(mut self, separators: Separators) -> Self {
+        self.separators = separators;
+        self
+    }
+
+    /// Start the output with a UNA service string advice for the separators
+    /// in use.
+    #[must_use]
+    pub fn with_una(mut self, una: bool) -> Self {
+        self.una = una;
+        self
+    }
+
+    /// Set the line layout written after segment terminators.
+    #[must_use]
+    pub fn with_segment_suffix(mut self, segment_suffix: SegmentSuffix) -> Self {
+        self.segment_suffix = segment_suffix;
+        self
+    }
+
+    /// Drop trailing empty elements and components (the default). When
+    /// disabled, empty trailing positions present in the IR are written.
+    #[must_use]
+    pub fn with_trim_trailing_empty(mut self, trim: bool) -> Self {
+        self.trim_trailing_empty = trim;
+        self
+    }
+
+    /// Reproduce the source text recorded by
+    /// [`EdifactParser::with_lossless`](crate::EdifactParser::with_lossless).
+    ///
+    /// Segments whose values are unchanged are written exactly as read,
+    /// together with the UNA and the whitespace around them. Modified
+    /// segments are rendered with the source separators and keep their
+    /// empty trailing positions; new segments follow the source line layout.
+    #[must_use]
+    pub fn with_lossless(mut self, lossless: bool) -> Self {
+        self.lossless = lossless;
+        self
+    }
+
+    /// Serialize a document to EDIFACT bytes in its declared character set.
+    ///
+    /// The character set is the one configured with [`Self::with_charset`],
+    /// otherwise the one named by the document's UNB syntax identifier.
+    /// Without either, the output is UTF-8. In lossless mode, UNOA and UNOB
+    /// data outside the repertoire is written back as read.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the document cannot be serialized or contains
+    /// characters the character set cannot represent.
+    pub fn serialize_document_bytes(&self, document: &Document) -> Result<Vec<u8>> {
+        let output = self.serialize_document(document)?;
+        let declared = declared_syntax(&document.root)
+            .and_then(|(identifier, _)| Charset::from_syntax_identifier(&identifier));
+        match self.charset.or(declared) {
+            Some(charset) if self.lossless => charset.encode_as_read(&output),
+            Some(charset) => charset.encode(&output),
+            None => Ok(output.into_bytes()),
+        }
     }
 
     /// Serialize a document to EDIFACT text.
@@ -25,12 +136,27 @@ impl EdifactSerializer {
     ///
     /// # Errors
     ///
-    /// Returns an error if the document does not contain any serializable EDIFACT segments.
+    /// Returns an error if the document does not contain any serializable EDIFACT segments,
+    /// or if the configured separators are not distinct.
     pub fn serialize_document(&self, document: &Document) -> Result<String> {
-        let mut output = String::new();
-        let native_segment_count = collect_native_segments_into_output(&document.root, &mut output);
-        if native_segment_count > 0 {
-            return Ok(output);
+        let syntax = self.syntax_for(
+            self.source_separators(&document.root)
+                .unwrap_or(self.separators),
+            declared_syntax(&document.root).as_ref(),
+            has_repeated_elements(&document.root),
+        )?;
+        if self.lossless {
+            let mut segments = Vec::new();
+            collect_segment_nodes(&document.root, &mut segments);
+            if !segments.is_empty() {
+                return Ok(self.lossless_layout(&document.root, &syntax, &segments));
+            }
+        }
+
+        let mut segments = Vec::new();
+        collect_native_segments(&document.root, &mut segments, &syntax);
+        if !segments.is_empty() {
+            return Ok(self.layout(&syntax, &segments));
         }
 
         let mut mapped_fields = Vec::new();
@@ -43,22 +169,143 @@ impl EdifactSerializer {
             ));
         }
 
-        let segments = build_segments_from_fields(&mapped_fields)?;
-        if segments.is_empty() {
+        let accumulators = build_segments_from_fields(&mapped_fields)?;
+        if accumulators.is_empty() {
             return Err(Error::Serialize(
                 "Unable to build EDIFACT segments from mapped fields".to_string(),
             ));
         }
 
-        output.clear();
-        for (index, segment) in segments.iter().enumerate() {
-            if index > 0 {
+        let segments: Vec<String> = accumulators
+            .iter()
+            .map(|segment| segment.render(&syntax))
+            .collect();
+        Ok(self.layout(&syntax, &segments))
+    }
+
+    /// Separators of the UNA recorded on `root` in lossless mode
+    fn source_separators(&self, root: &Node) -> Option<Separators> {
+        if !self.lossless {
+            return None;
+        }
+        let una = root
+            .attributes
+            .get(RAW_UNA_ATTRIBUTE)
+            .or_else(|| root.attributes.get(RAW_SEPARATORS_ATTRIBUTE))?;
+        Separators::from_una(una.as_bytes())
+    }
+
+    /// Resolve the output syntax for a document declaring `declared` in UNB;
+    /// `repeats` is set when the document has repeated elements
+    fn syntax_for(
+        &self,
+        mut separators: Separators,
+        declared: Option<&(String, Option<String>)>,
+        repeats: bool,
+    ) -> Result<Syntax> {
+        let version = declared
+            .and_then(|(_, version)| version.as_deref())
+            .and_then(|version| version.parse::<u8>().ok());
+        // The separator joining repeats must also be escaped inside values
+        if separators.repetition.is_none()
+            && (repeats || version.is_some_and(|version| version >= 4))
+        {
+            separators.repetition = Some(DEFAULT_REPETITION_SEPARATOR);
+        }
+
+        let mut service = vec![
+            separators.component,
+            separators.element,
+            separators.release,
+            separators.segment,
+        ];
+        service.extend(separators.repetition);
+        if separators.decimal != b'.' {
+            service.push(separators.decimal);
+        }
+        if (1..service.len()).any(|index| service[index..].contains(&service[index - 1])) {
+            return Err(Error::Serialize(format!(
+                "Separators must be distinct, got UNA '{}'",
+                String::from_utf8_lossy(&separators.to_una())
+            )));
+        }
+
+        Ok(Syntax {
+            separators,
+            trim_trailing_empty: self.trim_trailing_empty && !self.lossless,
+        })
+    }
+
+    /// Join rendered segments with the configured UNA and line layout
+    fn layout(&self, syntax: &Syntax, segments: &[String]) -> String {
+        let mut output = String::new();
+        let una = self
+            .una
+            .then(|| String::from_utf8_lossy(&syntax.separators.to_una()).into_owned());
+        for segment in una.iter().chain(segments) {
+            if !output.is_empty() && self.segment_suffix == SegmentSuffix::LineFeed {
+                output.push('\n');
+            }
+            output.push_str(segment);
+            if self.segment_suffix == SegmentSuffix::CrLf {
+                output.push_str("\r\n");
+            }
+        }
+        output
+    }
+
+    /// Write recorded source text for unmodified segments and render the
+    /// others
+    fn lossless_layout(&self, root: &Node, syntax: &Syntax, segments: &[&Node]) -> String {
+        let mut output = String::new();
+        match root.attributes.get(RAW_UNA_ATTRIBUTE) {
+            Some(una) => output.push_str(una),
+            None if self.una => {
+                output.push_str(&String::from_utf8_lossy(&syntax.separators.to_una()));
+                if self.segment_suffix == SegmentSuffix::CrLf {
+                    output.push_str("\r\n");
+                }
+            }
+            None => {}
+        }
+
+        // Leading whitespace belongs to the input only when serializing a
+        // whole interchange
+        let keep_leading = root.node_type == NodeType::Interchange;
+        let mut source_prefix: Option<&str> = None;
+        for segment in segments {
+            let raw = segment
+                .attributes
+                .get(RAW_SEGMENT_ATTRIBUTE)
+                .filter(|raw| is_unmodified(segment, raw, syntax));
+            let prefix = segment
+                .attributes
+                .get(RAW_PREFIX_ATTRIBUTE)
+                .map(String::as_str);
+            if let Some(prefix) = prefix {
+                if !output.is_empty() || keep_leading {
+                    output.push_str(prefix);
+                }
+                source_prefix = Some(prefix);
+            } else if let Some(prefix) = source_prefix {
+                output.push_str(prefix);
+            } else if !output.is_empty() && self.segment_suffix == SegmentSuffix::LineFeed {
                 output.push('\n');
             }
-            output.push_str(&segment.render());
+
+            match raw {
+                Some(raw) => output.push_str(raw),
+                None => output.push_str(&serialize_native_segment(segment, syntax)),
+            }
+            if source_prefix.is_none() && self.segment_suffix == SegmentSuffix::CrLf {
+                output.push_str("\r\n");
+            }
         }
 
-        Ok(output)
+        if let Some(suffix) = root.attributes.get(RAW_SUFFIX_ATTRIBUTE) {
+            output.push_str(suffix);
+        }
+        output
     }
 }
 
@@ -68,12 +315,12 @@ impl Default for EdifactSerializer {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 struct MappedField {
     descriptor: SegmentDescriptor,
     element_index: usize,
     component_index: Option<usize>,
-    value: String,
+    value: Value,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -90,13 +337,13 @@ enum Qualifier {
     Component(String),
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 enum ElementValue {
-    Simple(String),
-    Composite(BTreeMap<usize, String>),
+    Simple(Value),
+    Composite(BTreeMap<usize, Value>),
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 struct SegmentAccumulator {
     descriptor: SegmentDescriptor,
     elements: BTreeMap<usize, ElementValue>,
@@ -162,11 +409,12 @@ impl SegmentAccumulator {
     fn seed_qualifier(&mut self) {
         match &self.descriptor.qualifier {
             Some(Qualifier::Element(value)) => {
-                self.elements.insert(1, ElementValue::Simple(value.clone()));
+                self.elements
+                    .insert(1, ElementValue::Simple(Value::String(value.clone())));
             }
             Some(Qualifier::Component(value)) => {
                 let mut components = BTreeMap::new();
-                components.insert(1, value.clone());
+                components.insert(1, Value::String(value.clone()));
                 self.elements.insert(1, ElementValue::Composite(components));
             }
             None => {}
@@ -236,107 +484,247 @@ impl SegmentAccumulator {
         Ok(())
     }
 
-    fn render(&self) -> String {
+    fn render(&self, syntax: &Syntax) -> String {
         let max_element = self.elements.keys().copied().max().unwrap_or(0);
         let mut elements = Vec::with_capacity(max_element);
 
         for index in 1..=max_element {
             let element = match self.elements.get(&index) {
                 None => String::new(),
-                Some(ElementValue::Simple(value)) => escape_edifact_value(value),
+                Some(ElementValue::Simple(value)) => syntax.value(value),
                 Some(ElementValue::Composite(components)) => {
                     let max_component = components.keys().copied().max().unwrap_or(0);
-                    let mut component_values = Vec::with_capacity(max_component);
-                    for component_index in 1..=max_component {
-                        let value = components
-                            .get(&component_index)
-                            .map_or_else(String::new, |value| escape_edifact_value(value));
-                        component_values.push(value);
-                    }
-                    while component_values.last().is_some_and(String::is_empty) {
-                        component_values.pop();
-                    }
-                    component_values.join(":")
+                    let component_values = (1..=max_component)
+                        .map(|component_index| {
+                            components
+                                .get(&component_index)
+                                .map_or_else(String::new, |value| syntax.value(value))
+                        })
+                        .collect();
+                    syntax.join(component_values, syntax.separators.component)
                 }
             };
             elements.push(element);
         }
 
-        while elements.last().is_some_and(String::is_empty) {
-            elements.pop();
+        syntax.segment(&self.descriptor.tag, elements)
+    }
+}
+
+/// Output syntax resolved for one document
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Syntax {
+    separators: Separators,
+    trim_trailing_empty: bool,
+}
+
+impl Syntax {
+    /// Render a value with separators escaped. Decimal values are written
+    /// with the configured decimal mark and ISO 8601 dates and times in
+    /// EDIFACT form; text is written as is.
+    fn value(&self, value: &Value) -> String {
+        let text = edifact_date_time(value)
+            .or_else(|| value.as_string())
+            .unwrap_or_default();
+        let decimal = char::from(self.separators.decimal);
+        let release = char::from(self.separators.release);
+        let convert_decimal = decimal != '.' && matches!(value, Value::Decimal(_));
+
+        let mut escaped = String::with_capacity(text.len());
+        for ch in text.chars() {
+            let ch = if convert_decimal && ch == '.' {
+                decimal
+            } else {
+                ch
+            };
+            if u8::try_from(ch).is_ok_and(|byte| self.separators.is_special(byte)) {
+                escaped.push(release);
+            }
+            escaped.push(ch);
         }
+        escaped
+    }
 
-        if elements.is_empty() {
-            format!("{}'", self.descriptor.tag)
-        } else {
-            format!("{}+{}'", self.descriptor.tag, elements.join("+"))
+    /// Join element or component values, trimming trailing empty ones
+    fn join(&self, mut values: Vec<String>, separator: u8) -> String {
+        if self.trim_trailing_empty {
+            while values.last().is_some_and(String::is_empty) {
+                values.pop();
+            }
         }
+        values.join(char::from(separator).encode_utf8(&mut [0; 4]))
+    }
+
+    /// Render a segment from its tag and rendered elements
+    fn segment(&self, tag: &str, elements: Vec<String>) -> String {
+        let mut rendered = tag.to_string();
+        let has_elements = !elements.is_empty();
+        let elements = self.join(elements, self.separators.element);
+        if has_elements && (!elements.is_empty() || !self.trim_trailing_empty) {
+            rendered.push(char::from(self.separators.element));
+            rendered.push_str(&elements);
+        }
+        rendered.push(char::from(self.separators.segment));
+        rendered
+    }
+
+    /// Separator between occurrences of a repeated element
+    fn repetition(&self) -> u8 {
+        self.separators
+            .repetition
+            .unwrap_or(DEFAULT_REPETITION_SEPARATOR)
     }
 }
 
-fn collect_native_segments_into_output(node: &Node, output: &mut String) -> usize {
-    let mut segment_count = 0usize;
+/// EDIFACT form of an ISO 8601 date (CCYYMMDD, format 102), date and time
+/// (CCYYMMDDHHMM or CCYYMMDDHHMMSS, formats 203 and 204), time (HHMM or
+/// HHMMSS) or period of dates (CCYYMMDD-CCYYMMDD, format 718); `None` for
+/// other values and dates and times in other forms
+fn edifact_date_time(value: &Value) -> Option<String> {
+    let date = |text: &str| {
+        chrono::NaiveDate::parse_from_str(text, "%Y-%m-%d")
+            .ok()
+            .map(|date| date.format("%Y%m%d").to_string())
+    };
+    match value {
+        Value::Date(text) => date(text),
+        Value::Period(text) => {
+            let (start, end) = text.split_once('/')?;
+            Some(format!("{}-{}", date(start)?, date(end)?))
+        }
+        Value::DateTime(text) => [
+            ("%Y-%m-%dT%H:%M", "%Y%m%d%H%M"),
+            ("%Y-%m-%dT%H:%M:%S", "%Y%m%d%H%M%S"),
+        ]
+        .iter()
+        .find_map(|(iso, edifact)| {
+            chrono::NaiveDateTime::parse_from_str(text, iso)
+                .ok()
+                .map(|date_time| date_time.format(edifact).to_string())
+        }),
+        Value::Time(text) => {
+            [("%H:%M", "%H%M"), ("%H:%M:%S", "%H%M%S")]
+                .iter()
+                .find_map(|(iso, edifact)| {
+                    chrono::NaiveTime::parse_from_str(text, iso)
+                        .ok()
+                        .map(|time| time.format(edifact).to_string())
+                })
+        }
+        _ => None,
+    }
+}
+
+/// Syntax identifier and version named by the first UNB segment
+fn declared_syntax(node: &Node) -> Option<(String, Option<String>)> {
+    if node.node_type == NodeType::Segment && node.name == "UNB" {
+        let syntax = node.children.first()?;
+        let component = |index: usize| {
+            syntax
+                .children
+                .get(index)
+                .and_then(|component| component.value.as_ref())
+                .and_then(Value::as_string)
+        };
+        return match syntax.children.first() {
+            Some(_) => Some((component(0)?, component(1))),
+            None => Some((syntax.value.as_ref()?.as_string()?, None)),
+        };
+    }
+    node.children.iter().find_map(declared_syntax)
+}
 
+fn collect_segment_nodes<'a>(node: &'a Node, segments: &mut Vec<&'a Node>) {
     if matches!(node.node_type, NodeType::Segment) {
-        if !output.is_empty() {
-            output.push('\n');
-        }
-        output.push_str(&serialize_native_segment(node));
-        segment_count += 1;
+        segments.push(node);
     }
 
     for child in &node.children {
-        segment_count += collect_native_segments_into_output(child, output);
+        collect_segment_nodes(child, segments);
     }
+}
 
-    segment_count
+/// Returns `true` if `segment` still holds the values of its source text
+/// `raw`, compared in the canonical rendering
+fn is_unmodified(segment: &Node, raw: &str, syntax: &Syntax) -> bool {
+    let mut parser = SegmentParser::new(raw.as_bytes(), "raw");
+    parser.set_separators(syntax.separators);
+    let Some(Ok(original)) = parser.next_segment() else {
+        return false;
+    };
+    let canonical = Syntax {
+        trim_trailing_empty: false,
+        ..*syntax
+    };
+    serialize_native_segment(&original.to_node(), &canonical)
+        == serialize_native_segment(segment, &canonical)
 }
 
-fn serialize_native_segment(segment: &Node) -> String {
-    let mut rendered = segment.name.clone();
-    let mut elements = Vec::new();
+fn collect_native_segments(node: &Node, segments: &mut Vec<String>, syntax: &Syntax) {
+    if matches!(node.node_type, NodeType::Segment) {
+        segments.push(serialize_native_segment(node, syntax));
+    }
 
-    for element in &segment.children {
-        if !matches!(element.node_type, NodeType::Element) {
-            continue;
-        }
+    for child in &node.children {
+        collect_native_segments(child, segments, syntax);
+    }
+}
 
-        if element.children.is_empty() {
-            let value = element
-                .value
-                .as_ref()
-                .and_then(Value::as_string)
-                .unwrap_or_default();
-            elements.push(escape_edifact_value(&value));
-            continue;
-        }
+fn serialize_native_segment(segment: &Node, syntax: &Syntax) -> String {
+    let elements = segment
+        .children
+        .iter()
+        .filter(|element| matches!(element.node_type, NodeType::Element))
+        .map(|element| serialize_native_element(element, syntax))
+        .collect();
+    syntax.segment(&segment.name, elements)
+}
 
-        let components = element
+/// Render a simple, composite or repeated element. Repeated elements have
+/// `Element` children, one per occurrence, joined by the repetition separator.
+fn serialize_native_element(element: &Node, syntax: &Syntax) -> String {
+    if element.children.is_empty() {
+        return element
+            .value
+            .as_ref()
+            .map_or_else(String::new, |value| syntax.value(value));
+    }
+
+    if is_repeated(element) {
+        let occurrences = element
             .children
             .iter()
-            .map(|component| {
-                component
-                    .value
-                    .as_ref()
-                    .and_then(Value::as_string)
-                    .map_or_else(String::new, |value| escape_edifact_value(&value))
-            })
-            .collect::<Vec<_>>()
-            .join(":");
-        elements.push(components);
+            .map(|occurrence| serialize_native_element(occurrence, syntax))
+            .collect::<Vec<_>>();
+        return occurrences.join(char::from(syntax.repetition()).encode_utf8(&mut [0; 4]));
     }
 
-    while elements.last().is_some_and(String::is_empty) {
-        elements.pop();
-    }
+    let components = element
+        .children
+        .iter()
+        .map(|component| {
+            component
+                .value
+                .as_ref()
+                .map_or_else(String::new, |value| syntax.value(value))
+        })
+        .collect();
+    syntax.join(components, syntax.separators.component)
+}
 
-    if !elements.is_empty() {
-        rendered.push('+');
-        rendered.push_str(&elements.join("+"));
-    }
+/// Returns `true` for an element whose children are its occurrences
+fn is_repeated(element: &Node) -> bool {
+    !element.children.is_empty()
+        && element
+            .children
+            .iter()
+            .all(|child| matches!(child.node_type, NodeType::Element))
+}
 
-    rendered.push('\'');
-    rendered
+/// Returns `true` if `node` or a descendant is a repeated element
+fn has_repeated_elements(node: &Node) -> bool {
+    (matches!(node.node_type, NodeType::Element) && is_repeated(node))
+        || node.children.iter().any(has_repeated_elements)
 }
 
 fn collect_mapped_fields(
@@ -350,7 +738,11 @@ fn collect_mapped_fields(
         context_segment
     };
 
-    if let Some(value) = node.value.as_ref().and_then(Value::as_string) {
+    if let Some(value) = node
+        .value
+        .as_ref()
+        .filter(|value| value.as_string().is_some())
+    {
         if let Some((descriptor, element_index, component_index)) =
             parse_mapped_field_name(&node.name, next_context)
         {
@@ -358,7 +750,7 @@ fn collect_mapped_fields(
                 descriptor,
                 element_index,
                 component_index,
-                value,
+                value: value.clone(),
             });
         }
     }
@@ -425,20 +817,6 @@ fn build_segments_from_fields(fields: &[MappedField]) -> Result<Vec<SegmentAccum
     Ok(segments)
 }
 
-fn escape_edifact_value(value: &str) -> String {
-    let mut escaped = String::with_capacity(value.len());
-    for ch in value.chars() {
-        match ch {
-            '?' | '+' | ':' | '\'' => {
-                escaped.push('?');
-                escaped.push(ch);
-            }
-            _ => escaped.push(ch),
-        }
-    }
-    escaped
-}
-
 fn is_segment_context_name(name: &str) -> bool {
     SegmentDescriptor::from_token(name).is_some()
 }
@@ -552,4 +930,335 @@ mod tests {
             "unexpected error: {error}"
         );
     }
+
+    fn unb_document(syntax_identifier: &str, name: &str) -> Document {
+        let mut root = Node::new("ROOT", NodeType::Root);
+        let mut unb = Node::new("UNB", NodeType::Segment);
+        let mut syntax = Node::new("e1", NodeType::Element);
+        for value in [syntax_identifier, "3"] {
+            syntax.add_child(Node::with_value(
+                "c",
+                NodeType::Component,
+                Value::String(value.to_string()),
+            ));
+        }
+        unb.add_child(syntax);
+        root.add_child(unb);
+
+        let mut nad = Node::new("NAD", NodeType::Segment);
+        nad.add_child(Node::with_value(
+            "e1",
+            NodeType::Element,
+            Value::String(name.to_string()),
+        ));
+        root.add_child(nad);
+        Document::new(root)
+    }
+
+    #[test]
+    fn encodes_output_in_declared_charset() {
+        let bytes = EdifactSerializer::new()
+            .serialize_document_bytes(&unb_document("UNOC", "Müller"))
+            .expect("serialize");
+        assert_eq!(bytes, b"UNB+UNOC:3'\nNAD+M\xfcller'");
+
+        let bytes = EdifactSerializer::new()
+            .with_charset(Charset::Unoy)
+            .serialize_document_bytes(&unb_document("UNOC", "Müller"))
+            .expect("serialize");
+        assert_eq!(bytes, "UNB+UNOC:3'\nNAD+Müller'".as_bytes());
+    }
+
+    #[test]
+    fn rejects_characters_outside_declared_charset() {
+        let error = EdifactSerializer::new()
+            .serialize_document_bytes(&unb_document("UNOA", "Acme"))
+            .expect_err("lower case is not in UNOA");
+        assert!(
+            error.to_string().contains("UNOA"),
+            "unexpected error: {error}"
+        );
+    }
+
+    #[test]
+    fn serializes_repeated_elements_and_escapes_repetition_separator() {
+        let mut root = unb_document("UNOC", "ACME").root;
+        root.children[0].children[0].children[1].value = Some(Value::String("4".to_string()));
+
+        let mut ftx = Node::new("FTX", NodeType::Segment);
+        ftx.add_child(Node::with_value(
+            "e1",
+            NodeType::Element,
+            Value::String("AAI".to_string()),
+        ));
+        let mut text = Node::new("e2", NodeType::Element);
+        for value in ["LINE 1", "5*6"] {
+            text.add_child(Node::with_value(
+                "r",
+                NodeType::Element,
+                Value::String(value.to_string()),
+            ));
+        }
+        ftx.add_child(text);
+        root.add_child(ftx);
+
+        let rendered = EdifactSerializer::new()
+            .serialize_document(&Document::new(root))
+            .expect("serialize");
+        assert_eq!(
+            rendered.lines().last(),
+            Some("FTX+AAI+LINE 1*5?*6'"),
+            "{rendered}"
+        );
+    }
+
+    #[test]
+    fn round_trips_released_repetition_separator_in_messages() {
+        let data = b"UNB+UNOC:4+S+R+20200101:1200+1'UNH+1+ORDERS:D:96A:UN'\
+FTX+AAI+++A?*B*C'UNT+3+1'UNZ+1+1'";
+        let documents = crate::EdifactParser::new()
+            .parse(data, "test")
+            .expect("parse");
+
+        // Message documents carry no UNB, so the separator is chosen for the
+        // repeats themselves
+        let rendered = EdifactSerializer::new()
+            .serialize_document(&documents[0])
+            .expect("serialize");
+        assert!(rendered.contains("FTX+AAI+++A?*B*C'"), "{rendered}");
+
+        let reparsed = crate::EdifactParser::new()
+            .parse(
+                format!("UNB+UNOC:4+S+R+20200101:1200+1'{rendered}UNZ+1+1'").as_bytes(),
+                "test",
+            )
+            .expect("reparse");
+        let ftx = |document: &Document| {
+            document
+                .root
+                .children
+                .iter()
+                .find(|node| node.name == "FTX")
+                .expect("FTX")
+                .children[3]
+                .children
+                .iter()
+                .map(|occurrence| occurrence.value.as_ref().and_then(Value::as_string))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ftx(&reparsed[0]), ftx(&documents[0]));
+        assert_eq!(
+            ftx(&reparsed[0]),
+            vec![Some("A*B".to_string()), Some("C".to_string())]
+        );
+    }
+
+    fn decimal(text: &str) -> Value {
+        Value::Decimal(edi_ir::parse_decimal(text).expect("decimal"))
+    }
+
+    fn segment(tag: &str, values: &[&str]) -> Node {
+        let mut segment = Node::new(tag, NodeType::Segment);
+        for (index, value) in values.iter().enumerate() {
+            let element = if value.contains(':') {
+                let mut element = Node::new(format!("e{}", index + 1), NodeType::Element);
+                for (component, text) in value.split(':').enumerate() {
+                    element.add_child(Node::with_value(
+                        format!("c{}", component + 1),
+                        NodeType::Component,
+                        Value::String(text.to_string()),
+                    ));
+                }
+                element
+            } else {
+                Node::with_value(
+                    format!("e{}", index + 1),
+                    NodeType::Element,
+                    Value::String((*value).to_string()),
+                )
+            };
+            segment.add_child(element);
+        }
+        segment
+    }
+
+    fn document(segments: Vec<Node>) -> Document {
+        let mut root = Node::new("ROOT", NodeType::Root);
+        for segment in segments {
+            root.add_child(segment);
+        }
+        Document::new(root)
+    }
+
+    #[test]
+    fn writes_iso_dates_and_times_in_edifact_form() {
+        let mut document = document(vec![
+            segment("DTM", &["137:date:102"]),
+            segment("DTM", &["2:date:203"]),
+            segment("DTM", &["263:period:718"]),
+            segment("TIM", &["time"]),
+        ]);
+        let root = &mut document.root;
+        root.children[0].children[0].children[1].value =
+            Some(Value::Date("2026-03-12".to_string()));
+        root.children[1].children[0].children[1].value =
+            Some(Value::DateTime("2026-03-12T09:30".to_string()));
+        root.children[2].children[0].children[1].value =
+            Some(Value::Period("2026-03-01/2026-03-31".to_string()));
+        root.children[3].children[0].value = Some(Value::Time("09:30:15".to_string()));
+
+        let rendered = EdifactSerializer::new()
+            .with_segment_suffix(SegmentSuffix::None)
+            .serialize_document(&document)
+            .expect("serialize");
+
+        assert_eq!(
+            rendered,
+            "DTM+137:20260312:102'DTM+2:202603120930:203'DTM+263:20260301-20260331:718'TIM+093015'"
+        );
+    }
+
+    #[test]
+    fn uses_configured_separators_and_emits_una() {
+        let separators = Separators {
+            component: b'>',
+            element: b'|',
+            decimal: b',',
+            release: b'\\',
+            repetition: None,
+            segment: b'~',
+        };
+        let mut document = document(vec![
+            segment("BGM", &["220", "PO|1~A>B"]),
+            segment("QTY", &["21:12.5:PCE"]),
+        ]);
+        document.root.children[1].children[0].children[1].value = Some(decimal("12.5"));
+
+        let rendered = EdifactSerializer::new()
+            .with_separators(separators)
+            .with_una(true)
+            .with_segment_suffix(SegmentSuffix::None)
+            .serialize_document(&document)
+            .expect("serialize");
+
+        assert_eq!(
+            rendered,
+            "UNA>|,\\ ~BGM|220|PO\\|1\\~A\\>B~QTY|21>12,5>PCE~"
+        );
+    }
+
+    #[test]
+    fn writes_crlf_after_every_segment() {
+        let document = document(vec![segment("BGM", &["220"]), segment("DTM", &["137"])]);
+
+        let rendered = EdifactSerializer::new()
+            .with_segment_suffix(SegmentSuffix::CrLf)
+            .serialize_document(&document)
+            .expect("serialize");
+
+        assert_eq!(rendered, "BGM+220'\r\nDTM+137'\r\n");
+    }
+
+    #[test]
+    fn converts_decimal_mark_only_for_numeric_values() {
+        let mut document = document(vec![
+            segment("MOA", &["203:1234.50"]),
+            segment("FTX", &["AAI", "", "", "VERSION 1.2B"]),
+            segment("PRI", &["AAA:-.75"]),
+            segment("RFF", &["ON:12.5"]),
+            segment("QTY", &["21:3"]),
+        ]);
+        let root = &mut document.root;
+        root.children[0].children[0].children[1].value = Some(decimal("1234.50"));
+        root.children[2].children[0].children[1].value = Some(decimal("-.75"));
+        root.children[4].children[0].children[1].value = Some(Value::Integer(3));
+
+        let rendered = EdifactSerializer::new()
+            .with_separators(Separators {
+                decimal: b',',
+                ..Separators::default()
+            })
+            .serialize_document(&document)
+            .expect("serialize");
+
+        assert_eq!(
+            rendered.lines().collect::<Vec<_>>(),
+            vec![
+                "MOA+203:1234,50'",
+                "FTX+AAI+++VERSION 1.2B'",
+                "PRI+AAA:-0,75'",
+                "RFF+ON:12.5'",
+                "QTY+21:3'"
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_trailing_empty_positions_when_trimming_is_disabled() {
+        let document = document(vec![segment("NAD", &["BY", "123::9", "", ""])]);
+
+        let trimmed = EdifactSerializer::new()
+            .serialize_document(&document)
+            .expect("serialize");
+        assert_eq!(trimmed, "NAD+BY+123::9'");
+
+        let untrimmed = EdifactSerializer::new()
+            .with_trim_trailing_empty(false)
+            .serialize_document(&document)
+            .expect("serialize");
+        assert_eq!(untrimmed, "NAD+BY+123::9++'");
+
+        let document = self::document(vec![segment("NAD", &["BY", "123:9:"])]);
+        let untrimmed = EdifactSerializer::new()
+            .with_trim_trailing_empty(false)
+            .serialize_document(&document)
+            .expect("serialize");
+        assert_eq!(untrimmed, "NAD+BY+123:9:'");
+    }
+
+    #[test]
+    fn lossless_mode_writes_new_segments_in_source_layout() {
+        let data = b"UNH+1+ORDERS:D:96A:UN'\r\nBGM+220+PO1+9'\r\nUNT+3+1'";
+        let mut document = crate::EdifactParser::new()
+            .with_lossless(true)
+            .parse_message(data, "test")
+            .expect("parse");
+        document
+            .root
+            .children
+            .insert(2, segment("DTM", &["137:20200101:102"]));
+
+        let lossless = EdifactSerializer::new()
+            .with_lossless(true)
+            .serialize_document(&document)
+            .expect("serialize");
+        assert_eq!(
+            lossless,
+            "UNH+1+ORDERS:D:96A:UN'\r\nBGM+220+PO1+9'\r\nDTM+137:20200101:102'\r\nUNT+3+1'"
+        );
+
+        // Recorded source text is ignored unless requested
+        let rendered = EdifactSerializer::new()
+            .serialize_document(&document)
+            .expect("serialize");
+        assert_eq!(
+            rendered,
+            "UNH+1+ORDERS:D:96A:UN'\nBGM+220+PO1+9'\nDTM+137:20200101:102'\nUNT+3+1'"
+        );
+    }
+
+    #[test]
+    fn rejects_separators_that_are_not_distinct() {
+        let error = EdifactSerializer::new()
+            .with_separators(Separators {
+                component: b'+',
+                ..Separators::default()
+            })
+            .serialize_document(&document(vec![segment("BGM", &["220"])]))
+            .expect_err("component and element separators collide");
+        assert!(
+            error.to_string().contains("distinct"),
+            "unexpected error: {error}"
+        );
+    }
 }
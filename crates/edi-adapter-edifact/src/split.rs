@@ -0,0 +1,547 @@
+//! Interchange splitting and merging
+//!
+//! [`InterchangeSplitter`] distributes the messages of an interchange over new
+//! interchanges, one per [`SplitKey`] value; [`InterchangeMerger`] collects
+//! the messages of several interchanges into one interchange per recipient.
+//! Both rebuild the envelopes with an [`InterchangeWriter`], so every output
+//! gets a fresh interchange control reference, renumbered messages and
+//! recounted UNT/UNZ segments. Functional groups (UNG/UNE) are not carried
+//! over.
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use edi_ir::{Document, Node, NodeType, Value};
+
+use crate::envelopes::{self, ControlNumberGenerator, DateTime, PartyId, UnbSegment};
+use crate::parser::Segment;
+use crate::writer::{InterchangeWriter, PartnerProfile};
+use crate::{Error, Result};
+
+/// Error raised while computing a custom split key
+pub type KeyError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Computes the group key of a message document; `Ok(None)` means the
+/// message does not carry the key
+pub type KeyFn = dyn Fn(&Document) -> std::result::Result<Option<String>, KeyError> + Send + Sync;
+
+/// How messages are grouped into output interchanges
+#[derive(Clone)]
+pub enum SplitKey {
+    /// One interchange per message
+    Message,
+    /// One interchange per message type (e.g. "DESADV")
+    MessageType,
+    /// One interchange per party or value in element 2 of the first `tag`
+    /// segment qualified by `qualifier` in element 1, e.g. `NAD+DP` for the
+    /// delivery party
+    Qualified {
+        /// Segment tag (e.g. "NAD")
+        tag: String,
+        /// Qualifier in element 1 (e.g. "DP")
+        qualifier: String,
+    },
+    /// One interchange per key computed by the caller, e.g. from a mapping
+    Custom(Arc<KeyFn>),
+}
+
+impl SplitKey {
+    /// Key of `message`, or `None` if the message does not carry it
+    fn key(&self, index: usize, message: &Document) -> Result<Option<String>> {
+        Ok(match self {
+            Self::Message => Some((index + 1).to_string()),
+            Self::MessageType => segments(&message.root)
+                .into_iter()
+                .find(|segment| segment.name == "UNH")
+                .and_then(|unh| element_text(unh, 1)),
+            Self::Qualified { tag, qualifier } => segments(&message.root)
+                .into_iter()
+                .filter(|segment| &segment.name == tag)
+                .find(|segment| element_text(segment, 0).as_ref() == Some(qualifier))
+                .and_then(|segment| element_text(segment, 1)),
+            Self::Custom(key) => key(message).map_err(|error| {
+                Error::Envelope(format!(
+                    "Split key of message {} could not be computed: {error}",
+                    index + 1
+                ))
+            })?,
+        })
+    }
+}
+
+impl fmt::Debug for SplitKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Message => f.write_str("Message"),
+            Self::MessageType => f.write_str("MessageType"),
+            Self::Qualified { tag, qualifier } => f
+                .debug_struct("Qualified")
+                .field("tag", tag)
+                .field("qualifier", qualifier)
+                .finish(),
+            Self::Custom(_) => f.write_str("Custom(..)"),
+        }
+    }
+}
+
+impl FromStr for SplitKey {
+    type Err = Error;
+
+    /// Parse "message", "message-type" or a qualified segment such as
+    /// "NAD+DP"
+    fn from_str(value: &str) -> Result<Self> {
+        match value {
+            "message" => Ok(Self::Message),
+            "message-type" => Ok(Self::MessageType),
+            _ => match value.split_once('+') {
+                Some((tag, qualifier))
+                    if tag.len() == 3
+                        && tag.bytes().all(|byte| byte.is_ascii_uppercase())
+                        && !qualifier.is_empty() =>
+                {
+                    Ok(Self::Qualified {
+                        tag: tag.to_string(),
+                        qualifier: qualifier.to_string(),
+                    })
+                }
+                _ => Err(Error::Envelope(format!(
+                    "Invalid split key '{value}', expected 'message', 'message-type' or a \
+                     qualified segment such as 'NAD+DP'"
+                ))),
+            },
+        }
+    }
+}
+
+/// An interchange rebuilt by a split or merge
+#[derive(Debug, Clone)]
+pub struct InterchangePart {
+    /// Group key: the split key value, or for merges the recipient
+    /// identification with its qualifier and routing as in UNB (e.g.
+    /// `RETAILER1:14` or `RETAILER1:14:DEPT7`)
+    pub key: String,
+    /// Interchange document with fresh envelopes
+    pub interchange: Document,
+}
+
+/// Splits interchanges into one interchange per key value
+pub struct InterchangeSplitter {
+    key: SplitKey,
+    control_numbers: Arc<dyn ControlNumberGenerator>,
+    datetime: Option<DateTime>,
+}
+
+impl InterchangeSplitter {
+    /// Create a splitter grouping by `key` and drawing references from
+    /// `control_numbers`
+    pub fn new(key: SplitKey, control_numbers: Arc<dyn ControlNumberGenerator>) -> Self {
+        Self {
+            key,
+            control_numbers,
+            datetime: None,
+        }
+    }
+
+    /// Use a fixed preparation date and time instead of the current UTC time
+    #[must_use]
+    pub fn with_datetime(mut self, datetime: DateTime) -> Self {
+        self.datetime = Some(datetime);
+        self
+    }
+
+    /// Split an interchange document as returned by
+    /// [`EdifactParser::parse_interchanges`](crate::EdifactParser::parse_interchanges).
+    ///
+    /// Outputs keep the parties, syntax identifier and test indicator of the
+    /// source UNB and come in the order their keys first appear.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the interchange has no UNB or no messages, a
+    /// message does not carry the split key, or an envelope cannot be built.
+    pub fn split(&self, interchange: &Document) -> Result<Vec<InterchangePart>> {
+        let unb = interchange_unb(interchange)?;
+        let messages = interchange_messages(interchange);
+        if messages.is_empty() {
+            return Err(Error::Envelope(format!(
+                "Interchange {} contains no messages",
+                unb.control_ref
+            )));
+        }
+
+        let mut groups: Vec<(String, Vec<Document>)> = Vec::new();
+        for (index, message) in messages.into_iter().enumerate() {
+            let key = self.key.key(index, &message)?.ok_or_else(|| {
+                Error::Envelope(format!(
+                    "Message {} has no value for split key {:?}",
+                    index + 1,
+                    self.key
+                ))
+            })?;
+            match groups.iter_mut().find(|(existing, _)| *existing == key) {
+                Some((_, group)) => group.push(message),
+                None => groups.push((key, vec![message])),
+            }
+        }
+
+        let writer = writer(
+            PartnerProfile::from_unb(&unb),
+            &self.control_numbers,
+            self.datetime.as_ref(),
+        );
+        groups
+            .into_iter()
+            .map(|(key, messages)| {
+                Ok(InterchangePart {
+                    key,
+                    interchange: writer.build(&messages)?,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Merges the messages of several interchanges into one interchange per
+/// recipient
+pub struct InterchangeMerger {
+    control_numbers: Arc<dyn ControlNumberGenerator>,
+    datetime: Option<DateTime>,
+}
+
+impl InterchangeMerger {
+    /// Create a merger drawing references from `control_numbers`
+    pub fn new(control_numbers: Arc<dyn ControlNumberGenerator>) -> Self {
+        Self {
+            control_numbers,
+            datetime: None,
+        }
+    }
+
+    /// Use a fixed preparation date and time instead of the current UTC time
+    #[must_use]
+    pub fn with_datetime(mut self, datetime: DateTime) -> Self {
+        self.datetime = Some(datetime);
+        self
+    }
+
+    /// Merge interchange documents as returned by
+    /// [`EdifactParser::parse_interchanges`](crate::EdifactParser::parse_interchanges).
+    ///
+    /// Messages are grouped by UNB recipient in input order; each output
+    /// uses the sender and syntax identifier of the first interchange for
+    /// that recipient.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an interchange has no UNB, interchanges for the
+    /// same recipient come from different senders, or an envelope cannot be
+    /// built.
+    pub fn merge(&self, interchanges: &[Document]) -> Result<Vec<InterchangePart>> {
+        let mut groups: Vec<(UnbSegment, Vec<Document>)> = Vec::new();
+        for interchange in interchanges {
+            let unb = interchange_unb(interchange)?;
+            let messages = interchange_messages(interchange);
+            match groups
+                .iter_mut()
+                .find(|(existing, _)| existing.receiver == unb.receiver)
+            {
+                Some((existing, _)) if existing.sender != unb.sender => {
+                    return Err(Error::Envelope(format!(
+                        "Interchanges for recipient {} come from different senders ({} and {})",
+                        unb.receiver.id, existing.sender.id, unb.sender.id
+                    )));
+                }
+                Some((_, group)) => group.extend(messages),
+                None => groups.push((unb, messages)),
+            }
+        }
+
+        groups
+            .into_iter()
+            .filter(|(_, messages)| !messages.is_empty())
+            .map(|(unb, messages)| {
+                let writer = writer(
+                    PartnerProfile::from_unb(&unb),
+                    &self.control_numbers,
+                    self.datetime.as_ref(),
+                );
+                Ok(InterchangePart {
+                    key: party_key(&unb.receiver),
+                    interchange: writer.build(&messages)?,
+                })
+            })
+            .collect()
+    }
+}
+
+/// `party` as written in UNB (`id:qualifier:internal id:internal
+/// qualifier`), without trailing empty components
+fn party_key(party: &PartyId) -> String {
+    let components = [
+        Some(party.id.as_str()),
+        party.qualifier.as_deref(),
+        party.internal_id.as_deref(),
+        party.internal_qualifier.as_deref(),
+    ];
+    let used = components
+        .iter()
+        .rposition(|component| component.is_some_and(|value| !value.is_empty()))
+        .map_or(1, |last| last + 1);
+    components[..used]
+        .iter()
+        .map(|component| component.unwrap_or_default())
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+fn writer(
+    profile: PartnerProfile,
+    control_numbers: &Arc<dyn ControlNumberGenerator>,
+    datetime: Option<&DateTime>,
+) -> InterchangeWriter {
+    let writer = InterchangeWriter::new(profile, Arc::clone(control_numbers));
+    match datetime {
+        Some(datetime) => writer.with_datetime(datetime.clone()),
+        None => writer,
+    }
+}
+
+/// UNB of an interchange document
+fn interchange_unb(interchange: &Document) -> Result<UnbSegment> {
+    let unb = interchange
+        .root
+        .children
+        .iter()
+        .find(|child| child.node_type == NodeType::Segment && child.name == "UNB")
+        .ok_or_else(|| Error::Envelope("Interchange has no UNB segment".to_string()))?;
+    envelopes::parse_unb(&Segment::from_node(unb))
+}
+
+/// Message documents of an interchange, including those inside functional
+/// groups
+fn interchange_messages(interchange: &Document) -> Vec<Document> {
+    fn collect(node: &Node, messages: &mut Vec<Document>) {
+        for child in &node.children {
+            match child.node_type {
+                NodeType::Message => messages.push(Document::new(child.clone())),
+                NodeType::FunctionalGroup => collect(child, messages),
+                _ => {}
+            }
+        }
+    }
+
+    let mut messages = Vec::new();
+    collect(&interchange.root, &mut messages);
+    messages
+}
+
+fn segments(node: &Node) -> Vec<&Node> {
+    fn collect<'a>(node: &'a Node, segments: &mut Vec<&'a Node>) {
+        if node.node_type == NodeType::Segment {
+            segments.push(node);
+            return;
+        }
+        for child in &node.children {
+            collect(child, segments);
+        }
+    }
+
+    let mut segments = Vec::new();
+    collect(node, &mut segments);
+    segments
+}
+
+/// Text of element `index`, or of its first component when composite
+fn element_text(segment: &Node, index: usize) -> Option<String> {
+    let element = segment.children.get(index)?;
+    let node = element.children.first().unwrap_or(element);
+    node.value
+        .as_ref()
+        .and_then(Value::as_string)
+        .filter(|value| !value.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EdifactParser;
+    use crate::EdifactSerializer;
+    use crate::envelopes::MemoryControlNumberGenerator;
+
+    const DESADV: &[u8] = b"UNB+UNOC:3+SUPPLIER:14+RETAILER:14+260312:0930+42'\n\
+        UNH+7+DESADV:D:96A:UN:EAN005'\nBGM+351+D1'\nNAD+DP+STORE1::9'\nUNT+4+7'\n\
+        UNH+8+DESADV:D:96A:UN:EAN005'\nBGM+351+D2'\nNAD+DP+STORE2::9'\nUNT+4+8'\n\
+        UNH+9+DESADV:D:96A:UN:EAN005'\nBGM+351+D3'\nNAD+DP+STORE1::9'\nUNT+4+9'\n\
+        UNZ+3+42'";
+
+    fn datetime() -> DateTime {
+        DateTime {
+            date: "260313".to_string(),
+            time: "1000".to_string(),
+        }
+    }
+
+    fn parse(data: &[u8]) -> Vec<Document> {
+        EdifactParser::new()
+            .parse_interchanges(data, "test")
+            .unwrap()
+    }
+
+    fn text(part: &InterchangePart) -> String {
+        EdifactSerializer::new()
+            .serialize_document(&part.interchange)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_split_by_qualified_segment_rebuilds_envelopes() {
+        let interchange = &parse(DESADV)[0];
+        let splitter = InterchangeSplitter::new(
+            "NAD+DP".parse().unwrap(),
+            Arc::new(MemoryControlNumberGenerator::new()),
+        )
+        .with_datetime(datetime());
+
+        let parts = splitter.split(interchange).unwrap();
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].key, "STORE1");
+        assert_eq!(parts[1].key, "STORE2");
+        assert_eq!(
+            text(&parts[0]),
+            "UNB+UNOC:3+SUPPLIER:14+RETAILER:14+260313:1000+00000000000001'\n\
+             UNH+000001+DESADV:D:96A:UN:EAN005'\nBGM+351+D1'\nNAD+DP+STORE1::9'\nUNT+4+000001'\n\
+             UNH+000002+DESADV:D:96A:UN:EAN005'\nBGM+351+D3'\nNAD+DP+STORE1::9'\nUNT+4+000002'\n\
+             UNZ+2+00000000000001'"
+        );
+        assert_eq!(
+            parts[1].interchange.metadata.interchange_ref.as_deref(),
+            Some("00000000000002")
+        );
+    }
+
+    #[test]
+    fn test_split_per_message_and_by_custom_key() {
+        let interchange = &parse(DESADV)[0];
+        let control_numbers: Arc<dyn ControlNumberGenerator> =
+            Arc::new(MemoryControlNumberGenerator::new());
+
+        let parts = InterchangeSplitter::new(SplitKey::Message, control_numbers.clone())
+            .split(interchange)
+            .unwrap();
+        assert_eq!(parts.len(), 3);
+        assert!(
+            parts
+                .iter()
+                .all(|part| part.interchange.metadata.message_refs.len() == 1)
+        );
+
+        let by_type = SplitKey::Custom(Arc::new(|message: &Document| {
+            Ok(segments(&message.root)
+                .into_iter()
+                .find(|segment| segment.name == "BGM")
+                .and_then(|bgm| element_text(bgm, 0)))
+        }));
+        let parts = InterchangeSplitter::new(by_type, Arc::clone(&control_numbers))
+            .split(interchange)
+            .unwrap();
+        assert_eq!(parts.len(), 1);
+        assert_eq!(parts[0].key, "351");
+
+        let failing = SplitKey::Custom(Arc::new(|_: &Document| Err("no mapping output".into())));
+        let error = InterchangeSplitter::new(failing, control_numbers)
+            .split(interchange)
+            .unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("Split key of message 1 could not be computed: no mapping output"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn test_split_fails_when_a_message_lacks_the_key() {
+        let interchange = &parse(DESADV)[0];
+        let error = InterchangeSplitter::new(
+            "NAD+BY".parse().unwrap(),
+            Arc::new(MemoryControlNumberGenerator::new()),
+        )
+        .split(interchange)
+        .unwrap_err();
+        assert!(
+            error.to_string().contains("no value for split key"),
+            "{error}"
+        );
+        assert!("NADDP".parse::<SplitKey>().is_err());
+    }
+
+    #[test]
+    fn test_merge_groups_messages_per_recipient() {
+        let single = |recipient: &str, reference: &str| {
+            format!(
+                "UNB+UNOC:3+SUPPLIER:14+{recipient}:14+260312:0930+{reference}'\n\
+                 UNH+1+ORDRSP:D:96A:UN'\nBGM+231+{reference}'\nUNT+3+1'\nUNZ+1+{reference}'"
+            )
+        };
+        let interchanges = [
+            single("RETAILER1", "1"),
+            single("RETAILER2", "2"),
+            single("RETAILER1", "3"),
+        ]
+        .iter()
+        .flat_map(|data| parse(data.as_bytes()))
+        .collect::<Vec<_>>();
+
+        let parts = InterchangeMerger::new(Arc::new(MemoryControlNumberGenerator::new()))
+            .with_datetime(datetime())
+            .merge(&interchanges)
+            .unwrap();
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].key, "RETAILER1:14");
+        assert_eq!(
+            text(&parts[0]),
+            "UNB+UNOC:3+SUPPLIER:14+RETAILER1:14+260313:1000+00000000000001'\n\
+             UNH+000001+ORDRSP:D:96A:UN'\nBGM+231+1'\nUNT+3+000001'\n\
+             UNH+000002+ORDRSP:D:96A:UN'\nBGM+231+3'\nUNT+3+000002'\n\
+             UNZ+2+00000000000001'"
+        );
+        assert_eq!(parts[1].interchange.metadata.message_refs, ["000001"]);
+    }
+
+    #[test]
+    fn test_merge_keys_include_recipient_qualifier_and_routing() {
+        let interchanges = [
+            "UNB+UNOC:3+S+R:14+260312:0930+1'\nUNH+1+ORDRSP:D:96A:UN'\nUNT+2+1'\nUNZ+1+1'",
+            "UNB+UNOC:3+S+R:ZZ+260312:0930+2'\nUNH+1+ORDRSP:D:96A:UN'\nUNT+2+1'\nUNZ+1+2'",
+            "UNB+UNOC:3+S+R+260312:0930+3'\nUNH+1+ORDRSP:D:96A:UN'\nUNT+2+1'\nUNZ+1+3'",
+            "UNB+UNOC:3+S+R:14:DEPT7+260312:0930+4'\nUNH+1+ORDRSP:D:96A:UN'\nUNT+2+1'\nUNZ+1+4'",
+            "UNB+UNOC:3+S+R::DEPT7+260312:0930+5'\nUNH+1+ORDRSP:D:96A:UN'\nUNT+2+1'\nUNZ+1+5'",
+        ]
+        .iter()
+        .flat_map(|data| parse(data.as_bytes()))
+        .collect::<Vec<_>>();
+
+        let parts = InterchangeMerger::new(Arc::new(MemoryControlNumberGenerator::new()))
+            .merge(&interchanges)
+            .unwrap();
+        let keys: Vec<_> = parts.iter().map(|part| part.key.as_str()).collect();
+        assert_eq!(keys, ["R:14", "R:ZZ", "R", "R:14:DEPT7", "R::DEPT7"]);
+    }
+
+    #[test]
+    fn test_merge_rejects_conflicting_senders() {
+        let interchanges = [
+            "UNB+UNOC:3+A+R+260312:0930+1'\nUNH+1+ORDRSP:D:96A:UN'\nUNT+2+1'\nUNZ+1+1'",
+            "UNB+UNOC:3+B+R+260312:0930+2'\nUNH+1+ORDRSP:D:96A:UN'\nUNT+2+1'\nUNZ+1+2'",
+        ]
+        .iter()
+        .flat_map(|data| parse(data.as_bytes()))
+        .collect::<Vec<_>>();
+
+        let error = InterchangeMerger::new(Arc::new(MemoryControlNumberGenerator::new()))
+            .merge(&interchanges)
+            .unwrap_err();
+        assert!(error.to_string().contains("different senders"), "{error}");
+    }
+}
@@ -9,6 +9,8 @@ pub const DEFAULT_ELEMENT_SEPARATOR: u8 = b'+';
 pub const DEFAULT_DECIMAL_POINT: u8 = b'.';
 pub const DEFAULT_RELEASE_CHARACTER: u8 = b'?';
 pub const DEFAULT_SEGMENT_TERMINATOR: u8 = b'\'';
+/// Default repetition separator for syntax version 4
+pub const DEFAULT_REPETITION_SEPARATOR: u8 = b'*';
 
 /// Separators used for parsing EDIFACT
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -21,6 +23,9 @@ pub struct Separators {
     pub decimal: u8,
     /// Release character (default '?')
     pub release: u8,
+    /// Repetition separator (syntax version 4, default '*'); `None` when
+    /// UNA position 7 holds the reserved space of earlier versions
+    pub repetition: Option<u8>,
     /// Segment terminator (default '\'')
     pub segment: u8,
 }
@@ -32,16 +37,27 @@ impl Default for Separators {
             element: DEFAULT_ELEMENT_SEPARATOR,
             decimal: DEFAULT_DECIMAL_POINT,
             release: DEFAULT_RELEASE_CHARACTER,
+            repetition: None,
             segment: DEFAULT_SEGMENT_TERMINATOR,
         }
     }
 }
 
 impl Separators {
+    /// Default separators for a syntax version; version 4 adds the
+    /// repetition separator
+    pub fn for_syntax_version(version: u8) -> Self {
+        Self {
+            repetition: (version >= 4).then_some(DEFAULT_REPETITION_SEPARATOR),
+            ..Self::default()
+        }
+    }
+
     /// Parse separators from a UNA segment
-    /// UNA format: UNA:+.? '
+    /// UNA format: UNA:+.? '  (syntax version 4: UNA:+.?*')
     /// Positions:  012345678
-    ///              ^^^^ ^  (separators at positions 3,4,5,6,8)
+    ///              ^^^^^^  (separators at positions 3-8; position 7 is the
+    ///                       repetition separator, or a reserved space)
     pub fn from_una(una: &[u8]) -> Option<Self> {
         if una.len() < 9 || &una[0..3] != b"UNA" {
             return None;
@@ -52,7 +68,8 @@ impl Separators {
             element: una[4],
             decimal: una[5],
             release: una[6],
-            // Position 7 is reserved (space)
+            // Position 7 is the repetition separator, or reserved (space)
+            repetition: (una[7] != b' ').then_some(una[7]),
             segment: una[8],
         })
     }
@@ -67,7 +84,7 @@ impl Separators {
             self.element,
             self.decimal,
             self.release,
-            b' ', // reserved
+            self.repetition.unwrap_or(b' '), // repetition or reserved
             self.segment,
         ]
     }
@@ -78,6 +95,7 @@ impl Separators {
             || byte == self.element
             || byte == self.segment
             || byte == self.release
+            || Some(byte) == self.repetition
     }
 }
 
@@ -158,6 +176,7 @@ impl<'a> SyntaxBuffer<'a> {
             } else if byte == self.separators.component
                 || byte == self.separators.element
                 || byte == self.separators.segment
+                || Some(byte) == self.separators.repetition
             {
                 // Delimiter found
                 self.pos += 1;
@@ -264,6 +283,30 @@ mod tests {
         assert_eq!(sep.segment, b'~');
     }
 
+    #[test]
+    fn test_una_repetition_separator() {
+        let sep = Separators::from_una(b"UNA:+.?*'").unwrap();
+        assert_eq!(sep.repetition, Some(b'*'));
+        assert_eq!(sep.to_una(), b"UNA:+.?*'".to_vec());
+        assert!(sep.is_special(b'*'));
+
+        let sep = Separators::from_una(b"UNA:+.? '").unwrap();
+        assert_eq!(sep.repetition, None);
+        assert!(!sep.is_special(b'*'));
+
+        assert_eq!(Separators::for_syntax_version(4).repetition, Some(b'*'));
+        assert_eq!(Separators::for_syntax_version(3), Separators::default());
+    }
+
+    #[test]
+    fn test_repetition_separator_is_a_delimiter() {
+        let data = b"A*B?*C+";
+        let mut buf = SyntaxBuffer::with_separators(data, Separators::for_syntax_version(4));
+
+        assert_eq!(buf.read_until_delimiter(), (b"A".to_vec(), Some(b'*')));
+        assert_eq!(buf.read_until_delimiter(), (b"B*C".to_vec(), Some(b'+')));
+    }
+
     #[test]
     fn test_release_character_handling() {
         let data = b"ABC?+DEF+GHI'";
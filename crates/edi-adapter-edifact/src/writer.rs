@@ -0,0 +1,491 @@
+//! EDIFACT interchange writer
+//!
+//! Wraps outbound messages in UNB/UNH…UNT/UNZ envelopes. Control references
+//! are drawn from a [`ControlNumberGenerator`], sender and recipient come from
+//! a [`PartnerProfile`], and the UNT segment counts and UNZ message count are
+//! computed from the segments actually written.
+
+use std::sync::Arc;
+
+use edi_ir::{Document, Node, NodeType, Value};
+
+use crate::envelopes::{
+    self, ControlNumberGenerator, DateTime, MessageTypeIdentifier, PartyId, SyntaxIdentifier,
+    UnbSegment, UnhSegment, UntSegment, UnzSegment,
+};
+use crate::parser::{Segment, SegmentParser};
+use crate::serializer::EdifactSerializer;
+use crate::syntax::Separators;
+use crate::{Error, Result};
+
+/// Service segments that the writer generates itself and drops from message
+/// bodies
+const ENVELOPE_TAGS: [&str; 6] = ["UNA", "UNB", "UNG", "UNE", "UNT", "UNZ"];
+
+/// Envelope settings for one trading partner
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartnerProfile {
+    /// Interchange sender (UNB S002)
+    pub sender: PartyId,
+    /// Interchange recipient (UNB S003)
+    pub recipient: PartyId,
+    /// Syntax identifier and version (UNB S001)
+    pub syntax_identifier: SyntaxIdentifier,
+    /// Message type for messages that carry no UNH of their own
+    pub message_type: Option<MessageTypeIdentifier>,
+    /// Application reference (optional)
+    pub application_ref: Option<String>,
+    /// Mark interchanges as test interchanges
+    pub test: bool,
+}
+
+impl PartnerProfile {
+    /// Create a profile using syntax identifier UNOC version 3
+    pub fn new(sender: PartyId, recipient: PartyId) -> Self {
+        Self {
+            sender,
+            recipient,
+            syntax_identifier: SyntaxIdentifier {
+                identifier: "UNOC".to_string(),
+                version: "3".to_string(),
+                service_code_list: None,
+                encoding: None,
+                release: None,
+            },
+            message_type: None,
+            application_ref: None,
+            test: false,
+        }
+    }
+
+    /// Create a profile that repeats the parties, syntax identifier,
+    /// application reference and test indicator of an existing interchange
+    pub fn from_unb(unb: &UnbSegment) -> Self {
+        Self {
+            sender: unb.sender.clone(),
+            recipient: unb.receiver.clone(),
+            syntax_identifier: unb.syntax_identifier.clone(),
+            message_type: None,
+            application_ref: unb.application_ref.clone(),
+            test: unb.test_indicator.as_deref() == Some("1"),
+        }
+    }
+
+    /// Set the syntax identifier (e.g. "UNOA") and version (e.g. "4")
+    #[must_use]
+    pub fn with_syntax(
+        mut self,
+        identifier: impl Into<String>,
+        version: impl Into<String>,
+    ) -> Self {
+        self.syntax_identifier.identifier = identifier.into();
+        self.syntax_identifier.version = version.into();
+        self
+    }
+
+    /// Set the message type used for messages without a UNH segment
+    #[must_use]
+    pub fn with_message_type(mut self, message_type: MessageTypeIdentifier) -> Self {
+        self.message_type = Some(message_type);
+        self
+    }
+
+    /// Set the application reference
+    #[must_use]
+    pub fn with_application_ref(mut self, application_ref: impl Into<String>) -> Self {
+        self.application_ref = Some(application_ref.into());
+        self
+    }
+
+    /// Mark interchanges as test interchanges
+    #[must_use]
+    pub fn with_test_indicator(mut self, test: bool) -> Self {
+        self.test = test;
+        self
+    }
+
+    /// Syntax version number, defaulting to 3 when it is not numeric
+    fn syntax_version(&self) -> u8 {
+        self.syntax_identifier.version_number().unwrap_or(3)
+    }
+}
+
+/// Writer that wraps messages in an interchange envelope
+pub struct InterchangeWriter {
+    profile: PartnerProfile,
+    control_numbers: Arc<dyn ControlNumberGenerator>,
+    datetime: Option<DateTime>,
+    serializer: EdifactSerializer,
+}
+
+impl InterchangeWriter {
+    /// Create a writer for `profile` drawing references from `control_numbers`
+    pub fn new(profile: PartnerProfile, control_numbers: Arc<dyn ControlNumberGenerator>) -> Self {
+        Self {
+            profile,
+            control_numbers,
+            datetime: None,
+            serializer: EdifactSerializer::new(),
+        }
+    }
+
+    /// Use a fixed preparation date and time instead of the current UTC time
+    #[must_use]
+    pub fn with_datetime(mut self, datetime: DateTime) -> Self {
+        self.datetime = Some(datetime);
+        self
+    }
+
+    /// Serialize written interchanges with `serializer` (separators, UNA,
+    /// line layout)
+    #[must_use]
+    pub fn with_serializer(mut self, serializer: EdifactSerializer) -> Self {
+        self.serializer = serializer;
+        self
+    }
+
+    /// Partner profile used for the envelope
+    pub fn profile(&self) -> &PartnerProfile {
+        &self.profile
+    }
+
+    /// Build an interchange document around `messages`.
+    ///
+    /// Each message may be native EDIFACT IR or mapping output in field
+    /// notation. An existing UNH keeps its message type but gets a fresh
+    /// message reference; existing UNT segments are replaced.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `messages` is empty, a message cannot be
+    /// serialized, a message has no UNH and the profile declares no message
+    /// type, or the control number generator fails.
+    pub fn build(&self, messages: &[Document]) -> Result<Document> {
+        if messages.is_empty() {
+            return Err(Error::Envelope(
+                "An interchange must contain at least one message".to_string(),
+            ));
+        }
+
+        let separators = Separators::for_syntax_version(self.profile.syntax_version());
+        let control_ref = self.control_numbers.next_interchange_ref()?;
+        let unb = self.unb(control_ref.clone());
+
+        let mut root = Node::new("INTERCHANGE", NodeType::Interchange);
+        root.add_child(generated_node(&envelopes::generate_unb(&unb, &separators)));
+
+        let mut message_refs = Vec::with_capacity(messages.len());
+        for (index, message) in messages.iter().enumerate() {
+            let message_ref = self.control_numbers.next_message_ref(&control_ref)?;
+            let node = self
+                .envelope_message(message, &message_ref, &separators)
+                .map_err(|error| match error {
+                    Error::Envelope(message) | Error::Serialize(message) => Error::Envelope(
+                        format!("Message {} cannot be enveloped: {}", index + 1, message),
+                    ),
+                    other => other,
+                })?;
+            root.add_child(node);
+            message_refs.push(message_ref);
+        }
+
+        let unz = UnzSegment {
+            message_count: messages.len(),
+            control_ref: control_ref.clone(),
+        };
+        root.add_child(generated_node(&envelopes::generate_unz(&unz, &separators)));
+
+        let mut document = Document::new(root);
+        document.metadata.interchange_ref = Some(control_ref);
+        document.metadata.partner_id = Some(self.profile.recipient.id.clone());
+        document.metadata.message_refs = message_refs;
+        document.metadata.interchange = Some((&unb).into());
+        Ok(document)
+    }
+
+    /// Build an interchange around `messages` and serialize it in the
+    /// profile's character set with the configured serializer.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`Self::build`], or an error if the output
+    /// cannot be encoded in the declared character set.
+    pub fn write(&self, messages: &[Document]) -> Result<Vec<u8>> {
+        let document = self.build(messages)?;
+        self.serializer.serialize_document_bytes(&document)
+    }
+
+    fn unb(&self, control_ref: String) -> UnbSegment {
+        UnbSegment {
+            syntax_identifier: self.profile.syntax_identifier.clone(),
+            sender: self.profile.sender.clone(),
+            receiver: self.profile.recipient.clone(),
+            datetime: self
+                .datetime
+                .clone()
+                .unwrap_or_else(|| self.current_datetime()),
+            control_ref,
+            recipient_ref: None,
+            application_ref: self.profile.application_ref.clone(),
+            priority: None,
+            ack_request: None,
+            comms_agreement_id: None,
+            test_indicator: self.profile.test.then(|| "1".to_string()),
+        }
+    }
+
+    fn current_datetime(&self) -> DateTime {
+        let now = chrono::Utc::now();
+        let date_format = if self.profile.syntax_version() >= 4 {
+            "%Y%m%d"
+        } else {
+            "%y%m%d"
+        };
+        DateTime {
+            date: now.format(date_format).to_string(),
+            time: now.format("%H%M").to_string(),
+        }
+    }
+
+    fn envelope_message(
+        &self,
+        message: &Document,
+        message_ref: &str,
+        separators: &Separators,
+    ) -> Result<Node> {
+        let mut unh = None;
+        let mut body = Vec::new();
+        for segment in message_segments(message)? {
+            if segment.name == "UNH" && unh.is_none() {
+                unh = Some(segment);
+            } else if !ENVELOPE_TAGS.contains(&segment.name.as_str()) && segment.name != "UNH" {
+                body.push(segment);
+            }
+        }
+
+        let unh = match unh {
+            Some(mut unh) => {
+                let reference = Node::with_value(
+                    "e1",
+                    NodeType::Element,
+                    Value::String(message_ref.to_string()),
+                );
+                match unh.children.first_mut() {
+                    Some(first) => *first = reference,
+                    None => unh.children.push(reference),
+                }
+                unh
+            }
+            None => {
+                let message_type = self.profile.message_type.clone().ok_or_else(|| {
+                    Error::Envelope(
+                        "message has no UNH segment and the partner profile declares no \
+                         message type"
+                            .to_string(),
+                    )
+                })?;
+                let unh = UnhSegment {
+                    message_ref: message_ref.to_string(),
+                    message_type,
+                    common_access_ref: None,
+                    transfer_status: None,
+                    subset_id: None,
+                    implementation_id: None,
+                    scenario_id: None,
+                };
+                generated_node(&envelopes::generate_unh(&unh, separators))
+            }
+        };
+
+        let unt = UntSegment {
+            segment_count: body.len() + 2,
+            message_ref: message_ref.to_string(),
+        };
+
+        let mut node = Node::new("MESSAGE", NodeType::Message);
+        node.add_child(unh);
+        for segment in body {
+            node.add_child(segment);
+        }
+        node.add_child(generated_node(&envelopes::generate_unt(&unt, separators)));
+        Ok(node)
+    }
+}
+
+/// Segment nodes of a message in document order.
+///
+/// Mapping output in field notation is rendered by the serializer first and
+/// read back, so both IR shapes yield native segment nodes.
+fn message_segments(message: &Document) -> Result<Vec<Node>> {
+    let mut segments = Vec::new();
+    collect_segments(&message.root, &mut segments);
+    if !segments.is_empty() {
+        return Ok(segments);
+    }
+
+    let text = EdifactSerializer::new().serialize_document(message)?;
+    let mut parser = SegmentParser::new(text.as_bytes(), "mapped message");
+    while let Some(segment) = parser.next_segment() {
+        segments.push(generated_node(&segment?));
+    }
+    Ok(segments)
+}
+
+fn collect_segments(node: &Node, segments: &mut Vec<Node>) {
+    if node.node_type == NodeType::Segment {
+        segments.push(node.clone());
+        return;
+    }
+    for child in &node.children {
+        collect_segments(child, segments);
+    }
+}
+
+/// IR node for a generated segment, without source position attributes
+fn generated_node(segment: &Segment) -> Node {
+    let mut node = segment.to_node();
+    node.attributes.clear();
+    node
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EdifactParser;
+    use crate::envelopes::MemoryControlNumberGenerator;
+
+    fn profile() -> PartnerProfile {
+        let party = |id: &str| PartyId {
+            id: id.to_string(),
+            qualifier: Some("14".to_string()),
+            ..PartyId::default()
+        };
+        PartnerProfile::new(party("5412345000013"), party("4012345000016"))
+            .with_message_type("ORDERS:D:96A:UN:EAN008".parse().unwrap())
+    }
+
+    fn writer(profile: PartnerProfile) -> InterchangeWriter {
+        InterchangeWriter::new(profile, Arc::new(MemoryControlNumberGenerator::new()))
+            .with_datetime(DateTime {
+                date: "260312".to_string(),
+                time: "0930".to_string(),
+            })
+    }
+
+    fn mapped_orders(number: &str) -> Document {
+        let mut root = Node::new("ROOT", NodeType::Root);
+        for (name, value) in [
+            ("BGM.e1", "220"),
+            ("BGM.e2", number),
+            ("NAD_BY.e2", "BUYER"),
+        ] {
+            root.add_child(Node::with_value(
+                name,
+                NodeType::Field,
+                Value::String(value.to_string()),
+            ));
+        }
+        Document::new(root)
+    }
+
+    #[test]
+    fn test_write_wraps_mapped_messages_in_envelope() {
+        let output = writer(profile())
+            .write(&[mapped_orders("PO1"), mapped_orders("PO2")])
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "UNB+UNOC:3+5412345000013:14+4012345000016:14+260312:0930+00000000000001'\n\
+             UNH+000001+ORDERS:D:96A:UN:EAN008'\n\
+             BGM+220+PO1'\n\
+             NAD+BY+BUYER'\n\
+             UNT+4+000001'\n\
+             UNH+000002+ORDERS:D:96A:UN:EAN008'\n\
+             BGM+220+PO2'\n\
+             NAD+BY+BUYER'\n\
+             UNT+4+000002'\n\
+             UNZ+2+00000000000001'"
+        );
+    }
+
+    #[test]
+    fn test_existing_unh_is_renumbered_and_unt_recounted() {
+        let message = EdifactParser::new()
+            .parse_message(
+                b"UNH+77+DESADV:D:96A:UN:EAN005'BGM+351+DES1'DTM+137:20260312:102'UNT+99+77'",
+                "test",
+            )
+            .unwrap();
+
+        let document = writer(profile()).build(&[message]).unwrap();
+        let text = EdifactSerializer::new()
+            .serialize_document(&document)
+            .unwrap();
+
+        assert!(text.contains("UNH+000001+DESADV:D:96A:UN:EAN005'"));
+        assert!(text.contains("UNT+4+000001'"));
+        assert!(!text.contains("UNT+99"));
+        assert_eq!(document.metadata.message_refs, vec!["000001".to_string()]);
+        assert_eq!(
+            document.metadata.interchange_ref.as_deref(),
+            Some("00000000000001")
+        );
+    }
+
+    #[test]
+    fn test_control_numbers_advance_across_interchanges() {
+        let writer = writer(profile().with_test_indicator(true));
+        let first = writer.build(&[mapped_orders("PO1")]).unwrap();
+        let second = writer.build(&[mapped_orders("PO2")]).unwrap();
+
+        assert_eq!(
+            first.metadata.interchange_ref.as_deref(),
+            Some("00000000000001")
+        );
+        assert_eq!(
+            second.metadata.interchange_ref.as_deref(),
+            Some("00000000000002")
+        );
+        let interchange = second.metadata.interchange.unwrap();
+        assert!(interchange.is_test());
+        assert_eq!(interchange.recipient.id, "4012345000016");
+    }
+
+    #[test]
+    fn test_written_interchange_parses_with_matching_counts() {
+        let output = writer(
+            profile()
+                .with_syntax("UNOC", "4")
+                .with_application_ref("ORDERS"),
+        )
+        .with_datetime(DateTime {
+            date: "20260312".to_string(),
+            time: "0930".to_string(),
+        })
+        .with_serializer(EdifactSerializer::new().with_una(true))
+        .write(&[mapped_orders("PO1"), mapped_orders("PO2")])
+        .unwrap();
+        assert!(output.starts_with(b"UNA:+.?*'\nUNB+UNOC:4+"));
+
+        let outcome = EdifactParser::new()
+            .parse_with_warnings(&output, "written")
+            .unwrap();
+        assert!(outcome.warnings.is_empty(), "{:?}", outcome.warnings);
+        assert_eq!(outcome.documents.len(), 2);
+        let interchange = outcome.documents[1].metadata.interchange.as_ref().unwrap();
+        assert_eq!(interchange.sender.id, "5412345000013");
+        assert_eq!(interchange.application_ref.as_deref(), Some("ORDERS"));
+    }
+
+    #[test]
+    fn test_message_without_unh_requires_profile_message_type() {
+        let mut untyped = profile();
+        untyped.message_type = None;
+
+        let error = writer(untyped).build(&[mapped_orders("PO1")]).unwrap_err();
+        assert!(error.to_string().contains("Message 1"));
+        assert!(error.to_string().contains("no UNH"));
+        assert!(writer(profile()).build(&[]).is_err());
+    }
+}
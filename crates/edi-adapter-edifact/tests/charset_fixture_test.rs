@@ -0,0 +1,99 @@
+use std::fs;
+use std::path::PathBuf;
+
+use edi_adapter_edifact::{EdifactParser, EdifactSerializer};
+use edi_ir::{Node, NodeType};
+
+fn repo_root() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../..")
+}
+
+fn find_segment<'a>(node: &'a Node, tag: &str) -> Option<&'a Node> {
+    if node.node_type == NodeType::Segment && node.name == tag {
+        return Some(node);
+    }
+    node.children
+        .iter()
+        .find_map(|child| find_segment(child, tag))
+}
+
+fn element_text(segment: &Node, index: usize) -> String {
+    segment.children[index]
+        .value
+        .as_ref()
+        .and_then(edi_ir::Value::as_string)
+        .unwrap_or_default()
+}
+
+#[test]
+fn unoc_fixture_decodes_latin1_and_serializes_back_to_latin1() {
+    let edi_path = repo_root().join("testdata/edi/valid_orders_d96a_unoc_latin1.edi");
+    let data = fs::read(&edi_path).expect("UNOC fixture should load");
+    assert!(
+        std::str::from_utf8(&data).is_err(),
+        "fixture must contain raw Latin-1 bytes"
+    );
+
+    let outcome = EdifactParser::new()
+        .parse_with_warnings(&data, edi_path.to_string_lossy().as_ref())
+        .expect("UNOC fixture should parse");
+    assert!(outcome.warnings.is_empty(), "{:?}", outcome.warnings);
+    assert_eq!(outcome.documents.len(), 1);
+
+    let document = &outcome.documents[0];
+    let nad = find_segment(&document.root, "NAD").expect("NAD segment");
+    assert_eq!(element_text(nad, 3), "Müller Bäckerei GmbH");
+    assert_eq!(element_text(nad, 4), "Straße des 17. Juni 1");
+    assert_eq!(element_text(nad, 5), "Köln");
+
+    let serialized = EdifactSerializer::new()
+        .with_charset(edi_adapter_edifact::Charset::Unoc)
+        .serialize_document_bytes(document)
+        .expect("message should serialize as UNOC");
+    let nad_line = serialized
+        .split(|byte| *byte == b'\n')
+        .find(|line| line.starts_with(b"NAD"))
+        .expect("serialized NAD segment");
+    assert!(
+        data.windows(nad_line.len())
+            .any(|window| window == nad_line),
+        "serialized NAD should match the source bytes"
+    );
+}
+
+#[test]
+fn unoa_fixtures_report_lower_case_data_outside_the_repertoire() {
+    for (fixture, expected) in [
+        (
+            "valid_orders_d96a_full.edi",
+            vec!["IMD/e4/c4", "IMD/e4/c4", "IMD/e4/c4"],
+        ),
+        (
+            "edge_special_chars.edi",
+            vec!["NAD/e3", "NAD/e4", "IMD/e4/c4"],
+        ),
+    ] {
+        let edi_path = repo_root().join("testdata/edi").join(fixture);
+        let data = fs::read(&edi_path).expect("UNOA fixture should load");
+        let outcome = EdifactParser::new()
+            .parse_with_warnings(&data, edi_path.to_string_lossy().as_ref())
+            .expect("UNOA fixture should parse");
+        assert_eq!(outcome.documents.len(), 1);
+
+        assert!(
+            outcome
+                .warnings
+                .iter()
+                .all(|warning| warning.code == "CHARACTER_OUTSIDE_REPERTOIRE"
+                    && warning.message.contains("UNOA repertoire")),
+            "{fixture}: {:?}",
+            outcome.warnings
+        );
+        let paths: Vec<_> = outcome
+            .warnings
+            .iter()
+            .map(|warning| warning.message.split('\'').nth(1).unwrap_or_default())
+            .collect();
+        assert_eq!(paths, expected, "{fixture}");
+    }
+}
@@ -0,0 +1,58 @@
+use std::fs;
+use std::path::PathBuf;
+
+use edi_adapter_edifact::{EdifactParser, EdifactSerializer};
+use edi_ir::{Node, NodeType};
+
+fn repo_root() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../..")
+}
+
+fn names(node: &Node) -> Vec<&str> {
+    node.children
+        .iter()
+        .map(|child| child.name.as_str())
+        .collect()
+}
+
+#[test]
+fn functional_groups_are_exposed_in_the_interchange_hierarchy() {
+    let edi_path = repo_root().join("testdata/edi/valid_invoic_desadv_d96a_functional_groups.edi");
+    let data = fs::read(&edi_path).expect("functional group fixture should load");
+
+    let outcome = EdifactParser::new()
+        .parse_with_warnings(&data, edi_path.to_string_lossy().as_ref())
+        .expect("functional group fixture should parse");
+    assert!(outcome.warnings.is_empty(), "{:?}", outcome.warnings);
+
+    // Messages never contain the group envelope segments
+    assert_eq!(outcome.documents.len(), 3);
+    for document in &outcome.documents {
+        assert_eq!(names(&document.root).first(), Some(&"UNH"));
+        assert_eq!(names(&document.root).last(), Some(&"UNT"));
+        assert_eq!(
+            document.metadata.partner_id.as_deref(),
+            Some("1234567890123")
+        );
+        assert_eq!(document.metadata.interchange_ref.as_deref(), Some("12345"));
+    }
+
+    assert_eq!(outcome.interchanges.len(), 1);
+    let interchange = &outcome.interchanges[0].root;
+    assert_eq!(interchange.node_type, NodeType::Interchange);
+    assert_eq!(names(interchange), vec!["UNB", "GROUP", "GROUP", "UNZ"]);
+
+    let invoices = &interchange.children[1];
+    assert_eq!(invoices.node_type, NodeType::FunctionalGroup);
+    assert_eq!(names(invoices), vec!["UNG", "MESSAGE", "MESSAGE", "UNE"]);
+
+    let despatch_advices = &interchange.children[2];
+    assert_eq!(names(despatch_advices), vec!["UNG", "MESSAGE", "UNE"]);
+    assert_eq!(despatch_advices.children[1].node_type, NodeType::Message);
+
+    let serialized = EdifactSerializer::new()
+        .serialize_document(&outcome.interchanges[0])
+        .expect("interchange should serialize");
+    let source = String::from_utf8(data).expect("fixture is ASCII");
+    assert_eq!(serialized.trim_end(), source.trim_end());
+}
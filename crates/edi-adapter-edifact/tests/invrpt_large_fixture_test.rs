@@ -1,8 +1,9 @@
 use std::collections::HashMap;
-use std::fs;
+use std::fs::{self, File};
+use std::io::BufReader;
 use std::path::PathBuf;
 
-use edi_adapter_edifact::EdifactParser;
+use edi_adapter_edifact::{EdifactParser, EdifactReader};
 use edi_ir::{Node, NodeType};
 
 fn repo_root() -> PathBuf {
@@ -50,3 +51,31 @@ fn large_invrpt_d96a_fixture_parses_with_deterministic_message_and_segment_count
     assert_eq!(segment_counts.get("QTY"), Some(&12_993));
     assert_eq!(segment_counts.get("UNT"), Some(&42));
 }
+
+#[test]
+fn large_invrpt_d96a_fixture_streams_one_message_at_a_time() {
+    let edi_path = repo_root().join("testdata/edi/valid_invrpt_d96a_large.edi");
+    let file = File::open(&edi_path).expect("large INVRPT fixture should open");
+    let mut reader = EdifactReader::new(
+        BufReader::with_capacity(512, file),
+        edi_path.to_string_lossy(),
+    );
+
+    let mut messages = 0;
+    let mut segment_counts = HashMap::new();
+    while let Some(document) = reader.next_message() {
+        let document = document.expect("large INVRPT fixture should stream");
+        count_segments(&document.root, &mut segment_counts);
+        messages += 1;
+    }
+
+    assert!(
+        reader.take_warnings().is_empty(),
+        "valid fixture should not warn"
+    );
+    assert_eq!(messages, 42);
+    assert_eq!(segment_counts.get("UNH"), Some(&42));
+    assert_eq!(segment_counts.get("LIN"), Some(&12_993));
+    assert_eq!(segment_counts.get("QTY"), Some(&12_993));
+    assert_eq!(segment_counts.get("UNT"), Some(&42));
+}
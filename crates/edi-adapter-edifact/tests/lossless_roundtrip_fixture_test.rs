@@ -0,0 +1,102 @@
+use std::fs;
+use std::path::PathBuf;
+
+use edi_adapter_edifact::{EdifactParser, EdifactSerializer};
+use edi_ir::{Node, NodeType, Value};
+
+fn repo_root() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../..")
+}
+
+fn find_segment_mut<'a>(node: &'a mut Node, tag: &str) -> Option<&'a mut Node> {
+    if node.node_type == NodeType::Segment && node.name == tag {
+        return Some(node);
+    }
+    node.children
+        .iter_mut()
+        .find_map(|child| find_segment_mut(child, tag))
+}
+
+fn round_trip(data: &[u8], source_name: &str) -> Vec<u8> {
+    let interchanges = EdifactParser::new()
+        .with_lossless(true)
+        .parse_interchanges(data, source_name)
+        .expect("fixture should parse");
+    let serializer = EdifactSerializer::new().with_lossless(true);
+    interchanges
+        .iter()
+        .flat_map(|interchange| {
+            serializer
+                .serialize_document_bytes(interchange)
+                .expect("interchange should serialize")
+        })
+        .collect()
+}
+
+#[test]
+fn fixtures_round_trip_byte_for_byte() {
+    let mut fixtures = fs::read_dir(repo_root().join("testdata/edi"))
+        .expect("fixture directory should exist")
+        .map(|entry| entry.expect("fixture entry").path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "edi"))
+        // The large INVRPT fixture is covered by the streaming tests
+        .filter(|path| !path.ends_with("valid_invrpt_d96a_large.edi"))
+        .collect::<Vec<_>>();
+    fixtures.sort();
+    assert!(fixtures.len() > 20, "expected the EDI fixture set");
+
+    for path in fixtures {
+        let data = fs::read(&path).expect("fixture should load");
+        let output = round_trip(&data, path.to_string_lossy().as_ref());
+        assert!(
+            output == data,
+            "{} did not round-trip:\n{}",
+            path.display(),
+            String::from_utf8_lossy(&output)
+        );
+    }
+}
+
+#[test]
+fn modified_segment_is_rerendered_with_source_separators() {
+    let path = repo_root().join("testdata/edi/edge_una_crlf_layout.edi");
+    let data = fs::read(&path).expect("UNA fixture should load");
+    let mut interchange = EdifactParser::new()
+        .with_lossless(true)
+        .parse_interchanges(&data, path.to_string_lossy().as_ref())
+        .expect("UNA fixture should parse")
+        .remove(0);
+
+    let pri = find_segment_mut(&mut interchange.root, "PRI").expect("PRI segment");
+    pri.children[0].children[1].value = Some(Value::Decimal(
+        edi_ir::parse_decimal("16.5").expect("decimal"),
+    ));
+
+    let output = EdifactSerializer::new()
+        .with_lossless(true)
+        .serialize_document(&interchange)
+        .expect("interchange should serialize");
+    let expected = String::from_utf8(data)
+        .expect("fixture is ASCII")
+        .replace("PRI+AAA:15,99'", "PRI+AAA:16,5'");
+    assert_eq!(output, expected);
+}
+
+#[test]
+fn message_documents_reproduce_their_segments() {
+    let path = repo_root().join("testdata/edi/edge_special_chars.edi");
+    let data = fs::read_to_string(&path).expect("fixture should load");
+    let documents = EdifactParser::new()
+        .with_lossless(true)
+        .parse(data.as_bytes(), path.to_string_lossy().as_ref())
+        .expect("fixture should parse");
+    assert_eq!(documents.len(), 1);
+
+    let output = EdifactSerializer::new()
+        .with_lossless(true)
+        .serialize_document(&documents[0])
+        .expect("message should serialize");
+    let start = data.find("UNH").expect("UNH");
+    let end = data.find("UNZ").expect("UNZ");
+    assert_eq!(output, data[start..end].trim_end());
+}
@@ -0,0 +1,433 @@
+//! 997 functional acknowledgement generation
+//!
+//! A 997 answers one functional group: AK1 identifies the group, every
+//! transaction set gets an AK2/AK5 pair (with AK3/AK4 detail for segment
+//! errors) and AK9 summarises the group. Envelope errors found by the
+//! [`EnvelopeValidator`](crate::EnvelopeValidator) reject the affected
+//! transaction set or group. Interchange-level errors (ISA/IEA) belong in a
+//! TA1 and are not reported.
+
+use crate::envelopes::{
+    EnvelopeType, ErrorKind, ValidationError, ValidationReport, count_segments, value,
+};
+use crate::parser::{Element, Segment};
+use edi_ir::document::DocumentMetadata;
+use edi_ir::{Document, Node, NodeType};
+
+/// Maximum number of error codes in AK5 and AK9
+const MAX_ERROR_CODES: usize = 5;
+
+/// Segment syntax error codes (data element 720, AK304)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SegmentErrorCode {
+    /// 1: Unrecognized segment ID
+    UnrecognizedSegment,
+    /// 2: Unexpected segment
+    UnexpectedSegment,
+    /// 3: Mandatory segment missing
+    MandatorySegmentMissing,
+    /// 4: Loop occurs over maximum times
+    LoopOverMaximum,
+    /// 5: Segment exceeds maximum use
+    SegmentOverMaximum,
+    /// 6: Segment not in defined transaction set
+    NotInTransactionSet,
+    /// 7: Segment not in proper sequence
+    OutOfSequence,
+    /// 8: Segment has data element errors
+    DataElementErrors,
+}
+
+impl SegmentErrorCode {
+    /// Code value as transmitted in AK304
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::UnrecognizedSegment => "1",
+            Self::UnexpectedSegment => "2",
+            Self::MandatorySegmentMissing => "3",
+            Self::LoopOverMaximum => "4",
+            Self::SegmentOverMaximum => "5",
+            Self::NotInTransactionSet => "6",
+            Self::OutOfSequence => "7",
+            Self::DataElementErrors => "8",
+        }
+    }
+}
+
+/// Data element syntax error codes (data element 723, AK403)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ElementErrorCode {
+    /// 1: Mandatory data element missing
+    MandatoryElementMissing,
+    /// 2: Conditional required data element missing
+    ConditionalElementMissing,
+    /// 3: Too many data elements
+    TooManyElements,
+    /// 4: Data element too short
+    TooShort,
+    /// 5: Data element too long
+    TooLong,
+    /// 6: Invalid character in data element
+    InvalidCharacter,
+    /// 7: Invalid code value
+    InvalidCodeValue,
+    /// 8: Invalid date
+    InvalidDate,
+    /// 9: Invalid time
+    InvalidTime,
+    /// 10: Exclusion condition violated
+    ExclusionViolated,
+}
+
+impl ElementErrorCode {
+    /// Code value as transmitted in AK403
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::MandatoryElementMissing => "1",
+            Self::ConditionalElementMissing => "2",
+            Self::TooManyElements => "3",
+            Self::TooShort => "4",
+            Self::TooLong => "5",
+            Self::InvalidCharacter => "6",
+            Self::InvalidCodeValue => "7",
+            Self::InvalidDate => "8",
+            Self::InvalidTime => "9",
+            Self::ExclusionViolated => "10",
+        }
+    }
+}
+
+/// Segment error reported in AK3 (and AK4 for a data element) for a
+/// transaction set
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SegmentError {
+    /// ST02 of the transaction set
+    pub control_number: String,
+    /// GS06 of the functional group; `None` matches any group
+    pub group_control_number: Option<String>,
+    /// Segment identifier (AK301)
+    pub segment_id: String,
+    /// Position of the segment in the transaction set, ST being 1 (AK302)
+    pub position: usize,
+    /// Loop identifier (AK303)
+    pub loop_id: Option<String>,
+    /// Segment syntax error code (AK304)
+    pub code: SegmentErrorCode,
+    /// Data element error: position in the segment, error code and the
+    /// erroneous value (AK401, AK403, AK404)
+    pub element: Option<(usize, ElementErrorCode, Option<String>)>,
+}
+
+impl SegmentError {
+    /// Create a segment error for the transaction set with ST02
+    /// `control_number`
+    pub fn new(
+        control_number: impl Into<String>,
+        segment_id: impl Into<String>,
+        position: usize,
+        code: SegmentErrorCode,
+    ) -> Self {
+        Self {
+            control_number: control_number.into(),
+            group_control_number: None,
+            segment_id: segment_id.into(),
+            position,
+            loop_id: None,
+            code,
+            element: None,
+        }
+    }
+
+    /// Restrict the error to the functional group with GS06
+    /// `group_control_number`
+    #[must_use]
+    pub fn in_group(mut self, group_control_number: impl Into<String>) -> Self {
+        self.group_control_number = Some(group_control_number.into());
+        self
+    }
+
+    /// Report the loop the segment belongs to
+    #[must_use]
+    pub fn with_loop_id(mut self, loop_id: impl Into<String>) -> Self {
+        self.loop_id = Some(loop_id.into());
+        self
+    }
+
+    /// Report the data element at `position` as erroneous with `code`, and
+    /// optionally its bad value
+    #[must_use]
+    pub fn with_element_error(
+        mut self,
+        position: usize,
+        code: ElementErrorCode,
+        bad_value: Option<String>,
+    ) -> Self {
+        self.element = Some((position, code, bad_value));
+        self
+    }
+
+    fn applies_to(&self, group_control_number: Option<&str>, control_number: &str) -> bool {
+        self.control_number == control_number
+            && self
+                .group_control_number
+                .as_deref()
+                .is_none_or(|group| Some(group) == group_control_number)
+    }
+}
+
+/// Generator for 997 functional acknowledgements
+#[derive(Debug, Clone, Default)]
+pub struct FunctionalAckGenerator {
+    segment_errors: Vec<SegmentError>,
+}
+
+impl FunctionalAckGenerator {
+    /// Create a generator reporting envelope errors only
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also report `error`, e.g. found by schema validation of the
+    /// transaction set
+    #[must_use]
+    pub fn with_segment_error(mut self, error: SegmentError) -> Self {
+        self.segment_errors.push(error);
+        self
+    }
+
+    /// Build one 997 transaction set per functional group of `interchange`,
+    /// using the envelope errors of `report`.
+    ///
+    /// The 997s are numbered `0001`, `0002`, ...; wrap them in an interchange
+    /// back to the sender with
+    /// [`InterchangeBuilder`](crate::InterchangeBuilder) and functional
+    /// identifier `FA`.
+    pub fn generate(&self, interchange: &Document, report: &ValidationReport) -> Vec<Document> {
+        interchange
+            .root
+            .children
+            .iter()
+            .filter(|child| child.node_type == NodeType::FunctionalGroup)
+            .enumerate()
+            .map(|(index, group)| {
+                self.acknowledge_group(interchange, group, report, &format!("{:04}", index + 1))
+            })
+            .collect()
+    }
+
+    fn acknowledge_group(
+        &self,
+        interchange: &Document,
+        group: &Node,
+        report: &ValidationReport,
+        control_number: &str,
+    ) -> Document {
+        let gs = group.children.first().filter(|child| child.name == "GS");
+        let gs_value = |index| gs.and_then(|gs| value(gs, index)).unwrap_or_default();
+        let group_control_number = gs.and_then(|gs| value(gs, 6));
+
+        let mut segments = vec![segment("AK1", &[&gs_value(1), &gs_value(6)])];
+        let messages: Vec<&Node> = group
+            .children
+            .iter()
+            .filter(|child| child.node_type == NodeType::Message)
+            .collect();
+        let mut accepted = 0;
+        for message in &messages {
+            let st = message.children.first().filter(|child| child.name == "ST");
+            let st01 = st.and_then(|st| value(st, 1)).unwrap_or_default();
+            let st02 = st.and_then(|st| value(st, 2)).unwrap_or_default();
+            segments.push(segment("AK2", &[&st01, &st02]));
+
+            let segment_errors: Vec<&SegmentError> = self
+                .segment_errors
+                .iter()
+                .filter(|error| error.applies_to(group_control_number.as_deref(), &st02))
+                .collect();
+            for error in &segment_errors {
+                segments.extend(segment_error(error));
+            }
+
+            let mut codes: Vec<&str> = report
+                .transaction_set_errors(group_control_number.as_deref(), Some(&st02))
+                .filter_map(transaction_set_error_code)
+                .collect();
+            if !segment_errors.is_empty() {
+                codes.push("5");
+            }
+            codes.dedup();
+            codes.truncate(MAX_ERROR_CODES);
+            if codes.is_empty() {
+                accepted += 1;
+                segments.push(segment("AK5", &["A"]));
+            } else {
+                let mut values = vec!["R"];
+                values.extend(codes);
+                segments.push(segment("AK5", &values));
+            }
+        }
+
+        let mut codes: Vec<&str> = report
+            .group_errors(group_control_number.as_deref())
+            .filter_map(group_error_code)
+            .collect();
+        codes.dedup();
+        codes.truncate(MAX_ERROR_CODES);
+        let received = messages.len();
+        let status = if !codes.is_empty() || (accepted == 0 && received > 0) {
+            "R"
+        } else if accepted == received {
+            "A"
+        } else {
+            "P"
+        };
+        let included = group
+            .children
+            .last()
+            .filter(|child| child.name == "GE")
+            .and_then(|ge| value(ge, 1))
+            .unwrap_or_else(|| received.to_string());
+        let (received, accepted) = (received.to_string(), accepted.to_string());
+        let mut values = vec![status, &included, &received, &accepted];
+        values.extend(codes);
+        segments.push(segment("AK9", &values));
+
+        let mut root = Node::new("MESSAGE", NodeType::Message);
+        root.add_child(segment("ST", &["997", control_number]).to_node());
+        for segment in &segments {
+            root.add_child(segment.to_node());
+        }
+        let count = count_segments(&root) + 1;
+        root.add_child(segment("SE", &[&count.to_string(), control_number]).to_node());
+
+        let source_interchange = interchange.metadata.interchange.as_ref();
+        let metadata = DocumentMetadata {
+            doc_type: Some("997".to_string()),
+            version: gs.and_then(|gs| value(gs, 8)),
+            interchange_ref: interchange.metadata.interchange_ref.clone(),
+            partner_id: source_interchange
+                .map(|interchange| interchange.sender.id.clone())
+                .or_else(|| interchange.metadata.partner_id.clone()),
+            message_refs: vec![control_number.to_string()],
+            ..Default::default()
+        };
+        Document::with_metadata(root, metadata)
+    }
+}
+
+/// AK3 and, for a data element error, AK4
+fn segment_error(error: &SegmentError) -> Vec<Segment> {
+    let mut segments = vec![segment(
+        "AK3",
+        &[
+            &error.segment_id,
+            &error.position.to_string(),
+            error.loop_id.as_deref().unwrap_or_default(),
+            error.code.code(),
+        ],
+    )];
+    if let Some((position, code, bad_value)) = &error.element {
+        segments.push(segment(
+            "AK4",
+            &[
+                &position.to_string(),
+                "",
+                code.code(),
+                bad_value.as_deref().unwrap_or_default(),
+            ],
+        ));
+    }
+    segments
+}
+
+/// Transaction set syntax error code (data element 718, AK502)
+fn transaction_set_error_code(error: &ValidationError) -> Option<&'static str> {
+    debug_assert_eq!(error.envelope_type, EnvelopeType::TransactionSet);
+    match error.error_kind {
+        ErrorKind::MissingTrailer => Some("2"),
+        ErrorKind::MismatchedControlNumber => Some("3"),
+        ErrorKind::IncorrectSegmentCount => Some("4"),
+        ErrorKind::MissingHeader => Some("6"),
+        ErrorKind::InvalidControlNumber => Some("7"),
+        _ => None,
+    }
+}
+
+/// Functional group syntax error code (data element 716, AK905)
+fn group_error_code(error: &ValidationError) -> Option<&'static str> {
+    match error.error_kind {
+        ErrorKind::MissingTrailer => Some("3"),
+        ErrorKind::MismatchedControlNumber => Some("4"),
+        ErrorKind::IncorrectTransactionSetCount => Some("5"),
+        ErrorKind::InvalidControlNumber => Some("6"),
+        _ => None,
+    }
+}
+
+fn segment(tag: &str, values: &[&str]) -> Segment {
+    Segment::new(
+        tag,
+        values
+            .iter()
+            .map(|value| Element::Simple((*value).to_string()))
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{EnvelopeValidator, X12Parser, X12Serializer};
+
+    const PO: &str = "ISA*00*          *00*          *ZZ*BUYER          \
+        *ZZ*SELLER         *260312*0930*U*00401*000000101*0*P*>~\n\
+        GS*PO*BUYER*SELLER*20260312*0930*101*X*004010~\n\
+        ST*850*0001~\nBEG*00*SA*PO1**20260312~\nSE*3*0001~\n\
+        ST*850*0002~\nBEG*00*SA*PO2**20260312~\nSE*3*0002~\n\
+        GE*2*101~\nIEA*1*000000101~\n";
+
+    fn acknowledge(data: &str, generator: &FunctionalAckGenerator) -> String {
+        let interchange = X12Parser::new()
+            .parse_interchanges(data.as_bytes())
+            .unwrap()
+            .remove(0);
+        let report = EnvelopeValidator::new().validate(&interchange);
+        let acks = generator.generate(&interchange, &report);
+        assert_eq!(acks.len(), 1);
+        X12Serializer::new().serialize_document(&acks[0]).unwrap()
+    }
+
+    #[test]
+    fn test_997_accepts_valid_group() {
+        let ack = acknowledge(PO, &FunctionalAckGenerator::new());
+        assert_eq!(
+            ack,
+            "ST*997*0001~\nAK1*PO*101~\nAK2*850*0001~\nAK5*A~\n\
+             AK2*850*0002~\nAK5*A~\nAK9*A*2*2*2~\nSE*8*0001~\n"
+        );
+    }
+
+    #[test]
+    fn test_997_rejects_transaction_set_with_envelope_error() {
+        let data = PO.replace("SE*3*0002", "SE*4*0002");
+        let ack = acknowledge(&data, &FunctionalAckGenerator::new());
+        assert!(ack.contains("AK2*850*0002~\nAK5*R*4~\n"), "{ack}");
+        assert!(ack.contains("AK9*P*2*2*1~\n"), "{ack}");
+    }
+
+    #[test]
+    fn test_997_reports_segment_and_group_errors() {
+        let data = PO.replace("GE*2*101", "GE*3*101");
+        let generator = FunctionalAckGenerator::new().with_segment_error(
+            SegmentError::new("0001", "BEG", 2, SegmentErrorCode::DataElementErrors)
+                .in_group("101")
+                .with_element_error(2, ElementErrorCode::InvalidCodeValue, Some("SA".into())),
+        );
+        let ack = acknowledge(&data, &generator);
+        assert!(
+            ack.contains("AK2*850*0001~\nAK3*BEG*2**8~\nAK4*2**7*SA~\nAK5*R*5~\n"),
+            "{ack}"
+        );
+        assert!(ack.contains("AK9*R*3*2*1*5~\n"), "{ack}");
+    }
+}
@@ -0,0 +1,870 @@
+//! X12 envelope handling (ISA/IEA, GS/GE, ST/SE)
+//!
+//! This module validates the control numbers and counts of parsed
+//! interchanges and builds new interchanges around transaction sets.
+
+use crate::parser::{Element, Segment, interchange_info};
+use crate::syntax::Separators;
+use crate::{Error, Result};
+use edi_ir::{Document, Node, NodeType, PartyInfo, Position, SourceSpan, Value};
+
+// ============================================================================
+// Envelope Validator
+// ============================================================================
+
+/// Type of envelope being validated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnvelopeType {
+    /// Interchange envelope (ISA/IEA)
+    Interchange,
+    /// Functional group envelope (GS/GE)
+    Group,
+    /// Transaction set envelope (ST/SE)
+    TransactionSet,
+}
+
+impl std::fmt::Display for EnvelopeType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EnvelopeType::Interchange => write!(f, "Interchange"),
+            EnvelopeType::Group => write!(f, "Group"),
+            EnvelopeType::TransactionSet => write!(f, "Transaction set"),
+        }
+    }
+}
+
+/// Kind of validation error
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// Missing header segment (ISA, GS or ST)
+    MissingHeader,
+    /// Missing trailer segment (IEA, GE or SE)
+    MissingTrailer,
+    /// Control number mismatch between header and trailer
+    MismatchedControlNumber,
+    /// Control number that is not numeric or has the wrong length
+    InvalidControlNumber,
+    /// Incorrect segment count in SE01
+    IncorrectSegmentCount,
+    /// Incorrect transaction set count in GE01
+    IncorrectTransactionSetCount,
+    /// Incorrect functional group count in IEA01
+    IncorrectGroupCount,
+    /// Transaction set outside of a functional group
+    UngroupedTransactionSet,
+}
+
+impl std::fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ErrorKind::MissingHeader => write!(f, "missing header"),
+            ErrorKind::MissingTrailer => write!(f, "missing trailer"),
+            ErrorKind::MismatchedControlNumber => write!(f, "mismatched control number"),
+            ErrorKind::InvalidControlNumber => write!(f, "invalid control number"),
+            ErrorKind::IncorrectSegmentCount => write!(f, "incorrect segment count"),
+            ErrorKind::IncorrectTransactionSetCount => {
+                write!(f, "incorrect transaction set count")
+            }
+            ErrorKind::IncorrectGroupCount => write!(f, "incorrect functional group count"),
+            ErrorKind::UngroupedTransactionSet => {
+                write!(f, "transaction set outside of functional group")
+            }
+        }
+    }
+}
+
+/// Validation error with position and envelope context
+#[derive(Debug, Clone)]
+pub struct ValidationError {
+    /// Human-readable error message
+    pub message: String,
+    /// Type of envelope
+    pub envelope_type: EnvelopeType,
+    /// Position in source
+    pub position: Position,
+    /// Kind of error
+    pub error_kind: ErrorKind,
+    /// Expected value (if applicable)
+    pub expected: Option<String>,
+    /// Actual value (if applicable)
+    pub actual: Option<String>,
+    /// Header control number of the envelope the error was found in (ISA13,
+    /// GS06 or ST02)
+    pub control_number: Option<String>,
+    /// GS06 of the functional group the error was found in, for group and
+    /// transaction set errors
+    pub group_control_number: Option<String>,
+}
+
+impl ValidationError {
+    /// Create a new validation error
+    pub fn new(
+        message: impl Into<String>,
+        envelope_type: EnvelopeType,
+        position: Position,
+        error_kind: ErrorKind,
+    ) -> Self {
+        Self {
+            message: message.into(),
+            envelope_type,
+            position,
+            error_kind,
+            expected: None,
+            actual: None,
+            control_number: None,
+            group_control_number: None,
+        }
+    }
+
+    /// Add expected and actual values
+    pub fn with_values(mut self, expected: impl Into<String>, actual: impl Into<String>) -> Self {
+        self.expected = Some(expected.into());
+        self.actual = Some(actual.into());
+        self
+    }
+
+    /// Set the control number of the envelope the error was found in
+    pub fn with_control_number(mut self, control_number: Option<&str>) -> Self {
+        self.control_number = control_number.map(str::to_string);
+        self
+    }
+
+    /// Set the control number of the enclosing functional group
+    pub fn with_group_control_number(mut self, group_control_number: Option<&str>) -> Self {
+        self.group_control_number = group_control_number.map(str::to_string);
+        self
+    }
+}
+
+impl std::fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} validation error at line {}: {} (kind: {:?})",
+            self.envelope_type, self.position.line, self.message, self.error_kind
+        )?;
+        if let (Some(expected), Some(actual)) = (&self.expected, &self.actual) {
+            write!(f, " [expected: {}, actual: {}]", expected, actual)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// Validation report containing envelope errors
+#[derive(Debug, Clone, Default)]
+pub struct ValidationReport {
+    /// List of validation errors
+    pub errors: Vec<ValidationError>,
+}
+
+impl ValidationReport {
+    /// Create a new empty validation report
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether no errors were found
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Add an error to the report
+    pub fn add_error(&mut self, error: ValidationError) {
+        self.errors.push(error);
+    }
+
+    /// Errors of the transaction set `control_number` in the functional
+    /// group `group_control_number`
+    pub fn transaction_set_errors<'a>(
+        &'a self,
+        group_control_number: Option<&'a str>,
+        control_number: Option<&'a str>,
+    ) -> impl Iterator<Item = &'a ValidationError> {
+        self.errors.iter().filter(move |error| {
+            error.envelope_type == EnvelopeType::TransactionSet
+                && error.group_control_number.as_deref() == group_control_number
+                && error.control_number.as_deref() == control_number
+        })
+    }
+
+    /// Errors of the functional group envelope `group_control_number`
+    pub fn group_errors<'a>(
+        &'a self,
+        group_control_number: Option<&'a str>,
+    ) -> impl Iterator<Item = &'a ValidationError> {
+        self.errors.iter().filter(move |error| {
+            error.envelope_type == EnvelopeType::Group
+                && error.group_control_number.as_deref() == group_control_number
+        })
+    }
+}
+
+/// Validator for X12 envelope control numbers and counts
+///
+/// Validates that:
+/// - IEA01 counts the functional groups and IEA02 matches ISA13
+/// - GE01 counts the transaction sets and GE02 matches GS06
+/// - SE01 counts the segments from ST through SE and SE02 matches ST02
+/// - Control numbers are numeric, ISA13 nine digits
+/// - Transaction sets are enclosed in functional groups
+#[derive(Debug, Clone, Default)]
+pub struct EnvelopeValidator;
+
+impl EnvelopeValidator {
+    /// Create a new envelope validator
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Validate an interchange document as returned by
+    /// [`X12Parser::parse_interchanges`](crate::X12Parser::parse_interchanges)
+    pub fn validate(&self, interchange: &Document) -> ValidationReport {
+        let mut report = ValidationReport::new();
+        let root = &interchange.root;
+
+        let Some(isa) = first_segment(root, "ISA") else {
+            report.add_error(ValidationError::new(
+                "Interchange has no ISA header",
+                EnvelopeType::Interchange,
+                Position::default(),
+                ErrorKind::MissingHeader,
+            ));
+            return report;
+        };
+        let isa13 = value(isa, 13);
+        if !isa13.as_deref().is_some_and(|number| is_numeric(number, 9)) {
+            report.add_error(
+                ValidationError::new(
+                    "ISA13 must be a nine digit control number",
+                    EnvelopeType::Interchange,
+                    position(isa),
+                    ErrorKind::InvalidControlNumber,
+                )
+                .with_values("9 digits", isa13.clone().unwrap_or_default())
+                .with_control_number(isa13.as_deref()),
+            );
+        }
+
+        let groups: Vec<&Node> = children(root, NodeType::FunctionalGroup).collect();
+        for message in children(root, NodeType::Message) {
+            let st = first_segment(message, "ST");
+            report.add_error(
+                ValidationError::new(
+                    "Transaction set is not enclosed in a functional group",
+                    EnvelopeType::TransactionSet,
+                    st.map(position).unwrap_or_default(),
+                    ErrorKind::UngroupedTransactionSet,
+                )
+                .with_control_number(st.and_then(|st| value(st, 2)).as_deref()),
+            );
+            Self::validate_transaction_set(message, None, &mut report);
+        }
+        for group in &groups {
+            Self::validate_group(group, &mut report);
+        }
+
+        match last_segment(root, "IEA") {
+            None => report.add_error(
+                ValidationError::new(
+                    "Interchange has no IEA trailer",
+                    EnvelopeType::Interchange,
+                    position(isa),
+                    ErrorKind::MissingTrailer,
+                )
+                .with_control_number(isa13.as_deref()),
+            ),
+            Some(iea) => {
+                let error = |message: &str, kind, expected: String, actual: Option<String>| {
+                    ValidationError::new(message, EnvelopeType::Interchange, position(iea), kind)
+                        .with_values(expected, actual.unwrap_or_default())
+                        .with_control_number(isa13.as_deref())
+                };
+                let count = value(iea, 1);
+                if !count_matches(count.as_deref(), groups.len()) {
+                    report.add_error(error(
+                        "IEA01 does not match the number of functional groups",
+                        ErrorKind::IncorrectGroupCount,
+                        groups.len().to_string(),
+                        count,
+                    ));
+                }
+                let iea02 = value(iea, 2);
+                if iea02 != isa13 {
+                    report.add_error(error(
+                        "IEA02 does not match ISA13",
+                        ErrorKind::MismatchedControlNumber,
+                        isa13.clone().unwrap_or_default(),
+                        iea02,
+                    ));
+                }
+            }
+        }
+
+        report
+    }
+
+    fn validate_group(group: &Node, report: &mut ValidationReport) {
+        let Some(gs) = first_segment(group, "GS") else {
+            report.add_error(ValidationError::new(
+                "Functional group has no GS header",
+                EnvelopeType::Group,
+                Position::default(),
+                ErrorKind::MissingHeader,
+            ));
+            return;
+        };
+        let gs06 = value(gs, 6);
+        let new_error = |message: &str, node: &Node, kind| {
+            ValidationError::new(message, EnvelopeType::Group, position(node), kind)
+                .with_control_number(gs06.as_deref())
+                .with_group_control_number(gs06.as_deref())
+        };
+        if !gs06.as_deref().is_some_and(|number| is_numeric(number, 1)) {
+            report.add_error(
+                new_error(
+                    "GS06 must be a numeric control number",
+                    gs,
+                    ErrorKind::InvalidControlNumber,
+                )
+                .with_values("1-9 digits", gs06.clone().unwrap_or_default()),
+            );
+        }
+
+        let messages: Vec<&Node> = children(group, NodeType::Message).collect();
+        for message in &messages {
+            Self::validate_transaction_set(message, gs06.as_deref(), report);
+        }
+
+        let Some(ge) = last_segment(group, "GE") else {
+            report.add_error(new_error(
+                "Functional group has no GE trailer",
+                gs,
+                ErrorKind::MissingTrailer,
+            ));
+            return;
+        };
+        let count = value(ge, 1);
+        if !count_matches(count.as_deref(), messages.len()) {
+            report.add_error(
+                new_error(
+                    "GE01 does not match the number of transaction sets",
+                    ge,
+                    ErrorKind::IncorrectTransactionSetCount,
+                )
+                .with_values(messages.len().to_string(), count.unwrap_or_default()),
+            );
+        }
+        let ge02 = value(ge, 2);
+        if ge02 != gs06 {
+            report.add_error(
+                new_error(
+                    "GE02 does not match GS06",
+                    ge,
+                    ErrorKind::MismatchedControlNumber,
+                )
+                .with_values(gs06.clone().unwrap_or_default(), ge02.unwrap_or_default()),
+            );
+        }
+    }
+
+    fn validate_transaction_set(
+        message: &Node,
+        group_control_number: Option<&str>,
+        report: &mut ValidationReport,
+    ) {
+        let st = first_segment(message, "ST");
+        let st02 = st.and_then(|st| value(st, 2));
+        let new_error = |message: &str, node: Option<&Node>, kind| {
+            ValidationError::new(
+                message,
+                EnvelopeType::TransactionSet,
+                node.map(position).unwrap_or_default(),
+                kind,
+            )
+            .with_control_number(st02.as_deref())
+            .with_group_control_number(group_control_number)
+        };
+        if st.is_none() {
+            report.add_error(new_error(
+                "Transaction set has no ST header",
+                None,
+                ErrorKind::MissingHeader,
+            ));
+            return;
+        }
+
+        let Some(se) = last_segment(message, "SE") else {
+            report.add_error(new_error(
+                "Transaction set has no SE trailer",
+                st,
+                ErrorKind::MissingTrailer,
+            ));
+            return;
+        };
+        let segment_count = count_segments(message);
+        let count = value(se, 1);
+        if !count_matches(count.as_deref(), segment_count) {
+            report.add_error(
+                new_error(
+                    "SE01 does not match the number of segments",
+                    Some(se),
+                    ErrorKind::IncorrectSegmentCount,
+                )
+                .with_values(segment_count.to_string(), count.unwrap_or_default()),
+            );
+        }
+        let se02 = value(se, 2);
+        if se02 != st02 {
+            report.add_error(
+                new_error(
+                    "SE02 does not match ST02",
+                    Some(se),
+                    ErrorKind::MismatchedControlNumber,
+                )
+                .with_values(st02.clone().unwrap_or_default(), se02.unwrap_or_default()),
+            );
+        }
+    }
+}
+
+fn children(node: &Node, node_type: NodeType) -> impl Iterator<Item = &Node> {
+    node.children
+        .iter()
+        .filter(move |child| child.node_type == node_type)
+}
+
+fn first_segment<'a>(node: &'a Node, tag: &str) -> Option<&'a Node> {
+    children(node, NodeType::Segment)
+        .next()
+        .filter(|segment| segment.name == tag)
+}
+
+fn last_segment<'a>(node: &'a Node, tag: &str) -> Option<&'a Node> {
+    node.children
+        .last()
+        .filter(|segment| segment.node_type == NodeType::Segment && segment.name == tag)
+}
+
+/// Segments of a transaction set, including those nested in loop nodes
+pub(crate) fn count_segments(node: &Node) -> usize {
+    node.children
+        .iter()
+        .map(|child| {
+            if child.node_type == NodeType::Segment {
+                1
+            } else {
+                count_segments(child)
+            }
+        })
+        .sum()
+}
+
+/// Value of the element at 1-based X12 position `index`
+pub(crate) fn value(segment: &Node, index: usize) -> Option<String> {
+    let element = segment
+        .children
+        .iter()
+        .filter(|child| child.node_type == NodeType::Element)
+        .nth(index.checked_sub(1)?)?;
+    let node = element.children.first().unwrap_or(element);
+    node.value.as_ref().and_then(Value::as_string)
+}
+
+fn position(segment: &Node) -> Position {
+    segment
+        .span
+        .as_ref()
+        .map(SourceSpan::position)
+        .unwrap_or_default()
+}
+
+fn count_matches(count: Option<&str>, actual: usize) -> bool {
+    count.and_then(|count| count.parse::<usize>().ok()) == Some(actual)
+}
+
+fn is_numeric(value: &str, min_length: usize) -> bool {
+    (min_length..=9).contains(&value.len()) && value.bytes().all(|byte| byte.is_ascii_digit())
+}
+
+// ============================================================================
+// Interchange Builder
+// ============================================================================
+
+/// Builds an ISA/GS envelope around transaction sets.
+///
+/// Transaction sets are renumbered `0001`, `0002`, ... and their SE, GE and
+/// IEA counts computed, so the result passes [`EnvelopeValidator`].
+#[derive(Debug, Clone)]
+pub struct InterchangeBuilder {
+    sender: PartyInfo,
+    recipient: PartyInfo,
+    control_number: u32,
+    group_control_number: Option<u32>,
+    application_ids: Option<(String, String)>,
+    version: String,
+    test: bool,
+    acknowledgment_requested: bool,
+    prepared_at: Option<chrono::NaiveDateTime>,
+}
+
+impl InterchangeBuilder {
+    /// Highest control number that fits ISA13
+    pub const MAX_CONTROL_NUMBER: u32 = 999_999_999;
+
+    /// Create a builder for an interchange from `sender` to `recipient` with
+    /// interchange control number `control_number`. Parties without a
+    /// qualifier use `ZZ` (mutually defined).
+    pub fn new(sender: PartyInfo, recipient: PartyInfo, control_number: u32) -> Self {
+        Self {
+            sender,
+            recipient,
+            control_number,
+            group_control_number: None,
+            application_ids: None,
+            version: "004010".to_string(),
+            test: false,
+            acknowledgment_requested: false,
+            prepared_at: None,
+        }
+    }
+
+    /// Use `group_control_number` in GS06/GE02 instead of the interchange
+    /// control number
+    #[must_use]
+    pub fn with_group_control_number(mut self, group_control_number: u32) -> Self {
+        self.group_control_number = Some(group_control_number);
+        self
+    }
+
+    /// Application sender and receiver codes for GS02/GS03 instead of the
+    /// interchange party ids
+    #[must_use]
+    pub fn with_application_ids(
+        mut self,
+        sender: impl Into<String>,
+        receiver: impl Into<String>,
+    ) -> Self {
+        self.application_ids = Some((sender.into(), receiver.into()));
+        self
+    }
+
+    /// Version, release and industry identifier for GS08 (default
+    /// "004010"); ISA12 uses its first five digits
+    #[must_use]
+    pub fn with_version(mut self, version: impl Into<String>) -> Self {
+        self.version = version.into();
+        self
+    }
+
+    /// Mark the interchange as test data (ISA15 `T`)
+    #[must_use]
+    pub fn with_test(mut self, test: bool) -> Self {
+        self.test = test;
+        self
+    }
+
+    /// Request a TA1 interchange acknowledgment (ISA14 `1`)
+    #[must_use]
+    pub fn with_acknowledgment_requested(mut self, requested: bool) -> Self {
+        self.acknowledgment_requested = requested;
+        self
+    }
+
+    /// Use a fixed preparation date instead of the current UTC time
+    #[must_use]
+    pub fn with_prepared_at(mut self, prepared_at: chrono::NaiveDateTime) -> Self {
+        self.prepared_at = Some(prepared_at);
+        self
+    }
+
+    /// Build an interchange holding one functional group with functional
+    /// identifier `functional_id` (GS01, e.g. `PO` or `FA`) around
+    /// `transaction_sets`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a control number exceeds nine digits, the version
+    /// is shorter than five characters or a document has no ST segment.
+    pub fn build(&self, functional_id: &str, transaction_sets: &[Document]) -> Result<Document> {
+        let group_control_number = self.group_control_number.unwrap_or(self.control_number);
+        if self.control_number > Self::MAX_CONTROL_NUMBER
+            || group_control_number > Self::MAX_CONTROL_NUMBER
+        {
+            return Err(Error::Envelope(format!(
+                "Control number exceeds {} digits",
+                Self::MAX_CONTROL_NUMBER.to_string().len()
+            )));
+        }
+        let Some(isa12) = self.version.get(..5) else {
+            return Err(Error::Envelope(format!(
+                "Version '{}' is too short for ISA12",
+                self.version
+            )));
+        };
+
+        let prepared_at = self
+            .prepared_at
+            .unwrap_or_else(|| chrono::Utc::now().naive_utc());
+        let control_number = format!("{:09}", self.control_number);
+        let group_control_number = group_control_number.to_string();
+        let repetition = if isa12 < "00402" {
+            "U".to_string()
+        } else {
+            char::from(Separators::default().repetition.unwrap_or(b'^')).to_string()
+        };
+
+        let isa = segment(
+            "ISA",
+            &[
+                "00",
+                "",
+                "00",
+                "",
+                qualifier(&self.sender),
+                &self.sender.id,
+                qualifier(&self.recipient),
+                &self.recipient.id,
+                &prepared_at.format("%y%m%d").to_string(),
+                &prepared_at.format("%H%M").to_string(),
+                &repetition,
+                isa12,
+                &control_number,
+                if self.acknowledgment_requested {
+                    "1"
+                } else {
+                    "0"
+                },
+                if self.test { "T" } else { "P" },
+                &char::from(Separators::default().component).to_string(),
+            ],
+        );
+        let (application_sender, application_receiver) = self
+            .application_ids
+            .clone()
+            .unwrap_or_else(|| (self.sender.id.clone(), self.recipient.id.clone()));
+        let gs = segment(
+            "GS",
+            &[
+                functional_id,
+                &application_sender,
+                &application_receiver,
+                &prepared_at.format("%Y%m%d").to_string(),
+                &prepared_at.format("%H%M").to_string(),
+                &group_control_number,
+                "X",
+                &self.version,
+            ],
+        );
+
+        let mut group = Node::new("GROUP", NodeType::FunctionalGroup);
+        group.add_child(gs.to_node());
+        for (index, document) in transaction_sets.iter().enumerate() {
+            group.add_child(renumber(&document.root, index + 1)?);
+        }
+        let ge = segment(
+            "GE",
+            &[&transaction_sets.len().to_string(), &group_control_number],
+        );
+        group.add_child(ge.to_node());
+
+        let mut root = Node::new("INTERCHANGE", NodeType::Interchange);
+        root.add_child(isa.to_node());
+        root.add_child(group);
+        root.add_child(segment("IEA", &["1", &control_number]).to_node());
+
+        let info = interchange_info(&isa);
+        let mut document = Document::new(root);
+        document.metadata.interchange_ref = Some(info.control_ref.clone());
+        document.metadata.partner_id = Some(info.sender.id.clone());
+        document.metadata.interchange = Some(info);
+        Ok(document)
+    }
+}
+
+fn qualifier(party: &PartyInfo) -> &str {
+    party.qualifier.as_deref().unwrap_or("ZZ")
+}
+
+/// Copy of a transaction set with ST02/SE02 set to the `index`th control
+/// number and SE01 recounted; a missing SE is added
+fn renumber(message: &Node, index: usize) -> Result<Node> {
+    let control_number = format!("{index:04}");
+    let mut root = message.clone();
+    let Some(st) = root
+        .children
+        .iter_mut()
+        .find(|child| child.node_type == NodeType::Segment && child.name == "ST")
+    else {
+        return Err(Error::Envelope(format!(
+            "Transaction set {index} has no ST segment"
+        )));
+    };
+    let mut header = Segment::from_node(st);
+    set_element(&mut header, 2, &control_number);
+    *st = header.to_node();
+
+    if last_segment(&root, "SE").is_none() {
+        root.add_child(segment("SE", &["", ""]).to_node());
+    }
+    let count = count_segments(&root);
+    if let Some(se) = root.children.last_mut() {
+        *se = segment("SE", &[&count.to_string(), &control_number]).to_node();
+    }
+    Ok(root)
+}
+
+fn set_element(segment: &mut Segment, index: usize, value: &str) {
+    while segment.elements.len() < index {
+        segment.elements.push(Element::Simple(String::new()));
+    }
+    segment.elements[index - 1] = Element::Simple(value.to_string());
+}
+
+fn segment(tag: &str, values: &[&str]) -> Segment {
+    Segment::new(
+        tag,
+        values
+            .iter()
+            .map(|value| Element::Simple((*value).to_string()))
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::X12Parser;
+
+    const PO: &[u8] = b"ISA*00*          *00*          *ZZ*BUYER          \
+        *ZZ*SELLER         *260312*0930*U*00401*000000101*0*P*>~\n\
+        GS*PO*BUYER*SELLER*20260312*0930*101*X*004010~\n\
+        ST*850*0001~\nBEG*00*SA*PO1**20260312~\nSE*3*0001~\n\
+        ST*850*0002~\nBEG*00*SA*PO2**20260312~\nSE*3*0002~\n\
+        GE*2*101~\nIEA*1*000000101~\n";
+
+    fn interchange(data: &[u8]) -> Document {
+        X12Parser::new().parse_interchanges(data).unwrap().remove(0)
+    }
+
+    fn prepared_at() -> chrono::NaiveDateTime {
+        chrono::NaiveDate::from_ymd_opt(2026, 3, 12)
+            .unwrap()
+            .and_hms_opt(9, 30, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_validate_valid_interchange() {
+        let report = EnvelopeValidator::new().validate(&interchange(PO));
+        assert!(report.is_valid(), "{:?}", report.errors);
+    }
+
+    #[test]
+    fn test_validate_reports_control_mismatches() {
+        let data = String::from_utf8_lossy(PO)
+            .replace("SE*3*0002", "SE*4*0003")
+            .replace("GE*2*101", "GE*3*102")
+            .replace("IEA*1*000000101", "IEA*2*000000100");
+        let report = EnvelopeValidator::new().validate(&interchange(data.as_bytes()));
+
+        let kinds: Vec<(EnvelopeType, ErrorKind)> = report
+            .errors
+            .iter()
+            .map(|error| (error.envelope_type, error.error_kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                (
+                    EnvelopeType::TransactionSet,
+                    ErrorKind::IncorrectSegmentCount
+                ),
+                (
+                    EnvelopeType::TransactionSet,
+                    ErrorKind::MismatchedControlNumber
+                ),
+                (EnvelopeType::Group, ErrorKind::IncorrectTransactionSetCount),
+                (EnvelopeType::Group, ErrorKind::MismatchedControlNumber),
+                (EnvelopeType::Interchange, ErrorKind::IncorrectGroupCount),
+                (
+                    EnvelopeType::Interchange,
+                    ErrorKind::MismatchedControlNumber
+                ),
+            ]
+        );
+        let se = &report.errors[0];
+        assert_eq!(se.control_number.as_deref(), Some("0002"));
+        assert_eq!(se.group_control_number.as_deref(), Some("101"));
+        assert_eq!(se.expected.as_deref(), Some("3"));
+        assert_eq!(se.actual.as_deref(), Some("4"));
+        assert_eq!(se.position.line, 8);
+    }
+
+    #[test]
+    fn test_validate_reports_missing_trailers() {
+        let data = b"ISA*00*          *00*          *ZZ*BUYER          \
+            *ZZ*SELLER         *260312*0930*U*00401*000000101*0*P*>~\
+            GS*PO*BUYER*SELLER*20260312*0930*101*X*004010~ST*850*0001~BEG*00*SA*PO1~";
+        let report = EnvelopeValidator::new().validate(&interchange(data));
+        let kinds: Vec<(EnvelopeType, ErrorKind)> = report
+            .errors
+            .iter()
+            .map(|error| (error.envelope_type, error.error_kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                (EnvelopeType::TransactionSet, ErrorKind::MissingTrailer),
+                (EnvelopeType::Group, ErrorKind::MissingTrailer),
+                (EnvelopeType::Interchange, ErrorKind::MissingTrailer),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_builder_computes_control_counts() {
+        let transaction_sets = X12Parser::new().parse(PO, "po.x12").unwrap();
+        let built = InterchangeBuilder::new(
+            PartyInfo {
+                id: "BUYER".to_string(),
+                qualifier: Some("01".to_string()),
+            },
+            PartyInfo {
+                id: "SELLER".to_string(),
+                qualifier: None,
+            },
+            7,
+        )
+        .with_group_control_number(70)
+        .with_test(true)
+        .with_prepared_at(prepared_at())
+        .build("PO", &transaction_sets)
+        .unwrap();
+
+        assert!(EnvelopeValidator::new().validate(&built).is_valid());
+        let root = &built.root;
+        assert_eq!(value(&root.children[0], 7).as_deref(), Some("ZZ"));
+        assert_eq!(value(&root.children[0], 13).as_deref(), Some("000000007"));
+        assert_eq!(value(&root.children[0], 15).as_deref(), Some("T"));
+        let group = &root.children[1];
+        assert_eq!(value(&group.children[0], 6).as_deref(), Some("70"));
+        assert_eq!(value(&group.children[3], 1).as_deref(), Some("2"));
+        let second = &group.children[2];
+        assert_eq!(value(&second.children[0], 2).as_deref(), Some("0002"));
+        assert_eq!(built.metadata.interchange_ref.as_deref(), Some("000000007"));
+    }
+
+    #[test]
+    fn test_builder_rejects_oversized_control_number() {
+        let party = PartyInfo {
+            id: "X".to_string(),
+            qualifier: None,
+        };
+        let error = InterchangeBuilder::new(party.clone(), party, 1_000_000_000)
+            .build("PO", &[])
+            .unwrap_err();
+        assert!(error.to_string().contains("exceeds 9 digits"));
+    }
+}
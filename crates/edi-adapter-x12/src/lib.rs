@@ -0,0 +1,47 @@
+//! # edi-adapter-x12
+//!
+//! ANSI X12 parser/serializer, envelope validation and functional
+//! acknowledgements.
+//!
+//! X12 interchanges are parsed into the same IR shape as EDIFACT: an
+//! `INTERCHANGE` root holding ISA, `GROUP` nodes (GS ... GE) with one
+//! `MESSAGE` node per transaction set (ST ... SE), and IEA.
+
+pub mod ack;
+pub mod envelopes;
+pub mod parser;
+pub mod serializer;
+pub mod syntax;
+
+pub use ack::{ElementErrorCode, FunctionalAckGenerator, SegmentError, SegmentErrorCode};
+pub use envelopes::{
+    EnvelopeType, EnvelopeValidator, ErrorKind, InterchangeBuilder, ValidationError,
+    ValidationReport,
+};
+pub use parser::{Element, ParseOutcome, ParseWarning, Segment, SegmentParser, X12Parser};
+pub use serializer::X12Serializer;
+pub use syntax::Separators;
+
+use thiserror::Error;
+
+/// Errors that can occur when parsing/serializing X12
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Parse error at line {line}, col {column}: {message}")]
+    Parse {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+
+    #[error("Serialize error: {0}")]
+    Serialize(String),
+
+    #[error("Envelope error: {0}")]
+    Envelope(String),
+
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
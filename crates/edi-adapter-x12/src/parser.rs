@@ -0,0 +1,814 @@
+//! X12 parser
+//!
+//! Splits X12 data into segments with the separators declared by each ISA
+//! header and assembles them into transaction set documents and interchange
+//! documents with the same IR shape as the EDIFACT adapter.
+
+use crate::syntax::{ISA_ELEMENT_COUNT, Separators};
+use crate::{Error, Result};
+use edi_ir::document::DocumentMetadata;
+use edi_ir::{Document, InterchangeInfo, Node, NodeType, PartyInfo, Position, SourceSpan, Value};
+use tracing::warn;
+
+/// A parsed X12 segment
+#[derive(Debug, Clone)]
+pub struct Segment {
+    /// Segment identifier (2-3 characters)
+    pub tag: String,
+    /// Data elements
+    pub elements: Vec<Element>,
+    /// Position of the segment identifier in the source
+    pub position: Position,
+    /// Source spans of the segment and its elements, set for parsed segments
+    pub spans: Option<SegmentSpans>,
+}
+
+/// Source spans of a parsed segment, attached to its IR nodes by
+/// [`Segment::to_node`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SegmentSpans {
+    /// Segment text from the identifier through the segment terminator
+    pub segment: SourceSpan,
+    /// One span per element, in the order of [`Segment::elements`]
+    pub elements: Vec<ElementSpan>,
+}
+
+/// Source span of an element value, shaped like the [`Element`] it belongs to
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ElementSpan {
+    /// Element text without the surrounding separators; ISA elements
+    /// include their padding
+    pub span: SourceSpan,
+    /// Component spans of a composite, or occurrence spans of a repeated
+    /// element; empty for a simple element
+    pub parts: Vec<ElementSpan>,
+}
+
+impl ElementSpan {
+    /// Set the spans on `node`, built by [`Element::to_node`], and its
+    /// children
+    pub fn apply(&self, node: &mut Node) {
+        node.set_span(self.span);
+        for (part, child) in self.parts.iter().zip(&mut node.children) {
+            part.apply(child);
+        }
+    }
+}
+
+/// A data element
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Element {
+    /// Simple data element
+    Simple(String),
+    /// Composite data element with its component values
+    Composite(Vec<String>),
+    /// Repeated data element (X12 version 00402 and later)
+    Repeated(Vec<Element>),
+}
+
+impl Element {
+    /// Value of a simple element, or the first component of a composite or
+    /// the first occurrence of a repeated element
+    pub fn value(&self) -> &str {
+        match self {
+            Element::Simple(value) => value,
+            Element::Composite(components) => components.first().map_or("", String::as_str),
+            Element::Repeated(occurrences) => occurrences.first().map_or("", Element::value),
+        }
+    }
+
+    /// Convert to an IR node, using the EDIFACT adapter's layout: composite
+    /// components are `c1..`, repeated occurrences `r1..`
+    pub fn to_node(&self, name: String) -> Node {
+        match self {
+            Element::Simple(value) => {
+                Node::with_value(name, NodeType::Element, Value::String(value.clone()))
+            }
+            Element::Composite(components) => {
+                let mut node = Node::new(name, NodeType::Element);
+                for (j, component) in components.iter().enumerate() {
+                    node.add_child(Node::with_value(
+                        format!("c{}", j + 1),
+                        NodeType::Component,
+                        Value::String(component.clone()),
+                    ));
+                }
+                node
+            }
+            Element::Repeated(occurrences) => {
+                let mut node = Node::new(name, NodeType::Element);
+                for (k, occurrence) in occurrences.iter().enumerate() {
+                    node.add_child(occurrence.to_node(format!("r{}", k + 1)));
+                }
+                node
+            }
+        }
+    }
+
+    fn from_node(node: &Node) -> Self {
+        if node.children.is_empty() {
+            Element::Simple(node_value(node))
+        } else if node
+            .children
+            .iter()
+            .all(|child| child.node_type == NodeType::Element)
+        {
+            Element::Repeated(node.children.iter().map(Element::from_node).collect())
+        } else {
+            Element::Composite(node.children.iter().map(node_value).collect())
+        }
+    }
+}
+
+impl Segment {
+    /// Create a segment without source position
+    pub fn new(tag: impl Into<String>, elements: Vec<Element>) -> Self {
+        Self {
+            tag: tag.into(),
+            elements,
+            position: Position::default(),
+            spans: None,
+        }
+    }
+
+    /// Value of the element at 1-based X12 position `index` (e.g. 2 for ST02)
+    pub fn value(&self, index: usize) -> Option<&str> {
+        index
+            .checked_sub(1)
+            .and_then(|index| self.elements.get(index))
+            .map(Element::value)
+    }
+
+    /// Rebuild a segment from its IR node, the inverse of [`Self::to_node`]
+    pub fn from_node(node: &Node) -> Self {
+        Self {
+            tag: node.name.clone(),
+            elements: node
+                .children
+                .iter()
+                .filter(|child| child.node_type == NodeType::Element)
+                .map(Element::from_node)
+                .collect(),
+            position: Position::default(),
+            spans: None,
+        }
+    }
+
+    /// Convert this segment to an IR node
+    pub fn to_node(&self) -> Node {
+        let mut node = Node::new(&self.tag, NodeType::Segment);
+        if let Some(spans) = &self.spans {
+            node.set_span(spans.segment);
+        }
+        for (i, element) in self.elements.iter().enumerate() {
+            let mut child = element.to_node(format!("e{}", i + 1));
+            if let Some(span) = self.spans.as_ref().and_then(|spans| spans.elements.get(i)) {
+                span.apply(&mut child);
+            }
+            node.add_child(child);
+        }
+        node
+    }
+}
+
+fn node_value(node: &Node) -> String {
+    node.value
+        .as_ref()
+        .and_then(Value::as_string)
+        .unwrap_or_default()
+}
+
+/// Splits X12 data into segments.
+///
+/// Separators are re-derived from every ISA segment, so one input may hold
+/// several interchanges with different delimiters. Line breaks between
+/// segments are skipped.
+pub struct SegmentParser<'a> {
+    data: &'a [u8],
+    offset: usize,
+    line: usize,
+    column: usize,
+    separators: Option<Separators>,
+}
+
+impl<'a> SegmentParser<'a> {
+    /// Create a new segment parser from bytes
+    pub fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            offset: 0,
+            line: 1,
+            column: 1,
+            separators: None,
+        }
+    }
+
+    /// Separators of the current interchange, once an ISA has been read
+    pub fn separators(&self) -> Option<Separators> {
+        self.separators
+    }
+
+    /// Current position in the source
+    pub fn current_position(&self) -> Position {
+        Position::new(self.line, self.column, self.offset, 0)
+    }
+
+    /// Parse the next segment
+    pub fn next_segment(&mut self) -> Option<Result<Segment>> {
+        while let Some(&byte) = self.data.get(self.offset) {
+            if !matches!(byte, b'\r' | b'\n' | b' ' | b'\t') {
+                break;
+            }
+            self.advance(1);
+        }
+        if self.offset >= self.data.len() {
+            return None;
+        }
+
+        let rest = &self.data[self.offset..];
+        if rest.starts_with(b"ISA") {
+            match Separators::from_isa(rest) {
+                Some(separators) => self.separators = Some(separators),
+                None => return Some(Err(self.error("Incomplete ISA interchange header"))),
+            }
+        }
+        let Some(separators) = self.separators else {
+            return Some(Err(self.error("X12 data must start with an ISA segment")));
+        };
+        let Some(length) = rest.iter().position(|&byte| byte == separators.segment) else {
+            return Some(Err(self.error("Segment is missing its terminator")));
+        };
+
+        let text = String::from_utf8_lossy(&rest[..length]).into_owned();
+        let mut position = self.current_position();
+        position.length = length + 1;
+        let spans = Self::segment_spans(&rest[..=length], separators, &position);
+        // Malformed segments are consumed too, so that a caller may continue
+        self.advance(length + 1);
+        Some(
+            Self::split_segment(&text, separators, position).map(|mut segment| {
+                segment.spans = Some(spans);
+                segment
+            }),
+        )
+    }
+
+    /// Spans of `bytes`, a segment through its terminator starting at
+    /// `position`, split the way [`Self::split_segment`] splits its text
+    fn segment_spans(bytes: &[u8], separators: Separators, position: &Position) -> SegmentSpans {
+        let mut cursor = SpanCursor {
+            bytes,
+            base: position.offset,
+            walked: 0,
+            line: position.line,
+            column: position.column,
+        };
+        let segment = cursor.span(0, bytes.len());
+        let is_isa = bytes.starts_with(b"ISA");
+        let elements = split(bytes, 0, bytes.len() - 1, separators.element)
+            .into_iter()
+            .skip(1)
+            .map(|(start, end)| {
+                let span = cursor.span(start, end);
+                let parts = match separators.repetition {
+                    _ if is_isa => Vec::new(),
+                    Some(repetition) if bytes[start..end].contains(&repetition) => {
+                        split(bytes, start, end, repetition)
+                            .into_iter()
+                            .map(|(start, end)| ElementSpan {
+                                span: cursor.span(start, end),
+                                parts: cursor.components(start, end, separators.component),
+                            })
+                            .collect()
+                    }
+                    _ => cursor.components(start, end, separators.component),
+                };
+                ElementSpan { span, parts }
+            })
+            .collect();
+        SegmentSpans { segment, elements }
+    }
+
+    fn split_segment(text: &str, separators: Separators, position: Position) -> Result<Segment> {
+        let element_separator = char::from(separators.element);
+        let mut fields = text.split(element_separator);
+        let tag = fields.next().unwrap_or_default().to_string();
+        if !(2..=3).contains(&tag.len())
+            || !tag
+                .chars()
+                .all(|ch| ch.is_ascii_uppercase() || ch.is_ascii_digit())
+        {
+            return Err(Error::Parse {
+                line: position.line,
+                column: position.column,
+                message: format!("Invalid segment identifier '{tag}'"),
+            });
+        }
+
+        let elements: Vec<Element> = if tag == "ISA" {
+            // ISA is fixed width: values are space padded and ISA11/ISA16
+            // hold separators, so no further splitting applies
+            fields
+                .map(|field| Element::Simple(field.trim_end().to_string()))
+                .collect()
+        } else {
+            fields
+                .map(|field| Self::parse_element(field, separators))
+                .collect()
+        };
+        if tag == "ISA" && elements.len() != ISA_ELEMENT_COUNT {
+            return Err(Error::Parse {
+                line: position.line,
+                column: position.column,
+                message: format!(
+                    "ISA must have {ISA_ELEMENT_COUNT} elements, found {}",
+                    elements.len()
+                ),
+            });
+        }
+
+        Ok(Segment {
+            tag,
+            elements,
+            position,
+            spans: None,
+        })
+    }
+
+    fn parse_element(field: &str, separators: Separators) -> Element {
+        if let Some(repetition) = separators.repetition.map(char::from)
+            && field.contains(repetition)
+        {
+            return Element::Repeated(
+                field
+                    .split(repetition)
+                    .map(|occurrence| Self::parse_components(occurrence, separators))
+                    .collect(),
+            );
+        }
+        Self::parse_components(field, separators)
+    }
+
+    fn parse_components(field: &str, separators: Separators) -> Element {
+        let component = char::from(separators.component);
+        if field.contains(component) {
+            Element::Composite(field.split(component).map(str::to_string).collect())
+        } else {
+            Element::Simple(field.to_string())
+        }
+    }
+
+    fn advance(&mut self, count: usize) {
+        for &byte in &self.data[self.offset..self.offset + count] {
+            if byte == b'\n' {
+                self.line += 1;
+                self.column = 1;
+            } else {
+                self.column += 1;
+            }
+        }
+        self.offset += count;
+    }
+
+    fn error(&self, message: &str) -> Error {
+        Error::Parse {
+            line: self.line,
+            column: self.column,
+            message: message.to_string(),
+        }
+    }
+}
+
+/// Line and column tracking over the bytes of one segment, with the line
+/// rules of [`SegmentParser`]
+struct SpanCursor<'a> {
+    bytes: &'a [u8],
+    base: usize,
+    walked: usize,
+    line: usize,
+    column: usize,
+}
+
+impl SpanCursor<'_> {
+    /// Span of `bytes[start..end]`; `start` must not precede earlier starts
+    fn span(&mut self, start: usize, end: usize) -> SourceSpan {
+        for &byte in &self.bytes[self.walked..start] {
+            if byte == b'\n' {
+                self.line += 1;
+                self.column = 1;
+            } else {
+                self.column += 1;
+            }
+        }
+        self.walked = start;
+        SourceSpan::new(self.base + start, end - start, self.line, self.column)
+    }
+
+    /// Component spans of `bytes[start..end]`, empty unless it is a composite
+    fn components(&mut self, start: usize, end: usize, separator: u8) -> Vec<ElementSpan> {
+        if !self.bytes[start..end].contains(&separator) {
+            return Vec::new();
+        }
+        split(self.bytes, start, end, separator)
+            .into_iter()
+            .map(|(start, end)| ElementSpan {
+                span: self.span(start, end),
+                parts: Vec::new(),
+            })
+            .collect()
+    }
+}
+
+/// Ranges of `bytes[start..end]` between occurrences of `separator`
+fn split(bytes: &[u8], start: usize, end: usize, separator: u8) -> Vec<(usize, usize)> {
+    let mut ranges = Vec::new();
+    let mut from = start;
+    for (index, &byte) in bytes.iter().enumerate().take(end).skip(start) {
+        if byte == separator {
+            ranges.push((from, index));
+            from = index + 1;
+        }
+    }
+    ranges.push((from, end));
+    ranges
+}
+
+/// Non-fatal parser warning emitted while parsing X12 data
+#[derive(Debug, Clone)]
+pub struct ParseWarning {
+    /// Machine-readable warning code (e.g. `MISSING_SE`)
+    pub code: &'static str,
+    /// Warning message describing what happened
+    pub message: String,
+    /// Position in the source where the warning occurred
+    pub position: Position,
+    /// Transaction set control number from ST02 when available
+    pub control_number: Option<String>,
+}
+
+impl ParseWarning {
+    fn missing_se(position: Position, control_number: Option<String>) -> Self {
+        Self {
+            code: "MISSING_SE",
+            message: "Created partial transaction set at EOF because SE segment is missing"
+                .to_string(),
+            position,
+            control_number,
+        }
+    }
+
+    fn segment_outside_transaction_set(segment: &Segment) -> Self {
+        Self {
+            code: "SEGMENT_OUTSIDE_TRANSACTION_SET",
+            message: format!(
+                "Segment {} outside a transaction set was ignored",
+                segment.tag
+            ),
+            position: segment.position.clone(),
+            control_number: None,
+        }
+    }
+}
+
+/// Parse output that includes parsed documents and non-fatal warnings
+#[derive(Debug, Clone, Default)]
+pub struct ParseOutcome {
+    /// Parsed transaction sets, one `MESSAGE` document per ST ... SE
+    pub documents: Vec<Document>,
+    /// Parsed interchanges: an `Interchange` root holding ISA, one
+    /// `FunctionalGroup` node per GS ... GE and IEA
+    pub interchanges: Vec<Document>,
+    /// Non-fatal warnings collected during parsing
+    pub warnings: Vec<ParseWarning>,
+}
+
+/// Builds the interchange → functional group → transaction set hierarchy
+/// while segments are read
+#[derive(Debug, Default)]
+struct Assembler {
+    interchange: Option<Node>,
+    info: Option<InterchangeInfo>,
+    group: Option<Node>,
+    group_version: Option<String>,
+    transaction_set: Vec<Segment>,
+    outcome: ParseOutcome,
+}
+
+impl Assembler {
+    fn push(&mut self, segment: Segment) {
+        match segment.tag.as_str() {
+            "ISA" => {
+                self.close_interchange(None);
+                let mut interchange = Node::new("INTERCHANGE", NodeType::Interchange);
+                interchange.add_child(segment.to_node());
+                self.info = Some(interchange_info(&segment));
+                self.interchange = Some(interchange);
+            }
+            "IEA" => self.close_interchange(Some(&segment)),
+            "GS" => {
+                self.close_group(None);
+                let mut group = Node::new("GROUP", NodeType::FunctionalGroup);
+                group.add_child(segment.to_node());
+                self.group_version = segment.value(8).map(str::to_string);
+                self.group = Some(group);
+            }
+            "GE" => self.close_group(Some(&segment)),
+            "ST" => {
+                self.close_transaction_set();
+                self.transaction_set.push(segment);
+            }
+            "SE" if !self.transaction_set.is_empty() => {
+                self.transaction_set.push(segment);
+                self.close_transaction_set();
+            }
+            _ if !self.transaction_set.is_empty() => self.transaction_set.push(segment),
+            _ => self
+                .outcome
+                .warnings
+                .push(ParseWarning::segment_outside_transaction_set(&segment)),
+        }
+    }
+
+    fn close_transaction_set(&mut self) {
+        if self.transaction_set.is_empty() {
+            return;
+        }
+        let segments = std::mem::take(&mut self.transaction_set);
+        let st = &segments[0];
+        if segments.last().is_none_or(|segment| segment.tag != "SE") {
+            let position = segments.last().map(|s| s.position.clone());
+            self.outcome.warnings.push(ParseWarning::missing_se(
+                position.unwrap_or_default(),
+                st.value(2).map(str::to_string),
+            ));
+        }
+
+        let mut root = Node::new("MESSAGE", NodeType::Message);
+        for (index, segment) in segments.iter().enumerate() {
+            let mut node = segment.to_node();
+            node.set_segment_index(index + 1);
+            root.add_child(node);
+        }
+        let version = st
+            .value(3)
+            .filter(|version| !version.is_empty())
+            .map(str::to_string)
+            .or_else(|| self.group_version.clone());
+        let mut metadata = DocumentMetadata {
+            doc_type: st.value(1).map(str::to_string),
+            version,
+            ..Default::default()
+        };
+        if let Some(control_number) = st.value(2) {
+            metadata.message_refs.push(control_number.to_string());
+        }
+        if let Some(info) = &self.info {
+            metadata.interchange_ref = Some(info.control_ref.clone());
+            metadata.partner_id = Some(info.sender.id.clone());
+            metadata.interchange = Some(info.clone());
+        }
+
+        if let Some(parent) = self.group.as_mut().or(self.interchange.as_mut()) {
+            parent.add_child(root.clone());
+        }
+        self.outcome
+            .documents
+            .push(Document::with_metadata(root, metadata));
+    }
+
+    fn close_group(&mut self, ge: Option<&Segment>) {
+        self.close_transaction_set();
+        let Some(mut group) = self.group.take() else {
+            return;
+        };
+        if let Some(ge) = ge {
+            group.add_child(ge.to_node());
+        }
+        self.group_version = None;
+        if let Some(interchange) = self.interchange.as_mut() {
+            interchange.add_child(group);
+        }
+    }
+
+    fn close_interchange(&mut self, iea: Option<&Segment>) {
+        self.close_group(None);
+        let Some(mut interchange) = self.interchange.take() else {
+            return;
+        };
+        if let Some(iea) = iea {
+            interchange.add_child(iea.to_node());
+        }
+        let mut document = Document::new(interchange);
+        if let Some(info) = self.info.take() {
+            document.metadata.interchange_ref = Some(info.control_ref.clone());
+            document.metadata.partner_id = Some(info.sender.id.clone());
+            document.metadata.interchange = Some(info);
+        }
+        self.outcome.interchanges.push(document);
+    }
+
+    fn finish(mut self) -> ParseOutcome {
+        self.close_interchange(None);
+        self.outcome
+    }
+}
+
+/// Interchange envelope data from an ISA segment
+pub(crate) fn interchange_info(isa: &Segment) -> InterchangeInfo {
+    let value = |index| isa.value(index).unwrap_or_default().to_string();
+    let party = |qualifier: usize, id: usize| PartyInfo {
+        id: value(id),
+        qualifier: Some(value(qualifier)).filter(|qualifier| !qualifier.is_empty()),
+    };
+    InterchangeInfo {
+        sender: party(5, 6),
+        recipient: party(7, 8),
+        preparation_date: value(9),
+        preparation_time: value(10),
+        control_ref: value(13),
+        application_ref: None,
+        test_indicator: (value(15) == "T").then(|| "1".to_string()),
+    }
+}
+
+/// X12 parser
+#[derive(Debug, Clone, Default)]
+pub struct X12Parser;
+
+impl X12Parser {
+    /// Create a new parser
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Parse X12 data and return one `MESSAGE` document per transaction set
+    pub fn parse(&self, data: &[u8], source_name: impl Into<String>) -> Result<Vec<Document>> {
+        let source_name = source_name.into();
+        let outcome = self.parse_with_warnings(data)?;
+        for warning in &outcome.warnings {
+            warn!(
+                source = %source_name,
+                control_number = warning.control_number.as_deref().unwrap_or("unknown"),
+                line = warning.position.line,
+                column = warning.position.column,
+                "{}",
+                warning.message
+            );
+        }
+        Ok(outcome.documents)
+    }
+
+    /// Parse X12 data and return all transaction sets, interchanges and
+    /// warnings
+    pub fn parse_with_warnings(&self, data: &[u8]) -> Result<ParseOutcome> {
+        let mut parser = SegmentParser::new(data);
+        let mut assembler = Assembler::default();
+        while let Some(segment) = parser.next_segment() {
+            assembler.push(segment?);
+        }
+        Ok(assembler.finish())
+    }
+
+    /// Parse X12 data and return one document per interchange, preserving
+    /// the ISA/IEA and GS/GE envelopes
+    pub fn parse_interchanges(&self, data: &[u8]) -> Result<Vec<Document>> {
+        Ok(self.parse_with_warnings(data)?.interchanges)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PO: &[u8] = b"ISA*00*          *00*          *ZZ*BUYER          \
+        *ZZ*SELLER         *260312*0930*^*00501*000000101*0*T*:~\n\
+        GS*PO*BUYER*SELLER*20260312*0930*101*X*005010~\n\
+        ST*850*0001~\n\
+        BEG*00*SA*PO1**20260312~\n\
+        PID*F****WIDGET^GADGET~\n\
+        PO1*1*10*EA*9.95**UP*012345678905~\n\
+        REF*ZZ*A:B~\n\
+        SE*6*0001~\n\
+        GE*1*101~\n\
+        IEA*1*000000101~\n";
+
+    #[test]
+    fn test_parse_transaction_set() {
+        let documents = X12Parser::new().parse(PO, "po.x12").unwrap();
+        assert_eq!(documents.len(), 1);
+
+        let document = &documents[0];
+        assert_eq!(document.metadata.doc_type.as_deref(), Some("850"));
+        assert_eq!(document.metadata.version.as_deref(), Some("005010"));
+        assert_eq!(document.metadata.message_refs, vec!["0001"]);
+        assert_eq!(
+            document.metadata.interchange_ref.as_deref(),
+            Some("000000101")
+        );
+        assert_eq!(document.metadata.partner_id.as_deref(), Some("BUYER"));
+        let interchange = document.metadata.interchange.as_ref().unwrap();
+        assert_eq!(interchange.recipient.id, "SELLER");
+        assert_eq!(interchange.recipient.qualifier.as_deref(), Some("ZZ"));
+        assert!(interchange.is_test());
+
+        let segments: Vec<&str> = document
+            .root
+            .children
+            .iter()
+            .map(|node| node.name.as_str())
+            .collect();
+        assert_eq!(segments, ["ST", "BEG", "PID", "PO1", "REF", "SE"]);
+        let beg = &document.root.children[1];
+        let span = beg.span.unwrap();
+        assert_eq!(
+            (span.line, span.column, span.segment_index),
+            (4, 1, Some(2))
+        );
+        assert_eq!(node_value(&beg.children[2]), "PO1");
+    }
+
+    #[test]
+    fn test_parse_records_element_and_component_spans() {
+        let documents = X12Parser::new().parse(PO, "po.x12").unwrap();
+        let root = &documents[0].root;
+        let slice = |node: &Node| {
+            let span = node.span.unwrap();
+            std::str::from_utf8(span.slice(PO).unwrap()).unwrap()
+        };
+
+        let beg = &root.children[1];
+        assert_eq!(slice(beg), "BEG*00*SA*PO1**20260312~");
+        assert_eq!(slice(&beg.children[2]), "PO1");
+        assert_eq!(beg.children[2].span.unwrap().column, 11);
+        assert_eq!(beg.children[3].span.unwrap().length, 0);
+
+        let repeated = &root.children[2].children[4];
+        assert_eq!(slice(repeated), "WIDGET^GADGET");
+        assert_eq!(slice(&repeated.children[1]), "GADGET");
+
+        let reference = &root.children[4];
+        assert_eq!(slice(&reference.children[1].children[1]), "B");
+        assert_eq!(
+            reference.children[1].children[1]
+                .span
+                .unwrap()
+                .segment_index,
+            Some(5)
+        );
+    }
+
+    #[test]
+    fn test_parse_composite_and_repeated_elements() {
+        let documents = X12Parser::new().parse(PO, "po.x12").unwrap();
+        let root = &documents[0].root;
+
+        let pid = &root.children[2];
+        let repeated = &pid.children[4];
+        assert_eq!(repeated.children.len(), 2);
+        assert_eq!(repeated.children[1].name, "r2");
+        assert_eq!(node_value(&repeated.children[1]), "GADGET");
+
+        let reference = &root.children[4].children[1];
+        assert_eq!(reference.children[0].name, "c1");
+        assert_eq!(node_value(&reference.children[1]), "B");
+    }
+
+    #[test]
+    fn test_parse_interchange_hierarchy() {
+        let interchanges = X12Parser::new().parse_interchanges(PO).unwrap();
+        assert_eq!(interchanges.len(), 1);
+
+        let root = &interchanges[0].root;
+        assert_eq!(root.node_type, NodeType::Interchange);
+        let children: Vec<&str> = root.children.iter().map(|n| n.name.as_str()).collect();
+        assert_eq!(children, ["ISA", "GROUP", "IEA"]);
+        // ISA padding is removed
+        assert_eq!(node_value(&root.children[0].children[5]), "BUYER");
+
+        let group = &root.children[1];
+        assert_eq!(group.node_type, NodeType::FunctionalGroup);
+        let children: Vec<&str> = group.children.iter().map(|n| n.name.as_str()).collect();
+        assert_eq!(children, ["GS", "MESSAGE", "GE"]);
+    }
+
+    #[test]
+    fn test_parse_missing_se_warns() {
+        let data = b"ISA*00*          *00*          *ZZ*BUYER          \
+            *ZZ*SELLER         *260312*0930*U*00401*000000101*0*P*>~\
+            GS*PO*BUYER*SELLER*20260312*0930*101*X*004010~ST*850*0001~BEG*00*SA*PO1~";
+        let outcome = X12Parser::new().parse_with_warnings(data).unwrap();
+        assert_eq!(outcome.documents.len(), 1);
+        assert_eq!(outcome.documents[0].root.children.len(), 2);
+        assert_eq!(outcome.warnings.len(), 1);
+        assert_eq!(outcome.warnings[0].code, "MISSING_SE");
+        assert_eq!(outcome.warnings[0].control_number.as_deref(), Some("0001"));
+    }
+
+    #[test]
+    fn test_parse_requires_isa() {
+        let error = X12Parser::new()
+            .parse(b"GS*PO*BUYER~", "gs.x12")
+            .unwrap_err();
+        assert!(error.to_string().contains("must start with an ISA"));
+    }
+}
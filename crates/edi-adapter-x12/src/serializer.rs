@@ -0,0 +1,284 @@
+//! X12 serializer
+//!
+//! Writes interchange, functional group and transaction set documents back
+//! to X12 text. ISA values are padded to their fixed widths and ISA16 is
+//! always the configured component separator.
+
+use crate::parser::{Element, Segment};
+use crate::syntax::{ISA_ELEMENT_COUNT, ISA_ELEMENT_WIDTHS, Separators};
+use crate::{Error, Result};
+use edi_ir::{Document, Node, NodeType};
+
+/// Serializer for X12 documents
+#[derive(Debug, Clone)]
+pub struct X12Serializer {
+    separators: Separators,
+    line_breaks: bool,
+}
+
+impl Default for X12Serializer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl X12Serializer {
+    /// Create a new X12 serializer with the default separators
+    /// (`*`, `^`, `>` and `~`)
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            separators: Separators::default(),
+            line_breaks: true,
+        }
+    }
+
+    /// Write segments with `separators`, e.g. those of a parsed interchange
+    #[must_use]
+    pub fn with_separators(mut self, separators: Separators) -> Self {
+        self.separators = separators;
+        self
+    }
+
+    /// Follow every segment terminator with a line break (default `true`)
+    #[must_use]
+    pub fn with_line_breaks(mut self, line_breaks: bool) -> Self {
+        self.line_breaks = line_breaks;
+        self
+    }
+
+    /// Serialize every segment of `document`, whether its root is an
+    /// interchange, a functional group or a single transaction set.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a value contains a separator (X12 has no release
+    /// character) or an ISA value exceeds its fixed width.
+    pub fn serialize_document(&self, document: &Document) -> Result<String> {
+        let mut segments = Vec::new();
+        collect_segments(&document.root, &mut segments);
+
+        let mut output = String::new();
+        for node in segments {
+            let segment = Segment::from_node(node);
+            if segment.tag == "ISA" {
+                self.write_isa(&segment, &mut output)?;
+            } else {
+                self.write_segment(&segment, &mut output)?;
+            }
+            output.push(char::from(self.separators.segment));
+            if self.line_breaks && !matches!(self.separators.segment, b'\n' | b'\r') {
+                output.push('\n');
+            }
+        }
+        Ok(output)
+    }
+
+    fn write_isa(&self, isa: &Segment, output: &mut String) -> Result<()> {
+        if isa.elements.len() != ISA_ELEMENT_COUNT {
+            return Err(Error::Serialize(format!(
+                "ISA must have {ISA_ELEMENT_COUNT} elements, found {}",
+                isa.elements.len()
+            )));
+        }
+
+        output.push_str("ISA");
+        for (index, (element, width)) in isa.elements.iter().zip(ISA_ELEMENT_WIDTHS).enumerate() {
+            let position = index + 1;
+            let value = match position {
+                // Repetition separator, or the standards identifier before
+                // version 00402
+                11 => {
+                    let value = element.value();
+                    if !value.is_empty() && value.bytes().all(|byte| byte.is_ascii_alphanumeric()) {
+                        value.to_string()
+                    } else {
+                        char::from(self.separators.repetition.unwrap_or(b'U')).to_string()
+                    }
+                }
+                13 if element.value().bytes().all(|byte| byte.is_ascii_digit()) => {
+                    format!("{:0>width$}", element.value())
+                }
+                16 => char::from(self.separators.component).to_string(),
+                _ => element.value().to_string(),
+            };
+            if value.len() > width {
+                return Err(Error::Serialize(format!(
+                    "ISA{position:02} value '{value}' exceeds {width} characters"
+                )));
+            }
+            if position != 11 && position != 16 {
+                self.check_value(&isa.tag, position, &value)?;
+            }
+            output.push(char::from(self.separators.element));
+            output.push_str(&format!("{value:<width$}"));
+        }
+        Ok(())
+    }
+
+    fn write_segment(&self, segment: &Segment, output: &mut String) -> Result<()> {
+        let used = segment
+            .elements
+            .iter()
+            .rposition(|element| !is_empty(element))
+            .map_or(0, |index| index + 1);
+
+        output.push_str(&segment.tag);
+        for (index, element) in segment.elements[..used].iter().enumerate() {
+            output.push(char::from(self.separators.element));
+            self.write_element(&segment.tag, index + 1, element, output)?;
+        }
+        Ok(())
+    }
+
+    fn write_element(
+        &self,
+        tag: &str,
+        position: usize,
+        element: &Element,
+        output: &mut String,
+    ) -> Result<()> {
+        match element {
+            Element::Simple(value) => {
+                self.check_value(tag, position, value)?;
+                output.push_str(value);
+            }
+            Element::Composite(components) => {
+                let used = components
+                    .iter()
+                    .rposition(|component| !component.is_empty())
+                    .map_or(0, |index| index + 1);
+                for (index, component) in components[..used].iter().enumerate() {
+                    if index > 0 {
+                        output.push(char::from(self.separators.component));
+                    }
+                    self.check_value(tag, position, component)?;
+                    output.push_str(component);
+                }
+            }
+            Element::Repeated(occurrences) => {
+                let Some(repetition) = self.separators.repetition else {
+                    return Err(Error::Serialize(format!(
+                        "{tag}{position:02} repeats but no repetition separator is configured"
+                    )));
+                };
+                for (index, occurrence) in occurrences.iter().enumerate() {
+                    if index > 0 {
+                        output.push(char::from(repetition));
+                    }
+                    self.write_element(tag, position, occurrence, output)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn check_value(&self, tag: &str, position: usize, value: &str) -> Result<()> {
+        match value
+            .bytes()
+            .find(|&byte| self.separators.is_separator(byte))
+        {
+            Some(separator) => Err(Error::Serialize(format!(
+                "{tag}{position:02} value '{value}' contains the separator '{}'",
+                char::from(separator)
+            ))),
+            None => Ok(()),
+        }
+    }
+}
+
+fn is_empty(element: &Element) -> bool {
+    match element {
+        Element::Simple(value) => value.is_empty(),
+        Element::Composite(components) => components.iter().all(String::is_empty),
+        Element::Repeated(occurrences) => occurrences.iter().all(is_empty),
+    }
+}
+
+fn collect_segments<'a>(node: &'a Node, segments: &mut Vec<&'a Node>) {
+    if node.node_type == NodeType::Segment {
+        segments.push(node);
+        return;
+    }
+    for child in &node.children {
+        collect_segments(child, segments);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::X12Parser;
+
+    const PO: &str = "ISA*00*          *00*          *ZZ*BUYER          \
+        *ZZ*SELLER         *260312*0930*^*00501*000000101*0*T*:~\n\
+        GS*PO*BUYER*SELLER*20260312*0930*101*X*005010~\n\
+        ST*850*0001~\n\
+        BEG*00*SA*PO1**20260312~\n\
+        PID*F****WIDGET^GADGET~\n\
+        REF*ZZ*A:B~\n\
+        SE*5*0001~\n\
+        GE*1*101~\n\
+        IEA*1*000000101~\n";
+
+    #[test]
+    fn test_round_trip_interchange() {
+        let interchange = X12Parser::new()
+            .parse_interchanges(PO.as_bytes())
+            .unwrap()
+            .remove(0);
+        let separators = Separators::from_isa(PO.as_bytes()).unwrap();
+        let output = X12Serializer::new()
+            .with_separators(separators)
+            .serialize_document(&interchange)
+            .unwrap();
+        assert_eq!(output, PO);
+    }
+
+    #[test]
+    fn test_serialize_with_other_separators() {
+        let interchange = X12Parser::new()
+            .parse_interchanges(PO.as_bytes())
+            .unwrap()
+            .remove(0);
+        let output = X12Serializer::new()
+            .with_separators(Separators {
+                element: b'|',
+                repetition: Some(b'{'),
+                component: b'\\',
+                segment: b'\n',
+            })
+            .serialize_document(&interchange)
+            .unwrap();
+        assert!(output.ends_with("IEA|1|000000101\n"), "{output}");
+        assert!(output.contains("|0930|{|00501|000000101|0|T|\\\n"));
+        assert!(output.contains("PID|F||||WIDGET{GADGET\n"));
+        assert!(output.contains("REF|ZZ|A\\B\n"));
+    }
+
+    #[test]
+    fn test_serialize_trims_trailing_empty_elements() {
+        let documents = X12Parser::new().parse(PO.as_bytes(), "po.x12").unwrap();
+        let mut document = documents[0].clone();
+        let beg = &mut document.root.children[1];
+        beg.children.push(edi_ir::Node::with_value(
+            "e6",
+            NodeType::Element,
+            edi_ir::Value::String(String::new()),
+        ));
+        let output = X12Serializer::new().serialize_document(&document).unwrap();
+        assert!(output.contains("BEG*00*SA*PO1**20260312~\n"), "{output}");
+    }
+
+    #[test]
+    fn test_serialize_rejects_separator_in_value() {
+        let documents = X12Parser::new().parse(PO.as_bytes(), "po.x12").unwrap();
+        let mut document = documents[0].clone();
+        document.root.children[1].children[2].value =
+            Some(edi_ir::Value::String("PO*1".to_string()));
+        let error = X12Serializer::new()
+            .serialize_document(&document)
+            .unwrap_err();
+        assert!(error.to_string().contains("BEG03"), "{error}");
+    }
+}
@@ -0,0 +1,135 @@
+//! X12 syntax definitions and delimiter handling
+//!
+//! X12 has no service string advice: the separators are taken from the
+//! fixed-width ISA interchange header that starts every interchange.
+
+/// Default X12 separators used when writing new interchanges
+pub const DEFAULT_ELEMENT_SEPARATOR: u8 = b'*';
+pub const DEFAULT_COMPONENT_SEPARATOR: u8 = b'>';
+pub const DEFAULT_REPETITION_SEPARATOR: u8 = b'^';
+pub const DEFAULT_SEGMENT_TERMINATOR: u8 = b'~';
+
+/// Number of elements in the ISA segment
+pub const ISA_ELEMENT_COUNT: usize = 16;
+
+/// Fixed widths of ISA01 through ISA16
+pub const ISA_ELEMENT_WIDTHS: [usize; ISA_ELEMENT_COUNT] =
+    [2, 10, 2, 10, 2, 15, 2, 15, 6, 4, 1, 5, 9, 1, 1, 1];
+
+/// Length of a well-formed ISA segment, including its terminator
+pub const ISA_LENGTH: usize = 106;
+
+/// Separators used for parsing X12
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Separators {
+    /// Element separator (ISA04 position, default '*')
+    pub element: u8,
+    /// Repetition separator (ISA11 from version 00402, default '^'); `None`
+    /// when ISA11 holds the standards identifier `U` of earlier versions
+    pub repetition: Option<u8>,
+    /// Component separator (ISA16, default '>')
+    pub component: u8,
+    /// Segment terminator (the character after ISA16, default '~')
+    pub segment: u8,
+}
+
+impl Default for Separators {
+    fn default() -> Self {
+        Self {
+            element: DEFAULT_ELEMENT_SEPARATOR,
+            repetition: Some(DEFAULT_REPETITION_SEPARATOR),
+            component: DEFAULT_COMPONENT_SEPARATOR,
+            segment: DEFAULT_SEGMENT_TERMINATOR,
+        }
+    }
+}
+
+impl Separators {
+    /// Derive separators from the ISA segment at the start of `isa`.
+    ///
+    /// The element separator is the byte after `ISA`; the component
+    /// separator is the single byte of ISA16, found after the sixteenth
+    /// element separator, and the segment terminator follows it. Returns
+    /// `None` when `isa` does not start with a complete ISA segment.
+    pub fn from_isa(isa: &[u8]) -> Option<Self> {
+        if isa.len() < 4 || &isa[0..3] != b"ISA" {
+            return None;
+        }
+        let element = isa[3];
+        if element.is_ascii_alphanumeric() || element.is_ascii_whitespace() {
+            return None;
+        }
+
+        let mut separators_seen = 0;
+        let mut isa11 = None;
+        let mut isa11_start = 0;
+        for (index, &byte) in isa.iter().enumerate().skip(3) {
+            if byte != element {
+                continue;
+            }
+            separators_seen += 1;
+            match separators_seen {
+                11 => isa11_start = index + 1,
+                12 => isa11 = Some(&isa[isa11_start..index]),
+                ISA_ELEMENT_COUNT => {
+                    let component = *isa.get(index + 1)?;
+                    let segment = *isa.get(index + 2)?;
+                    let repetition = isa11
+                        .filter(|value| value.len() == 1 && !value[0].is_ascii_alphanumeric())
+                        .map(|value| value[0]);
+                    return Some(Self {
+                        element,
+                        repetition,
+                        component,
+                        segment,
+                    });
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// Whether `byte` is one of the separators
+    pub fn is_separator(&self, byte: u8) -> bool {
+        byte == self.element
+            || byte == self.component
+            || byte == self.segment
+            || self.repetition == Some(byte)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ISA_4010: &[u8] = b"ISA*00*          *00*          *ZZ*SENDER         \
+        *ZZ*RECEIVER       *260312*0930*U*00401*000000001*0*T*>~";
+
+    #[test]
+    fn test_separators_from_isa_4010() {
+        let separators = Separators::from_isa(ISA_4010).unwrap();
+        assert_eq!(separators.element, b'*');
+        assert_eq!(separators.component, b'>');
+        assert_eq!(separators.segment, b'~');
+        assert_eq!(separators.repetition, None);
+        assert_eq!(ISA_4010.len(), ISA_LENGTH);
+    }
+
+    #[test]
+    fn test_separators_from_isa_with_repetition() {
+        let isa = b"ISA|00|          |00|          |ZZ|SENDER         \
+            |ZZ|RECEIVER       |260312|0930|^|00501|000000001|0|P|:\n";
+        let separators = Separators::from_isa(isa).unwrap();
+        assert_eq!(separators.element, b'|');
+        assert_eq!(separators.component, b':');
+        assert_eq!(separators.segment, b'\n');
+        assert_eq!(separators.repetition, Some(b'^'));
+    }
+
+    #[test]
+    fn test_separators_from_truncated_isa() {
+        assert!(Separators::from_isa(&ISA_4010[..100]).is_none());
+        assert!(Separators::from_isa(b"GS*PO").is_none());
+    }
+}
@@ -0,0 +1,159 @@
+use std::fs;
+use std::path::PathBuf;
+
+use edi_adapter_x12::{
+    EnvelopeType, EnvelopeValidator, ErrorKind, FunctionalAckGenerator, InterchangeBuilder,
+    Separators, X12Parser, X12Serializer,
+};
+use edi_ir::{NodeType, PartyInfo};
+
+const VALID_FIXTURES: &[(&str, &str)] = &[
+    ("valid_850_4010.x12", "850"),
+    ("valid_856_4010.x12", "856"),
+    ("valid_810_5010.x12", "810"),
+];
+
+fn fixture(name: &str) -> Vec<u8> {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("../../testdata/x12")
+        .join(name);
+    fs::read(&path).unwrap_or_else(|error| panic!("read {}: {error}", path.display()))
+}
+
+#[test]
+fn valid_fixtures_parse_validate_and_round_trip() {
+    for (name, transaction_set) in VALID_FIXTURES {
+        let data = fixture(name);
+        let parser = X12Parser::new();
+
+        let documents = parser.parse(&data, *name).expect("fixture should parse");
+        assert!(!documents.is_empty(), "{name}");
+        for document in &documents {
+            assert_eq!(document.root.node_type, NodeType::Message, "{name}");
+            assert_eq!(
+                document.metadata.doc_type.as_deref(),
+                Some(*transaction_set),
+                "{name}"
+            );
+        }
+
+        let interchanges = parser
+            .parse_interchanges(&data)
+            .expect("fixture should parse");
+        assert_eq!(interchanges.len(), 1, "{name}");
+        let report = EnvelopeValidator::new().validate(&interchanges[0]);
+        assert!(report.is_valid(), "{name}: {:?}", report.errors);
+
+        let separators = Separators::from_isa(&data).expect("fixture should start with ISA");
+        let output = X12Serializer::new()
+            .with_separators(separators)
+            .serialize_document(&interchanges[0])
+            .expect("fixture should serialize");
+        assert_eq!(output.as_bytes(), data.as_slice(), "{name}");
+    }
+}
+
+#[test]
+fn control_number_errors_are_reported_and_acknowledged() {
+    let data = fixture("invalid_850_control_numbers.x12");
+    let interchange = X12Parser::new()
+        .parse_interchanges(&data)
+        .expect("fixture should parse")
+        .remove(0);
+
+    let report = EnvelopeValidator::new().validate(&interchange);
+    let errors: Vec<(EnvelopeType, ErrorKind, Option<&str>)> = report
+        .errors
+        .iter()
+        .map(|error| {
+            (
+                error.envelope_type,
+                error.error_kind,
+                error.control_number.as_deref(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        errors,
+        [
+            (
+                EnvelopeType::TransactionSet,
+                ErrorKind::IncorrectSegmentCount,
+                Some("0002")
+            ),
+            (
+                EnvelopeType::Interchange,
+                ErrorKind::MismatchedControlNumber,
+                Some("000000102")
+            ),
+        ]
+    );
+
+    let acks = FunctionalAckGenerator::new().generate(&interchange, &report);
+    assert_eq!(acks.len(), 1);
+    let sender = interchange.metadata.interchange.clone().expect("ISA info");
+    let ack_interchange = InterchangeBuilder::new(sender.recipient, sender.sender, 900)
+        .with_prepared_at(
+            chrono::NaiveDate::from_ymd_opt(2026, 3, 12)
+                .unwrap()
+                .and_hms_opt(10, 0, 0)
+                .unwrap(),
+        )
+        .build("FA", &acks)
+        .expect("997 interchange should build");
+    assert!(
+        EnvelopeValidator::new()
+            .validate(&ack_interchange)
+            .is_valid()
+    );
+
+    let output = X12Serializer::new()
+        .serialize_document(&ack_interchange)
+        .expect("997 should serialize");
+    assert_eq!(
+        output,
+        "ISA*00*          *00*          *ZZ*SELLERID       *ZZ*BUYERID        \
+         *260312*1000*U*00401*000000900*0*P*>~\n\
+         GS*FA*SELLERID*BUYERID*20260312*1000*900*X*004010~\n\
+         ST*997*0001~\n\
+         AK1*PO*102~\n\
+         AK2*850*0001~\n\
+         AK5*A~\n\
+         AK2*850*0002~\n\
+         AK5*R*4~\n\
+         AK9*P*2*2*1~\n\
+         SE*8*0001~\n\
+         GE*1*900~\n\
+         IEA*1*000000900~\n"
+    );
+}
+
+#[test]
+fn interchange_builder_output_parses_back() {
+    let data = fixture("valid_856_4010.x12");
+    let transaction_sets = X12Parser::new()
+        .parse(&data, "valid_856_4010.x12")
+        .expect("fixture should parse");
+    let party = |id: &str| PartyInfo {
+        id: id.to_string(),
+        qualifier: Some("ZZ".to_string()),
+    };
+    let interchange = InterchangeBuilder::new(party("SELLERID"), party("BUYERID"), 42)
+        .with_version("005010")
+        .build("SH", &transaction_sets)
+        .expect("interchange should build");
+
+    let output = X12Serializer::new()
+        .serialize_document(&interchange)
+        .expect("interchange should serialize");
+    assert!(output.contains("*^*00501*000000042*0*P*>~"), "{output}");
+    let reparsed = X12Parser::new()
+        .parse_interchanges(output.as_bytes())
+        .expect("output should parse")
+        .remove(0);
+    assert!(EnvelopeValidator::new().validate(&reparsed).is_valid());
+    assert_eq!(
+        reparsed.metadata.interchange_ref.as_deref(),
+        Some("000000042")
+    );
+}
@@ -6,19 +6,26 @@
 //! EDI transformations and managing configurations.
 
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::env;
 use std::fmt::Write as _;
 use std::fs::File;
 use std::io::{IsTerminal, Write};
 use std::path::{Path, PathBuf};
 use std::process::ExitCode;
+use std::sync::Arc;
 
 use anyhow::{Context, anyhow, bail};
 use clap::{Parser, Subcommand, ValueEnum};
 use edi_adapter_csv::{ColumnDef, CsvConfig, CsvSchema, CsvWriter};
+use edi_adapter_edifact::envelopes::{
+    ControlNumberGenerator, FileBasedControlNumberGenerator, MemoryControlNumberGenerator, PartyId,
+};
 use edi_adapter_edifact::parser::ParseWarning;
-use edi_adapter_edifact::{EdifactParser, EdifactSerializer};
+use edi_adapter_edifact::{
+    EdifactParser, EdifactSerializer, InterchangeMerger, InterchangePart, InterchangeSplitter,
+    InterchangeWriter, PartnerProfile, SplitKey,
+};
 use edi_ir::Document;
 use edi_ir::NodeType;
 use edi_ir::Value;
@@ -27,7 +34,7 @@ use edi_mapping::{
     lint_mapping_with_schema,
 };
 use edi_schema::{Schema, SchemaLoader};
-use edi_validation::{Severity, ValidationEngine, ValidationIssue};
+use edi_validation::{Severity, ValidationEngine, ValidationIssue, apply_schema_types};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -91,6 +98,23 @@ struct ProfileConfig {
     color: Option<ColorMode>,
     progress: Option<bool>,
     progress_threshold_bytes: Option<u64>,
+    envelope: Option<EnvelopeConfig>,
+}
+
+/// Interchange envelope applied to `generate` output for a partner profile
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct EnvelopeConfig {
+    sender: String,
+    sender_qualifier: Option<String>,
+    recipient: String,
+    recipient_qualifier: Option<String>,
+    syntax_identifier: Option<String>,
+    syntax_version: Option<String>,
+    message_type: Option<String>,
+    application_ref: Option<String>,
+    test: bool,
+    control_numbers: Option<PathBuf>,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -400,6 +424,47 @@ enum Commands {
         #[arg(long, value_enum)]
         input_format: Option<GenerateInputFormat>,
     },
+
+    /// Split an EDIFACT interchange into one interchange per message, message type, or party
+    Split {
+        /// Input EDIFACT file
+        input: String,
+
+        /// Directory for the split interchanges
+        #[arg(short, long)]
+        output_dir: String,
+
+        /// Grouping key: message, message-type, or a qualified segment such as NAD+DP
+        #[arg(long, default_value = "message")]
+        by: String,
+
+        /// Mapping that selects the grouping key (overrides --by)
+        #[arg(short, long, requires = "key_field")]
+        mapping: Option<String>,
+
+        /// Mapping output field holding the grouping key
+        #[arg(long, requires = "mapping")]
+        key_field: Option<String>,
+
+        /// Control number state file for new interchange references
+        #[arg(long)]
+        control_numbers: Option<PathBuf>,
+    },
+
+    /// Merge EDIFACT interchanges into one interchange per recipient
+    Merge {
+        /// Input EDIFACT files
+        #[arg(required = true)]
+        inputs: Vec<String>,
+
+        /// Directory for the merged interchanges
+        #[arg(short, long)]
+        output_dir: String,
+
+        /// Control number state file for new interchange references
+        #[arg(long)]
+        control_numbers: Option<PathBuf>,
+    },
 }
 
 #[derive(Subcommand)]
@@ -784,8 +849,48 @@ fn run() -> anyhow::Result<CliExitCode> {
                     profile.and_then(|p| p.mapping.as_ref()),
                     "mapping",
                 )?;
-                generate(&input, output.as_deref(), &mapping, input_format, runtime)
+                let envelope = profile.and_then(|p| p.envelope.as_ref());
+                generate(
+                    &input,
+                    output.as_deref(),
+                    &mapping,
+                    input_format,
+                    envelope,
+                    runtime,
+                )
             }
+            Commands::Split {
+                input,
+                output_dir,
+                by,
+                mapping,
+                key_field,
+                control_numbers,
+            } => {
+                let key = match (mapping, key_field) {
+                    (Some(mapping), Some(key_field)) => mapping_split_key(&mapping, key_field)?,
+                    _ => by
+                        .parse()
+                        .with_context(|| format!("Invalid --by value '{}'", by))?,
+                };
+                split(
+                    &input,
+                    &output_dir,
+                    key,
+                    control_numbers.as_deref(),
+                    base_runtime,
+                )
+            }
+            Commands::Merge {
+                inputs,
+                output_dir,
+                control_numbers,
+            } => merge(
+                &inputs,
+                &output_dir,
+                control_numbers.as_deref(),
+                base_runtime,
+            ),
         }
     })();
 
@@ -881,6 +986,9 @@ fn resolve_profile_paths(base_dir: &Path, profile: &mut ProfileConfig) {
     absolutize_profile_path(base_dir, &mut profile.schema);
     absolutize_profile_path(base_dir, &mut profile.mapping);
     absolutize_profile_path(base_dir, &mut profile.quarantine);
+    if let Some(envelope) = profile.envelope.as_mut() {
+        absolutize_profile_path(base_dir, &mut envelope.control_numbers);
+    }
 }
 
 fn absolutize_profile_path(base_dir: &Path, value: &mut Option<PathBuf>) {
@@ -1465,6 +1573,7 @@ fn recipes_run(
             output,
             required_recipe_arg(name, "mapping", mapping)?,
             Some(GenerateInputFormat::Csv),
+            None,
             runtime,
         ),
         RecipeName::BatchValidateDirectory => batch_validate_directory(
@@ -2317,23 +2426,12 @@ fn transform(
         "Starting transform command"
     );
 
-    if let Some(schema) = schema_path {
-        tracing::warn!(schema = %schema, "Transform schema argument is not implemented in this MVP and will be ignored");
-        print_warning(
-            runtime.color,
-            &format!(
-                "Transform schema argument is not implemented in this MVP; ignoring '{}'.",
-                schema
-            ),
-        );
-    }
-
     emit_progress(runtime, input_path, "reading EDIFACT input");
     let input_bytes = std::fs::read(input_path)
         .with_context(|| format!("Failed to read input file '{}'", input_path))?;
 
     let parser = EdifactParser::new();
-    let parsed = parser
+    let mut parsed = parser
         .parse_with_warnings(&input_bytes, input_path)
         .with_context(|| format!("Failed to parse EDIFACT input '{}'", input_path))?;
 
@@ -2341,6 +2439,10 @@ fn transform(
         bail!("No EDIFACT messages were found in '{}'", input_path);
     }
 
+    if let Some(schema_path) = schema_path {
+        apply_transform_schema_types(&mut parsed.documents, schema_path, input_path, runtime)?;
+    }
+
     let mapping = MappingDsl::parse_file(Path::new(mapping_path))
         .with_context(|| format!("Failed to parse mapping '{}'", mapping_path))?;
     let output_format = TransformOutputFormat::from_target_type(&mapping.target_type)
@@ -2551,6 +2653,7 @@ fn generate(
     output_path: Option<&str>,
     mapping_path: &str,
     input_format: Option<GenerateInputFormat>,
+    envelope: Option<&EnvelopeConfig>,
     runtime: RuntimeOptions,
 ) -> anyhow::Result<CliExitCode> {
     tracing::info!(
@@ -2607,9 +2710,23 @@ fn generate(
             )
         })?;
 
+    let output_document = match envelope {
+        Some(envelope) => {
+            emit_progress(
+                runtime,
+                input_path,
+                "wrapping output in interchange envelope",
+            );
+            interchange_writer(envelope)?
+                .build(std::slice::from_ref(&mapped_document))
+                .context("Failed to build interchange envelope")?
+        }
+        None => mapped_document,
+    };
+
     emit_progress(runtime, input_path, "writing generated EDI output");
     write_transformed_output(
-        std::slice::from_ref(&mapped_document),
+        std::slice::from_ref(&output_document),
         TransformOutputFormat::Edi,
         output_path,
     )?;
@@ -2624,6 +2741,203 @@ fn generate(
     Ok(CliExitCode::Success)
 }
 
+fn interchange_writer(envelope: &EnvelopeConfig) -> anyhow::Result<InterchangeWriter> {
+    if envelope.sender.is_empty() || envelope.recipient.is_empty() {
+        bail!("Profile envelope requires both 'sender' and 'recipient'");
+    }
+
+    let party = |id: &str, qualifier: &Option<String>| PartyId {
+        id: id.to_string(),
+        qualifier: qualifier.clone(),
+        ..PartyId::default()
+    };
+    let mut profile = PartnerProfile::new(
+        party(&envelope.sender, &envelope.sender_qualifier),
+        party(&envelope.recipient, &envelope.recipient_qualifier),
+    )
+    .with_test_indicator(envelope.test);
+    if envelope.syntax_identifier.is_some() || envelope.syntax_version.is_some() {
+        profile = profile.with_syntax(
+            envelope.syntax_identifier.as_deref().unwrap_or("UNOC"),
+            envelope.syntax_version.as_deref().unwrap_or("3"),
+        );
+    }
+    if let Some(message_type) = &envelope.message_type {
+        let message_type = message_type
+            .parse()
+            .with_context(|| "Invalid profile envelope 'message_type'")?;
+        profile = profile.with_message_type(message_type);
+    }
+    if let Some(application_ref) = &envelope.application_ref {
+        profile = profile.with_application_ref(application_ref);
+    }
+
+    let control_numbers = control_number_generator(envelope.control_numbers.as_deref())?;
+
+    Ok(InterchangeWriter::new(profile, control_numbers))
+}
+
+fn split(
+    input_path: &str,
+    output_dir: &str,
+    key: SplitKey,
+    control_numbers: Option<&Path>,
+    runtime: RuntimeOptions,
+) -> anyhow::Result<CliExitCode> {
+    tracing::info!(input = %input_path, output_dir = %output_dir, key = ?key, "Starting split command");
+
+    emit_progress(runtime, input_path, "reading EDIFACT input");
+    let interchanges = read_interchanges(input_path)?;
+    let splitter = InterchangeSplitter::new(key, control_number_generator(control_numbers)?);
+    let mut parts = Vec::new();
+    for interchange in &interchanges {
+        parts.extend(
+            splitter
+                .split(interchange)
+                .with_context(|| format!("Failed to split interchange in '{}'", input_path))?,
+        );
+    }
+
+    emit_progress(runtime, input_path, "writing split interchanges");
+    let stem = Path::new(input_path)
+        .file_stem()
+        .map_or_else(|| "interchange".into(), |stem| stem.to_string_lossy());
+    let written = write_interchange_parts(&parts, output_dir, |index, part| {
+        format!(
+            "{}_{:03}_{}.edi",
+            sanitize_quarantine_id(&stem),
+            index + 1,
+            sanitize_quarantine_id(&part.key)
+        )
+    })?;
+
+    println!("Split '{}' into {} interchange(s)", input_path, written);
+    Ok(CliExitCode::Success)
+}
+
+fn merge(
+    input_paths: &[String],
+    output_dir: &str,
+    control_numbers: Option<&Path>,
+    runtime: RuntimeOptions,
+) -> anyhow::Result<CliExitCode> {
+    tracing::info!(inputs = input_paths.len(), output_dir = %output_dir, "Starting merge command");
+
+    let mut interchanges = Vec::new();
+    for input_path in input_paths {
+        emit_progress(runtime, input_path, "reading EDIFACT input");
+        interchanges.extend(read_interchanges(input_path)?);
+    }
+    let parts = InterchangeMerger::new(control_number_generator(control_numbers)?)
+        .merge(&interchanges)
+        .context("Failed to merge interchanges")?;
+
+    let written = write_interchange_parts(&parts, output_dir, |_, part| {
+        format!("{}.edi", sanitize_quarantine_id(&part.key))
+    })?;
+
+    println!(
+        "Merged {} file(s) into {} interchange(s)",
+        input_paths.len(),
+        written
+    );
+    Ok(CliExitCode::Success)
+}
+
+/// Build a split key that runs `mapping_path` over each message and reads `key_field` from the output
+fn mapping_split_key(mapping_path: &str, key_field: String) -> anyhow::Result<SplitKey> {
+    let mapping = MappingDsl::parse_file(Path::new(mapping_path))
+        .with_context(|| format!("Failed to parse mapping '{}'", mapping_path))?;
+    Ok(SplitKey::Custom(Arc::new(move |message: &Document| {
+        let mapped = MappingRuntime::new().execute(&mapping, message)?;
+        Ok(find_node(&mapped.root, &key_field).and_then(node_value))
+    })))
+}
+
+fn find_node<'a>(node: &'a edi_ir::Node, name: &str) -> Option<&'a edi_ir::Node> {
+    if node.name == name {
+        return Some(node);
+    }
+    node.children
+        .iter()
+        .find_map(|child| find_node(child, name))
+}
+
+fn read_interchanges(input_path: &str) -> anyhow::Result<Vec<Document>> {
+    let input_bytes = std::fs::read(input_path)
+        .with_context(|| format!("Failed to read input file '{}'", input_path))?;
+    let interchanges = EdifactParser::new()
+        .parse_interchanges(&input_bytes, input_path)
+        .with_context(|| format!("Failed to parse EDIFACT input '{}'", input_path))?;
+    if interchanges.is_empty() {
+        bail!("No EDIFACT interchanges were found in '{}'", input_path);
+    }
+    Ok(interchanges)
+}
+
+fn control_number_generator(
+    path: Option<&Path>,
+) -> anyhow::Result<Arc<dyn ControlNumberGenerator>> {
+    Ok(match path {
+        Some(path) => Arc::new(
+            FileBasedControlNumberGenerator::new(path.to_string_lossy()).with_context(|| {
+                format!("Failed to load control numbers from '{}'", path.display())
+            })?,
+        ),
+        None => Arc::new(MemoryControlNumberGenerator::new()),
+    })
+}
+
+/// Write each part to `output_dir` under `file_name`; names that collide
+/// after sanitizing get a numeric suffix, and existing files are never
+/// overwritten
+fn write_interchange_parts(
+    parts: &[InterchangePart],
+    output_dir: &str,
+    file_name: impl Fn(usize, &InterchangePart) -> String,
+) -> anyhow::Result<usize> {
+    std::fs::create_dir_all(output_dir)
+        .with_context(|| format!("Failed to create output directory '{}'", output_dir))?;
+    let serializer = EdifactSerializer::new();
+    let mut used = HashSet::new();
+    for (index, part) in parts.iter().enumerate() {
+        let path = Path::new(output_dir).join(unique_file_name(file_name(index, part), &mut used));
+        let bytes = serializer
+            .serialize_document_bytes(&part.interchange)
+            .with_context(|| format!("Failed to serialize interchange for '{}'", part.key))?;
+        let mut file = match std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+        {
+            Err(error) if error.kind() == std::io::ErrorKind::AlreadyExists => {
+                bail!("Output file '{}' already exists", path.display())
+            }
+            result => result.with_context(|| format!("Failed to create '{}'", path.display()))?,
+        };
+        file.write_all(&bytes)
+            .with_context(|| format!("Failed to write '{}'", path.display()))?;
+        tracing::info!(path = %path.display(), key = %part.key, "Wrote interchange");
+    }
+    Ok(parts.len())
+}
+
+/// `name`, or `name` with a `_2`, `_3`, ... suffix before its extension
+/// when an earlier part already took it
+fn unique_file_name(name: String, used: &mut HashSet<String>) -> String {
+    if used.insert(name.clone()) {
+        return name;
+    }
+    let (stem, extension) = name.rsplit_once('.').unwrap_or((name.as_str(), ""));
+    (2..)
+        .map(|n| match extension {
+            "" => format!("{stem}_{n}"),
+            extension => format!("{stem}_{n}.{extension}"),
+        })
+        .find(|candidate| used.insert(candidate.clone()))
+        .expect("an unused suffix exists")
+}
+
 fn resolve_generate_input_format(
     input_path: &str,
     input_format: Option<GenerateInputFormat>,
@@ -2757,6 +3071,40 @@ fn json_array_item_to_node(item_name: &str, value: &serde_json::Value) -> edi_ir
     }
 }
 
+/// Convert the parsed values to the types `schema_path` defines, so mapping
+/// rules see numbers and dates; values that do not convert stay strings and
+/// are reported as warnings
+fn apply_transform_schema_types(
+    documents: &mut [Document],
+    schema_path: &str,
+    input_path: &str,
+    runtime: RuntimeOptions,
+) -> anyhow::Result<()> {
+    let schema = SchemaLoader::new(Vec::new())
+        .load_from_file(Path::new(schema_path))
+        .with_context(|| format!("Failed to load schema '{}'", schema_path))?;
+
+    for (index, document) in documents.iter_mut().enumerate() {
+        let result = apply_schema_types(document, &schema);
+        for issue in result.report.all_issues() {
+            let location = issue
+                .line
+                .map(|line| format!("{input_path}:{line}:{}", issue.column.unwrap_or(1)))
+                .unwrap_or_else(|| input_path.to_string());
+            print_warning(
+                runtime.color,
+                &format!(
+                    "{location}: message {}: {} ({})",
+                    index + 1,
+                    issue.message,
+                    issue.path
+                ),
+            );
+        }
+    }
+    Ok(())
+}
+
 fn json_scalar_to_ir_value(value: &serde_json::Value) -> Value {
     match value {
         serde_json::Value::Null => Value::Null,
@@ -2770,7 +3118,7 @@ fn json_scalar_to_ir_value(value: &serde_json::Value) -> Value {
                 } else {
                     Value::String(number.to_string())
                 }
-            } else if let Some(decimal) = number.as_f64() {
+            } else if let Some(decimal) = edi_ir::parse_decimal(&number.to_string()) {
                 Value::Decimal(decimal)
             } else {
                 Value::String(number.to_string())
@@ -2851,16 +3199,18 @@ fn serialize_documents_as_edi<W: Write>(
     let serializer = EdifactSerializer::new();
 
     for (index, document) in mapped_documents.iter().enumerate() {
-        let payload = serializer.serialize_document(document).map_err(|error| {
-            anyhow!(
-                "message {} does not match EDIFACT output shape requirements: {}",
-                index + 1,
-                error
-            )
-        })?;
+        let payload = serializer
+            .serialize_document_bytes(document)
+            .map_err(|error| {
+                anyhow!(
+                    "message {} does not match EDIFACT output shape requirements: {}",
+                    index + 1,
+                    error
+                )
+            })?;
 
         writer
-            .write_all(payload.as_bytes())
+            .write_all(&payload)
             .with_context(|| format!("failed to write EDI payload for message {}", index + 1))?;
 
         if index + 1 < mapped_documents.len() {
@@ -3085,6 +3435,8 @@ struct ValidationReportIssue {
     component_index: Option<usize>,
     line: Option<usize>,
     column: Option<usize>,
+    offset: Option<usize>,
+    length: Option<usize>,
     snippet: Option<String>,
     context: Option<String>,
 }
@@ -3114,6 +3466,8 @@ impl ValidationReportIssue {
             component_index: issue.component_pos,
             line: issue.line,
             column: issue.column,
+            offset: issue.span.map(|span| span.offset),
+            length: issue.span.map(|span| span.length),
             snippet: issue.segment_pos.and_then(|pos| snippets.segment(pos)),
             context: issue.context.clone(),
         }
@@ -3133,6 +3487,8 @@ impl ValidationReportIssue {
             component_index: None,
             line: Some(warning.position.line),
             column: Some(warning.position.column),
+            offset: Some(warning.position.offset),
+            length: None,
             snippet: None,
             context: None,
         }
@@ -3304,6 +3660,10 @@ fn validation_report_to_sarif(report: &ValidationCliReport) -> serde_json::Value
                     "startLine": line,
                     "startColumn": issue.column.unwrap_or(1)
                 });
+                if let (Some(offset), Some(length)) = (issue.offset, issue.length) {
+                    location["physicalLocation"]["region"]["byteOffset"] = offset.into();
+                    location["physicalLocation"]["region"]["byteLength"] = length.into();
+                }
             }
             serde_json::json!({
                 "ruleId": issue.rule_id,
@@ -271,3 +271,84 @@ fn generate_help_includes_mapping_and_input_format_flags() {
     assert!(stdout.contains("--mapping <MAPPING>"));
     assert!(stdout.contains("--input-format <INPUT_FORMAT>"));
 }
+
+#[test]
+fn generate_wraps_output_in_profile_envelope_with_persisted_control_numbers() {
+    let csv_input = write_temp_file(
+        "generate-envelope-orders",
+        "csv",
+        "DOCUMENT_NUMBER,DOCUMENT_TYPE\nORDER123,220\n",
+    );
+    let mapping = write_temp_file(
+        "generate-envelope-mapping",
+        "yaml",
+        r#"
+name: generate_orders_from_csv
+source_type: CSV_ORDERS
+target_type: EANCOM_D96A_ORDERS
+rules:
+  - type: field
+    source: /rows/row/DOCUMENT_TYPE
+    target: BGM.e1
+  - type: field
+    source: /rows/row/DOCUMENT_NUMBER
+    target: BGM.e2
+"#,
+    );
+    let control_numbers = unique_temp_path("generate-envelope-control-numbers", "json");
+    let config = write_temp_file(
+        "generate-envelope-config",
+        "yaml",
+        &format!(
+            r#"profiles:
+  acme:
+    envelope:
+      sender: "5412345000013"
+      sender_qualifier: "14"
+      recipient: "4012345000016"
+      recipient_qualifier: "14"
+      message_type: ORDERS:D:96A:UN:EAN008
+      test: true
+      control_numbers: {}
+"#,
+            control_numbers.display()
+        ),
+    );
+    let output_path = unique_temp_path("generate-envelope-output", "edi");
+
+    for expected_ref in ["00000000000001", "00000000000002"] {
+        let output = run_generate(&[
+            "--config",
+            config.to_string_lossy().as_ref(),
+            "--profile",
+            "acme",
+            "generate",
+            csv_input.to_string_lossy().as_ref(),
+            output_path.to_string_lossy().as_ref(),
+            "-m",
+            mapping.to_string_lossy().as_ref(),
+        ]);
+        assert_exit_code(&output, 0);
+
+        let edi = fs::read_to_string(&output_path).expect("generated EDI should be readable");
+        let lines: Vec<&str> = edi.lines().collect();
+        assert!(
+            lines[0].starts_with("UNB+UNOC:3+5412345000013:14+4012345000016:14+"),
+            "{edi}"
+        );
+        assert!(
+            lines[0].ends_with(&format!("+{expected_ref}+++++1'")),
+            "{edi}"
+        );
+        assert_eq!(lines[1], "UNH+000001+ORDERS:D:96A:UN:EAN008'");
+        assert_eq!(lines[2], "BGM+220+ORDER123'");
+        assert_eq!(lines[3], "UNT+3+000001'");
+        assert_eq!(lines[4], format!("UNZ+1+{expected_ref}'"));
+    }
+
+    remove_if_exists(&csv_input);
+    remove_if_exists(&mapping);
+    remove_if_exists(&config);
+    remove_if_exists(&control_numbers);
+    remove_if_exists(&output_path);
+}
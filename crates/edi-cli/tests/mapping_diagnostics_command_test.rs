@@ -86,6 +86,45 @@ rules:
     let _ = fs::remove_file(mapping_path);
 }
 
+#[test]
+fn mapping_lint_accepts_data_element_id_and_composite_selector_keys() {
+    let binary = cargo_bin();
+    let mapping_path = unique_temp_path("mapping-lint-element-ids", "yaml");
+    fs::write(
+        &mapping_path,
+        r#"
+name: element_id_selectors
+source_type: EANCOM_ORDERS
+target_type: JSON_ORDERS
+rules:
+  - type: field
+    source: /DTM/C507[2380=20260115]/2005
+    target: date_qualifier
+  - type: field
+    source: /DTM[C507/2379=102]/e1/c2
+    target: order_date
+  - type: field
+    source: /NAD[3035=BY]/e2
+    target: buyer
+"#,
+    )
+    .expect("write temp mapping");
+
+    let output = Command::new(binary)
+        .args(["mapping", "lint", mapping_path.to_string_lossy().as_ref()])
+        .output()
+        .expect("run edi mapping lint");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(0), "stdout: {stdout}");
+    assert!(
+        !stdout.contains("unsupported selector key"),
+        "stdout: {stdout}"
+    );
+
+    let _ = fs::remove_file(mapping_path);
+}
+
 #[test]
 fn mapping_lint_with_schema_suggests_closest_segment_for_typo() {
     let binary = cargo_bin();
@@ -128,6 +167,51 @@ rules:
     let _ = fs::remove_file(mapping_path);
 }
 
+#[test]
+fn mapping_lint_with_schema_checks_segments_after_parent_steps() {
+    let binary = cargo_bin();
+    let mapping_path = unique_temp_path("mapping-lint-parent-step", "yaml");
+    let schema = testdata_path("testdata/schemas/eancom_orders_d96a.yaml");
+    fs::write(
+        &mapping_path,
+        r#"
+name: parent_step_typo
+source_type: EANCOM_ORDERS
+target_type: JSON_ORDERS
+rules:
+  - type: foreach
+    source: //LIN
+    target: lines
+    rules:
+      - type: field
+        source: ../BGMN/e2
+        target: order_number
+"#,
+    )
+    .expect("write temp mapping");
+
+    let output = Command::new(binary)
+        .args([
+            "mapping",
+            "lint",
+            mapping_path.to_string_lossy().as_ref(),
+            "--schema",
+            schema.to_string_lossy().as_ref(),
+        ])
+        .output()
+        .expect("run edi mapping lint with schema");
+
+    let code = output.status.code().unwrap_or(-1);
+    assert_eq!(code, 1, "lint with warnings should exit 1");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("unknown segment 'BGMN' in path '../BGMN/e2'"),
+        "stdout: {stdout}"
+    );
+
+    let _ = fs::remove_file(mapping_path);
+}
+
 #[test]
 fn mapping_lint_with_schema_accepts_runtime_segment_groups() {
     let binary = cargo_bin();
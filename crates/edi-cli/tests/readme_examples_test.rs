@@ -140,8 +140,11 @@ fn transform_target_outputs_table_driven() {
     }
 
     enum Expectation {
+        /// Output is written; non-empty `warnings` are expected on stderr
+        /// together with the warnings exit code
         Success {
             output_substrings: Vec<&'static str>,
+            warnings: Vec<&'static str>,
         },
         Failure {
             exit_code: i32,
@@ -168,6 +171,10 @@ fn transform_target_outputs_table_driven() {
                     "document_type,line_number,product_code",
                     "No description supplied",
                 ],
+                // The fixture declares UNOA but has mixed-case descriptions
+                warnings: vec![
+                    "Data in 'IMD/e4/c4' contains characters outside the UNOA repertoire",
+                ],
             },
         },
         Case {
@@ -193,6 +200,7 @@ rules:
             output_extension: "edi",
             expectation: Expectation::Success {
                 output_substrings: vec!["UNH+1'", "BGM+220+ORDER123'"],
+                warnings: vec![],
             },
         },
         Case {
@@ -239,14 +247,30 @@ rules:
             .expect("run edi transform");
 
         match &case.expectation {
-            Expectation::Success { output_substrings } => {
-                assert!(
-                    command_output.status.success(),
-                    "expected transform case '{}' to succeed; stdout: {}; stderr: {}",
-                    case.name,
-                    String::from_utf8_lossy(&command_output.stdout),
-                    String::from_utf8_lossy(&command_output.stderr)
+            Expectation::Success {
+                output_substrings,
+                warnings,
+            } => {
+                let stderr = String::from_utf8_lossy(&command_output.stderr);
+                assert_exit_code(
+                    command_output.status,
+                    if warnings.is_empty() { 0 } else { 1 },
+                    &format!(
+                        "expected transform case '{}' to succeed; stdout: {}; stderr: {}",
+                        case.name,
+                        String::from_utf8_lossy(&command_output.stdout),
+                        stderr
+                    ),
                 );
+                for warning in warnings {
+                    assert!(
+                        stderr.contains(warning),
+                        "expected stderr for case '{}' to contain '{}', got: {}",
+                        case.name,
+                        warning,
+                        stderr
+                    );
+                }
 
                 let rendered = fs::read_to_string(&output)
                     .expect("transform output should be readable for success case");
@@ -0,0 +1,241 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+static TEMP_FILE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+fn cargo_bin() -> PathBuf {
+    if let Ok(path) = env::var("CARGO_BIN_EXE_edi") {
+        return PathBuf::from(path);
+    }
+
+    let target_dir = env::var("CARGO_TARGET_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| repo_root().join("target"));
+    let executable_name = format!("edi{}", std::env::consts::EXE_SUFFIX);
+    let fallback = target_dir.join("debug").join(executable_name);
+
+    if fallback.exists() {
+        return fallback;
+    }
+
+    panic!(
+        "CARGO_BIN_EXE_edi is not set and fallback binary was not found at {}",
+        fallback.display()
+    );
+}
+
+fn repo_root() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("..")
+        .join("..")
+}
+
+fn unique_temp_path(name: &str) -> PathBuf {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("system time after epoch")
+        .as_nanos();
+    let counter = TEMP_FILE_COUNTER.fetch_add(1, Ordering::SeqCst);
+    env::temp_dir().join(format!(
+        "edi-cli-{name}-{}-{nanos}-{counter}",
+        std::process::id()
+    ))
+}
+
+fn run_edi(args: &[&str]) -> Output {
+    Command::new(cargo_bin())
+        .args(args)
+        .output()
+        .expect("run edi")
+}
+
+fn assert_success(output: &Output) {
+    assert!(
+        output.status.success(),
+        "expected success; stdout: {}; stderr: {}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+fn read_dir_sorted(dir: &Path) -> Vec<(String, String)> {
+    let mut files = fs::read_dir(dir)
+        .expect("output directory should exist")
+        .map(|entry| {
+            let path = entry.expect("directory entry").path();
+            let name = path
+                .file_name()
+                .expect("file name")
+                .to_string_lossy()
+                .into_owned();
+            let content = fs::read_to_string(&path).expect("output file should be readable");
+            (name, content)
+        })
+        .collect::<Vec<_>>();
+    files.sort();
+    files
+}
+
+#[test]
+fn split_by_message_type_and_merge_back_per_recipient() {
+    let input = repo_root().join("testdata/edi/valid_invoic_desadv_d96a_functional_groups.edi");
+    let split_dir = unique_temp_path("split");
+    let merge_dir = unique_temp_path("merge");
+
+    let output = run_edi(&[
+        "split",
+        input.to_string_lossy().as_ref(),
+        "--output-dir",
+        split_dir.to_string_lossy().as_ref(),
+        "--by",
+        "message-type",
+    ]);
+    assert_success(&output);
+
+    let files = read_dir_sorted(&split_dir);
+    let names = files
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        [
+            "valid_invoic_desadv_d96a_functional_groups_001_INVOIC.edi",
+            "valid_invoic_desadv_d96a_functional_groups_002_DESADV.edi",
+        ]
+    );
+    let invoic = &files[0].1;
+    assert!(invoic.starts_with("UNB+UNOA:3+1234567890123:14+9876543210987:14+"));
+    assert!(invoic.contains("+00000000000001'"));
+    assert_eq!(invoic.matches("UNH+").count(), 2);
+    assert!(!invoic.contains("UNG+"));
+    assert!(invoic.trim_end().ends_with("UNZ+2+00000000000001'"));
+    assert!(files[1].1.contains("UNH+000001+DESADV:D:96A:UN'"));
+
+    let split_paths = files
+        .iter()
+        .map(|(name, _)| split_dir.join(name).to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    let mut args = vec!["merge"];
+    args.extend(split_paths.iter().map(String::as_str));
+    let merge_dir_arg = merge_dir.to_string_lossy().into_owned();
+    args.extend(["--output-dir", merge_dir_arg.as_str()]);
+    let output = run_edi(&args);
+    assert_success(&output);
+
+    let merged = read_dir_sorted(&merge_dir);
+    assert_eq!(merged.len(), 1);
+    assert_eq!(merged[0].0, "9876543210987-14.edi");
+    assert_eq!(merged[0].1.matches("UNH+").count(), 3);
+    assert!(merged[0].1.contains("UNT+6+000003'"));
+    assert!(merged[0].1.trim_end().ends_with("UNZ+3+00000000000001'"));
+
+    let _ = fs::remove_dir_all(&split_dir);
+    let _ = fs::remove_dir_all(&merge_dir);
+}
+
+#[test]
+fn split_by_mapping_selected_key() {
+    let input = repo_root().join("testdata/edi/valid_invoic_desadv_d96a_functional_groups.edi");
+    let mapping = unique_temp_path("split-mapping").with_extension("yaml");
+    fs::write(
+        &mapping,
+        r#"
+name: split_by_document_number
+source_type: EDIFACT
+target_type: JSON
+rules:
+  - type: field
+    source: /BGM/e2
+    target: document_number
+"#,
+    )
+    .expect("mapping should be writable");
+    let split_dir = unique_temp_path("split-mapping-out");
+
+    let output = run_edi(&[
+        "split",
+        input.to_string_lossy().as_ref(),
+        "--output-dir",
+        split_dir.to_string_lossy().as_ref(),
+        "--mapping",
+        mapping.to_string_lossy().as_ref(),
+        "--key-field",
+        "document_number",
+    ]);
+    assert_success(&output);
+
+    let names = read_dir_sorted(&split_dir)
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        [
+            "valid_invoic_desadv_d96a_functional_groups_001_INV001.edi",
+            "valid_invoic_desadv_d96a_functional_groups_002_INV002.edi",
+            "valid_invoic_desadv_d96a_functional_groups_003_DESADV001.edi",
+        ]
+    );
+
+    let _ = fs::remove_file(&mapping);
+    let _ = fs::remove_dir_all(&split_dir);
+}
+
+#[test]
+fn split_rejects_messages_without_the_key() {
+    let input = repo_root().join("testdata/edi/valid_invoic_desadv_d96a_functional_groups.edi");
+    let split_dir = unique_temp_path("split-missing-key");
+
+    let output = run_edi(&[
+        "split",
+        input.to_string_lossy().as_ref(),
+        "--output-dir",
+        split_dir.to_string_lossy().as_ref(),
+        "--by",
+        "NAD+DP",
+    ]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("no value for split key"));
+
+    let _ = fs::remove_dir_all(&split_dir);
+}
+
+#[test]
+fn merge_keeps_recipients_whose_file_names_collide_and_never_overwrites() {
+    let input = unique_temp_path("merge-collide").with_extension("edi");
+    fs::write(
+        &input,
+        "UNB+UNOC:3+S+R.1:14+260312:0930+1'UNH+1+ORDRSP:D:96A:UN'UNT+2+1'UNZ+1+1'\
+         UNB+UNOC:3+S+R-1:14+260312:0930+2'UNH+1+ORDRSP:D:96A:UN'UNT+2+1'UNZ+1+2'\
+         UNB+UNOC:3+S+R:1:14+260312:0930+3'UNH+1+ORDRSP:D:96A:UN'UNT+2+1'UNZ+1+3'",
+    )
+    .expect("input should be writable");
+    let merge_dir = unique_temp_path("merge-collide-out");
+    let input_arg = input.to_string_lossy().into_owned();
+    let merge_dir_arg = merge_dir.to_string_lossy().into_owned();
+    let args = [
+        "merge",
+        input_arg.as_str(),
+        "--output-dir",
+        merge_dir_arg.as_str(),
+    ];
+
+    assert_success(&run_edi(&args));
+    let names = read_dir_sorted(&merge_dir)
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["R-1-14.edi", "R-1-14_2.edi", "R-1-14_3.edi"]);
+
+    let output = run_edi(&args);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("already exists"));
+
+    let _ = fs::remove_file(&input);
+    let _ = fs::remove_dir_all(&merge_dir);
+}
@@ -192,3 +192,92 @@ rules:
         let _ = fs::remove_file(&output_path);
     }
 }
+
+const INVOIC_TYPES_SCHEMA: &str = r#"
+name: INVOIC_TYPES
+version: D96A
+segments:
+  - tag: DTM
+    elements:
+      - id: C507
+        name: Date/time/period
+        data_type: c
+        components:
+          - id: '2005'
+            name: Date/time/period qualifier
+            data_type: an
+          - id: '2380'
+            name: Date/time/period
+            data_type: an
+          - id: '2379'
+            name: Date/time/period format qualifier
+            data_type: an
+  - tag: QTY
+    elements:
+      - id: C186
+        name: Quantity details
+        data_type: c
+        components:
+          - id: '6063'
+            name: Quantity qualifier
+            data_type: an
+          - id: '6060'
+            name: Quantity
+            data_type: n
+"#;
+
+#[test]
+fn transform_with_schema_maps_typed_dates_and_numbers() {
+    let binary = cargo_bin();
+    let input = testdata_path("testdata/edi/valid_invoic_d96a_full.edi");
+    let mapping = testdata_path("testdata/mappings/invoic_to_json.yaml");
+    let schema_path = unique_temp_path("invoic-types-schema", "yaml");
+    fs::write(&schema_path, INVOIC_TYPES_SCHEMA).expect("write schema");
+
+    let output = Command::new(&binary)
+        .args([
+            "transform",
+            input.to_string_lossy().as_ref(),
+            "-m",
+            mapping.to_string_lossy().as_ref(),
+            "-s",
+            schema_path.to_string_lossy().as_ref(),
+        ])
+        .output()
+        .expect("run edi transform");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "stderr: {stderr}");
+    assert!(!stderr.contains("WARNING"), "stderr: {stderr}");
+    let payload = String::from_utf8(output.stdout).expect("stdout should be valid UTF-8");
+    assert!(payload.contains(r#""Date": "2020-01-01""#), "{payload}");
+    assert!(payload.contains(r#""Decimal": "10""#), "{payload}");
+
+    let bad_input = unique_temp_path("invoic-bad-date", "edi");
+    let data = fs::read_to_string(&input).expect("read input");
+    fs::write(
+        &bad_input,
+        data.replace("DTM+137:20200101", "DTM+137:20201301"),
+    )
+    .expect("write input");
+    let output = Command::new(&binary)
+        .args([
+            "transform",
+            bad_input.to_string_lossy().as_ref(),
+            "-m",
+            mapping.to_string_lossy().as_ref(),
+            "-s",
+            schema_path.to_string_lossy().as_ref(),
+        ])
+        .output()
+        .expect("run edi transform");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "stderr: {stderr}");
+    assert!(stderr.contains(":4:"), "stderr: {stderr}");
+    assert!(stderr.contains("'20201301'"), "stderr: {stderr}");
+    assert!(stderr.contains("DTM[3]/C507/2380"), "stderr: {stderr}");
+
+    let _ = fs::remove_file(&schema_path);
+    let _ = fs::remove_file(&bad_input);
+}
@@ -266,6 +266,30 @@ fn validate_report_output_file_keeps_stdout_concise() {
     );
 }
 
+#[test]
+fn validate_sarif_report_includes_byte_region_of_offending_segment() {
+    let input = testdata_path("testdata/edi/invalid_invoic_d96a_missing_element.edi");
+    let schema = testdata_path("testdata/schemas/eancom_invoic_d96a.yaml");
+    let output = run_validate_args(&input, &schema, &["--report", "sarif"]);
+
+    assert_exit_code(&output, 2);
+
+    let sarif: serde_json::Value =
+        serde_json::from_slice(&output.stdout).expect("sarif should be JSON");
+    let result = sarif["runs"][0]["results"]
+        .as_array()
+        .expect("results should be an array")
+        .iter()
+        .find(|result| result["ruleId"] == "MISSING_MANDATORY_ELEMENT")
+        .expect("missing element result");
+    let region = &result["locations"][0]["physicalLocation"]["region"];
+    let offset = region["byteOffset"].as_u64().expect("byteOffset") as usize;
+    let length = region["byteLength"].as_u64().expect("byteLength") as usize;
+
+    let data = fs::read(&input).expect("fixture should load");
+    assert!(data[offset..offset + length].starts_with(b"BGM"));
+}
+
 #[test]
 fn validate_html_report_labels_message_index_column() {
     let input = testdata_path("testdata/edi/invalid_orders_missing_bgm.edi");
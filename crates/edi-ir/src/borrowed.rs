@@ -0,0 +1,256 @@
+//! Borrowed IR trees referencing their input
+//!
+//! A [`BorrowedNode`] holds its name and value as [`Cow`]s: parsers borrow
+//! segment tags and element values straight from the input buffer and only
+//! allocate for values that had to be unescaped. Positional names (`e1`,
+//! `c2`, `r3`, ...) come from a shared table of interned names, so a large
+//! message does not allocate one name per element. Nodes carry no attributes
+//! or schema types; convert to the owned [`Document`] with
+//! [`BorrowedDocument::to_document`] when those, paths or editing are needed.
+#![allow(clippy::must_use_candidate)] // Accessors and constructors follow the owned node API.
+
+use std::borrow::Cow;
+use std::sync::OnceLock;
+
+use crate::document::{Document, DocumentMetadata};
+use crate::metadata::SourceSpan;
+use crate::node::{Node, NodeType, Value};
+
+/// Positions with an interned name; later positions allocate their name
+const INTERNED_POSITIONS: usize = 99;
+
+/// A node of a borrowed IR tree
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BorrowedNode<'a> {
+    /// Node name (e.g., segment tag, element position)
+    pub name: Cow<'a, str>,
+
+    /// Node type
+    pub node_type: NodeType,
+
+    /// String value (if applicable)
+    pub value: Option<Cow<'a, str>>,
+
+    /// Child nodes
+    pub children: Vec<BorrowedNode<'a>>,
+
+    /// Location in the source data, for nodes produced by a parser
+    pub span: Option<SourceSpan>,
+}
+
+impl<'a> BorrowedNode<'a> {
+    /// Create a new node
+    pub fn new(name: impl Into<Cow<'a, str>>, node_type: NodeType) -> Self {
+        Self {
+            name: name.into(),
+            node_type,
+            value: None,
+            children: Vec::new(),
+            span: None,
+        }
+    }
+
+    /// Create a node with a value
+    pub fn with_value(
+        name: impl Into<Cow<'a, str>>,
+        node_type: NodeType,
+        value: impl Into<Cow<'a, str>>,
+    ) -> Self {
+        Self {
+            value: Some(value.into()),
+            ..Self::new(name, node_type)
+        }
+    }
+
+    /// Add a child node
+    pub fn add_child(&mut self, child: BorrowedNode<'a>) -> &mut Self {
+        self.children.push(child);
+        self
+    }
+
+    /// Set the source span
+    pub fn set_span(&mut self, span: SourceSpan) -> &mut Self {
+        self.span = Some(span);
+        self
+    }
+
+    /// Record the index of the enclosing segment within its message on this
+    /// node's span and those of its descendants
+    pub fn set_segment_index(&mut self, segment_index: usize) -> &mut Self {
+        if let Some(span) = &mut self.span {
+            span.segment_index = Some(segment_index);
+        }
+        for child in &mut self.children {
+            child.set_segment_index(segment_index);
+        }
+        self
+    }
+
+    /// Find a child by name
+    pub fn find_child(&self, name: &str) -> Option<&BorrowedNode<'a>> {
+        self.children.iter().find(|c| c.name == name)
+    }
+
+    /// Find all children by name
+    pub fn find_children<'s>(
+        &'s self,
+        name: &'s str,
+    ) -> impl Iterator<Item = &'s BorrowedNode<'a>> + 's {
+        self.children.iter().filter(move |c| c.name == name)
+    }
+
+    /// The node's value, without copying it
+    pub fn text(&self) -> Option<&str> {
+        self.value.as_deref()
+    }
+
+    /// Owned copy of this subtree; values become [`Value::String`]
+    pub fn to_node(&self) -> Node {
+        let mut node = match &self.value {
+            Some(value) => Node::with_value(
+                self.name.as_ref(),
+                self.node_type,
+                Value::String(value.to_string()),
+            ),
+            None => Node::new(self.name.as_ref(), self.node_type),
+        };
+        node.span = self.span;
+        node.children = self.children.iter().map(BorrowedNode::to_node).collect();
+        node
+    }
+}
+
+/// A document whose tree borrows from its input
+#[derive(Debug, Clone)]
+pub struct BorrowedDocument<'a> {
+    /// Root node of the document
+    pub root: BorrowedNode<'a>,
+
+    /// Document-level metadata
+    pub metadata: DocumentMetadata,
+}
+
+impl<'a> BorrowedDocument<'a> {
+    /// Create a new document with the given root node
+    pub fn new(root: BorrowedNode<'a>) -> Self {
+        Self::with_metadata(root, DocumentMetadata::default())
+    }
+
+    /// Create a new document with metadata
+    pub fn with_metadata(root: BorrowedNode<'a>, metadata: DocumentMetadata) -> Self {
+        Self { root, metadata }
+    }
+
+    /// Owned copy of this document
+    pub fn to_document(&self) -> Document {
+        Document::with_metadata(self.root.to_node(), self.metadata.clone())
+    }
+}
+
+/// Interned name of the data element at one-based `index` (`e1`, `e2`, ...)
+pub fn element_name(index: usize) -> Cow<'static, str> {
+    static NAMES: OnceLock<Vec<String>> = OnceLock::new();
+    positional_name(&NAMES, "e", index)
+}
+
+/// Interned name of the component at one-based `index` (`c1`, `c2`, ...)
+pub fn component_name(index: usize) -> Cow<'static, str> {
+    static NAMES: OnceLock<Vec<String>> = OnceLock::new();
+    positional_name(&NAMES, "c", index)
+}
+
+/// Interned name of the repetition at one-based `index` (`r1`, `r2`, ...)
+pub fn repetition_name(index: usize) -> Cow<'static, str> {
+    static NAMES: OnceLock<Vec<String>> = OnceLock::new();
+    positional_name(&NAMES, "r", index)
+}
+
+fn positional_name(
+    names: &'static OnceLock<Vec<String>>,
+    prefix: &str,
+    index: usize,
+) -> Cow<'static, str> {
+    let names = names.get_or_init(|| {
+        (1..=INTERNED_POSITIONS)
+            .map(|position| format!("{prefix}{position}"))
+            .collect()
+    });
+    match index
+        .checked_sub(1)
+        .and_then(|position| names.get(position))
+    {
+        Some(name) => Cow::Borrowed(name.as_str()),
+        None => Cow::Owned(format!("{prefix}{index}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(input: &str) -> BorrowedNode<'_> {
+        let (tag, value) = input.split_at(3);
+        let mut element = BorrowedNode::new(element_name(1), NodeType::Element);
+        for (idx, component) in value.split(':').enumerate() {
+            element.add_child(BorrowedNode::with_value(
+                component_name(idx + 1),
+                NodeType::Component,
+                component,
+            ));
+        }
+        let mut segment = BorrowedNode::new(tag, NodeType::Segment);
+        segment.set_span(SourceSpan::new(0, input.len(), 1, 1));
+        segment.add_child(element);
+        segment
+    }
+
+    #[test]
+    fn test_names_and_values_borrow_from_input() {
+        let input = String::from("QTY21:10:KGM");
+        let node = segment(&input);
+
+        assert!(matches!(node.name, Cow::Borrowed(_)));
+        let element = node.find_child("e1").unwrap();
+        assert!(matches!(element.name, Cow::Borrowed(_)));
+        let unit = element.find_child("c3").unwrap();
+        assert_eq!(unit.text(), Some("KGM"));
+        assert!(matches!(unit.value, Some(Cow::Borrowed(_))));
+        assert_eq!(element.find_children("c2").count(), 1);
+    }
+
+    #[test]
+    fn test_positional_names_are_interned() {
+        let (Cow::Borrowed(first), Cow::Borrowed(second)) = (element_name(7), element_name(7))
+        else {
+            panic!("expected interned names");
+        };
+        assert_eq!(first, "e7");
+        assert!(std::ptr::eq(first, second));
+
+        assert_eq!(component_name(1), "c1");
+        assert_eq!(repetition_name(2), "r2");
+        assert!(matches!(element_name(100), Cow::Owned(name) if name == "e100"));
+        assert!(matches!(element_name(0), Cow::Owned(name) if name == "e0"));
+    }
+
+    #[test]
+    fn test_to_document_copies_tree() {
+        let input = String::from("QTY21:10:KGM");
+        let mut root = BorrowedNode::new("MESSAGE", NodeType::Message);
+        root.add_child(segment(&input));
+        let mut borrowed = BorrowedDocument::new(root);
+        borrowed.metadata.doc_type = Some("INVRPT".to_string());
+        borrowed.root.set_segment_index(1);
+
+        let doc = borrowed.to_document();
+
+        assert_eq!(doc.metadata.doc_type.as_deref(), Some("INVRPT"));
+        let qty = &doc.root.children[0];
+        assert_eq!(qty.name, "QTY");
+        assert_eq!(qty.span.and_then(|span| span.segment_index), Some(1));
+        assert_eq!(
+            qty.children[0].children[2].value,
+            Some(Value::String("KGM".to_string()))
+        );
+    }
+}
@@ -0,0 +1,84 @@
+//! Exact decimal numbers
+//!
+//! Amounts and quantities are kept as arbitrary-precision decimals so that
+//! sums reconcile exactly with totals transmitted in the message (e.g. the
+//! EDIFACT MOA segment) and the scale of the source value ("12.50") survives
+//! every conversion.
+
+use std::str::FromStr;
+
+/// Arbitrary-precision decimal number with scale
+pub use bigdecimal::BigDecimal as Decimal;
+/// Rounding modes for [`Decimal::with_scale_round`]
+pub use bigdecimal::RoundingMode;
+
+/// Parse a decimal number from flat-file or database text.
+///
+/// Surrounding whitespace and a leading sign are accepted and the decimal
+/// mark is a full stop. Returns `None` if `text` is not a plain decimal
+/// number; exponents and group separators are rejected, so "1,234" is not a
+/// number here. Use [`parse_edi_decimal`] for EDI data.
+#[must_use]
+pub fn parse_decimal(text: &str) -> Option<Decimal> {
+    parse(text, false)
+}
+
+/// Parse a decimal number from EDI data.
+///
+/// Like [`parse_decimal`], but the decimal mark may be either a full stop or
+/// a comma, as ISO 9735 allows both.
+#[must_use]
+pub fn parse_edi_decimal(text: &str) -> Option<Decimal> {
+    parse(text, true)
+}
+
+fn parse(text: &str, decimal_comma: bool) -> Option<Decimal> {
+    let text = text.trim();
+    let digits = text.strip_prefix(['+', '-']).unwrap_or(text);
+    let mut marks = 0;
+    let mut has_digit = false;
+    for byte in digits.bytes() {
+        match byte {
+            b'0'..=b'9' => has_digit = true,
+            b'.' => marks += 1,
+            b',' if decimal_comma => marks += 1,
+            _ => return None,
+        }
+    }
+    if !has_digit || marks > 1 {
+        return None;
+    }
+    Decimal::from_str(&text.replace(',', ".")).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_decimal_keeps_scale() {
+        let value = parse_decimal("12.50").unwrap();
+        assert_eq!(value.to_plain_string(), "12.50");
+        assert_eq!(parse_decimal(" -0.5 ").unwrap().to_plain_string(), "-0.5");
+        assert_eq!(parse_decimal("+7").unwrap().to_plain_string(), "7");
+        assert_eq!(parse_decimal(".5").unwrap().to_plain_string(), "0.5");
+    }
+
+    #[test]
+    fn test_parse_edi_decimal_with_decimal_comma() {
+        assert_eq!(parse_edi_decimal("1234,56"), parse_decimal("1234.56"));
+        assert_eq!(
+            parse_edi_decimal("1234,560").unwrap().to_plain_string(),
+            "1234.560"
+        );
+        assert_eq!(parse_decimal("1,234"), None);
+    }
+
+    #[test]
+    fn test_parse_decimal_rejects_non_numbers() {
+        for text in ["", "-", ".", "1.2.3", "1,234.5", "1e10", "abc", "12 34"] {
+            assert!(parse_decimal(text).is_none(), "{text}");
+            assert!(parse_edi_decimal(text).is_none(), "{text}");
+        }
+    }
+}
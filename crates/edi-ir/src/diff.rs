@@ -0,0 +1,216 @@
+//! Structural comparison of IR trees
+//!
+//! [`diff`] reports the nodes that were added, removed or changed between an
+//! original and an edited tree, e.g. to show what a mapping or a
+//! partner-specific fixup did to a message. Children are paired by name and
+//! occurrence: the second `QTY` under a node is compared with the second
+//! `QTY` under its counterpart. Added and removed subtrees are reported once,
+//! at their top node.
+//!
+//! Change paths are absolute [`Path`](crate::Path) expressions
+//! (`/SG25[1]/QTY/e1/c3`), so they can be fed back into the query and editing
+//! APIs. A name is indexed when either tree has several nodes of that name
+//! under the same parent.
+#![allow(clippy::must_use_candidate)] // Diff results are plain data.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::document::Document;
+use crate::node::{Node, Value};
+
+/// Kind of a structural change
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChangeKind {
+    /// The node only exists in the new tree
+    Added,
+
+    /// The node only exists in the old tree
+    Removed,
+
+    /// The node exists in both trees with a different value
+    Changed,
+}
+
+/// A node that differs between two trees
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Change {
+    /// Kind of change
+    pub kind: ChangeKind,
+
+    /// Path of the node from the root
+    pub path: String,
+
+    /// Value in the old tree (if any)
+    pub old_value: Option<Value>,
+
+    /// Value in the new tree (if any)
+    pub new_value: Option<Value>,
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = |value: &Option<Value>| {
+            value
+                .as_ref()
+                .and_then(Value::as_string)
+                .map_or_else(|| "(none)".to_string(), |text| format!("'{text}'"))
+        };
+        match self.kind {
+            ChangeKind::Added => write!(f, "+ {} = {}", self.path, text(&self.new_value)),
+            ChangeKind::Removed => write!(f, "- {} = {}", self.path, text(&self.old_value)),
+            ChangeKind::Changed => write!(
+                f,
+                "~ {}: {} -> {}",
+                self.path,
+                text(&self.old_value),
+                text(&self.new_value)
+            ),
+        }
+    }
+}
+
+impl Document {
+    /// Changes that turn this document's tree into `other`'s
+    pub fn diff(&self, other: &Document) -> Vec<Change> {
+        diff(&self.root, &other.root)
+    }
+}
+
+/// Changes that turn the tree at `old` into the tree at `new`, parents before
+/// children and siblings grouped by name. Only names, values and structure
+/// are compared; attributes, schema types and source spans are ignored.
+pub fn diff(old: &Node, new: &Node) -> Vec<Change> {
+    let mut changes = Vec::new();
+    if old.name == new.name {
+        diff_nodes(old, new, "", &mut changes);
+    } else {
+        changes.push(change(ChangeKind::Removed, "/", Some(old), None));
+        changes.push(change(ChangeKind::Added, "/", None, Some(new)));
+    }
+    changes
+}
+
+fn diff_nodes(old: &Node, new: &Node, path: &str, changes: &mut Vec<Change>) {
+    if old.value != new.value {
+        let path = if path.is_empty() { "/" } else { path };
+        changes.push(change(ChangeKind::Changed, path, Some(old), Some(new)));
+    }
+
+    let mut names: Vec<&str> = Vec::new();
+    for child in old.children.iter().chain(&new.children) {
+        if !names.contains(&child.name.as_str()) {
+            names.push(&child.name);
+        }
+    }
+
+    for name in names {
+        let old_children = old.find_children(name);
+        let new_children = new.find_children(name);
+        let indexed = old_children.len() > 1 || new_children.len() > 1;
+        for index in 0..old_children.len().max(new_children.len()) {
+            let child_path = if indexed {
+                format!("{path}/{name}[{index}]")
+            } else {
+                format!("{path}/{name}")
+            };
+            match (old_children.get(index), new_children.get(index)) {
+                (Some(old), Some(new)) => diff_nodes(old, new, &child_path, changes),
+                (Some(old), None) => {
+                    changes.push(change(ChangeKind::Removed, &child_path, Some(old), None));
+                }
+                (None, Some(new)) => {
+                    changes.push(change(ChangeKind::Added, &child_path, None, Some(new)));
+                }
+                (None, None) => {}
+            }
+        }
+    }
+}
+
+fn change(kind: ChangeKind, path: &str, old: Option<&Node>, new: Option<&Node>) -> Change {
+    Change {
+        kind,
+        path: path.to_string(),
+        old_value: old.and_then(|node| node.value.clone()),
+        new_value: new.and_then(|node| node.value.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node::NodeType;
+    use crate::path::Path;
+    use crate::testing::{self, segment};
+
+    fn order() -> Document {
+        let mut doc = testing::order(&[("1", "21"), ("2", "47")]);
+        doc.root.children.insert(0, segment("BGM", &["220"]));
+        doc
+    }
+
+    #[test]
+    fn test_identical_documents_have_no_changes() {
+        assert!(order().diff(&order()).is_empty());
+    }
+
+    #[test]
+    fn test_reports_changed_added_and_removed_nodes() {
+        let old = order();
+        let mut new = order();
+        new.set_value("//QTY['21']/e1/c3", &Value::String("PCE".to_string()))
+            .unwrap();
+        new.append_child("SG25[1]", &segment("FTX", &["AAI"]))
+            .unwrap();
+        new.remove("BGM").unwrap();
+
+        let changes = old.diff(&new);
+
+        assert_eq!(
+            changes.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec![
+                "- /BGM = (none)",
+                "~ /SG25[0]/QTY/e1/c3: 'KGM' -> 'PCE'",
+                "+ /SG25[1]/FTX = (none)",
+            ]
+        );
+        assert_eq!(changes[1].kind, ChangeKind::Changed);
+        assert_eq!(changes[1].old_value, Some(Value::String("KGM".to_string())));
+
+        // Change paths select the changed node
+        let change_path = Path::parse(&changes[1].path).unwrap();
+        assert_eq!(
+            change_path
+                .select_first(&new.root)
+                .and_then(|n| n.value.clone()),
+            Some(Value::String("PCE".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_extra_occurrences_are_added_or_removed() {
+        let old = order();
+        let mut new = order();
+        new.remove("SG25[last()]").unwrap();
+
+        let changes = old.diff(&new);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].kind, ChangeKind::Removed);
+        assert_eq!(changes[0].path, "/SG25[1]");
+
+        let changes = new.diff(&old);
+        assert_eq!(changes[0].kind, ChangeKind::Added);
+        assert_eq!(changes[0].path, "/SG25[1]");
+    }
+
+    #[test]
+    fn test_different_roots_replace_the_tree() {
+        let old = order();
+        let new = Document::new(Node::new("INVOIC", NodeType::Root));
+
+        let kinds: Vec<_> = old.diff(&new).iter().map(|c| c.kind).collect();
+        assert_eq!(kinds, vec![ChangeKind::Removed, ChangeKind::Added]);
+    }
+}
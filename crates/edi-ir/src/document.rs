@@ -40,10 +40,55 @@ pub struct DocumentMetadata {
     /// Message reference numbers
     pub message_refs: Vec<String>,
 
+    /// Interchange envelope the document was received in (if any)
+    pub interchange: Option<InterchangeInfo>,
+
     /// Creation timestamp
     pub created_at: Option<chrono::DateTime<chrono::Utc>>,
 }
 
+/// Interchange envelope data shared by all messages of an interchange
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InterchangeInfo {
+    /// Interchange sender
+    pub sender: PartyInfo,
+
+    /// Interchange recipient
+    pub recipient: PartyInfo,
+
+    /// Date of preparation as sent (e.g., YYMMDD or CCYYMMDD)
+    pub preparation_date: String,
+
+    /// Time of preparation as sent (HHMM)
+    pub preparation_time: String,
+
+    /// Interchange control reference
+    pub control_ref: String,
+
+    /// Application reference (if any)
+    pub application_ref: Option<String>,
+
+    /// Test indicator (if any, e.g., "1" for a test interchange)
+    pub test_indicator: Option<String>,
+}
+
+impl InterchangeInfo {
+    /// Whether the interchange is flagged as a test interchange
+    pub fn is_test(&self) -> bool {
+        self.test_indicator.as_deref() == Some("1")
+    }
+}
+
+/// Identification of an interchange party
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PartyInfo {
+    /// Party identification (e.g., a GLN)
+    pub id: String,
+
+    /// Identification code qualifier (e.g., "14" for GS1)
+    pub qualifier: Option<String>,
+}
+
 impl Document {
     /// Create a new document with the given root node
     #[must_use]
@@ -102,6 +147,7 @@ mod tests {
             partner_id: Some("PARTNER001".to_string()),
             interchange_ref: Some("12345".to_string()),
             message_refs: vec!["MSG001".to_string(), "MSG002".to_string()],
+            interchange: None,
             created_at: Some(chrono::Utc::now()),
         };
 
@@ -174,6 +220,27 @@ mod tests {
         assert!(doc.metadata.partner_id.is_none());
         assert!(doc.metadata.interchange_ref.is_none());
         assert!(doc.metadata.message_refs.is_empty());
+        assert!(doc.metadata.interchange.is_none());
         assert!(doc.metadata.created_at.is_none());
     }
+
+    #[test]
+    fn test_interchange_info_test_indicator() {
+        let mut info = InterchangeInfo {
+            sender: PartyInfo {
+                id: "5412345000013".to_string(),
+                qualifier: Some("14".to_string()),
+            },
+            control_ref: "12345".to_string(),
+            ..InterchangeInfo::default()
+        };
+        assert!(!info.is_test());
+
+        info.test_indicator = Some("1".to_string());
+        assert!(info.is_test());
+
+        let json = serde_json::to_string(&info).unwrap();
+        let restored: InterchangeInfo = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, info);
+    }
 }
@@ -0,0 +1,170 @@
+//! Path-driven editing of documents
+//!
+//! Every operation selects its targets with a [`Path`] expression evaluated
+//! from the document root, applies itself to each selected node and returns
+//! how many nodes it changed. A path that selects nothing is not an error:
+//!
+//! ```
+//! # use edi_ir::{Document, Node, NodeType, Value};
+//! # let mut doc = Document::new(Node::new("ORDERS", NodeType::Root));
+//! // Set the unit of every ordered quantity (QTY qualifier 21) to pieces
+//! doc.set_value("//QTY['21']/e1/c3", &Value::String("PCE".to_string()))?;
+//! # Ok::<(), edi_ir::Error>(())
+//! ```
+//!
+//! Targets are edited deepest and last first, so removing or inserting
+//! nodes never shifts a target that is still to be edited.
+#![allow(clippy::missing_errors_doc)] // Every operation fails only on an invalid path.
+
+use crate::Result;
+use crate::document::Document;
+use crate::node::{Node, Value};
+use crate::path::Path;
+
+impl Document {
+    /// Set the value of every node selected by `path`
+    pub fn set_value(&mut self, path: &str, value: &Value) -> Result<usize> {
+        self.update(path, |node| node.value = Some(value.clone()))
+    }
+
+    /// Apply `edit` to every node selected by `path`
+    pub fn update(&mut self, path: &str, mut edit: impl FnMut(&mut Node)) -> Result<usize> {
+        let routes = self.routes(path)?;
+        for route in &routes {
+            edit(node_at(&mut self.root, route));
+        }
+        Ok(routes.len())
+    }
+
+    /// Append a copy of `child` to every node selected by `path`
+    pub fn append_child(&mut self, path: &str, child: &Node) -> Result<usize> {
+        self.update(path, |parent| {
+            parent.add_child(child.clone());
+        })
+    }
+
+    /// Insert a copy of `child` at `index` into every node selected by
+    /// `path`; an index past the last child appends
+    pub fn insert_child(&mut self, path: &str, index: usize, child: &Node) -> Result<usize> {
+        self.update(path, |parent| {
+            let index = index.min(parent.children.len());
+            parent.children.insert(index, child.clone());
+        })
+    }
+
+    /// Replace every node selected by `path` with a copy of `node`
+    pub fn replace(&mut self, path: &str, node: &Node) -> Result<usize> {
+        self.update(path, |target| *target = node.clone())
+    }
+
+    /// Remove every node selected by `path`, except the root
+    pub fn remove(&mut self, path: &str) -> Result<usize> {
+        let mut removed = 0;
+        for route in self.routes(path)? {
+            let Some((index, parent)) = route.split_last() else {
+                continue;
+            };
+            node_at(&mut self.root, parent).children.remove(*index);
+            removed += 1;
+        }
+        Ok(removed)
+    }
+
+    /// Routes of the nodes selected by `path`, deepest and last first
+    fn routes(&self, path: &str) -> Result<Vec<Vec<usize>>> {
+        let mut routes = Path::parse(path)?.select_routes(&self.root);
+        routes.sort_unstable_by(|a, b| b.cmp(a));
+        Ok(routes)
+    }
+}
+
+fn node_at<'a>(root: &'a mut Node, route: &[usize]) -> &'a mut Node {
+    route
+        .iter()
+        .fold(root, |node, &index| &mut node.children[index])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Error;
+    use crate::testing::{self, segment};
+
+    fn order() -> Document {
+        testing::order(&[("1", "21"), ("2", "47"), ("3", "21")])
+    }
+
+    fn units(doc: &Document) -> Vec<String> {
+        Path::parse("//QTY/e1/c3")
+            .unwrap()
+            .select(&doc.root)
+            .into_iter()
+            .filter_map(|node| node.value.as_ref().and_then(Value::as_string))
+            .collect()
+    }
+
+    #[test]
+    fn test_set_value_on_every_match() {
+        let mut doc = order();
+
+        let changed = doc
+            .set_value("//QTY['21']/e1/c3", &Value::String("PCE".to_string()))
+            .unwrap();
+
+        assert_eq!(changed, 2);
+        assert_eq!(units(&doc), vec!["PCE", "KGM", "PCE"]);
+        assert_eq!(doc.set_value("//QTY['99']/e1/c3", &Value::Null).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_insert_append_and_replace() {
+        let mut doc = order();
+        let ftx = segment("FTX", &["AAI"]);
+
+        assert_eq!(doc.insert_child("SG25[LIN/e1=5]", 1, &ftx).unwrap(), 0);
+        assert_eq!(doc.insert_child("SG25[LIN/e1=2]", 1, &ftx).unwrap(), 1);
+        assert_eq!(doc.append_child("SG25[last()]", &ftx).unwrap(), 1);
+        assert_eq!(doc.insert_child("SG25[0]", 10, &ftx).unwrap(), 1);
+
+        let names = |group: &Node| -> Vec<String> {
+            group.children.iter().map(|c| c.name.clone()).collect()
+        };
+        assert_eq!(names(&doc.root.children[0]), vec!["LIN", "QTY", "FTX"]);
+        assert_eq!(names(&doc.root.children[1]), vec!["LIN", "FTX", "QTY"]);
+        assert_eq!(names(&doc.root.children[2]), vec!["LIN", "QTY", "FTX"]);
+
+        let replaced = doc.replace("//FTX", &segment("FTX", &["ZZZ"])).unwrap();
+        assert_eq!(replaced, 3);
+        assert_eq!(
+            Path::parse("//FTX['ZZZ']").unwrap().select(&doc.root).len(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_remove_nested_and_sibling_matches() {
+        let mut doc = order();
+
+        assert_eq!(doc.remove("//QTY['47']/..").unwrap(), 1);
+        // A group and a segment inside it, and sibling groups
+        assert_eq!(doc.remove("//*[LIN/e1=3 or e1/c2=10]").unwrap(), 3);
+        assert_eq!(doc.remove("/").unwrap(), 0);
+
+        assert_eq!(doc.root.children.len(), 1);
+        let names: Vec<_> = doc.root.children[0]
+            .children
+            .iter()
+            .map(|c| c.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["LIN"]);
+    }
+
+    #[test]
+    fn test_invalid_path_is_reported() {
+        let mut doc = order();
+        assert!(matches!(
+            doc.set_value("SG25[", &Value::Null),
+            Err(Error::InvalidPath { .. })
+        ));
+    }
+}
@@ -12,21 +12,44 @@
 //! EDI documents in a format-neutral way, enabling transformations between
 //! different formats (EDIFACT, CSV, database, etc.).
 
+/// Borrowed IR trees referencing their input.
+pub mod borrowed;
+/// Exact decimal numbers for amounts and quantities.
+pub mod decimal;
+/// Structural comparison of IR trees.
+pub mod diff;
 /// Document container and top-level IR metadata accessors.
 pub mod document;
+/// Path-driven editing of documents.
+pub mod edit;
 /// Source and validation metadata attached to documents and nodes.
 pub mod metadata;
 /// Core tree node model used for EDI message representation.
 pub mod node;
+/// Compiled path expressions shared by cursors and mappings.
+pub mod path;
+/// Fixture builders for tests working on IR trees.
+#[cfg(any(test, feature = "test-util"))]
+pub mod testing;
 /// Cursor-based traversal helpers for navigating IR trees.
 pub mod traversal;
 
+/// Borrowed IR nodes and interned positional names.
+pub use borrowed::{BorrowedDocument, BorrowedNode, component_name, element_name, repetition_name};
+/// Exact decimal type and parsing of EDI decimal notation.
+pub use decimal::{Decimal, parse_decimal, parse_edi_decimal};
+/// Structural diff of two trees.
+pub use diff::{Change, ChangeKind, diff};
 /// Primary IR document type.
 pub use document::Document;
+/// Interchange envelope data attached to document metadata.
+pub use document::{InterchangeInfo, PartyInfo};
 /// Position and source metadata plus accumulated validation state.
-pub use metadata::{Position, SourceInfo, ValidationState};
+pub use metadata::{Position, SourceInfo, SourceSpan, ValidationState};
 /// Node primitives for tree structure and value typing.
 pub use node::{Node, NodeType, Value};
+/// Compiled path expressions and qualifier data elements.
+pub use path::Path;
 /// Traversal entry points for iterative tree navigation.
 pub use traversal::{Cursor, Traversal};
 
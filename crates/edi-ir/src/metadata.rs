@@ -20,6 +20,31 @@ pub struct Position {
     pub length: usize,
 }
 
+/// Location of a parsed node in its source data.
+///
+/// Offsets are byte offsets into the parsed input. A segment span runs from
+/// the first byte of the tag through the segment terminator; element and
+/// component spans cover the value only, without separators.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SourceSpan {
+    /// Byte offset from start of input
+    pub offset: usize,
+
+    /// Length in bytes
+    pub length: usize,
+
+    /// Line number (1-indexed)
+    pub line: usize,
+
+    /// Column number (1-indexed)
+    pub column: usize,
+
+    /// Index of the enclosing segment within its message (1-indexed), set
+    /// once the segment is placed in a message
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub segment_index: Option<usize>,
+}
+
 /// Information about the source of the data
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SourceInfo {
@@ -90,6 +115,42 @@ impl Position {
     }
 }
 
+impl SourceSpan {
+    /// Create a span without a segment index
+    #[must_use]
+    pub fn new(offset: usize, length: usize, line: usize, column: usize) -> Self {
+        Self {
+            offset,
+            length,
+            line,
+            column,
+            segment_index: None,
+        }
+    }
+
+    /// Set the index of the enclosing segment within its message
+    #[must_use]
+    pub fn with_segment_index(mut self, segment_index: usize) -> Self {
+        self.segment_index = Some(segment_index);
+        self
+    }
+
+    /// Byte offset just past the end of the span
+    pub fn end(&self) -> usize {
+        self.offset + self.length
+    }
+
+    /// Convert to a [`Position`] for [`SourceInfo`]
+    pub fn position(&self) -> Position {
+        Position::new(self.line, self.column, self.offset, self.length)
+    }
+
+    /// The bytes of `input` covered by the span, if it lies within `input`
+    pub fn slice<'a>(&self, input: &'a [u8]) -> Option<&'a [u8]> {
+        input.get(self.offset..self.end())
+    }
+}
+
 impl SourceInfo {
     /// Create new source info
     #[must_use]
@@ -181,6 +242,19 @@ mod tests {
         assert_eq!(source.context, Some("Header row".to_string()));
     }
 
+    #[test]
+    fn test_source_span_accessors() {
+        let input = b"UNH+1+ORDERS'";
+        let span = SourceSpan::new(4, 1, 1, 5).with_segment_index(1);
+
+        assert_eq!(span.end(), 5);
+        assert_eq!(span.slice(input), Some(&b"1"[..]));
+        assert_eq!(span.segment_index, Some(1));
+        assert_eq!(span.position().offset, 4);
+        assert_eq!(span.position().column, 5);
+        assert!(SourceSpan::new(12, 4, 1, 13).slice(input).is_none());
+    }
+
     #[test]
     fn test_validation_message_creation() {
         let msg =
@@ -2,6 +2,8 @@
 #![allow(clippy::must_use_candidate)] // Accessors and search helpers follow existing API style.
 #![allow(clippy::doc_markdown)] // `DateTime`/ISO token style appears throughout existing docs.
 
+use crate::decimal::{Decimal, parse_edi_decimal};
+use crate::metadata::SourceSpan;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -25,6 +27,10 @@ pub struct Node {
 
     /// Schema type reference
     pub schema_type: Option<String>,
+
+    /// Location in the source data, for nodes produced by a parser
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub span: Option<SourceSpan>,
 }
 
 /// Types of nodes in the IR
@@ -36,6 +42,9 @@ pub enum NodeType {
     /// Interchange envelope (e.g., UNB/UNZ)
     Interchange,
 
+    /// Functional group envelope (e.g., UNG/UNE)
+    FunctionalGroup,
+
     /// Message envelope (e.g., UNH/UNT)
     Message,
 
@@ -67,8 +76,8 @@ pub enum Value {
     /// Integer value
     Integer(i64),
 
-    /// Decimal value
-    Decimal(f64),
+    /// Decimal value, exact and with the scale of its source
+    Decimal(Decimal),
 
     /// Boolean value
     Boolean(bool),
@@ -82,6 +91,9 @@ pub enum Value {
     /// `DateTime` value
     DateTime(String), // ISO 8601 format
 
+    /// Period between two dates
+    Period(String), // ISO 8601 interval ("2026-03-01/2026-03-31")
+
     /// Raw bytes
     Binary(Vec<u8>),
 
@@ -99,6 +111,7 @@ impl Node {
             children: Vec::new(),
             attributes: HashMap::new(),
             schema_type: None,
+            span: None,
         }
     }
 
@@ -111,6 +124,7 @@ impl Node {
             children: Vec::new(),
             attributes: HashMap::new(),
             schema_type: None,
+            span: None,
         }
     }
 
@@ -132,6 +146,24 @@ impl Node {
         self
     }
 
+    /// Set the source span
+    pub fn set_span(&mut self, span: SourceSpan) -> &mut Self {
+        self.span = Some(span);
+        self
+    }
+
+    /// Record the index of the enclosing segment within its message on this
+    /// node's span and those of its descendants
+    pub fn set_segment_index(&mut self, segment_index: usize) -> &mut Self {
+        if let Some(span) = &mut self.span {
+            span.segment_index = Some(segment_index);
+        }
+        for child in &mut self.children {
+            child.set_segment_index(segment_index);
+        }
+        self
+    }
+
     /// Find a child by name
     #[must_use]
     pub fn find_child(&self, name: &str) -> Option<&Node> {
@@ -152,15 +184,28 @@ impl Value {
         match self {
             Value::String(s) => Some(s.clone()),
             Value::Integer(i) => Some(i.to_string()),
-            Value::Decimal(d) => Some(d.to_string()),
+            Value::Decimal(d) => Some(d.to_plain_string()),
             Value::Boolean(b) => Some(b.to_string()),
             Value::Date(d) => Some(d.clone()),
             Value::Time(t) => Some(t.clone()),
             Value::DateTime(dt) => Some(dt.clone()),
+            Value::Period(p) => Some(p.clone()),
             Value::Binary(_) | Value::Null => None,
         }
     }
 
+    /// Numeric value as an exact decimal; strings are parsed with
+    /// [`parse_edi_decimal`]
+    #[must_use]
+    pub fn as_decimal(&self) -> Option<Decimal> {
+        match self {
+            Value::Integer(i) => Some(Decimal::from(*i)),
+            Value::Decimal(d) => Some(d.clone()),
+            Value::String(s) => parse_edi_decimal(s),
+            _ => None,
+        }
+    }
+
     /// Check if value is null
     #[must_use]
     pub fn is_null(&self) -> bool {
@@ -225,6 +270,22 @@ mod tests {
         assert_eq!(node.schema_type, Some("EANCOM_D96A_ORDERS".to_string()));
     }
 
+    #[test]
+    fn test_set_segment_index_reaches_descendant_spans() {
+        let mut segment = Node::new("BGM", NodeType::Segment);
+        segment.set_span(SourceSpan::new(0, 14, 1, 1));
+        let mut element = Node::new("e1", NodeType::Element);
+        element.set_span(SourceSpan::new(4, 3, 1, 5));
+        element.add_child(Node::new("c1", NodeType::Component));
+        segment.add_child(element);
+
+        segment.set_segment_index(2);
+
+        assert_eq!(segment.span.unwrap().segment_index, Some(2));
+        assert_eq!(segment.children[0].span.unwrap().segment_index, Some(2));
+        assert!(segment.children[0].children[0].span.is_none());
+    }
+
     #[test]
     fn test_find_child() {
         let mut parent = Node::new("PARENT", NodeType::SegmentGroup);
@@ -285,6 +346,9 @@ mod tests {
         let interchange = Node::new("UNB", NodeType::Interchange);
         assert_eq!(interchange.node_type, NodeType::Interchange);
 
+        let group = Node::new("UNG", NodeType::FunctionalGroup);
+        assert_eq!(group.node_type, NodeType::FunctionalGroup);
+
         let message = Node::new("UNH", NodeType::Message);
         assert_eq!(message.node_type, NodeType::Message);
 
@@ -315,7 +379,7 @@ mod tests {
         );
         assert_eq!(Value::Integer(42).as_string(), Some("42".to_string()));
         assert_eq!(
-            Value::Decimal(123.45).as_string(),
+            Value::Decimal("123.45".parse().unwrap()).as_string(),
             Some("123.45".to_string())
         );
         assert_eq!(Value::Boolean(true).as_string(), Some("true".to_string()));
@@ -332,6 +396,10 @@ mod tests {
             Value::DateTime("2024-01-15T14:30:00Z".to_string()).as_string(),
             Some("2024-01-15T14:30:00Z".to_string())
         );
+        assert_eq!(
+            Value::Period("2024-01-01/2024-01-31".to_string()).as_string(),
+            Some("2024-01-01/2024-01-31".to_string())
+        );
         assert_eq!(Value::Binary(vec![0x00, 0x01, 0x02]).as_string(), None);
         assert_eq!(Value::Null.as_string(), None);
     }
@@ -341,11 +409,12 @@ mod tests {
         assert!(Value::Null.is_null());
         assert!(!Value::String(String::new()).is_null());
         assert!(!Value::Integer(0).is_null());
-        assert!(!Value::Decimal(0.0).is_null());
+        assert!(!Value::Decimal(Decimal::from(0)).is_null());
         assert!(!Value::Boolean(false).is_null());
         assert!(!Value::Date(String::new()).is_null());
         assert!(!Value::Time(String::new()).is_null());
         assert!(!Value::DateTime(String::new()).is_null());
+        assert!(!Value::Period(String::new()).is_null());
         assert!(!Value::Binary(vec![]).is_null());
     }
 
@@ -354,10 +423,23 @@ mod tests {
         let int_val = Value::Integer(-12345);
         assert_eq!(int_val.as_string(), Some("-12345".to_string()));
 
-        let dec_val = Value::Decimal(-9876.54321);
+        let dec_val = Value::Decimal("-9876.54321".parse().unwrap());
         assert_eq!(dec_val.as_string(), Some("-9876.54321".to_string()));
 
         let bool_val = Value::Boolean(true);
         assert_eq!(bool_val.as_string(), Some("true".to_string()));
     }
+
+    #[test]
+    fn test_value_as_decimal() {
+        let amount = Value::Decimal("12.50".parse().unwrap());
+        assert_eq!(amount.as_string(), Some("12.50".to_string()));
+        assert_eq!(Value::Integer(3).as_decimal(), Some(Decimal::from(3)));
+        assert_eq!(
+            Value::String("12,50".to_string()).as_decimal(),
+            amount.as_decimal()
+        );
+        assert_eq!(Value::String("n/a".to_string()).as_decimal(), None);
+        assert_eq!(Value::Boolean(true).as_decimal(), None);
+    }
 }
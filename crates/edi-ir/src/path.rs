@@ -0,0 +1,949 @@
+//! Compiled path expressions for selecting IR nodes
+//!
+//! A [`Path`] is parsed once and can then be evaluated against any number of
+//! trees. The same language is used by [`Cursor`](crate::Cursor) navigation
+//! and by mapping rules:
+//!
+//! | Expression                  | Selects                                          |
+//! |-----------------------------|--------------------------------------------------|
+//! | `SG2/NAD`                   | `NAD` children of `SG2` children                 |
+//! | `/UNH`                      | `UNH` children of the root                       |
+//! | `*`                         | every child                                      |
+//! | `//DTM`, `SG25//QTY`        | `DTM`/`QTY` nodes at any depth                   |
+//! | `.`, `..`                   | the current node, its parent                     |
+//! | `ancestor::SG2`             | enclosing `SG2` nodes, nearest first             |
+//! | `LIN[0]`, `LIN[last()]`     | by zero-based position (`last()-1`, ...)         |
+//! | `NAD[e1=BY]`                | nodes whose `e1` value is `BY`                   |
+//! | `NAD[e1=BY and e2/c3=9]`    | `and`, `or`, `not(...)` and parentheses combine  |
+//! | `QTY[e1/c2 >= 10]`          | `=`, `!=`, `<`, `<=`, `>`, `>=`                  |
+//! | `LIN[QTY]`                  | nodes that have a `QTY` child                    |
+//! | `DTM['137']`, `DTM[2005=137]` | segments by their qualifier                    |
+//! | `LIN[*]`                    | every node, unfiltered                           |
+//!
+//! A name matches a node's name or its schema type, so typed documents can
+//! also be queried by data element id. Values on the right of a comparison
+//! may be quoted (`'BY'`) or bare (`BY`).
+//!
+//! Inside a predicate, a field that is not a child of the candidate node is
+//! looked up among the components of its first element, so `DTM[c2=...]`
+//! reads `DTM/e1/c2` and, in a typed document, `DTM[2005=...]` reads the
+//! component typed `2005`. `c1` falls back to the first element itself,
+//! whose value is its own or that of its first component; so does a data
+//! element id when the first element has no schema type, which makes
+//! `NAD[3035=BY]` select the party qualifier of untyped documents. `=` and `!=` compare
+//! text; the ordering operators compare numerically when both sides are
+//! numbers and as text otherwise, which orders ISO 8601 and `CCYYMMDD` dates.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::decimal::parse_edi_decimal;
+use crate::node::{Node, Value};
+use crate::{Error, Result};
+
+/// A compiled path expression
+#[derive(Debug, Clone, PartialEq)]
+pub struct Path {
+    source: String,
+    absolute: bool,
+    steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Step {
+    axis: Axis,
+    /// Name test; `None` matches any node
+    name: Option<String>,
+    predicates: Vec<Expr>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Axis {
+    Current,
+    Parent,
+    Child,
+    Descendant,
+    Ancestor,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    True,
+    Or(Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Exists(Path),
+    Compare(Operand, Comparison, Operand),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Operand {
+    Field(Path),
+    Literal(String),
+    Position,
+    /// `last()` minus an offset
+    Last(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// A selected node together with its ancestors, root first, each with its
+/// index among its parent's children
+type Chain<'a> = Vec<(usize, &'a Node)>;
+
+impl Path {
+    /// Compile a path expression
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidPath`] when `source` is not a valid path.
+    pub fn parse(source: &str) -> Result<Self> {
+        let mut parser = Parser { source, pos: 0 };
+        let path = parser.path()?;
+        if let Some(c) = parser.peek() {
+            return Err(parser.error(format!("Unexpected '{c}' at offset {}", parser.pos)));
+        }
+        Ok(path)
+    }
+
+    /// The expression this path was compiled from
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    /// Whether the path starts at the root (`/` or `//`) rather than at the
+    /// context node
+    #[must_use]
+    pub fn is_absolute(&self) -> bool {
+        self.absolute
+    }
+
+    /// Names that the predicates of this path compare or test, in order
+    /// (e.g. `e1` and `e2` for `NAD[e1=BY and e2/c3=9]`)
+    #[must_use]
+    pub fn predicate_fields(&self) -> Vec<&str> {
+        let mut fields = Vec::new();
+        for step in &self.steps {
+            for predicate in &step.predicates {
+                predicate.collect_fields(&mut fields);
+            }
+        }
+        fields
+    }
+
+    /// Select all matching nodes in document order, starting at `node`.
+    ///
+    /// Absolute paths treat `node` as the root, and parent and ancestor steps
+    /// cannot leave the subtree of `node`.
+    #[must_use]
+    pub fn select<'a>(&self, node: &'a Node) -> Vec<&'a Node> {
+        self.select_indexed(vec![(0, node)])
+            .into_iter()
+            .filter_map(|chain| chain.last().map(|&(_, node)| node))
+            .collect()
+    }
+
+    /// Select the first matching node, starting at `node`
+    #[must_use]
+    pub fn select_first<'a>(&self, node: &'a Node) -> Option<&'a Node> {
+        self.select(node).into_iter().next()
+    }
+
+    /// Select matches from `node` as child-index routes, so they can be
+    /// resolved again through a mutable borrow
+    pub(crate) fn select_routes(&self, node: &Node) -> Vec<Vec<usize>> {
+        self.select_indexed(vec![(0, node)])
+            .into_iter()
+            .map(|chain| chain.into_iter().skip(1).map(|(index, _)| index).collect())
+            .collect()
+    }
+
+    /// Select matches from a context node given with its ancestors, root
+    /// first; each match is returned with its own ancestors.
+    ///
+    /// Unlike [`Path::select`], parent and ancestor steps can leave the
+    /// context node, up to the first node of `chain`, and absolute paths
+    /// start at that first node.
+    #[must_use]
+    pub fn select_chains<'a>(&self, chain: Vec<&'a Node>) -> Vec<Vec<&'a Node>> {
+        // Indices of the given nodes are never read, only those selected below
+        let chain = chain.into_iter().map(|node| (0, node)).collect();
+        self.select_indexed(chain)
+            .into_iter()
+            .map(|chain| chain.into_iter().map(|(_, node)| node).collect())
+            .collect()
+    }
+
+    fn select_indexed<'a>(&self, chain: Chain<'a>) -> Vec<Chain<'a>> {
+        let start = if self.absolute {
+            chain.into_iter().take(1).collect()
+        } else {
+            chain
+        };
+        if start.is_empty() {
+            return Vec::new();
+        }
+        apply_steps(&self.steps, vec![start])
+    }
+
+    /// Nodes a predicate field selects from a candidate node, with the
+    /// first-element fallbacks described in the module documentation
+    fn select_field<'a>(&self, chain: &[(usize, &'a Node)]) -> Vec<Chain<'a>> {
+        let found = self.select_indexed(chain.to_vec());
+        if !found.is_empty() || self.absolute {
+            return found;
+        }
+        let Some((first, rest)) = self.steps.split_first() else {
+            return found;
+        };
+        let (Axis::Child, Some(name)) = (first.axis, first.name.as_deref()) else {
+            return found;
+        };
+        let Some(element) = chain.last().and_then(|(_, node)| node.children.first()) else {
+            return found;
+        };
+
+        let element_chain = extend(chain, 0, element);
+        let mut candidates: Vec<Chain<'a>> = element
+            .children
+            .iter()
+            .enumerate()
+            .filter(|(_, component)| name_matches(Some(name), component))
+            .map(|(index, component)| extend(&element_chain, index, component))
+            .collect();
+        let untyped_qualifier = element.schema_type.is_none() && is_data_element_id(name);
+        if candidates.is_empty() && (name == "c1" || untyped_qualifier) {
+            candidates.push(element_chain);
+        }
+        apply_steps(rest, first.filter(candidates))
+    }
+}
+
+impl FromStr for Path {
+    type Err = Error;
+
+    fn from_str(source: &str) -> Result<Self> {
+        Self::parse(source)
+    }
+}
+
+impl fmt::Display for Path {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+fn apply_steps<'a>(steps: &[Step], mut contexts: Vec<Chain<'a>>) -> Vec<Chain<'a>> {
+    for step in steps {
+        contexts = step.apply(contexts);
+    }
+    contexts
+}
+
+fn extend<'a>(chain: &[(usize, &'a Node)], index: usize, node: &'a Node) -> Chain<'a> {
+    let mut extended = chain.to_vec();
+    extended.push((index, node));
+    extended
+}
+
+/// Whether `name` is a data element id such as `3035`, as opposed to a
+/// node name
+fn is_data_element_id(name: &str) -> bool {
+    !name.is_empty() && name.bytes().all(|byte| byte.is_ascii_digit())
+}
+
+fn name_matches(name: Option<&str>, node: &Node) -> bool {
+    name.is_none_or(|name| node.name == name || node.schema_type.as_deref() == Some(name))
+}
+
+/// Text of a node for comparisons; a composite without a value of its own
+/// reads as its first component
+fn node_text(node: &Node) -> Option<String> {
+    node.value.as_ref().and_then(Value::as_string).or_else(|| {
+        node.children
+            .first()?
+            .value
+            .as_ref()
+            .and_then(Value::as_string)
+    })
+}
+
+impl Step {
+    fn apply<'a>(&self, contexts: Vec<Chain<'a>>) -> Vec<Chain<'a>> {
+        let mut seen = HashSet::new();
+        let mut selected = Vec::new();
+        for chain in contexts {
+            for matched in self.filter(self.candidates(&chain)) {
+                let node = matched.last().map(|&(_, node)| std::ptr::from_ref(node));
+                if seen.insert(node) {
+                    selected.push(matched);
+                }
+            }
+        }
+        selected
+    }
+
+    fn candidates<'a>(&self, chain: &[(usize, &'a Node)]) -> Vec<Chain<'a>> {
+        let Some(&(_, node)) = chain.last() else {
+            return Vec::new();
+        };
+        let candidates = match self.axis {
+            Axis::Current => vec![chain.to_vec()],
+            Axis::Parent => chain
+                .len()
+                .checked_sub(1)
+                .filter(|&len| len > 0)
+                .map(|len| chain[..len].to_vec())
+                .into_iter()
+                .collect(),
+            Axis::Child => node
+                .children
+                .iter()
+                .enumerate()
+                .map(|(index, child)| extend(chain, index, child))
+                .collect(),
+            Axis::Descendant => {
+                let mut descendants = Vec::new();
+                collect_descendants(chain, &mut descendants);
+                descendants
+            }
+            Axis::Ancestor => (1..chain.len())
+                .rev()
+                .map(|len| chain[..len].to_vec())
+                .collect(),
+        };
+        candidates
+            .into_iter()
+            .filter(|candidate| {
+                candidate
+                    .last()
+                    .is_some_and(|(_, node)| name_matches(self.name.as_deref(), node))
+            })
+            .collect()
+    }
+
+    /// Apply the predicates in order; positions count within the candidates
+    /// left by the previous predicate
+    fn filter<'a>(&self, mut candidates: Vec<Chain<'a>>) -> Vec<Chain<'a>> {
+        for predicate in &self.predicates {
+            let size = candidates.len();
+            candidates = candidates
+                .into_iter()
+                .enumerate()
+                .filter(|(position, chain)| predicate.eval(chain, *position, size))
+                .map(|(_, chain)| chain)
+                .collect();
+        }
+        candidates
+    }
+}
+
+fn collect_descendants<'a>(chain: &[(usize, &'a Node)], descendants: &mut Vec<Chain<'a>>) {
+    let Some(&(_, node)) = chain.last() else {
+        return;
+    };
+    for (index, child) in node.children.iter().enumerate() {
+        let child_chain = extend(chain, index, child);
+        descendants.push(child_chain.clone());
+        collect_descendants(&child_chain, descendants);
+    }
+}
+
+impl Expr {
+    fn eval(&self, chain: &[(usize, &Node)], position: usize, size: usize) -> bool {
+        match self {
+            Expr::True => true,
+            Expr::Or(left, right) => {
+                left.eval(chain, position, size) || right.eval(chain, position, size)
+            }
+            Expr::And(left, right) => {
+                left.eval(chain, position, size) && right.eval(chain, position, size)
+            }
+            Expr::Not(inner) => !inner.eval(chain, position, size),
+            Expr::Exists(path) => !path.select_field(chain).is_empty(),
+            Expr::Compare(left, comparison, right) => {
+                let right = right.values(chain, position, size);
+                left.values(chain, position, size)
+                    .iter()
+                    .any(|left| right.iter().any(|right| comparison.holds(left, right)))
+            }
+        }
+    }
+
+    fn collect_fields<'p>(&'p self, fields: &mut Vec<&'p str>) {
+        match self {
+            Expr::True => {}
+            Expr::Or(left, right) | Expr::And(left, right) => {
+                left.collect_fields(fields);
+                right.collect_fields(fields);
+            }
+            Expr::Not(inner) => inner.collect_fields(fields),
+            Expr::Exists(path) => path.collect_field_name(fields),
+            Expr::Compare(left, _, right) => {
+                for operand in [left, right] {
+                    if let Operand::Field(path) = operand {
+                        path.collect_field_name(fields);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Path {
+    fn collect_field_name<'p>(&'p self, fields: &mut Vec<&'p str>) {
+        if let Some(name) = self.steps.first().and_then(|step| step.name.as_deref()) {
+            fields.push(name);
+        }
+        fields.extend(self.predicate_fields());
+    }
+}
+
+impl Operand {
+    fn values(&self, chain: &[(usize, &Node)], position: usize, size: usize) -> Vec<String> {
+        match self {
+            Operand::Field(path) => path
+                .select_field(chain)
+                .iter()
+                .filter_map(|chain| chain.last().and_then(|&(_, node)| node_text(node)))
+                .collect(),
+            Operand::Literal(literal) => vec![literal.clone()],
+            Operand::Position => vec![position.to_string()],
+            Operand::Last(offset) => (size.checked_sub(1 + offset))
+                .map(|last| last.to_string())
+                .into_iter()
+                .collect(),
+        }
+    }
+}
+
+impl Comparison {
+    fn holds(self, left: &str, right: &str) -> bool {
+        let ordering = || match (parse_edi_decimal(left), parse_edi_decimal(right)) {
+            (Some(left), Some(right)) => left.cmp(&right),
+            _ => left.cmp(right),
+        };
+        match self {
+            Comparison::Eq => left == right,
+            Comparison::Ne => left != right,
+            Comparison::Lt => ordering().is_lt(),
+            Comparison::Le => ordering().is_le(),
+            Comparison::Gt => ordering().is_gt(),
+            Comparison::Ge => ordering().is_ge(),
+        }
+    }
+}
+
+/// Recursive-descent parser over the path source
+struct Parser<'s> {
+    source: &'s str,
+    pos: usize,
+}
+
+impl<'s> Parser<'s> {
+    fn error(&self, reason: impl Into<String>) -> Error {
+        Error::invalid_path(self.source, reason)
+    }
+
+    fn rest(&self) -> &'s str {
+        &self.source[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        if self.rest().starts_with(token) {
+            self.pos += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    /// Consume `keyword` if it is followed by something other than a name
+    /// character
+    fn keyword(&mut self, keyword: &str) -> bool {
+        let follows_name = self
+            .rest()
+            .strip_prefix(keyword)
+            .is_some_and(|after| !after.starts_with(is_name_char));
+        follows_name && self.eat(keyword)
+    }
+
+    fn name(&mut self) -> &'s str {
+        let rest = self.rest();
+        let len = rest.find(|c| !is_name_char(c)).unwrap_or(rest.len());
+        self.pos += len;
+        &rest[..len]
+    }
+
+    fn path(&mut self) -> Result<Path> {
+        let start = self.pos;
+        let mut axis = Axis::Child;
+        let absolute = if self.eat("//") {
+            axis = Axis::Descendant;
+            true
+        } else {
+            self.eat("/")
+        };
+
+        let mut steps = Vec::new();
+        while self
+            .peek()
+            .is_some_and(|c| is_name_char(c) || c == '*' || c == '.')
+        {
+            steps.push(self.step(axis)?);
+            if self.eat("//") {
+                axis = Axis::Descendant;
+            } else if self.eat("/") {
+                axis = Axis::Child;
+            } else {
+                break;
+            }
+        }
+
+        Ok(Path {
+            source: self.source[start..self.pos].to_string(),
+            absolute,
+            steps,
+        })
+    }
+
+    fn step(&mut self, axis: Axis) -> Result<Step> {
+        if self.eat("..") {
+            return Ok(Step {
+                axis: Axis::Parent,
+                name: None,
+                predicates: Vec::new(),
+            });
+        }
+        if self.eat(".") {
+            return Ok(Step {
+                axis: Axis::Current,
+                name: None,
+                predicates: Vec::new(),
+            });
+        }
+
+        let axis = if self.eat("ancestor::") {
+            Axis::Ancestor
+        } else {
+            axis
+        };
+        let name = if self.eat("*") {
+            None
+        } else {
+            match self.name() {
+                "" => return Err(self.error(format!("Expected a name at offset {}", self.pos))),
+                name => Some(name.to_string()),
+            }
+        };
+
+        let mut predicates = Vec::new();
+        while self.eat("[") {
+            self.skip_whitespace();
+            let wildcard = self
+                .rest()
+                .strip_prefix('*')
+                .is_some_and(|rest| rest.trim_start().starts_with(']'));
+            let predicate = if wildcard && self.eat("*") {
+                Expr::True
+            } else {
+                self.expr()?
+            };
+            self.skip_whitespace();
+            if !self.eat("]") {
+                return Err(self.error(format!("Unclosed predicate at offset {}", self.pos)));
+            }
+            predicates.push(predicate);
+        }
+
+        Ok(Step {
+            axis,
+            name,
+            predicates,
+        })
+    }
+
+    fn expr(&mut self) -> Result<Expr> {
+        let mut left = self.and_expr()?;
+        loop {
+            self.skip_whitespace();
+            if !self.keyword("or") {
+                return Ok(left);
+            }
+            left = Expr::Or(Box::new(left), Box::new(self.and_expr()?));
+        }
+    }
+
+    fn and_expr(&mut self) -> Result<Expr> {
+        let mut left = self.primary()?;
+        loop {
+            self.skip_whitespace();
+            if !self.keyword("and") {
+                return Ok(left);
+            }
+            left = Expr::And(Box::new(left), Box::new(self.primary()?));
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr> {
+        self.skip_whitespace();
+        if self.keyword("not") {
+            self.skip_whitespace();
+            if !self.rest().starts_with('(') {
+                return Err(self.error("Expected '(' after 'not'"));
+            }
+            return Ok(Expr::Not(Box::new(self.primary()?)));
+        }
+        if self.eat("(") {
+            let inner = self.expr()?;
+            self.skip_whitespace();
+            if !self.eat(")") {
+                return Err(self.error(format!("Unclosed '(' at offset {}", self.pos)));
+            }
+            return Ok(inner);
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Expr> {
+        // Qualifier shorthand: ['137'] and [='137'] mean [c1='137']
+        if self.peek().is_some_and(|c| c == '\'' || c == '"') || self.eat("=") {
+            let qualifier = Path {
+                source: "c1".to_string(),
+                absolute: false,
+                steps: vec![Step {
+                    axis: Axis::Child,
+                    name: Some("c1".to_string()),
+                    predicates: Vec::new(),
+                }],
+            };
+            let value = self.value()?;
+            return Ok(Expr::Compare(
+                Operand::Field(qualifier),
+                Comparison::Eq,
+                value,
+            ));
+        }
+
+        let left = if self.eat("position()") {
+            Operand::Position
+        } else if self.eat("last()") {
+            self.last_offset()?
+        } else if let Some(index) = self.index()? {
+            return Ok(Expr::Compare(
+                Operand::Position,
+                Comparison::Eq,
+                Operand::Literal(index.to_string()),
+            ));
+        } else {
+            let path = self.path()?;
+            if path.steps.is_empty() && !path.absolute {
+                return Err(self.error(format!("Expected a predicate at offset {}", self.pos)));
+            }
+            Operand::Field(path)
+        };
+
+        self.skip_whitespace();
+        let Some(comparison) = self.comparison_operator() else {
+            return Ok(match left {
+                Operand::Field(path) => Expr::Exists(path),
+                position => Expr::Compare(Operand::Position, Comparison::Eq, position),
+            });
+        };
+        let right = self.value()?;
+        Ok(Expr::Compare(left, comparison, right))
+    }
+
+    /// A bare position such as `[2]`; a number followed by a comparison is a
+    /// data element id instead (`[2005=137]`)
+    fn index(&mut self) -> Result<Option<usize>> {
+        if !self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            return Ok(None);
+        }
+        let start = self.pos;
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| !(is_name_char(c) || c == '.'))
+            .unwrap_or(rest.len());
+        let token = &rest[..len];
+        self.pos += len;
+        self.skip_whitespace();
+        let followed_by_comparison = self.rest().starts_with(['=', '!', '<', '>']);
+        if followed_by_comparison || self.rest().starts_with('/') {
+            self.pos = start;
+            return Ok(None);
+        }
+        token
+            .parse()
+            .map(Some)
+            .map_err(|_| self.error(format!("Invalid index '{token}'")))
+    }
+
+    fn last_offset(&mut self) -> Result<Operand> {
+        if !self.eat("-") {
+            return Ok(Operand::Last(0));
+        }
+        let digits = self.name();
+        digits
+            .parse()
+            .map(Operand::Last)
+            .map_err(|_| self.error(format!("Invalid offset '{digits}' after 'last()'")))
+    }
+
+    fn comparison_operator(&mut self) -> Option<Comparison> {
+        [
+            ("!=", Comparison::Ne),
+            ("<=", Comparison::Le),
+            (">=", Comparison::Ge),
+            ("=", Comparison::Eq),
+            ("<", Comparison::Lt),
+            (">", Comparison::Gt),
+        ]
+        .into_iter()
+        .find(|(token, _)| self.eat(token))
+        .map(|(_, comparison)| comparison)
+    }
+
+    /// Right-hand side of a comparison: a quoted or bare literal, or a
+    /// position function
+    fn value(&mut self) -> Result<Operand> {
+        self.skip_whitespace();
+        if self.eat("position()") {
+            return Ok(Operand::Position);
+        }
+        if self.eat("last()") {
+            return self.last_offset();
+        }
+        if let Some(quote) = self.peek().filter(|c| *c == '\'' || *c == '"') {
+            self.pos += 1;
+            let Some(len) = self.rest().find(quote) else {
+                return Err(self.error(format!("Unterminated string at offset {}", self.pos - 1)));
+            };
+            let literal = self.rest()[..len].to_string();
+            self.pos += len + 1;
+            return Ok(Operand::Literal(literal));
+        }
+
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| c.is_whitespace() || c == ']' || c == ')')
+            .unwrap_or(rest.len());
+        if len == 0 {
+            return Err(self.error(format!("Expected a value at offset {}", self.pos)));
+        }
+        self.pos += len;
+        Ok(Operand::Literal(rest[..len].to_string()))
+    }
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '-'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node::{NodeType, Value};
+
+    fn segment(tag: &str, elements: &[&[&str]]) -> Node {
+        let mut segment = Node::new(tag, NodeType::Segment);
+        for (idx, components) in elements.iter().enumerate() {
+            let name = format!("e{}", idx + 1);
+            if let [value] = components {
+                segment.add_child(Node::with_value(
+                    name,
+                    NodeType::Element,
+                    Value::String((*value).to_string()),
+                ));
+                continue;
+            }
+            let mut element = Node::new(name, NodeType::Element);
+            for (idx, value) in components.iter().enumerate() {
+                element.add_child(Node::with_value(
+                    format!("c{}", idx + 1),
+                    NodeType::Component,
+                    Value::String((*value).to_string()),
+                ));
+            }
+            segment.add_child(element);
+        }
+        segment
+    }
+
+    fn order() -> Node {
+        let mut root = Node::new("ORDERS", NodeType::Root);
+        root.add_child(segment("DTM", &[&["137", "20260115", "102"]]));
+        for (party, id) in [("BY", "4012345000009"), ("SU", "4098765000002")] {
+            let mut group = Node::new("SG2", NodeType::SegmentGroup);
+            group.add_child(segment("NAD", &[&[party], &[id, "", "9"]]));
+            root.add_child(group);
+        }
+        for (line, quantity) in [("1", "120"), ("2", "8"), ("3", "35")] {
+            let mut group = Node::new("SG25", NodeType::SegmentGroup);
+            group.add_child(segment("LIN", &[&[line]]));
+            group.add_child(segment("QTY", &[&["21", quantity, "PCE"]]));
+            root.add_child(group);
+        }
+        root
+    }
+
+    fn texts(path: &str, node: &Node) -> Vec<String> {
+        Path::parse(path)
+            .unwrap()
+            .select(node)
+            .into_iter()
+            .filter_map(node_text)
+            .collect()
+    }
+
+    #[test]
+    fn test_child_steps_wildcards_and_descendants() {
+        let root = order();
+
+        assert_eq!(texts("SG2/NAD/e1", &root), vec!["BY", "SU"]);
+        assert_eq!(
+            texts("/SG25/*/e1", &root),
+            vec!["1", "21", "2", "21", "3", "21"]
+        );
+        assert_eq!(texts("//QTY/e1/c2", &root), vec!["120", "8", "35"]);
+        assert_eq!(texts("SG25//c3", &root), vec!["PCE", "PCE", "PCE"]);
+        assert_eq!(Path::parse("").unwrap().select(&root).len(), 1);
+    }
+
+    #[test]
+    fn test_positions() {
+        let root = order();
+
+        assert_eq!(texts("SG25[0]/LIN/e1", &root), vec!["1"]);
+        assert_eq!(texts("SG25[last()]/LIN/e1", &root), vec!["3"]);
+        assert_eq!(texts("SG25[last()-1]/LIN/e1", &root), vec!["2"]);
+        assert_eq!(texts("SG25[position() > 0]/LIN/e1", &root), vec!["2", "3"]);
+        assert!(texts("SG25[5]/LIN/e1", &root).is_empty());
+    }
+
+    #[test]
+    fn test_predicates_filter_on_values() {
+        let root = order();
+
+        assert_eq!(texts("SG2/NAD[e1=BY]/e2", &root), vec!["4012345000009"]);
+        assert_eq!(
+            texts("SG2/NAD[e1='SU' and e2/c3=9]/e2", &root),
+            vec!["4098765000002"]
+        );
+        assert_eq!(texts("SG2/NAD[e1=BY or e1=SU]/e1", &root), vec!["BY", "SU"]);
+        assert_eq!(texts("SG2/NAD[not(e1=BY)]/e1", &root), vec!["SU"]);
+        assert_eq!(texts("SG25[QTY/e1/c2 >= 35]/LIN/e1", &root), vec!["1", "3"]);
+        assert_eq!(
+            texts("SG25[QTY/e1/c2 < 100][last()]/LIN/e1", &root),
+            vec!["3"]
+        );
+        assert_eq!(texts("SG25[LIN/e1 != 2]/LIN/e1", &root), vec!["1", "3"]);
+        assert_eq!(texts("DTM[c2 > 20251231]/e1/c2", &root), vec!["20260115"]);
+        assert_eq!(texts("SG2[NAD]/NAD/e1", &root), vec!["BY", "SU"]);
+        assert_eq!(texts("SG2[*]/NAD/e1", &root), vec!["BY", "SU"]);
+    }
+
+    #[test]
+    fn test_qualifier_shorthands() {
+        let root = order();
+
+        assert_eq!(texts("DTM['137']/e1/c2", &root), vec!["20260115"]);
+        assert_eq!(texts("DTM[='137']/e1/c2", &root), vec!["20260115"]);
+        assert_eq!(texts("DTM[2005=137]/e1/c2", &root), vec!["20260115"]);
+        assert_eq!(texts("SG2/NAD[3035=SU]/e2", &root), vec!["4098765000002"]);
+        assert!(texts("DTM[typo=137]/e1/c2", &root).is_empty());
+    }
+
+    #[test]
+    fn test_typed_qualifiers_resolve_through_schema_types() {
+        let mut root = order();
+        let dtm = &mut root.children[0];
+        dtm.children[0].set_schema_type("C507");
+        dtm.children[0].children[0].set_schema_type("2005");
+
+        assert_eq!(texts("DTM[2005=137]/e1/c2", &root), vec!["20260115"]);
+        assert!(texts("DTM[2379=137]/e1/c2", &root).is_empty());
+        assert_eq!(texts("SG2/NAD[3035=SU]/e2", &root), vec!["4098765000002"]);
+    }
+
+    #[test]
+    fn test_names_match_schema_types() {
+        let mut root = order();
+        let dtm = &mut root.children[0];
+        dtm.children[0].set_schema_type("C507");
+        dtm.children[0].children[1].set_schema_type("2380");
+
+        assert_eq!(texts("DTM/C507/2380", &root), vec!["20260115"]);
+        assert_eq!(texts("DTM[C507/2380=20260115]/e1/c3", &root), vec!["102"]);
+    }
+
+    #[test]
+    fn test_parent_and_ancestor_steps() {
+        let root = order();
+
+        assert_eq!(texts("//QTY[e1/c2=8]/../LIN/e1", &root), vec!["2"]);
+        assert_eq!(texts("//c2[.=8]/ancestor::SG25/LIN/e1", &root), vec!["2"]);
+        assert_eq!(
+            texts("//NAD/ancestor::*[0]/NAD/e1", &root),
+            vec!["BY", "SU"]
+        );
+        assert!(texts("..", &root).is_empty());
+        assert_eq!(texts("SG25/..//LIN[e1=1]/e1", &root), vec!["1"]);
+    }
+
+    #[test]
+    fn test_invalid_paths() {
+        for (path, reason) in [
+            ("LIN[0", "Unclosed predicate"),
+            ("LIN[]", "Expected a predicate"),
+            ("LIN[=]", "Expected a value"),
+            ("LIN[1.5]", "Invalid index"),
+            ("LIN[e1='BY]", "Unterminated string"),
+            ("LIN[last()-x]", "Invalid offset"),
+            ("LIN[not e1]", "Expected '('"),
+            ("LIN]", "Unexpected ']'"),
+        ] {
+            match Path::parse(path) {
+                Err(Error::InvalidPath {
+                    path: source,
+                    reason: actual,
+                }) => {
+                    assert_eq!(source, path);
+                    assert!(actual.contains(reason), "{path}: {actual}");
+                }
+                other => panic!("{path}: expected invalid path, got {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_predicate_fields() {
+        let path: Path = "SG2[NAD/e1=BY]/NAD[e2/c3=9 and not(c1='x')]"
+            .parse()
+            .unwrap();
+        assert_eq!(path.predicate_fields(), vec!["NAD", "e2", "c1"]);
+        assert_eq!(
+            path.to_string(),
+            "SG2[NAD/e1=BY]/NAD[e2/c3=9 and not(c1='x')]"
+        );
+    }
+}
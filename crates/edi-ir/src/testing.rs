@@ -0,0 +1,38 @@
+//! Fixture builders for tests
+//!
+//! Compiled for this crate's tests and, with the `test-util` feature, for
+//! the tests of crates that build IR trees by hand.
+
+use crate::document::Document;
+use crate::node::{Node, NodeType, Value};
+
+/// Segment `tag` with a single composite element whose components hold
+/// `values` (`e1/c1`, `e1/c2`, ...)
+#[must_use]
+pub fn segment(tag: &str, values: &[&str]) -> Node {
+    let mut element = Node::new("e1", NodeType::Element);
+    for (idx, value) in values.iter().enumerate() {
+        element.add_child(Node::with_value(
+            format!("c{}", idx + 1),
+            NodeType::Component,
+            Value::String((*value).to_string()),
+        ));
+    }
+    let mut segment = Node::new(tag, NodeType::Segment);
+    segment.add_child(element);
+    segment
+}
+
+/// ORDERS document with one `SG25` group per `(line number, quantity
+/// qualifier)` pair, holding `LIN` and a `QTY` of 10 KGM
+#[must_use]
+pub fn order(lines: &[(&str, &str)]) -> Document {
+    let mut root = Node::new("ORDERS", NodeType::Root);
+    for (line, qualifier) in lines {
+        let mut group = Node::new("SG25", NodeType::SegmentGroup);
+        group.add_child(segment("LIN", &[line]));
+        group.add_child(segment("QTY", &[qualifier, "10", "KGM"]));
+        root.add_child(group);
+    }
+    Document::new(root)
+}
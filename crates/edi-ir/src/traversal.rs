@@ -5,12 +5,16 @@
 use crate::Error;
 use crate::Result;
 use crate::node::Node;
+use crate::path::Path;
 
 /// A cursor for navigating the IR tree
 pub struct Cursor<'a> {
     /// Current node
     node: &'a Node,
 
+    /// Ancestors of the current node, root first (for parent steps)
+    ancestors: Vec<&'a Node>,
+
     /// Path to current node (for error reporting)
     path: Vec<String>,
 }
@@ -38,6 +42,7 @@ impl<'a> Cursor<'a> {
     pub fn new(node: &'a Node) -> Self {
         Self {
             node,
+            ancestors: Vec::new(),
             path: vec![node.name.clone()],
         }
     }
@@ -66,6 +71,7 @@ impl<'a> Cursor<'a> {
                 new_path.push(name.to_string());
                 Ok(Cursor {
                     node: child,
+                    ancestors: self.chain(),
                     path: new_path,
                 })
             }
@@ -89,6 +95,7 @@ impl<'a> Cursor<'a> {
                 new_path.push(format!("[{index}]"));
                 Ok(Cursor {
                     node: child,
+                    ancestors: self.chain(),
                     path: new_path,
                 })
             }
@@ -112,67 +119,87 @@ impl<'a> Cursor<'a> {
                 new_path.push(format!("{name}[{idx}]"));
                 Cursor {
                     node: child,
+                    ancestors: self.chain(),
                     path: new_path,
                 }
             })
             .collect()
     }
 
-    /// Navigate using a path (e.g., "ORDERS/MSG/ITEM[0]/LIN")
+    /// Navigate to the first node selected by a [`Path`] expression (e.g.,
+    /// "ORDERS/MSG/ITEM[0]/LIN" or "//NAD[e1=BY]")
+    ///
+    /// Absolute paths start at the node the cursor was created on.
     ///
     /// # Errors
     ///
     /// Returns [`Error::InvalidPath`] for malformed path syntax and
-    /// [`Error::NodeNotFound`] when a referenced node does not exist.
+    /// [`Error::NodeNotFound`] when the path selects no node.
     pub fn navigate(&self, path: &str) -> Result<Cursor<'a>> {
-        let mut current_node = self.node;
-        let mut current_path = self.path.clone();
+        self.select(path)?.into_iter().next().ok_or_else(|| {
+            Error::node_not_found(format!(
+                "{}/{}",
+                self.path.join("/"),
+                path.trim_start_matches('/')
+            ))
+        })
+    }
 
-        for segment in path.split('/') {
-            if segment.is_empty() {
-                continue;
-            }
+    /// Get cursors for all nodes selected by a [`Path`] expression, in
+    /// document order
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidPath`] for malformed path syntax.
+    pub fn select(&self, path: &str) -> Result<Vec<Cursor<'a>>> {
+        Ok(self.select_path(&Path::parse(path)?))
+    }
+
+    /// Get cursors for all nodes selected by a compiled path
+    #[must_use]
+    pub fn select_path(&self, path: &Path) -> Vec<Cursor<'a>> {
+        path.select_chains(self.chain())
+            .into_iter()
+            .filter_map(|mut chain| {
+                let node = chain.pop()?;
+                let path = Self::chain_path(&chain, node);
+                Some(Cursor {
+                    node,
+                    ancestors: chain,
+                    path,
+                })
+            })
+            .collect()
+    }
+
+    /// Ancestors followed by the current node
+    fn chain(&self) -> Vec<&'a Node> {
+        let mut chain = self.ancestors.clone();
+        chain.push(self.node);
+        chain
+    }
 
-            // Handle array indexing like "ITEM[0]"
-            if let Some(open_bracket) = segment.find('[') {
-                let name = &segment[..open_bracket];
-                let close_bracket = segment.find(']').ok_or_else(|| {
-                    Error::invalid_path(path, format!("Unclosed bracket in segment '{segment}'"))
-                })?;
-                let index: usize =
-                    segment[open_bracket + 1..close_bracket]
-                        .parse()
-                        .map_err(|_| {
-                            Error::invalid_path(
-                                path,
-                                format!("Invalid index in segment '{segment}'"),
-                            )
-                        })?;
-
-                // Find children by name
-                let children: Vec<&Node> = current_node
-                    .children
-                    .iter()
-                    .filter(|c| c.name == name)
-                    .collect();
-
-                current_node = children.get(index).ok_or_else(|| {
-                    Error::node_not_found(format!("{}/{}", current_path.join("/"), segment))
-                })?;
-                current_path.push(format!("{name}[{index}]"));
-            } else {
-                current_node = current_node.find_child(segment).ok_or_else(|| {
-                    Error::node_not_found(format!("{}/{}", current_path.join("/"), segment))
-                })?;
-                current_path.push(segment.to_string());
+    /// Path names from the root to `node`; a node that shares its name with
+    /// siblings is shown with its index (`ITEM[1]`)
+    fn chain_path(ancestors: &[&Node], node: &Node) -> Vec<String> {
+        let mut path = Vec::with_capacity(ancestors.len() + 1);
+        let mut parent: Option<&Node> = None;
+        for current in ancestors.iter().copied().chain(std::iter::once(node)) {
+            let siblings: Vec<&Node> = parent
+                .map(|parent| parent.find_children(&current.name))
+                .unwrap_or_default();
+            match siblings
+                .iter()
+                .position(|sibling| std::ptr::eq(*sibling, current))
+            {
+                Some(index) if siblings.len() > 1 => {
+                    path.push(format!("{}[{index}]", current.name));
+                }
+                _ => path.push(current.name.clone()),
             }
+            parent = Some(current);
         }
-
-        // Return a new cursor with the accumulated path
-        Ok(Cursor {
-            node: current_node,
-            path: current_path,
-        })
+        path
     }
 }
 
@@ -382,11 +409,11 @@ mod tests {
         }
 
         // Invalid index
-        let result = cursor.navigate("ITEM[abc]");
+        let result = cursor.navigate("ITEM[1.5]");
         assert!(result.is_err());
         match result {
             Err(Error::InvalidPath { path, reason }) => {
-                assert_eq!(path, "ITEM[abc]");
+                assert_eq!(path, "ITEM[1.5]");
                 assert!(reason.contains("Invalid index"));
             }
             _ => panic!("Expected InvalidPath error"),
@@ -406,6 +433,34 @@ mod tests {
         assert_eq!(child_cursor.node().name, "CHILD");
     }
 
+    #[test]
+    fn test_cursor_select_with_predicates_and_parent_steps() {
+        let mut root = Node::new("ROOT", NodeType::Root);
+        for qualifier in ["BY", "SU"] {
+            let mut group = Node::new("GROUP", NodeType::SegmentGroup);
+            let mut nad = Node::new("NAD", NodeType::Segment);
+            nad.add_child(Node::with_value(
+                "e1",
+                NodeType::Element,
+                crate::node::Value::String(qualifier.to_string()),
+            ));
+            group.add_child(nad);
+            root.add_child(group);
+        }
+
+        let cursor = Cursor::new(&root);
+        let supplier = cursor.navigate("//NAD[e1=SU]").unwrap();
+        assert_eq!(supplier.path(), &["ROOT", "GROUP[1]", "NAD"]);
+
+        let group = supplier.navigate("..").unwrap();
+        assert_eq!(group.path(), &["ROOT", "GROUP[1]"]);
+        let nads = group.select("/GROUP/NAD").unwrap();
+        assert_eq!(nads.len(), 2);
+
+        let child = cursor.child_at(0).unwrap().child("NAD").unwrap();
+        assert_eq!(child.navigate("ancestor::ROOT").unwrap().path(), &["ROOT"]);
+    }
+
     // Test visitor for traversal tests
     struct TestVisitor {
         visited: Vec<String>,
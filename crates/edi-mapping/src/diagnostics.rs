@@ -6,6 +6,7 @@
 
 use std::fmt::Write as _;
 
+use edi_ir::Path;
 use edi_schema::Schema;
 
 use crate::dsl::{Condition, Mapping, MappingRule, Transform};
@@ -194,15 +195,20 @@ fn lint_path_against_schema(
     schema: &Schema,
     diagnostics: &mut Vec<MappingDiagnostic>,
 ) {
+    // The first named step: `.` and `..` steps are skipped and ancestor
+    // steps name the group they select
     let Some(segment) = path
         .split('/')
-        .find(|part| !part.is_empty())
-        .map(strip_selector)
+        .map(|part| strip_selector(part).trim_start_matches("ancestor::"))
+        .find(|part| !matches!(*part, "" | "." | ".."))
     else {
         return;
     };
 
-    if schema.find_segment(segment).is_some() || is_schema_agnostic_path(segment) {
+    if schema.find_segment(segment).is_some()
+        || schema.find_group(segment).is_some()
+        || is_schema_agnostic_path(segment)
+    {
         return;
     }
 
@@ -321,52 +327,44 @@ fn lint_condition(
 }
 
 fn lint_path(path: &str, rule_path: &str, diagnostics: &mut Vec<MappingDiagnostic>) {
-    for component in path.split('/').filter(|part| !part.is_empty()) {
-        let Some((raw_key, raw_value)) = extract_selector(component) else {
-            continue;
-        };
-        let key = raw_key.trim();
-        let value = clean_selector_literal(raw_value);
-        if key == "*" || (key.is_empty() && value == "*") {
-            continue;
+    let compiled = match Path::parse(path) {
+        Ok(compiled) => compiled,
+        Err(error) => {
+            diagnostics.push(MappingDiagnostic {
+                severity: DiagnosticSeverity::Warning,
+                rule_path: rule_path.to_string(),
+                source_path: path.to_string(),
+                message: error.to_string(),
+            });
+            return;
         }
-        let normalized_key = if key.is_empty() { "c1" } else { key };
-        if !is_supported_selector_key(normalized_key) {
+    };
+
+    for key in compiled.predicate_fields() {
+        if !is_supported_selector_key(key) {
             diagnostics.push(MappingDiagnostic {
                 severity: DiagnosticSeverity::Warning,
                 rule_path: rule_path.to_string(),
                 source_path: path.to_string(),
                 message: format!(
-                    "unsupported selector key '{normalized_key}' in path '{path}'; supported selector keys are cN, eN, and known EDI qualifier codes (1153, 2005, 3035, 5025, 5125, 6063)"
+                    "unsupported selector key '{key}' in path '{path}'; supported selector keys are cN, eN, segment, group or composite names, and data element ids"
                 ),
             });
         }
     }
 }
 
-fn extract_selector(component: &str) -> Option<(&str, &str)> {
-    let selector_start = component.find('[')?;
-    if !component.ends_with(']') || selector_start == 0 {
-        return None;
-    }
-    let selector = &component[selector_start + 1..component.len() - 1];
-    selector.split_once('=').or(Some(("", selector)))
-}
-
-fn clean_selector_literal(value: &str) -> &str {
-    value.trim().trim_matches('\'').trim_matches('"')
-}
-
+/// Predicate keys a path can resolve on EDIFACT trees: components,
+/// elements, child segments, groups or composites by name or schema type,
+/// and data element ids
 fn is_supported_selector_key(key: &str) -> bool {
-    let normalized = key.trim();
-    normalized.eq_ignore_ascii_case("c1")
-        || normalized
-            .strip_prefix(['c', 'C', 'e', 'E'])
-            .is_some_and(|suffix| !suffix.is_empty() && suffix.chars().all(|c| c.is_ascii_digit()))
-        || matches!(
-            normalized,
-            "1153" | "2005" | "3035" | "5025" | "5125" | "6063"
-        )
+    key.strip_prefix(['c', 'C', 'e', 'E'])
+        .is_some_and(|suffix| !suffix.is_empty() && suffix.chars().all(|c| c.is_ascii_digit()))
+        || key.starts_with(|c: char| c.is_ascii_uppercase())
+            && key
+                .chars()
+                .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+        || !key.is_empty() && key.chars().all(|c| c.is_ascii_digit())
 }
 
 fn explain_rules(rules: &[MappingRule], indent: usize, output: &mut String) {
@@ -2,7 +2,7 @@
 //!
 //! Provides mechanisms for registering and calling custom extension functions.
 
-use crate::numeric::value_to_f64;
+use crate::numeric::value_to_decimal;
 use edi_ir::Value;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
@@ -384,8 +384,8 @@ pub fn create_math_utils_extension() -> Extension {
                 "add requires 2 arguments".to_string(),
             ));
         }
-        let a = value_to_f64(&args[0], "first")?;
-        let b = value_to_f64(&args[1], "second")?;
+        let a = value_to_decimal(&args[0], "first")?;
+        let b = value_to_decimal(&args[1], "second")?;
         Ok(Value::Decimal(a + b))
     })
     .register_function("multiply", |args| {
@@ -394,8 +394,8 @@ pub fn create_math_utils_extension() -> Extension {
                 "multiply requires 2 arguments".to_string(),
             ));
         }
-        let a = value_to_f64(&args[0], "first")?;
-        let b = value_to_f64(&args[1], "second")?;
+        let a = value_to_decimal(&args[0], "first")?;
+        let b = value_to_decimal(&args[1], "second")?;
         Ok(Value::Decimal(a * b))
     });
 
@@ -405,6 +405,7 @@ pub fn create_math_utils_extension() -> Extension {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use edi_ir::Decimal;
 
     // Register extension tests
     #[test]
@@ -481,14 +482,7 @@ mod tests {
         let mut ext = Extension::new("math_ops", "1.0.0");
 
         ext.register_function("sum", |args| {
-            let sum: f64 = args
-                .iter()
-                .filter_map(|v| match v {
-                    Value::Integer(i) => i.to_string().parse::<f64>().ok(),
-                    Value::Decimal(d) => Some(*d),
-                    _ => None,
-                })
-                .sum();
+            let sum: Decimal = args.iter().filter_map(Value::as_decimal).sum();
             Ok(Value::Decimal(sum))
         });
 
@@ -498,10 +492,14 @@ mod tests {
             .call(
                 "math_ops",
                 "sum",
-                &[Value::Integer(10), Value::Integer(20), Value::Decimal(5.5)],
+                &[
+                    Value::Integer(10),
+                    Value::Integer(20),
+                    Value::Decimal("5.5".parse().unwrap()),
+                ],
             )
             .unwrap();
-        assert_eq!(result, Value::Decimal(35.5));
+        assert_eq!(result, Value::Decimal("35.5".parse().unwrap()));
     }
 
     #[test]
@@ -751,17 +749,17 @@ mod tests {
                 &[Value::Integer(10), Value::Integer(20)],
             )
             .unwrap();
-        assert_eq!(result, Value::Decimal(30.0));
+        assert_eq!(result, Value::Decimal("30.0".parse().unwrap()));
 
         // Test multiply
         let result = registry
             .call(
                 "math_utils",
                 "multiply",
-                &[Value::Decimal(5.5), Value::Integer(2)],
+                &[Value::Decimal("5.5".parse().unwrap()), Value::Integer(2)],
             )
             .unwrap();
-        assert_eq!(result, Value::Decimal(11.0));
+        assert_eq!(result, Value::Decimal("11.0".parse().unwrap()));
     }
 
     // Edge case tests
@@ -904,7 +902,7 @@ mod tests {
                 ],
             )
             .unwrap();
-        assert_eq!(result, Value::Decimal(31.0));
+        assert_eq!(result, Value::Decimal("31.0".parse().unwrap()));
     }
 
     #[test]
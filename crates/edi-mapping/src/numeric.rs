@@ -1,14 +1,10 @@
-use edi_ir::Value;
+use edi_ir::{Decimal, Value, parse_edi_decimal};
 
-pub(crate) fn value_to_f64(value: &Value, arg_label: &str) -> crate::Result<f64> {
+pub(crate) fn value_to_decimal(value: &Value, arg_label: &str) -> crate::Result<Decimal> {
     match value {
-        Value::Integer(i) => i.to_string().parse::<f64>().map_err(|error| {
-            crate::Error::Transform(format!(
-                "Cannot convert {arg_label} integer argument to number: {error}"
-            ))
-        }),
-        Value::Decimal(d) => Ok(*d),
-        Value::String(s) => s.parse::<f64>().map_err(|_| {
+        Value::Integer(i) => Ok(Decimal::from(*i)),
+        Value::Decimal(d) => Ok(d.clone()),
+        Value::String(s) => parse_edi_decimal(s).ok_or_else(|| {
             crate::Error::Transform(format!("Cannot parse {arg_label} argument as number"))
         }),
         _ => Err(crate::Error::Transform(format!(
@@ -2,7 +2,7 @@
 //!
 //! Provides runtime execution engine for DSL mappings.
 
-use edi_ir::{Document, Node, NodeType, Value};
+use edi_ir::{Decimal, Document, Node, NodeType, Path, SourceSpan, Value, parse_edi_decimal};
 use serde::Serialize;
 use std::collections::{HashMap, HashSet};
 
@@ -18,14 +18,11 @@ pub struct MappingRuntime {
     /// Lookup tables available for the current mapping execution
     lookup_tables: HashMap<String, LookupDefinition>,
 
-    /// Root node for absolute path resolution
-    root_node: Option<Node>,
-
-    /// Context stack for nested execution
-    context_stack: Vec<MappingContext>,
-
     /// Rule diagnostics captured during traced execution.
     trace_events: Option<Vec<MappingTraceEvent>>,
+
+    /// Compiled source paths by expression; `None` for invalid paths
+    paths: HashMap<String, Option<Path>>,
 }
 
 /// Trace for a complete mapping dry run.
@@ -78,13 +75,23 @@ pub struct MappingTraceEvent {
     /// Whether a lookup found an explicit entry.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub lookup_hit: Option<bool>,
+    /// Source location of the node a field or lookup rule read from.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_span: Option<SourceSpan>,
 }
 
-/// Execution context for a mapping
+/// Execution context for a mapping, borrowing the source document
 #[derive(Debug, Clone)]
-pub struct MappingContext {
+pub struct MappingContext<'a> {
     /// Current source node being processed
-    pub source_node: Node,
+    pub source_node: &'a Node,
+
+    /// Root of the source document, for absolute paths
+    pub root_node: &'a Node,
+
+    /// Ancestors of the source node, root first, for parent and ancestor
+    /// steps
+    pub ancestors: Vec<&'a Node>,
 
     /// Current target node being built
     pub target_node: Option<Node>,
@@ -99,12 +106,14 @@ pub struct MappingContext {
     pub loop_index: Option<usize>,
 }
 
-impl MappingContext {
-    /// Create a new mapping context
+impl<'a> MappingContext<'a> {
+    /// Create a new mapping context rooted at `source_node`
     #[must_use]
-    pub fn new(source_node: Node) -> Self {
+    pub fn new(source_node: &'a Node) -> Self {
         Self {
             source_node,
+            root_node: source_node,
+            ancestors: Vec::new(),
             target_node: None,
             variables: HashMap::new(),
             path: String::new(),
@@ -112,11 +121,14 @@ impl MappingContext {
         }
     }
 
-    /// Create a child context for nested execution
+    /// Create a child context for nested execution, with `source_node` as a
+    /// child of the current source node
     #[must_use]
-    pub fn child_context(&self, source_node: Node, path: impl Into<String>) -> Self {
+    pub fn child_context(&self, source_node: &'a Node, path: impl Into<String>) -> Self {
         Self {
             source_node,
+            root_node: self.root_node,
+            ancestors: self.chain(),
             target_node: None,
             variables: self.variables.clone(),
             path: path.into(),
@@ -124,6 +136,13 @@ impl MappingContext {
         }
     }
 
+    /// The source node with its ancestors, root first
+    fn chain(&self) -> Vec<&'a Node> {
+        let mut chain = self.ancestors.clone();
+        chain.push(self.source_node);
+        chain
+    }
+
     /// Set a variable
     pub fn set_variable(&mut self, name: impl Into<String>, value: Value) {
         self.variables.insert(name.into(), value);
@@ -147,17 +166,14 @@ impl MappingContext {
 }
 
 impl MappingRuntime {
-    const INVALID_SELECTOR_KEY: &str = "__invalid_selector_key__";
-
     /// Create a new mapping runtime
     #[must_use]
     pub fn new() -> Self {
         Self {
             extensions: ExtensionRegistry::new(),
             lookup_tables: HashMap::new(),
-            root_node: None,
-            context_stack: Vec::new(),
             trace_events: None,
+            paths: HashMap::new(),
         }
     }
 
@@ -167,9 +183,8 @@ impl MappingRuntime {
         Self {
             extensions,
             lookup_tables: HashMap::new(),
-            root_node: None,
-            context_stack: Vec::new(),
             trace_events: None,
+            paths: HashMap::new(),
         }
     }
 
@@ -179,10 +194,9 @@ impl MappingRuntime {
     ///
     /// Returns an error if any mapping rule fails during execution.
     pub fn execute(&mut self, mapping: &Mapping, document: &Document) -> crate::Result<Document> {
-        let root_node = document.root.clone();
         self.lookup_tables.clone_from(&mapping.lookups);
-        self.root_node = Some(root_node.clone());
-        let mut context = MappingContext::new(root_node);
+        self.compile_rule_paths(&mapping.rules);
+        let mut context = MappingContext::new(&document.root);
 
         let result = (|| {
             // Execute all rules
@@ -200,7 +214,6 @@ impl MappingRuntime {
         })();
 
         self.lookup_tables.clear();
-        self.root_node = None;
         result
     }
 
@@ -238,7 +251,7 @@ impl MappingRuntime {
     fn execute_rule(
         &mut self,
         rule: &MappingRule,
-        context: &mut MappingContext,
+        context: &mut MappingContext<'_>,
     ) -> crate::Result<()> {
         match rule {
             MappingRule::Field {
@@ -280,10 +293,13 @@ impl MappingRuntime {
         source_path: &str,
         target_name: &str,
         transform: Option<&Transform>,
-        context: &mut MappingContext,
+        context: &mut MappingContext<'_>,
     ) -> crate::Result<()> {
         // Get value from source
-        let value = self.resolve_path(&context.source_node, source_path)?;
+        let value = self.resolve_path(context, source_path);
+        let source_span = self
+            .resolve_node(context, source_path)
+            .and_then(|node| node.span);
 
         // Apply transform if present
         let transformed_value = if let Some(tfm) = transform {
@@ -302,6 +318,7 @@ impl MappingRuntime {
             condition_result: None,
             lookup_table: None,
             lookup_hit: None,
+            source_span,
         });
 
         // Create target node
@@ -323,10 +340,10 @@ impl MappingRuntime {
         source_path: &str,
         target_name: &str,
         rules: &[MappingRule],
-        context: &mut MappingContext,
+        context: &mut MappingContext<'_>,
     ) -> crate::Result<()> {
-        // Find source collection
-        let collection = self.find_collection(&context.source_node, source_path)?;
+        // Find source collection, with the ancestors of each item
+        let collection = self.select_chains(context, source_path);
         self.emit_trace(MappingTraceEvent {
             rule_type: "foreach".to_string(),
             source: Some(source_path.to_string()),
@@ -337,15 +354,19 @@ impl MappingRuntime {
             condition_result: None,
             lookup_table: None,
             lookup_hit: None,
+            source_span: None,
         });
 
         // Create target container
         let mut container = Node::new(target_name, NodeType::SegmentGroup);
 
         // Process each item
-        for (index, item) in collection.iter().enumerate() {
-            let mut child_context =
-                context.child_context(item.clone(), format!("{source_path}[{index}]"));
+        for (index, chain) in collection.iter().enumerate() {
+            let Some((item, ancestors)) = chain.split_last() else {
+                continue;
+            };
+            let mut child_context = context.child_context(item, format!("{source_path}[{index}]"));
+            child_context.ancestors = ancestors.to_vec();
             child_context.loop_index = Some(index);
 
             // Execute rules for this item
@@ -375,9 +396,9 @@ impl MappingRuntime {
         source_path: &str,
         target_name: &str,
         op: AggregateOp,
-        context: &mut MappingContext,
+        context: &mut MappingContext<'_>,
     ) -> crate::Result<()> {
-        let selected_nodes = self.find_collection(&context.source_node, source_path)?;
+        let selected_nodes = self.select_nodes(context, source_path);
         let input_values = selected_nodes
             .iter()
             .map(|node| node.value.clone().unwrap_or(Value::Null))
@@ -394,6 +415,7 @@ impl MappingRuntime {
             condition_result: None,
             lookup_table: None,
             lookup_hit: None,
+            source_span: None,
         });
 
         let target_node = Node::with_value(target_name, NodeType::Field, result_value);
@@ -412,7 +434,7 @@ impl MappingRuntime {
         condition: &Condition,
         then_rules: &[MappingRule],
         else_rules: &[MappingRule],
-        context: &mut MappingContext,
+        context: &mut MappingContext<'_>,
     ) -> crate::Result<()> {
         let condition_met = self.evaluate_condition(condition, context)?;
         self.emit_trace(MappingTraceEvent {
@@ -425,6 +447,7 @@ impl MappingRuntime {
             condition_result: Some(condition_met),
             lookup_table: None,
             lookup_hit: None,
+            source_span: None,
         });
 
         if condition_met {
@@ -447,10 +470,13 @@ impl MappingRuntime {
         key_source: &str,
         target_name: &str,
         default_value: Option<&String>,
-        context: &mut MappingContext,
+        context: &mut MappingContext<'_>,
     ) -> crate::Result<()> {
         // Get key from source
-        let key = self.resolve_path(&context.source_node, key_source)?;
+        let key = self.resolve_path(context, key_source);
+        let source_span = self
+            .resolve_node(context, key_source)
+            .and_then(|node| node.span);
         let key_str = key.as_string().ok_or_else(|| {
             crate::Error::Runtime(format!("Lookup key '{key_source}' is not a string"))
         })?;
@@ -481,6 +507,7 @@ impl MappingRuntime {
             condition_result: None,
             lookup_table: Some(table.to_string()),
             lookup_hit: Some(lookup_hit),
+            source_span,
         });
 
         // Create target node
@@ -496,245 +523,110 @@ impl MappingRuntime {
     }
 
     /// Resolve a path to a value
-    fn resolve_path(&self, node: &Node, path: &str) -> crate::Result<Value> {
-        if path.is_empty() {
-            return Ok(node.value.clone().unwrap_or(Value::Null));
-        }
-
-        // Handle absolute paths
-        if let Some(relative_path) = path.strip_prefix('/') {
-            let root = self.root_node.as_ref().unwrap_or(node);
-            return self.resolve_path(root, relative_path);
-        }
-
-        // Split path into components
-        let components: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
-
-        if components.is_empty() {
-            return Ok(node.value.clone().unwrap_or(Value::Null));
-        }
-
-        // Traverse path
-        let mut current = node;
-        for (i, component) in components.iter().enumerate() {
-            let (component_name, selector) = Self::parse_component(component);
-            if i == components.len() - 1 {
-                // Last component - return value
-                if let Some(child) =
-                    Self::find_first_matching_child(current, component_name, selector)
-                {
-                    return Ok(child.value.clone().unwrap_or(Value::Null));
-                }
-                return Ok(Value::Null);
-            }
-            // Intermediate component - traverse deeper
-            if let Some(child) = Self::find_first_matching_child(current, component_name, selector)
-            {
-                current = child;
-            } else {
-                return Ok(Value::Null);
-            }
-        }
-
-        Ok(Value::Null)
-    }
-
-    /// Find a collection of nodes
-    fn find_collection(&self, node: &Node, path: &str) -> crate::Result<Vec<Node>> {
-        if let Some(relative_path) = path.strip_prefix('/') {
-            let root = self.root_node.as_ref().unwrap_or(node);
-            return self.find_collection(root, relative_path);
-        }
-
-        let components: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
-
-        if components.is_empty() {
-            return Ok(vec![node.clone()]);
-        }
-
-        let mut current = vec![node.clone()];
-
-        for component in components {
-            let (component_name, selector) = Self::parse_component(component);
-            let mut next = Vec::new();
-            for node in current {
-                for child in Self::find_matching_children(&node, component_name, selector) {
-                    next.push(child.clone());
-                }
-            }
-            current = next;
-        }
-
-        Ok(current)
-    }
-
-    fn parse_component(component: &str) -> (&str, Option<(&str, &str)>) {
-        let Some(selector_start) = component.find('[') else {
-            return (component, None);
-        };
-        if !component.ends_with(']') || selector_start == 0 {
-            return (component, None);
-        }
-
-        let component_name = &component[..selector_start];
-        let selector_content = &component[selector_start + 1..component.len() - 1];
-        let selector =
-            Self::parse_selector(selector_content).or(Some((Self::INVALID_SELECTOR_KEY, "")));
-        (component_name, selector)
-    }
-
-    /// Parse a bracket selector expression into `(key, value)`.
-    ///
-    /// Empty keys and bare values default to `c1`, so `[='137']` and `['137']`
-    /// are accepted shorthand for `[c1='137']`. Empty or malformed selectors
-    /// return `None` so callers can treat them as invalid selectors.
-    fn parse_selector(selector: &str) -> Option<(&str, &str)> {
-        let trimmed = selector.trim();
-        if trimmed.is_empty() {
-            return None;
-        }
-
-        if let Some((key, raw_value)) = trimmed.split_once('=') {
-            let key = key.trim();
-            let value = Self::clean_selector_literal(raw_value);
-            if value.is_empty() {
-                return None;
-            }
-            let key = if key.is_empty() { "c1" } else { key };
-            if !Self::is_supported_selector_key(key) {
-                tracing::warn!(
-                    selector_key = key,
-                    "unrecognized selector key; selector will not match"
-                );
-                return Some((Self::INVALID_SELECTOR_KEY, value));
-            }
-            return Some((key, value));
-        }
-
-        Some(("c1", Self::clean_selector_literal(trimmed)))
-    }
-
-    fn clean_selector_literal(value: &str) -> &str {
-        value.trim().trim_matches('\'').trim_matches('"')
+    fn resolve_path(&self, context: &MappingContext<'_>, path: &str) -> Value {
+        self.resolve_node(context, path)
+            .and_then(|node| node.value.clone())
+            .unwrap_or(Value::Null)
     }
 
-    fn is_supported_selector_key(key: &str) -> bool {
-        let normalized = key.trim();
-        normalized.eq_ignore_ascii_case("c1")
-            || normalized
-                .strip_prefix(['c', 'C', 'e', 'E'])
-                .is_some_and(|suffix| {
-                    !suffix.is_empty() && suffix.chars().all(|c| c.is_ascii_digit())
-                })
-            || normalized.chars().all(|c| c.is_ascii_digit())
+    /// Resolve a path to the node it selects, if any
+    fn resolve_node<'a>(&self, context: &MappingContext<'a>, path: &str) -> Option<&'a Node> {
+        self.select_nodes(context, path).into_iter().next()
     }
 
-    fn find_first_matching_child<'a>(
-        node: &'a Node,
-        component_name: &str,
-        selector: Option<(&str, &str)>,
-    ) -> Option<&'a Node> {
-        node.children
-            .iter()
-            .find(|child| child.name == component_name && Self::matches_selector(child, selector))
-    }
-
-    fn find_matching_children<'a>(
-        node: &'a Node,
-        component_name: &str,
-        selector: Option<(&str, &str)>,
-    ) -> Vec<&'a Node> {
-        node.children
-            .iter()
-            .filter(|child| child.name == component_name && Self::matches_selector(child, selector))
+    /// Select the nodes a [`Path`] expression matches, by reference
+    fn select_nodes<'a>(&self, context: &MappingContext<'a>, path: &str) -> Vec<&'a Node> {
+        self.select_chains(context, path)
+            .into_iter()
+            .filter_map(|chain| chain.last().copied())
             .collect()
     }
 
-    fn matches_selector(node: &Node, selector: Option<(&str, &str)>) -> bool {
-        let Some((key, expected)) = selector else {
-            return true;
+    /// Select the nodes a [`Path`] expression matches, each with its
+    /// ancestors; absolute paths start at the document root and parent and
+    /// ancestor steps can leave a foreach item. Invalid paths select nothing.
+    fn select_chains<'a>(&self, context: &MappingContext<'a>, path: &str) -> Vec<Vec<&'a Node>> {
+        let compiled;
+        let path = match self.paths.get(path) {
+            Some(Some(path)) => path,
+            Some(None) => return Vec::new(),
+            None => match Path::parse(path) {
+                Ok(path) => {
+                    compiled = path;
+                    &compiled
+                }
+                Err(error) => {
+                    tracing::warn!(%error, "invalid source path; path will not match");
+                    return Vec::new();
+                }
+            },
         };
-        if key == Self::INVALID_SELECTOR_KEY {
-            return false;
-        }
-
-        Self::selector_value(node, key).is_some_and(|actual| actual == expected)
-    }
-
-    fn selector_value(node: &Node, key: &str) -> Option<String> {
-        let normalized = key.trim();
-        let normalized_lower = normalized.to_ascii_lowercase();
-        if normalized_lower == "c1" {
-            return Self::qualifier_component_value(node);
-        }
-
-        if normalized_lower.starts_with('c') {
-            if let Some(value) = node
-                .find_child("e1")
-                .and_then(|element| element.find_child(&normalized_lower))
-                .and_then(|component| component.value.as_ref())
-                .and_then(Value::as_string)
-            {
-                return Some(value);
+        path.select_chains(context.chain())
+    }
+
+    /// Compile the source paths of `rules` that are not compiled yet
+    fn compile_rule_paths(&mut self, rules: &[MappingRule]) {
+        for rule in rules {
+            match rule {
+                MappingRule::Field { source, .. }
+                | MappingRule::Aggregate { source, .. }
+                | MappingRule::Lookup {
+                    key_source: source, ..
+                } => self.compile_path(source),
+                MappingRule::Foreach { source, rules, .. } => {
+                    self.compile_path(source);
+                    self.compile_rule_paths(rules);
+                }
+                MappingRule::Condition {
+                    when,
+                    then,
+                    else_rules,
+                } => {
+                    self.compile_condition_paths(when);
+                    self.compile_rule_paths(then);
+                    self.compile_rule_paths(else_rules);
+                }
+                MappingRule::Block { rules } => self.compile_rule_paths(rules),
             }
-            return node
-                .find_child(&normalized_lower)
-                .and_then(|component| component.value.as_ref())
-                .and_then(Value::as_string);
-        }
-
-        if normalized_lower.starts_with('e') {
-            return node
-                .find_child(&normalized_lower)
-                .and_then(|element| element.value.as_ref())
-                .and_then(Value::as_string);
         }
+    }
 
-        // Common EDI qualifier code selectors (e.g. 2005, 3035, 6063) map to
-        // the segment qualifier value, but arbitrary numeric keys should fail
-        // closed instead of silently matching the first element's qualifier.
-        if normalized.chars().all(|ch| ch.is_ascii_digit()) {
-            if Self::is_supported_numeric_qualifier_key(normalized) {
-                return Self::qualifier_component_value(node);
+    fn compile_condition_paths(&mut self, condition: &Condition) {
+        match condition {
+            Condition::Exists { field }
+            | Condition::Equals { field, .. }
+            | Condition::Contains { field, .. }
+            | Condition::Matches { field, .. } => self.compile_path(field),
+            Condition::And { conditions } | Condition::Or { conditions } => {
+                for condition in conditions {
+                    self.compile_condition_paths(condition);
+                }
             }
-            tracing::warn!(
-                selector_key = normalized,
-                node_name = node.name.as_str(),
-                "unrecognized numeric selector key; selector will not match"
-            );
-            return None;
+            Condition::Not { condition } => self.compile_condition_paths(condition),
         }
-
-        None
-    }
-
-    fn is_supported_numeric_qualifier_key(key: &str) -> bool {
-        matches!(key, "1153" | "2005" | "3035" | "5025" | "5125" | "6063")
     }
 
-    fn qualifier_component_value(node: &Node) -> Option<String> {
-        let element = node.find_child("e1")?;
-        element
-            .value
-            .as_ref()
-            .and_then(Value::as_string)
-            .or_else(|| {
-                element
-                    .find_child("c1")
-                    .and_then(|component| component.value.as_ref())
-                    .and_then(Value::as_string)
+    fn compile_path(&mut self, path: &str) {
+        if self.paths.contains_key(path) {
+            return;
+        }
+        let compiled = Path::parse(path)
+            .inspect_err(|error| {
+                tracing::warn!(%error, path, "invalid source path; path will not match");
             })
+            .ok();
+        self.paths.insert(path.to_string(), compiled);
     }
 
     /// Evaluate a condition
     fn evaluate_condition(
         &self,
         condition: &Condition,
-        context: &MappingContext,
+        context: &MappingContext<'_>,
     ) -> crate::Result<bool> {
         match condition {
             Condition::Exists { field } => {
-                let value = self.resolve_path(&context.source_node, field)?;
+                let value = self.resolve_path(context, field);
                 Ok(
                     !matches!(value, Value::Null)
                         && !value.as_string().is_none_or(|s| s.is_empty()),
@@ -744,11 +636,17 @@ impl MappingRuntime {
                 field,
                 value: expected,
             } => {
-                let actual = self.resolve_path(&context.source_node, field)?;
+                let actual = self.resolve_path(context, field);
                 match actual {
-                    Value::String(s) => Ok(&s == expected),
+                    Value::String(s)
+                    | Value::Date(s)
+                    | Value::Time(s)
+                    | Value::DateTime(s)
+                    | Value::Period(s) => Ok(&s == expected),
                     Value::Integer(i) => Ok(i.to_string() == *expected),
-                    Value::Decimal(d) => Ok(d.to_string() == *expected),
+                    Value::Decimal(d) => {
+                        Ok(parse_edi_decimal(expected).is_some_and(|expected| d == expected))
+                    }
                     Value::Boolean(b) => Ok(b.to_string() == *expected),
                     _ => Ok(false),
                 }
@@ -757,14 +655,14 @@ impl MappingRuntime {
                 field,
                 value: expected,
             } => {
-                let actual = self.resolve_path(&context.source_node, field)?;
+                let actual = self.resolve_path(context, field);
                 match actual {
                     Value::String(s) => Ok(s.contains(expected)),
                     _ => Ok(false),
                 }
             }
             Condition::Matches { field, pattern } => {
-                let actual = self.resolve_path(&context.source_node, field)?;
+                let actual = self.resolve_path(context, field);
                 match actual {
                     Value::String(s) => {
                         // Simple pattern matching - in production use regex
@@ -831,22 +729,6 @@ impl MappingRuntime {
         }
     }
 
-    /// Push a context onto the stack
-    pub fn push_context(&mut self, context: MappingContext) {
-        self.context_stack.push(context);
-    }
-
-    /// Pop a context from the stack
-    pub fn pop_context(&mut self) -> Option<MappingContext> {
-        self.context_stack.pop()
-    }
-
-    /// Get current context
-    #[must_use]
-    pub fn current_context(&self) -> Option<&MappingContext> {
-        self.context_stack.last()
-    }
-
     /// Get extension registry
     #[must_use]
     pub fn extensions(&self) -> &ExtensionRegistry {
@@ -872,12 +754,12 @@ fn aggregate_values(source_path: &str, op: AggregateOp, values: &[Value]) -> cra
             .map_err(|_| crate::Error::Runtime("aggregate count overflowed i64".to_string())),
         AggregateOp::Sum => values
             .iter()
-            .try_fold(0.0, |acc, value| {
+            .try_fold(Decimal::from(0), |acc, value| {
                 Ok(acc + aggregate_number(source_path, op, value)?)
             })
             .map(Value::Decimal),
-        AggregateOp::Min => aggregate_extreme(source_path, op, values, f64::min),
-        AggregateOp::Max => aggregate_extreme(source_path, op, values, f64::max),
+        AggregateOp::Min => aggregate_extreme(source_path, op, values, std::cmp::min),
+        AggregateOp::Max => aggregate_extreme(source_path, op, values, std::cmp::max),
         AggregateOp::First => Ok(values.first().cloned().unwrap_or(Value::Null)),
         AggregateOp::Last => Ok(values.last().cloned().unwrap_or(Value::Null)),
         AggregateOp::Distinct => Ok(Value::String(distinct_values(values).join(","))),
@@ -888,7 +770,7 @@ fn aggregate_extreme(
     source_path: &str,
     op: AggregateOp,
     values: &[Value],
-    combine: impl Fn(f64, f64) -> f64,
+    combine: impl Fn(Decimal, Decimal) -> Decimal,
 ) -> crate::Result<Value> {
     let mut numbers = values
         .iter()
@@ -903,16 +785,13 @@ fn aggregate_extreme(
     Ok(Value::Decimal(result))
 }
 
-fn aggregate_number(source_path: &str, op: AggregateOp, value: &Value) -> crate::Result<f64> {
+fn aggregate_number(source_path: &str, op: AggregateOp, value: &Value) -> crate::Result<Decimal> {
     match value {
-        Value::Integer(i) => {
-            #[allow(clippy::cast_precision_loss, reason = "aggregate numeric output is f64-based")]
-            Ok(*i as f64)
-        }
-        Value::Decimal(d) => Ok(*d),
-        Value::String(s) => s.parse::<f64>().map_err(|error| {
+        Value::Integer(i) => Ok(Decimal::from(*i)),
+        Value::Decimal(d) => Ok(d.clone()),
+        Value::String(s) => parse_edi_decimal(s).ok_or_else(|| {
             crate::Error::Runtime(format!(
-                "aggregate rule {op:?} at '{source_path}' expected numeric input but found '{s}': {error}"
+                "aggregate rule {op:?} at '{source_path}' expected numeric input but found '{s}'"
             ))
         }),
         Value::Null => Err(crate::Error::Runtime(format!(
@@ -1044,6 +923,33 @@ rules:
         );
     }
 
+    #[test]
+    fn test_trace_reports_source_span_of_field_rule() {
+        let dsl = r"
+name: span_trace_test
+source_type: TEST
+target_type: OUTPUT
+rules:
+  - type: field
+    source: /HEADER/ORDER_NUMBER
+    target: order_id
+  - type: field
+    source: /HEADER/MISSING
+    target: missing
+";
+
+        let mapping = MappingDsl::parse(dsl).unwrap();
+        let mut document = create_test_document();
+        let span = edi_ir::SourceSpan::new(12, 8, 2, 5).with_segment_index(2);
+        document.root.children[0].children[0].set_span(span);
+        let mut runtime = MappingRuntime::new();
+
+        let (_, events) = runtime.execute_with_trace(&mapping, &document).unwrap();
+
+        assert_eq!(events[0].source_span, Some(span));
+        assert_eq!(events[1].source_span, None);
+    }
+
     #[test]
     fn test_execute_foreach() {
         let dsl = r"
@@ -1095,6 +1001,125 @@ rules:
         );
     }
 
+    #[test]
+    fn test_foreach_items_resolve_absolute_paths_from_document_root() {
+        let dsl = r"
+name: foreach_root_test
+source_type: TEST
+target_type: OUTPUT
+rules:
+  - type: foreach
+    source: /ITEMS/ITEM
+    target: lines
+    rules:
+      - type: field
+        source: /HEADER/ORDER_NUMBER
+        target: order_id
+      - type: aggregate
+        source: /ITEMS/ITEM/QTY
+        target: order_total
+        op: sum
+";
+
+        let mapping = MappingDsl::parse(dsl).unwrap();
+        let document = create_test_document();
+        let mut runtime = MappingRuntime::new();
+
+        let result = runtime.execute(&mapping, &document).unwrap();
+        let lines = first_mapped_node(&result);
+
+        assert_eq!(lines.children.len(), 2);
+        for line in &lines.children {
+            assert_eq!(line.value, Some(Value::String("ORD12345".to_string())));
+            assert_eq!(line.children[0].name, "order_total");
+            assert_eq!(
+                line.children[0].value.as_ref().and_then(Value::as_string),
+                Some("15".to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn test_foreach_items_resolve_parent_and_ancestor_steps() {
+        let dsl = r"
+name: foreach_ancestor_test
+source_type: TEST
+target_type: OUTPUT
+rules:
+  - type: foreach
+    source: //SG5
+    target: contacts
+    rules:
+      - type: field
+        source: CTA/e2
+        target: name
+      - type: field
+        source: ../NAD/e2
+        target: party
+      - type: field
+        source: ancestor::SG2/NAD/e1
+        target: role
+";
+        let party = |qualifier: &str, id: &str, contact: &str| {
+            let mut nad = Node::new("NAD", NodeType::Segment);
+            nad.add_child(Node::with_value(
+                "e1",
+                NodeType::Element,
+                Value::String(qualifier.to_string()),
+            ));
+            nad.add_child(Node::with_value(
+                "e2",
+                NodeType::Element,
+                Value::String(id.to_string()),
+            ));
+            let mut cta = Node::new("CTA", NodeType::Segment);
+            cta.add_child(Node::with_value(
+                "e2",
+                NodeType::Element,
+                Value::String(contact.to_string()),
+            ));
+            let mut sg5 = Node::new("SG5", NodeType::SegmentGroup);
+            sg5.add_child(cta);
+            let mut sg2 = Node::new("SG2", NodeType::SegmentGroup);
+            sg2.add_child(nad);
+            sg2.add_child(sg5);
+            sg2
+        };
+        let mut root = Node::new("ROOT", NodeType::Root);
+        root.add_child(party("BY", "5412345000013", "ALICE"));
+        root.add_child(party("SU", "4012345000016", "BOB"));
+
+        let mapping = MappingDsl::parse(dsl).unwrap();
+        let result = MappingRuntime::new()
+            .execute(&mapping, &Document::new(root))
+            .unwrap();
+        let contacts = first_mapped_node(&result);
+
+        let fields = |contact: &Node| {
+            std::iter::once(contact)
+                .chain(&contact.children)
+                .map(|field| field.value.as_ref().and_then(Value::as_string))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(contacts.children.len(), 2);
+        assert_eq!(
+            fields(&contacts.children[0]),
+            vec![
+                Some("ALICE".to_string()),
+                Some("5412345000013".to_string()),
+                Some("BY".to_string())
+            ]
+        );
+        assert_eq!(
+            fields(&contacts.children[1]),
+            vec![
+                Some("BOB".to_string()),
+                Some("4012345000016".to_string()),
+                Some("SU".to_string())
+            ]
+        );
+    }
+
     #[test]
     fn test_execute_condition() {
         let dsl = r#"
@@ -1362,11 +1387,11 @@ rules:
         let mut root = Node::new("ROOT", NodeType::Root);
         let mut dtm = Node::new("DTM", NodeType::Segment);
         let mut dtm_e1 = Node::new("e1", NodeType::Element);
-        dtm_e1.add_child(Node::with_value(
-            "c1",
-            NodeType::Component,
-            Value::String("137".to_string()),
-        ));
+        dtm_e1.set_schema_type("C507");
+        let mut qualifier =
+            Node::with_value("c1", NodeType::Component, Value::String("137".to_string()));
+        qualifier.set_schema_type("2005");
+        dtm_e1.add_child(qualifier);
         dtm_e1.add_child(Node::with_value(
             "c2",
             NodeType::Component,
@@ -1588,6 +1613,97 @@ rules:
         );
     }
 
+    #[test]
+    fn test_path_predicates_combine_and_compare_values() {
+        let supplier_dsl = r"
+name: path_query_supplier_test
+source_type: TEST
+target_type: OUTPUT
+rules:
+  - type: field
+    source: //NAD[e1=SU and e2/c3=9]/e2/c1
+    target: supplier_gln
+";
+        let lines_dsl = r"
+name: path_query_lines_test
+source_type: TEST
+target_type: OUTPUT
+rules:
+  - type: foreach
+    source: /SG25[QTY/e1/c2 >= 100]
+    target: large_lines
+    rules:
+      - type: field
+        source: QTY/../LIN/e1
+        target: line
+";
+        let mut root = Node::new("ROOT", NodeType::Root);
+        for (party, agency) in [("SU", "92"), ("SU", "9")] {
+            let mut group = Node::new("SG2", NodeType::SegmentGroup);
+            let mut nad = Node::new("NAD", NodeType::Segment);
+            nad.add_child(Node::with_value(
+                "e1",
+                NodeType::Element,
+                Value::String(party.to_string()),
+            ));
+            let mut party_id = Node::new("e2", NodeType::Element);
+            for (name, value) in [("c1", format!("GLN-{agency}")), ("c2", String::new())] {
+                party_id.add_child(Node::with_value(
+                    name,
+                    NodeType::Component,
+                    Value::String(value),
+                ));
+            }
+            party_id.add_child(Node::with_value(
+                "c3",
+                NodeType::Component,
+                Value::String(agency.to_string()),
+            ));
+            nad.add_child(party_id);
+            group.add_child(nad);
+            root.add_child(group);
+        }
+        for (line, quantity) in [("1", "120"), ("2", "8"), ("3", "100")] {
+            let mut group = Node::new("SG25", NodeType::SegmentGroup);
+            let mut lin = Node::new("LIN", NodeType::Segment);
+            lin.add_child(Node::with_value(
+                "e1",
+                NodeType::Element,
+                Value::String(line.to_string()),
+            ));
+            group.add_child(lin);
+            let mut qty = Node::new("QTY", NodeType::Segment);
+            let mut qty_e1 = Node::new("e1", NodeType::Element);
+            for (name, value) in [("c1", "21"), ("c2", quantity)] {
+                qty_e1.add_child(Node::with_value(
+                    name,
+                    NodeType::Component,
+                    Value::String(value.to_string()),
+                ));
+            }
+            qty.add_child(qty_e1);
+            group.add_child(qty);
+            root.add_child(group);
+        }
+
+        let document = Document::new(root);
+        let mut runtime = MappingRuntime::new();
+
+        let mapping = MappingDsl::parse(supplier_dsl).unwrap();
+        let result = runtime.execute(&mapping, &document).unwrap();
+        let supplier = first_mapped_node(&result);
+        assert_eq!(supplier.value, Some(Value::String("GLN-9".to_string())));
+
+        let mapping = MappingDsl::parse(lines_dsl).unwrap();
+        let result = runtime.execute(&mapping, &document).unwrap();
+        let lines: Vec<_> = first_mapped_node(&result)
+            .children
+            .iter()
+            .filter_map(|line| line.value.as_ref().and_then(Value::as_string))
+            .collect();
+        assert_eq!(lines, vec!["1", "3"]);
+    }
+
     #[test]
     fn test_unrecognized_selector_key_does_not_match() {
         let dsl = r"
@@ -1736,7 +1852,7 @@ rules:
     #[test]
     fn test_mapping_context() {
         let node = Node::new("TEST", NodeType::Segment);
-        let mut context = MappingContext::new(node);
+        let mut context = MappingContext::new(&node);
 
         // Test variable setting/getting
         context.set_variable("test_var", Value::String("value".to_string()));
@@ -1748,7 +1864,7 @@ rules:
 
         // Test child context
         let child_node = Node::new("CHILD", NodeType::Field);
-        let child_context = context.child_context(child_node, "/child/path");
+        let child_context = context.child_context(&child_node, "/child/path");
 
         // Child should inherit variables
         assert_eq!(
@@ -1756,6 +1872,8 @@ rules:
             Some(&Value::String("value".to_string()))
         );
         assert_eq!(child_context.path, "/child/path");
+        assert_eq!(child_context.source_node.name, "CHILD");
+        assert_eq!(child_context.root_node.name, "TEST");
     }
 
     #[test]
@@ -1998,27 +2116,6 @@ rules:
         assert!(runtime.extensions().is_empty().unwrap());
     }
 
-    #[test]
-    fn test_context_stack_operations() {
-        let mut runtime = MappingRuntime::new();
-
-        let node1 = Node::new("NODE1", NodeType::Segment);
-        let ctx1 = MappingContext::new(node1);
-
-        runtime.push_context(ctx1);
-        assert!(runtime.current_context().is_some());
-
-        let node2 = Node::new("NODE2", NodeType::Segment);
-        let ctx2 = MappingContext::new(node2);
-        runtime.push_context(ctx2);
-
-        assert_eq!(runtime.context_stack.len(), 2);
-
-        let popped = runtime.pop_context();
-        assert!(popped.is_some());
-        assert_eq!(runtime.context_stack.len(), 1);
-    }
-
     #[test]
     fn test_empty_foreach() {
         let dsl = r"
@@ -2,8 +2,9 @@
 //!
 //! Provides various transformation functions for mapping values.
 
-use crate::numeric::value_to_f64;
-use edi_ir::Value;
+use crate::numeric::value_to_decimal;
+use edi_ir::decimal::RoundingMode;
+use edi_ir::{Value, parse_edi_decimal};
 
 /// Transform a value using the specified operation
 ///
@@ -192,11 +193,11 @@ pub fn transform_number_format(
 ) -> crate::Result<Value> {
     let num = match value {
         Value::Null => return Ok(Value::Null),
-        _ => value_to_f64(value, "value")?,
+        _ => value_to_decimal(value, "value")?,
     };
-    let precision = usize::try_from(decimals)
-        .map_err(|_| crate::Error::Transform("Unsupported decimal precision".to_string()))?;
-    let mut rounded = format!("{num:.precision$}");
+    let mut rounded = num
+        .with_scale_round(i64::from(decimals), RoundingMode::HalfUp)
+        .to_plain_string();
     let formatted = if let Some(sep) = thousands_sep {
         if let Some((int_part, frac_part)) = rounded.split_once('.') {
             let trimmed_frac = frac_part.trim_end_matches('0');
@@ -346,9 +347,15 @@ fn evaluate_condition_simple(
             field: _,
             value: expected,
         } => match value {
-            Value::String(s) => Ok(s == expected),
+            Value::String(s)
+            | Value::Date(s)
+            | Value::Time(s)
+            | Value::DateTime(s)
+            | Value::Period(s) => Ok(s == expected),
             Value::Integer(i) => Ok(i.to_string() == *expected),
-            Value::Decimal(d) => Ok(d.to_string() == *expected),
+            Value::Decimal(d) => {
+                Ok(parse_edi_decimal(expected).is_some_and(|expected| *d == expected))
+            }
             Value::Boolean(b) => Ok(b.to_string() == *expected),
             _ => Ok(false),
         },
@@ -528,7 +535,7 @@ mod tests {
 
     #[test]
     fn test_transform_number_format_decimal() {
-        let value = Value::Decimal(1234.5678);
+        let value = Value::Decimal("1234.5678".parse().unwrap());
         let result = transform_number_format(&value, 2, None).unwrap();
         assert_eq!(result, Value::String("1234.57".to_string()));
     }
@@ -563,14 +570,14 @@ mod tests {
 
     #[test]
     fn test_transform_number_format_negative() {
-        let value = Value::Decimal(-1234.56);
+        let value = Value::Decimal("-1234.56".parse().unwrap());
         let result = transform_number_format(&value, 2, Some(",")).unwrap();
         assert_eq!(result, Value::String("-1,234.56".to_string()));
     }
 
     #[test]
     fn test_transform_number_format_rounding() {
-        let value = Value::Decimal(99.999);
+        let value = Value::Decimal("99.999".parse().unwrap());
         let result = transform_number_format(&value, 2, None).unwrap();
         assert_eq!(result, Value::String("100.00".to_string()));
     }
@@ -876,15 +883,27 @@ mod tests {
 
     #[test]
     fn test_transform_number_format_many_decimals() {
-        let value = Value::Decimal(std::f64::consts::PI);
+        let value = Value::Decimal("3.14159265358979".parse().unwrap());
         let result = transform_number_format(&value, 6, None).unwrap();
         assert_eq!(result, Value::String("3.141593".to_string()));
     }
 
     #[test]
     fn test_transform_number_format_no_decimals() {
-        let value = Value::Decimal(123.999);
+        let value = Value::Decimal("123.999".parse().unwrap());
         let result = transform_number_format(&value, 0, None).unwrap();
         assert_eq!(result, Value::String("124".to_string()));
     }
+
+    #[test]
+    fn test_transform_number_format_rounds_exact_decimal_half_up() {
+        // 2.675 has no exact binary representation and would round down
+        let value = Value::Decimal("2.675".parse().unwrap());
+        let result = transform_number_format(&value, 2, None).unwrap();
+        assert_eq!(result, Value::String("2.68".to_string()));
+
+        let value = Value::String("1234,5".to_string());
+        let result = transform_number_format(&value, 2, None).unwrap();
+        assert_eq!(result, Value::String("1234.50".to_string()));
+    }
 }
@@ -9,17 +9,17 @@ fn field<'a>(node: &'a Node, name: &str) -> &'a Value {
         .unwrap_or_else(|| panic!("missing field {name}"))
 }
 
-fn line(quantity: f64, net_amount: f64, sku: &str) -> Node {
+fn line(quantity: &str, net_amount: &str, sku: &str) -> Node {
     let mut line = Node::new("line", NodeType::Record);
     line.add_child(Node::with_value(
         "quantity",
         NodeType::Field,
-        Value::Decimal(quantity),
+        Value::Decimal(quantity.parse().unwrap()),
     ));
     line.add_child(Node::with_value(
         "net_amount",
         NodeType::Field,
-        Value::Decimal(net_amount),
+        Value::Decimal(net_amount.parse().unwrap()),
     ));
     line.add_child(Node::with_value(
         "sku",
@@ -32,9 +32,9 @@ fn line(quantity: f64, net_amount: f64, sku: &str) -> Node {
 fn order_document() -> Document {
     let mut root = Node::new("order", NodeType::Root);
     let mut lines = Node::new("lines", NodeType::SegmentGroup);
-    lines.add_child(line(2.0, 19.5, "SKU-1"));
-    lines.add_child(line(3.0, 7.25, "SKU-2"));
-    lines.add_child(line(4.0, 11.0, "SKU-1"));
+    lines.add_child(line("2.0", "19.5", "SKU-1"));
+    lines.add_child(line("3.0", "7.25", "SKU-2"));
+    lines.add_child(line("4.0", "11.0", "SKU-1"));
     root.add_child(lines);
     Document::new(root)
 }
@@ -84,10 +84,16 @@ rules:
         .find_child("total_quantity")
         .expect("first mapped node");
     assert_eq!(summary.name, "total_quantity");
-    assert_eq!(summary.value, Some(Value::Decimal(9.0)));
+    assert_eq!(summary.value, Some(Value::Decimal("9.0".parse().unwrap())));
     assert_eq!(field(summary, "line_count"), &Value::Integer(3));
-    assert_eq!(field(summary, "first_amount"), &Value::Decimal(19.5));
-    assert_eq!(field(summary, "last_amount"), &Value::Decimal(11.0));
+    assert_eq!(
+        field(summary, "first_amount"),
+        &Value::Decimal("19.5".parse().unwrap())
+    );
+    assert_eq!(
+        field(summary, "last_amount"),
+        &Value::Decimal("11.0".parse().unwrap())
+    );
     assert_eq!(
         field(summary, "distinct_skus"),
         &Value::String("SKU-1,SKU-2".to_string())
@@ -116,3 +122,50 @@ rules:
     assert!(message.contains("/lines/line/sku"), "{message}");
     assert!(message.contains("SKU-1"), "{message}");
 }
+
+#[test]
+fn aggregate_sum_of_cent_amounts_reconciles_exactly() {
+    let mut root = Node::new("invoice", NodeType::Root);
+    let mut lines = Node::new("lines", NodeType::SegmentGroup);
+    for _ in 0..300 {
+        let mut line = Node::new("line", NodeType::Record);
+        // EDIFACT amounts as transmitted, with a decimal comma
+        line.add_child(Node::with_value(
+            "amount",
+            NodeType::Field,
+            Value::String("0,10".to_string()),
+        ));
+        lines.add_child(line);
+    }
+    root.add_child(lines);
+
+    let yaml = r#"
+name: invoice_total
+source_type: invoice
+target_type: invoice_summary
+rules:
+  - type: aggregate
+    source: /lines/line/amount
+    target: total
+    op: sum
+  - type: aggregate
+    source: /lines/line/amount
+    target: largest
+    op: max
+"#;
+    let mapping = MappingDsl::parse(yaml).expect("mapping parses");
+    let mut runtime = MappingRuntime::new();
+    let output = runtime
+        .execute(&mapping, &Document::new(root))
+        .expect("mapping executes");
+
+    let total = output.root.find_child("total").expect("total mapped");
+    assert_eq!(
+        total.value.as_ref().and_then(Value::as_string),
+        Some("30.00".to_string())
+    );
+    assert_eq!(
+        field(total, "largest").as_string(),
+        Some("0.10".to_string())
+    );
+}
@@ -49,7 +49,7 @@ fn test_multiple_extensions() {
     let result = registry
         .call("math_utils", "add", &[Value::Integer(5), Value::Integer(3)])
         .unwrap();
-    assert_eq!(result, Value::Decimal(8.0));
+    assert_eq!(result, Value::Decimal("8.0".parse().unwrap()));
 }
 
 #[test]
@@ -65,7 +65,7 @@ fn test_custom_extension_creation() {
             }
             match &args[0] {
                 Value::Integer(i) => Ok(Value::Integer(i * 2)),
-                Value::Decimal(d) => Ok(Value::Decimal(d * 2.0)),
+                Value::Decimal(d) => Ok(Value::Decimal(d * edi_ir::Decimal::from(2))),
                 _ => Err(edi_mapping::Error::Transform(
                     "Can only double numbers".to_string(),
                 )),
@@ -310,17 +310,20 @@ fn test_math_utils_functions() {
             &[Value::Integer(10), Value::Integer(20)],
         )
         .unwrap();
-    assert_eq!(result, Value::Decimal(30.0));
+    assert_eq!(result, Value::Decimal("30.0".parse().unwrap()));
 
     // Test add with decimals
     let result = registry
         .call(
             "math_utils",
             "add",
-            &[Value::Decimal(10.5), Value::Decimal(20.5)],
+            &[
+                Value::Decimal("10.5".parse().unwrap()),
+                Value::Decimal("20.5".parse().unwrap()),
+            ],
         )
         .unwrap();
-    assert_eq!(result, Value::Decimal(31.0));
+    assert_eq!(result, Value::Decimal("31.0".parse().unwrap()));
 
     // Test multiply
     let result = registry
@@ -330,17 +333,17 @@ fn test_math_utils_functions() {
             &[Value::Integer(5), Value::Integer(6)],
         )
         .unwrap();
-    assert_eq!(result, Value::Decimal(30.0));
+    assert_eq!(result, Value::Decimal("30.0".parse().unwrap()));
 
     // Test multiply with mixed types
     let result = registry
         .call(
             "math_utils",
             "multiply",
-            &[Value::Decimal(2.5), Value::Integer(4)],
+            &[Value::Decimal("2.5".parse().unwrap()), Value::Integer(4)],
         )
         .unwrap();
-    assert_eq!(result, Value::Decimal(10.0));
+    assert_eq!(result, Value::Decimal("10.0".parse().unwrap()));
 }
 
 #[test]
@@ -388,7 +391,7 @@ fn test_extension_with_various_types() {
             .call(
                 "type_test",
                 "type_checker",
-                &[Value::Decimal(std::f64::consts::PI)]
+                &[Value::Decimal("3.14159265358979".parse().unwrap())]
             )
             .unwrap(),
         Value::String("decimal".to_string())
@@ -185,7 +185,7 @@ fn create_test_orders_document() -> Document {
     moa1.add_child(Node::with_value(
         "MonetaryAmount",
         NodeType::Field,
-        Value::Decimal(29.99),
+        Value::Decimal("29.99".parse().unwrap()),
     ));
     lin1.add_child(moa1);
 
@@ -252,7 +252,7 @@ fn create_test_orders_document() -> Document {
     moa2.add_child(Node::with_value(
         "MonetaryAmount",
         NodeType::Field,
-        Value::Decimal(49.99),
+        Value::Decimal("49.99".parse().unwrap()),
     ));
     lin2.add_child(moa2);
 
@@ -0,0 +1,443 @@
+//! Duplicate interchange and message detection
+//!
+//! Partners resend files, so the pipeline can consult a persistent
+//! [`DuplicateRegistry`] before processing a message. Interchanges are keyed
+//! by sender, recipient and interchange control reference; messages by
+//! sender, recipient, UNH message reference and BGM document number.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::fs::OpenOptions;
+use std::future::Future;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use edi_adapter_db::{
+    ColumnDef, ColumnType, DbConnection, DbReader, DbValue, DbWriter, Row, SchemaMapping,
+    TableSchema,
+};
+use edi_ir::{Document, Node, NodeType};
+
+use crate::{Error, Result};
+
+/// Key identifying a received interchange or message
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DuplicateKey {
+    /// Interchange (UNB sender, recipient and control reference)
+    Interchange {
+        sender: String,
+        recipient: String,
+        control_ref: String,
+    },
+    /// Message (interchange parties, UNH reference and BGM document number)
+    Message {
+        sender: String,
+        recipient: String,
+        message_ref: String,
+        document_number: String,
+    },
+}
+
+impl DuplicateKey {
+    /// Key of the interchange `document` was received in, if it had one
+    #[must_use]
+    pub fn interchange(document: &Document) -> Option<Self> {
+        let interchange = document.metadata.interchange.as_ref()?;
+        Some(Self::Interchange {
+            sender: interchange.sender.id.clone(),
+            recipient: interchange.recipient.id.clone(),
+            control_ref: interchange.control_ref.clone(),
+        })
+    }
+
+    /// Key of the message `document`, if it was received in an interchange
+    /// and has a UNH reference and a BGM document number
+    ///
+    /// Without the parties and the document number, a UNH reference alone
+    /// would flag unrelated messages that happen to reuse it.
+    #[must_use]
+    pub fn message(document: &Document) -> Option<Self> {
+        let interchange = document.metadata.interchange.as_ref()?;
+        Some(Self::Message {
+            sender: interchange.sender.id.clone(),
+            recipient: interchange.recipient.id.clone(),
+            message_ref: document.metadata.message_refs.first()?.clone(),
+            document_number: document_number(&document.root)?,
+        })
+    }
+
+    /// Stable textual form used as the persisted registry key
+    #[must_use]
+    pub fn id(&self) -> String {
+        let parts = match self {
+            Self::Interchange {
+                sender,
+                recipient,
+                control_ref,
+            } => vec!["interchange", sender, recipient, control_ref],
+            Self::Message {
+                sender,
+                recipient,
+                message_ref,
+                document_number,
+            } => vec!["message", sender, recipient, message_ref, document_number],
+        };
+        serde_json::Value::from(parts).to_string()
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::Interchange { .. } => "interchange",
+            Self::Message { .. } => "message",
+        }
+    }
+}
+
+impl fmt::Display for DuplicateKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Interchange {
+                sender,
+                recipient,
+                control_ref,
+            } => write!(
+                f,
+                "interchange {control_ref} from '{sender}' to '{recipient}'"
+            ),
+            Self::Message {
+                sender,
+                recipient,
+                message_ref,
+                document_number,
+            } => write!(
+                f,
+                "message {message_ref} (document {document_number}) from '{sender}' to '{recipient}'"
+            ),
+        }
+    }
+}
+
+/// Persistent set of interchanges and messages already processed
+pub trait DuplicateRegistry: fmt::Debug + Send {
+    /// Whether `key` was recorded before
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the registry cannot be read.
+    fn contains(&self, key: &DuplicateKey) -> Result<bool>;
+
+    /// Record `key` as processed
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the registry cannot be written.
+    fn record(&mut self, key: &DuplicateKey) -> Result<()>;
+}
+
+/// Registry persisted as one key per line in a local file
+#[derive(Debug)]
+pub struct FileDuplicateRegistry {
+    path: PathBuf,
+    keys: HashSet<String>,
+}
+
+impl FileDuplicateRegistry {
+    /// Open the registry at `path`, creating it on first record
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an existing registry file cannot be read.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let keys = match std::fs::read_to_string(&path) {
+            Ok(content) => content
+                .lines()
+                .filter(|line| !line.is_empty())
+                .map(str::to_string)
+                .collect(),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => HashSet::new(),
+            Err(error) => {
+                return Err(Error::io(
+                    "read-duplicate-registry",
+                    path.to_string_lossy(),
+                    error.to_string(),
+                ));
+            }
+        };
+        Ok(Self { path, keys })
+    }
+
+    /// Number of recorded keys
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Whether no keys are recorded
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+
+impl DuplicateRegistry for FileDuplicateRegistry {
+    fn contains(&self, key: &DuplicateKey) -> Result<bool> {
+        Ok(self.keys.contains(&key.id()))
+    }
+
+    fn record(&mut self, key: &DuplicateKey) -> Result<()> {
+        let id = key.id();
+        if self.keys.contains(&id) {
+            return Ok(());
+        }
+
+        let write_error = |error: std::io::Error| {
+            Error::io(
+                "write-duplicate-registry",
+                self.path.to_string_lossy(),
+                error.to_string(),
+            )
+        };
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(write_error)?;
+        writeln!(file, "{id}").map_err(write_error)?;
+        self.keys.insert(id);
+        Ok(())
+    }
+}
+
+/// Registry stored in a libsql database table
+pub struct LibsqlDuplicateRegistry {
+    connection: DbConnection,
+    table: String,
+    runtime: Option<tokio::runtime::Runtime>,
+}
+
+impl fmt::Debug for LibsqlDuplicateRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LibsqlDuplicateRegistry")
+            .field("database_url", &self.connection.config().database_url)
+            .field("table", &self.table)
+            .finish_non_exhaustive()
+    }
+}
+
+impl LibsqlDuplicateRegistry {
+    /// Default table holding the recorded keys
+    pub const DEFAULT_TABLE: &'static str = "edi_duplicate_keys";
+
+    /// Connect to the database and create the registry table if needed
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the connection or table creation fails.
+    pub fn new(connection: DbConnection) -> Result<Self> {
+        Self::with_table(connection, Self::DEFAULT_TABLE)
+    }
+
+    /// Like [`new`](Self::new), storing keys in `table`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the connection or table creation fails.
+    pub fn with_table(connection: DbConnection, table: impl Into<String>) -> Result<Self> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|error| Error::pipeline("duplicate-registry", "libsql", error.to_string()))?;
+        let registry = Self {
+            connection,
+            table: table.into(),
+            runtime: Some(runtime),
+        };
+
+        let mut schema = SchemaMapping::new();
+        schema.add_table(
+            TableSchema::new(&registry.table)
+                .with_column(ColumnDef::new("key", ColumnType::String).primary_key())
+                .with_column(ColumnDef::new("kind", ColumnType::String)),
+        );
+        let connection = registry.connection.clone();
+        registry.block_on(async move {
+            connection.connect().await?;
+            connection.apply_schema(&schema).await
+        })?;
+        Ok(registry)
+    }
+
+    /// Run a database operation on the registry's runtime, from a separate
+    /// thread when called inside another runtime
+    fn block_on<T, F>(&self, future: F) -> Result<T>
+    where
+        T: Send,
+        F: Future<Output = edi_adapter_db::Result<T>> + Send,
+    {
+        let runtime = self
+            .runtime
+            .as_ref()
+            .expect("runtime is only taken on drop");
+        let result = if tokio::runtime::Handle::try_current().is_ok() {
+            std::thread::scope(|scope| {
+                scope
+                    .spawn(|| runtime.block_on(future))
+                    .join()
+                    .map_err(|_| {
+                        Error::pipeline(
+                            "duplicate-registry",
+                            self.table.clone(),
+                            "Registry worker thread panicked",
+                        )
+                    })
+            })?
+        } else {
+            runtime.block_on(future)
+        };
+        result.map_err(|error| {
+            Error::pipeline("duplicate-registry", self.table.clone(), error.to_string())
+        })
+    }
+}
+
+impl Drop for LibsqlDuplicateRegistry {
+    fn drop(&mut self) {
+        // A runtime cannot be dropped from within another runtime
+        if let Some(runtime) = self.runtime.take() {
+            runtime.shutdown_background();
+        }
+    }
+}
+
+impl DuplicateRegistry for LibsqlDuplicateRegistry {
+    fn contains(&self, key: &DuplicateKey) -> Result<bool> {
+        let reader = DbReader::new(self.connection.clone());
+        let filter = Row::from([("key".to_string(), DbValue::String(key.id()))]);
+        let row = self.block_on(async { reader.read_single(&self.table, &filter).await })?;
+        Ok(row.is_some())
+    }
+
+    fn record(&mut self, key: &DuplicateKey) -> Result<()> {
+        let writer = DbWriter::new(self.connection.clone());
+        let row = Row::from([
+            ("key".to_string(), DbValue::String(key.id())),
+            ("kind".to_string(), DbValue::String(key.kind().to_string())),
+        ]);
+        self.block_on(async { writer.upsert(&self.table, "key", row).await })
+    }
+}
+
+/// First component of BGM element 2 (document number)
+fn document_number(root: &Node) -> Option<String> {
+    let bgm = find_segment(root, "BGM")?;
+    let element = bgm.children.get(1)?;
+    let node = element.children.first().unwrap_or(element);
+    node.value
+        .as_ref()
+        .and_then(edi_ir::Value::as_string)
+        .filter(|value| !value.is_empty())
+}
+
+fn find_segment<'a>(node: &'a Node, tag: &str) -> Option<&'a Node> {
+    if node.node_type == NodeType::Segment && node.name == tag {
+        return Some(node);
+    }
+    node.children
+        .iter()
+        .find_map(|child| find_segment(child, tag))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use edi_adapter_edifact::EdifactParser;
+
+    const INVOIC: &[u8] = b"UNB+UNOC:3+SUPPLIER:14+RETAILER:14+260312:0930+42'\n\
+        UNH+1+INVOIC:D:96A:UN:EAN008'\nBGM+380+INV001+9'\nUNT+3+1'\nUNZ+1+42'";
+
+    fn document() -> Document {
+        EdifactParser::new()
+            .parse(INVOIC, "invoic.edi")
+            .unwrap()
+            .remove(0)
+    }
+
+    #[test]
+    fn test_keys_from_document() {
+        let document = document();
+        assert_eq!(
+            DuplicateKey::interchange(&document),
+            Some(DuplicateKey::Interchange {
+                sender: "SUPPLIER".to_string(),
+                recipient: "RETAILER".to_string(),
+                control_ref: "42".to_string(),
+            })
+        );
+        let message = DuplicateKey::message(&document).unwrap();
+        assert_eq!(
+            message.id(),
+            r#"["message","SUPPLIER","RETAILER","1","INV001"]"#
+        );
+        assert_eq!(
+            message.to_string(),
+            "message 1 (document INV001) from 'SUPPLIER' to 'RETAILER'"
+        );
+    }
+
+    #[test]
+    fn test_message_key_needs_interchange_and_document_number() {
+        let mut document = document();
+        document.metadata.interchange = None;
+        assert_eq!(DuplicateKey::message(&document), None);
+
+        let without_number = EdifactParser::new()
+            .parse(
+                b"UNB+UNOC:3+SUPPLIER:14+RETAILER:14+260312:0930+42'\n\
+                  UNH+1+INVOIC:D:96A:UN:EAN008'\nBGM+380'\nUNT+3+1'\nUNZ+1+42'",
+                "invoic.edi",
+            )
+            .unwrap()
+            .remove(0);
+        assert_eq!(DuplicateKey::message(&without_number), None);
+    }
+
+    #[test]
+    fn test_file_registry_persists_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("duplicates.jsonl");
+        let key = DuplicateKey::message(&document()).unwrap();
+
+        let mut registry = FileDuplicateRegistry::open(&path).unwrap();
+        assert!(!registry.contains(&key).unwrap());
+        registry.record(&key).unwrap();
+        registry.record(&key).unwrap();
+        assert!(registry.contains(&key).unwrap());
+
+        let reopened = FileDuplicateRegistry::open(&path).unwrap();
+        assert_eq!(reopened.len(), 1);
+        assert!(reopened.contains(&key).unwrap());
+    }
+
+    #[test]
+    fn test_libsql_registry_records_keys() {
+        let mut registry = LibsqlDuplicateRegistry::new(DbConnection::new()).unwrap();
+        let document = document();
+        let interchange = DuplicateKey::interchange(&document).unwrap();
+        let message = DuplicateKey::message(&document).unwrap();
+
+        assert!(!registry.contains(&interchange).unwrap());
+        registry.record(&interchange).unwrap();
+        registry.record(&interchange).unwrap();
+        assert!(registry.contains(&interchange).unwrap());
+        assert!(!registry.contains(&message).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_libsql_registry_inside_runtime() {
+        let mut registry = LibsqlDuplicateRegistry::new(DbConnection::new()).unwrap();
+        let key = DuplicateKey::message(&document()).unwrap();
+        registry.record(&key).unwrap();
+        assert!(registry.contains(&key).unwrap());
+    }
+}
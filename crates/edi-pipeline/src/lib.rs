@@ -12,6 +12,7 @@
 //! EDI files with configurable error handling and streaming support.
 
 pub mod batch;
+pub mod duplicates;
 mod numeric;
 pub mod pipeline;
 pub mod policies;
@@ -19,6 +20,9 @@ pub mod quarantine;
 pub mod streaming;
 
 pub use batch::{Batch, BatchConfig, BatchItem, BatchResult, ItemStatus};
+pub use duplicates::{
+    DuplicateKey, DuplicateRegistry, FileDuplicateRegistry, LibsqlDuplicateRegistry,
+};
 pub use pipeline::{
     ErrorSeverity, FileResult, Mapper, OutputFormat, Pipeline, PipelineBatchResult, PipelineConfig,
     PipelineMetrics, PipelineStats, ValidationError, Validator,
@@ -3,16 +3,17 @@
 //! This module provides the main `Pipeline` for processing EDI files with
 //! support for validation, mapping, batching, streaming, and quarantine.
 
+use std::collections::HashSet;
 use std::path::Path;
 use std::time::{Duration, Instant};
 
 use crate::{
-    AcceptancePolicy, Batch, BatchConfig, Error, QuarantineReason, QuarantineStore, Result,
-    StreamConfig, StreamMessage, StreamProcessor, StrictnessLevel, numeric::u128_to_f64,
-    numeric::usize_to_f64,
+    AcceptancePolicy, Batch, BatchConfig, DuplicateKey, DuplicateRegistry, Error, ErrorCategory,
+    ErrorContext, QuarantineReason, QuarantineStore, Result, StreamConfig, StreamMessage,
+    StreamProcessor, StrictnessLevel, numeric::u128_to_f64, numeric::usize_to_f64,
 };
 use edi_adapter_edifact::EdifactParser;
-use edi_adapter_edifact::parser::ParseWarning;
+use edi_adapter_edifact::parser::{ParseFailure, ParseWarning};
 use edi_ir::{Document, Node, NodeType, Value};
 use tracing::{debug, info_span, warn};
 
@@ -71,6 +72,7 @@ pub enum OutputFormat {
 pub struct Pipeline {
     config: PipelineConfig,
     quarantine: QuarantineStore<Vec<u8>>,
+    duplicates: Option<Box<dyn DuplicateRegistry>>,
     stats: PipelineStats,
     running: bool,
 }
@@ -94,6 +96,8 @@ pub struct PipelineStats {
     pub messages_failed: usize,
     /// Total validation errors.
     pub validation_errors: usize,
+    /// Total messages rejected as duplicates.
+    pub duplicates: usize,
     /// Total bytes processed.
     pub bytes_processed: usize,
     /// Total processing time.
@@ -121,6 +125,8 @@ pub struct FileResult {
     pub duration: Duration,
     /// Whether any message was quarantined.
     pub quarantined: bool,
+    /// Number of messages rejected as duplicates.
+    pub duplicate_count: usize,
 }
 
 /// Result of processing a batch of files.
@@ -181,11 +187,27 @@ struct FileSummary {
     success_count: usize,
     failure_count: usize,
     validation_failures: usize,
+    duplicate_count: usize,
     quarantined: bool,
     file_error: Option<String>,
     fatal_error: Option<String>,
 }
 
+#[derive(Debug, Clone, Default)]
+struct MessageKeys {
+    interchange: Option<DuplicateKey>,
+    message: Option<DuplicateKey>,
+}
+
+/// Messages left to process after duplicate detection
+#[derive(Debug, Default)]
+struct DuplicateCheck {
+    documents: Vec<Document>,
+    warning_counts: Vec<usize>,
+    keys: Vec<MessageKeys>,
+    duplicates: Vec<MessageOutcome>,
+}
+
 #[derive(Debug)]
 struct StreamWorkItem {
     index: usize,
@@ -200,6 +222,7 @@ impl Pipeline {
         Self {
             config,
             quarantine: QuarantineStore::with_defaults(),
+            duplicates: None,
             stats: PipelineStats::default(),
             running: false,
         }
@@ -211,6 +234,18 @@ impl Pipeline {
         Self::new(PipelineConfig::default())
     }
 
+    /// Reject interchanges and messages already recorded in `registry`.
+    ///
+    /// Repeats fail with a duplicate error and are quarantined with
+    /// [`QuarantineReason::Duplicate`] under the quarantine policy. Keys are
+    /// recorded for messages processed successfully, and for the interchange
+    /// once all of its messages were processed successfully.
+    #[must_use]
+    pub fn with_duplicate_registry(mut self, registry: impl DuplicateRegistry + 'static) -> Self {
+        self.duplicates = Some(Box::new(registry));
+        self
+    }
+
     /// Start the pipeline.
     pub fn start(&mut self) {
         self.running = true;
@@ -287,6 +322,7 @@ impl Pipeline {
                 self.stats.messages_successful += summary.success_count;
                 self.stats.messages_failed += summary.failure_count;
                 self.stats.validation_errors += summary.validation_failures;
+                self.stats.duplicates += summary.duplicate_count;
 
                 if summary.quarantined {
                     self.stats.files_quarantined += 1;
@@ -328,6 +364,7 @@ impl Pipeline {
                     failure_count: summary.failure_count,
                     duration,
                     quarantined: summary.quarantined,
+                    duplicate_count: summary.duplicate_count,
                 })
             }
             Err(error) => {
@@ -382,12 +419,23 @@ impl Pipeline {
         validator: Option<&dyn Validator>,
         mapper: Option<&dyn Mapper>,
     ) -> Result<FileSummary> {
-        let parser = EdifactParser::new();
+        // Under quarantine, damaged messages are skipped and quarantined on
+        // their own instead of failing the whole file
+        let recover = matches!(self.config.acceptance_policy, AcceptancePolicy::Quarantine);
+        let parser = EdifactParser::new().with_error_recovery(recover);
         let parse_outcome = parser
             .parse_with_warnings(content, path)
             .map_err(|error| Error::pipeline("parse", path.to_string(), error.to_string()))?;
 
+        let mut summary = FileSummary::default();
+        for failure in &parse_outcome.failures {
+            self.quarantine_parse_failure(content, path, failure, &mut summary)?;
+        }
+
         if parse_outcome.documents.is_empty() {
+            if summary.message_count > 0 {
+                return Ok(summary);
+            }
             let mut summary = FileSummary {
                 file_error: Some("No messages were parsed from file".to_string()),
                 ..FileSummary::default()
@@ -400,15 +448,25 @@ impl Pipeline {
             return Ok(summary);
         }
 
+        let parse_failed = !parse_outcome.failures.is_empty();
         let warning_counts =
             warning_counts_for_documents(&parse_outcome.documents, &parse_outcome.warnings);
+        let DuplicateCheck {
+            documents,
+            warning_counts,
+            keys,
+            duplicates,
+        } = self.check_duplicates(parse_outcome.documents, warning_counts)?;
+        summary.duplicate_count = duplicates.len();
         let processing_config = self.processing_config();
         let stop_on_failure = matches!(self.config.acceptance_policy, AcceptancePolicy::FailAll);
 
-        let outcomes = if self.config.streaming && validator.is_none() && mapper.is_none() {
+        let outcomes = if stop_on_failure && !duplicates.is_empty() {
+            Vec::new()
+        } else if self.config.streaming && validator.is_none() && mapper.is_none() {
             self.process_documents_streaming(
                 processing_config,
-                parse_outcome.documents,
+                documents,
                 &warning_counts,
                 stop_on_failure,
             )?
@@ -421,7 +479,7 @@ impl Pipeline {
 
             process_documents_sequential(
                 processing_config,
-                parse_outcome.documents,
+                documents,
                 &warning_counts,
                 validator,
                 mapper,
@@ -429,12 +487,10 @@ impl Pipeline {
             )
         };
 
-        let mut summary = FileSummary {
-            message_count: outcomes.len(),
-            ..FileSummary::default()
-        };
+        self.record_processed(&keys, &outcomes, parse_failed || !duplicates.is_empty())?;
+        summary.message_count += duplicates.len() + outcomes.len();
 
-        for outcome in outcomes {
+        for outcome in duplicates.into_iter().chain(outcomes) {
             let message_span = info_span!(
                 "pipeline.process_message",
                 path = %path,
@@ -479,6 +535,136 @@ impl Pipeline {
         Ok(summary)
     }
 
+    /// Split off messages whose interchange or message key was recorded
+    /// before, or that repeat a message earlier in the same file
+    fn check_duplicates(
+        &self,
+        documents: Vec<Document>,
+        warning_counts: Vec<usize>,
+    ) -> Result<DuplicateCheck> {
+        let Some(registry) = &self.duplicates else {
+            return Ok(DuplicateCheck {
+                keys: vec![MessageKeys::default(); documents.len()],
+                documents,
+                warning_counts,
+                duplicates: Vec::new(),
+            });
+        };
+
+        let mut check = DuplicateCheck::default();
+        let mut seen = HashSet::new();
+        for (index, (document, warning_count)) in
+            documents.into_iter().zip(warning_counts).enumerate()
+        {
+            let keys = MessageKeys {
+                interchange: DuplicateKey::interchange(&document),
+                message: DuplicateKey::message(&document),
+            };
+
+            let mut repeated = None;
+            if let Some(key) = &keys.interchange
+                && registry.contains(key)?
+            {
+                repeated = Some(key.clone());
+            }
+            if repeated.is_none()
+                && let Some(key) = &keys.message
+                && (registry.contains(key)? || !seen.insert(key.clone()))
+            {
+                repeated = Some(key.clone());
+            }
+
+            if let Some(key) = repeated {
+                warn!(key = %key, "Rejecting duplicate");
+                check.duplicates.push(MessageOutcome {
+                    message_id: message_id(&document, index),
+                    success: false,
+                    error: Some(format!("Duplicate {key}")),
+                    validation_failures: 0,
+                    quarantine_reason: QuarantineReason::Duplicate,
+                    quarantine_payload: serde_json::to_vec(&document).unwrap_or_default(),
+                });
+            } else {
+                check.documents.push(document);
+                check.warning_counts.push(warning_count);
+                check.keys.push(keys);
+            }
+        }
+        Ok(check)
+    }
+
+    /// Record the message keys of successfully processed messages, and the
+    /// key of an interchange once every one of its messages succeeded.
+    ///
+    /// `skipped` tells that messages of the file failed to parse or were
+    /// rejected as duplicates; their interchange is then only partially
+    /// processed and a resend must still be accepted for the other messages.
+    fn record_processed(
+        &mut self,
+        keys: &[MessageKeys],
+        outcomes: &[MessageOutcome],
+        skipped: bool,
+    ) -> Result<()> {
+        let Some(registry) = &mut self.duplicates else {
+            return Ok(());
+        };
+        let mut incomplete = HashSet::new();
+        for (index, keys) in keys.iter().enumerate() {
+            let success = outcomes.get(index).is_some_and(|outcome| outcome.success);
+            if let Some(key) = &keys.interchange
+                && (skipped || !success)
+            {
+                incomplete.insert(key);
+            }
+            if success && let Some(key) = &keys.message {
+                registry.record(key)?;
+            }
+        }
+        let mut recorded = HashSet::new();
+        for key in keys.iter().filter_map(|keys| keys.interchange.as_ref()) {
+            if !incomplete.contains(key) && recorded.insert(key) {
+                registry.record(key)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Quarantine the source bytes of a message the parser skipped
+    fn quarantine_parse_failure(
+        &mut self,
+        content: &[u8],
+        path: &str,
+        failure: &ParseFailure,
+        summary: &mut FileSummary,
+    ) -> Result<()> {
+        let message_id = failure
+            .message_ref
+            .clone()
+            .unwrap_or_else(|| format!("offset-{}", failure.range.start));
+        let error = format!("Parse error in message {message_id}: {}", failure.message);
+        let error_context = ErrorContext::new(error.clone(), ErrorCategory::Parsing)
+            .with_position(format!(
+                "line {}, column {}",
+                failure.position.line, failure.position.column
+            ))
+            .with_detail(
+                "byte_range",
+                format!("{}..{}", failure.range.start, failure.range.end),
+            );
+        self.quarantine.quarantine_with_context(
+            format!("{path}:{message_id}"),
+            content[failure.range.clone()].to_vec(),
+            QuarantineReason::ProcessingError,
+            error_context,
+        )?;
+
+        summary.message_count += 1;
+        summary.failure_count += 1;
+        summary.quarantined = true;
+        summary.file_error.get_or_insert(error);
+        Ok(())
+    }
+
     fn process_documents_streaming(
         &self,
         processing_config: MessageProcessingConfig,
@@ -660,6 +846,7 @@ impl Pipeline {
                                 failure_count: 0,
                                 duration: Duration::ZERO,
                                 quarantined: false,
+                                duplicate_count: 0,
                             });
 
                             if matches!(self.config.acceptance_policy, AcceptancePolicy::FailAll) {
@@ -1311,6 +1498,7 @@ impl Default for Pipeline {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::FileDuplicateRegistry;
     use std::fmt::Write as _;
     use std::io::Write;
     use std::path::PathBuf;
@@ -1560,6 +1748,153 @@ mod tests {
         assert_eq!(stats.messages_failed, 1);
     }
 
+    #[test]
+    fn test_quarantine_policy_isolates_unparseable_message() {
+        let broken = valid_multi_message_file(3).replace("BGM+220+PO2+9'", "BGM220+PO2+9'");
+        let file = create_test_file(&broken);
+
+        let mut accept_all = Pipeline::with_defaults();
+        assert!(accept_all.process_file(file.path()).is_err());
+
+        let config = PipelineConfig {
+            acceptance_policy: AcceptancePolicy::Quarantine,
+            ..PipelineConfig::default()
+        };
+        let mut pipeline = Pipeline::new(config);
+        pipeline.start();
+
+        let result = pipeline
+            .process_file(file.path())
+            .expect("damaged message should be quarantined");
+        assert!(result.success);
+        assert_eq!(result.message_count, 3);
+        assert_eq!(result.success_count, 2);
+        assert_eq!(result.failure_count, 1);
+        assert!(result.quarantined);
+
+        let path = file.path().to_string_lossy();
+        let quarantined = pipeline
+            .quarantine()
+            .get(&format!("{path}:2"))
+            .expect("message 2 should be quarantined");
+        assert_eq!(
+            String::from_utf8_lossy(&quarantined.data),
+            "UNH+2+ORDERS:D:96A:UN'\nBGM220+PO2+9'\nUNT+3+2'\n"
+        );
+        assert_eq!(quarantined.error_context.category, ErrorCategory::Parsing);
+        assert_eq!(pipeline.quarantine().len(), 1);
+    }
+
+    #[test]
+    fn test_duplicate_interchange_is_quarantined_across_runs() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let registry_path = dir.path().join("duplicates.jsonl");
+        let file = create_test_file(&valid_multi_message_file(2));
+        let config = PipelineConfig {
+            acceptance_policy: AcceptancePolicy::Quarantine,
+            ..PipelineConfig::default()
+        };
+
+        let mut first = Pipeline::new(config.clone()).with_duplicate_registry(
+            FileDuplicateRegistry::open(&registry_path).expect("registry should open"),
+        );
+        let result = first.process_file(file.path()).expect("first run");
+        assert_eq!(result.success_count, 2);
+        assert_eq!(result.duplicate_count, 0);
+
+        let mut second = Pipeline::new(config).with_duplicate_registry(
+            FileDuplicateRegistry::open(&registry_path).expect("registry should reopen"),
+        );
+        let result = second.process_file(file.path()).expect("second run");
+        assert!(result.success);
+        assert_eq!(result.message_count, 2);
+        assert_eq!(result.success_count, 0);
+        assert_eq!(result.duplicate_count, 2);
+        assert!(result.quarantined);
+        assert_eq!(second.stats().duplicates, 2);
+
+        let path = file.path().to_string_lossy();
+        let quarantined = second
+            .quarantine()
+            .get(&format!("{path}:1"))
+            .expect("message 1 should be quarantined");
+        assert_eq!(quarantined.reason, QuarantineReason::Duplicate);
+        assert!(
+            quarantined
+                .error_context
+                .message
+                .contains("Duplicate interchange 1 from 'SENDER' to 'RECEIVER'"),
+            "{}",
+            quarantined.error_context.message
+        );
+    }
+
+    #[test]
+    fn test_partially_processed_interchange_accepts_resend_of_failed_message() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let registry_path = dir.path().join("duplicates.jsonl");
+        let config = PipelineConfig {
+            acceptance_policy: AcceptancePolicy::Quarantine,
+            ..PipelineConfig::default()
+        };
+        let broken = valid_multi_message_file(3).replace("BGM+220+PO2+9'", "BGM220+PO2+9'");
+
+        let mut first = Pipeline::new(config.clone()).with_duplicate_registry(
+            FileDuplicateRegistry::open(&registry_path).expect("registry should open"),
+        );
+        let result = first
+            .process_file(create_test_file(&broken).path())
+            .expect("first run");
+        assert_eq!(result.success_count, 2);
+        assert_eq!(result.failure_count, 1);
+
+        // The partner resends the whole interchange with message 2 repaired
+        let mut second = Pipeline::new(config).with_duplicate_registry(
+            FileDuplicateRegistry::open(&registry_path).expect("registry should reopen"),
+        );
+        let result = second
+            .process_file(create_test_file(&valid_multi_message_file(3)).path())
+            .expect("second run");
+        assert_eq!(result.message_count, 3);
+        assert_eq!(result.success_count, 1);
+        assert_eq!(result.duplicate_count, 2);
+    }
+
+    #[test]
+    fn test_duplicate_message_is_flagged_under_accept_all() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let mut pipeline = Pipeline::with_defaults().with_duplicate_registry(
+            FileDuplicateRegistry::open(dir.path().join("duplicates.jsonl"))
+                .expect("registry should open"),
+        );
+
+        pipeline
+            .process_file(create_test_file(&valid_multi_message_file(1)).path())
+            .expect("first file");
+
+        // Same message resent in a new interchange, and repeated within it
+        let file = create_test_file(
+            "UNB+UNOA:3+SENDER+RECEIVER+240102:1200+2'\n\
+             UNH+1+ORDERS:D:96A:UN'\nBGM+220+PO1+9'\nUNT+3+1'\n\
+             UNH+2+ORDERS:D:96A:UN'\nBGM+220+PO2+9'\nUNT+3+2'\n\
+             UNH+2+ORDERS:D:96A:UN'\nBGM+220+PO2+9'\nUNT+3+2'\n\
+             UNZ+3+2'\n",
+        );
+        let result = pipeline.process_file(file.path()).expect("second file");
+
+        assert!(result.success);
+        assert_eq!(result.message_count, 3);
+        assert_eq!(result.success_count, 1);
+        assert_eq!(result.failure_count, 2);
+        assert_eq!(result.duplicate_count, 2);
+        assert!(!result.quarantined);
+        assert_eq!(
+            result.error.as_deref(),
+            None,
+            "accept-all reports duplicates without failing the file"
+        );
+    }
+
     #[test]
     fn test_process_with_validation_accept_all_continues() {
         let config = PipelineConfig {
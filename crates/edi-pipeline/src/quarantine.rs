@@ -74,6 +74,8 @@ pub enum QuarantineReason {
     Manual,
     /// Timeout
     Timeout,
+    /// Interchange or message received before
+    Duplicate,
 }
 
 /// Quarantine store for holding bad messages
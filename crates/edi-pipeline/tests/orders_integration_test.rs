@@ -226,6 +226,38 @@ fn test_orders_with_partner_profile() {
     assert!(!csv_output.is_empty(), "CSV output should not be empty");
 }
 
+#[test]
+fn test_orders_partner_profile_restricts_party_qualifiers() {
+    let loader = SchemaLoader::new(vec![testdata_path().join("schemas")]);
+    let schema = loader
+        .load_with_inheritance(&edi_schema::SchemaRef::new("partner_acme_orders", "1.0"))
+        .expect("Failed to load ACME schema with inheritance");
+
+    let party_list = schema
+        .find_code_list("3035")
+        .expect("ACME schema should inherit the 3035 code list");
+    assert_eq!(party_list.codes, vec!["BY", "SU", "DP"]);
+
+    let data = b"UNH+1+ORDERS:D:96A:UN'\
+BGM+220+PO1+9'\
+NAD+BY+5412345000013::9'\
+NAD+IV+5412345000020::9'\
+UNT+5+1'";
+    let doc = EdifactParser::new()
+        .parse_message(data, "acme_invoicee.edi")
+        .expect("Failed to parse message");
+    let result = validate_document(&doc, &schema);
+
+    let violations: Vec<_> = result
+        .report
+        .all_issues()
+        .iter()
+        .filter(|issue| issue.code.as_deref() == Some("CODELIST_VIOLATION"))
+        .collect();
+    assert_eq!(violations.len(), 1, "issues: {:?}", result.report);
+    assert!(violations[0].message.contains("'IV'"));
+}
+
 #[test]
 fn test_orders_multiple_line_items() {
     // Test that multiple line items are handled correctly
@@ -1,6 +1,9 @@
 //! Schema inheritance and merge logic
 
-use crate::model::{Constraint, Schema, SegmentDefinition};
+use crate::model::{
+    CodeListDefinition, CodeListMode, Constraint, Schema, SegmentDefinition,
+    SegmentGroupDefinition, merge_dependencies,
+};
 use std::collections::HashSet;
 
 /// Error type for inheritance operations
@@ -88,42 +91,117 @@ pub fn detect_circular_dependency(child: &str, parent: &str, visited: &HashSet<S
 /// Merge parent schema into child schema
 /// Child properties take precedence over parent properties
 pub fn merge_schemas(parent: &Schema, child: &Schema) -> Schema {
+    let merged_segments = merge_segment_lists(&parent.segments, &child.segments);
+    let merged_groups = merge_group_lists(&parent.groups, &child.groups);
+    let merged_code_lists = merge_code_lists(&parent.code_lists, &child.code_lists);
+
+    let mut result = Schema::new(&child.name, &child.version)
+        .with_segments(merged_segments)
+        .with_groups(merged_groups)
+        .with_code_lists(merged_code_lists);
+
+    // Preserve inheritance metadata from child
+    result.inheritance = child.inheritance.clone();
+
+    result
+}
+
+fn merge_segment_lists(
+    parent: &[SegmentDefinition],
+    child: &[SegmentDefinition],
+) -> Vec<SegmentDefinition> {
     // Collect child tag names first (owned Strings to avoid borrow issues)
-    let child_tags: HashSet<String> = child.segments.iter().map(|s| s.tag.clone()).collect();
+    let child_tags: HashSet<String> = child.iter().map(|s| s.tag.clone()).collect();
 
     // Start with child's segments
-    let mut merged_segments = child.segments.clone();
+    let mut merged_segments = child.to_vec();
 
     // Add parent segments that child doesn't have
-    for parent_segment in &parent.segments {
+    for parent_segment in parent {
         if !child_tags.contains(&parent_segment.tag) {
             merged_segments.push(parent_segment.clone());
         }
     }
 
     // For segments that exist in both, merge elements
-    // Collect tags to iterate over to avoid borrow issues
-    let segment_tags_to_merge: Vec<String> = parent
-        .segments
+    for parent_segment in parent {
+        if child_tags.contains(&parent_segment.tag) {
+            if let Some(child_segment) = merged_segments
+                .iter_mut()
+                .find(|s| s.tag == parent_segment.tag)
+            {
+                merge_segment_definitions(parent_segment, child_segment);
+            }
+        }
+    }
+
+    merged_segments
+}
+
+fn merge_group_lists(
+    parent: &[SegmentGroupDefinition],
+    child: &[SegmentGroupDefinition],
+) -> Vec<SegmentGroupDefinition> {
+    let mut merged_groups = child.to_vec();
+
+    for parent_group in parent {
+        if let Some(child_group) = merged_groups.iter_mut().find(|g| g.id == parent_group.id) {
+            merge_group_definitions(parent_group, child_group);
+        } else {
+            merged_groups.push(parent_group.clone());
+        }
+    }
+
+    merged_groups
+}
+
+/// Merge code lists by ID, applying each child list's mode to the parent's
+/// codes. Merged lists are fully resolved and carry `CodeListMode::Replace`.
+pub fn merge_code_lists(
+    parent: &[CodeListDefinition],
+    child: &[CodeListDefinition],
+) -> Vec<CodeListDefinition> {
+    let mut merged: Vec<CodeListDefinition> = parent
         .iter()
-        .filter(|ps| child.segments.iter().any(|cs| cs.tag == ps.tag))
-        .map(|ps| ps.tag.clone())
+        .filter(|p| !child.iter().any(|c| c.id == p.id))
+        .cloned()
         .collect();
 
-    for tag in segment_tags_to_merge {
-        if let Some(parent_segment) = parent.segments.iter().find(|s| s.tag == tag) {
-            if let Some(child_segment) = merged_segments.iter_mut().find(|s| s.tag == tag) {
-                merge_segment_definitions(parent_segment, child_segment);
+    for child_list in child {
+        let mut list = child_list.clone();
+        if let Some(parent_list) = parent.iter().find(|p| p.id == child_list.id) {
+            list.codes = match child_list.mode {
+                CodeListMode::Replace => child_list.codes.clone(),
+                CodeListMode::Extend => extend_codelist(&parent_list.codes, &child_list.codes),
+                CodeListMode::Restrict => restrict_codelist(&parent_list.codes, &child_list.codes),
+            };
+            if list.description.is_none() {
+                list.description.clone_from(&parent_list.description);
             }
         }
+        list.mode = CodeListMode::Replace;
+        merged.push(list);
     }
 
-    let mut result = Schema::new(&child.name, &child.version).with_segments(merged_segments);
+    merged
+}
 
-    // Preserve inheritance metadata from child
-    result.inheritance = child.inheritance.clone();
+/// Merge a parent group into a child group with the same ID
+/// Segments and nested groups are merged recursively; the child's maximum
+/// occurrences override the parent's, and the stricter minimum wins
+fn merge_group_definitions(parent: &SegmentGroupDefinition, child: &mut SegmentGroupDefinition) {
+    child.min_occurs = child.min_occurs.max(parent.min_occurs);
 
-    result
+    if child.max_occurs.is_none() {
+        child.max_occurs = parent.max_occurs;
+    }
+
+    if child.position.is_none() {
+        child.position = parent.position;
+    }
+
+    child.segments = merge_segment_lists(&parent.segments, &child.segments);
+    child.groups = merge_group_lists(&parent.groups, &child.groups);
 }
 
 fn merge_segment_definitions(parent: &SegmentDefinition, child: &mut SegmentDefinition) {
@@ -137,6 +215,17 @@ fn merge_segment_definitions(parent: &SegmentDefinition, child: &mut SegmentDefi
         }
     }
 
+    // Merge composite components of elements defined in both
+    for parent_element in &parent.elements {
+        if child_element_ids.contains(&parent_element.id) {
+            if let Some(child_element) = child.find_element_mut(&parent_element.id) {
+                child_element.merge(parent_element);
+            }
+        }
+    }
+
+    merge_dependencies(&mut child.dependencies, &parent.dependencies);
+
     // Merge segment-level properties
     // Child mandatory overrides parent optional
     if child.is_mandatory || parent.is_mandatory {
@@ -148,6 +237,10 @@ fn merge_segment_definitions(parent: &SegmentDefinition, child: &mut SegmentDefi
     if child.max_repetitions.is_none() && parent.max_repetitions.is_some() {
         child.max_repetitions = parent.max_repetitions;
     }
+
+    if child.position.is_none() {
+        child.position = parent.position;
+    }
 }
 
 /// Build inheritance chain from base to most specific
@@ -214,10 +307,24 @@ pub fn merge_constraints(parent: &[Constraint], child: &[Constraint]) -> Vec<Con
 }
 
 /// Extend codelist constraints by merging allowed values
+/// Parent codes keep their order, new child codes are appended
 pub fn extend_codelist(parent_codes: &[String], child_codes: &[String]) -> Vec<String> {
-    let mut result: HashSet<String> = parent_codes.iter().cloned().collect();
-    result.extend(child_codes.iter().cloned());
-    result.into_iter().collect()
+    let mut result = parent_codes.to_vec();
+    for code in child_codes {
+        if !result.contains(code) {
+            result.push(code.clone());
+        }
+    }
+    result
+}
+
+/// Restrict codelist constraints to parent values also allowed by the child
+pub fn restrict_codelist(parent_codes: &[String], child_codes: &[String]) -> Vec<String> {
+    parent_codes
+        .iter()
+        .filter(|code| child_codes.contains(code))
+        .cloned()
+        .collect()
 }
 
 #[cfg(test)]
@@ -239,6 +346,8 @@ mod tests {
             elements,
             is_mandatory: mandatory,
             max_repetitions: None,
+            position: None,
+            dependencies: Vec::new(),
         }
     }
 
@@ -401,6 +510,8 @@ mod tests {
                 elements: vec![create_element("C002", "Overridden Name", false)],
                 is_mandatory: false, // Override: was true, now false
                 max_repetitions: Some(99),
+                position: None,
+                dependencies: Vec::new(),
             }],
         );
 
@@ -411,6 +522,108 @@ mod tests {
         assert_eq!(bgm.elements[0].name, "Overridden Name"); // Child value preserved
     }
 
+    #[test]
+    fn test_merge_overrides_nested_group_cardinality() {
+        let parent = create_test_schema("parent", vec![]).with_groups(vec![
+            SegmentGroupDefinition::new("SG2", "NAD")
+                .position(80)
+                .max_occurs(99)
+                .with_segments(vec![create_segment("NAD", vec![], true)])
+                .with_groups(vec![
+                    SegmentGroupDefinition::new("SG5", "CTA")
+                        .max_occurs(5)
+                        .with_segments(vec![
+                            create_segment("CTA", vec![], true),
+                            create_segment("COM", vec![], false),
+                        ]),
+                ]),
+        ]);
+
+        let child = create_test_schema("child", vec![]).with_groups(vec![
+            SegmentGroupDefinition::new("SG2", "NAD").with_groups(vec![
+                SegmentGroupDefinition::new("SG5", "CTA")
+                    .min_occurs(1)
+                    .max_occurs(1),
+            ]),
+        ]);
+
+        let merged = merge_schemas(&parent, &child);
+
+        let sg2 = merged.find_group("SG2").unwrap();
+        assert_eq!(sg2.position, Some(80));
+        assert_eq!(sg2.max_occurs, Some(99));
+        assert!(sg2.segments.iter().any(|s| s.tag == "NAD"));
+
+        let sg5 = merged.find_group("SG5").unwrap();
+        assert_eq!(sg5.min_occurs, 1);
+        assert_eq!(sg5.max_occurs, Some(1));
+        assert_eq!(sg5.segments.len(), 2);
+    }
+
+    #[test]
+    fn test_merge_inherits_composite_components() {
+        use crate::model::ComponentDefinition;
+
+        let parent = create_test_schema(
+            "parent",
+            vec![create_segment(
+                "DTM",
+                vec![
+                    ElementDefinition::new("C507", "Date/time/period", "c").with_components(vec![
+                        ComponentDefinition::new("2005", "Qualifier", "an").mandatory(true),
+                        ComponentDefinition::new("2380", "Value", "an").length(1, 35),
+                        ComponentDefinition::new("2379", "Format", "an").length(1, 3),
+                    ]),
+                ],
+                true,
+            )],
+        );
+        let child = create_test_schema(
+            "child",
+            vec![create_segment(
+                "DTM",
+                vec![
+                    ElementDefinition::new("C507", "Date/time/period", "c").with_components(vec![
+                        ComponentDefinition::new("2380", "Value", "an").length(8, 8),
+                    ]),
+                ],
+                false,
+            )],
+        );
+
+        let merged = merge_schemas(&parent, &child);
+        let c507 = merged
+            .find_segment("DTM")
+            .unwrap()
+            .find_element("C507")
+            .unwrap();
+
+        assert_eq!(c507.components.len(), 3);
+        assert_eq!(c507.components[1].min_length, 8);
+        assert!(c507.components[0].is_mandatory);
+    }
+
+    #[test]
+    fn test_merge_inherits_dependency_notes() {
+        use crate::model::{DependencyKind, DependencyNote};
+
+        let note = DependencyNote::new(DependencyKind::OneOrMore, vec!["e2", "e4"]);
+        let parent = create_test_schema(
+            "parent",
+            vec![create_segment("NAD", vec![], true).with_dependencies(vec![note.clone()])],
+        );
+        let child = create_test_schema(
+            "child",
+            vec![create_segment("NAD", vec![], true).with_dependencies(vec![
+                DependencyNote::new(DependencyKind::IfFirstThenAll, vec!["e5", "e6"]),
+                note,
+            ])],
+        );
+
+        let merged = merge_schemas(&parent, &child);
+        assert_eq!(merged.find_segment("NAD").unwrap().dependencies.len(), 2);
+    }
+
     #[test]
     fn test_merge_constraints() {
         let parent_constraints = vec![
@@ -604,6 +817,73 @@ mod tests {
         assert!(result.contains(&"B".to_string()));
     }
 
+    #[test]
+    fn test_restrict_codelist() {
+        let parent = vec!["BY".to_string(), "SU".to_string(), "DP".to_string()];
+        let child = vec!["SU".to_string(), "BY".to_string(), "XX".to_string()];
+
+        assert_eq!(restrict_codelist(&parent, &child), vec!["BY", "SU"]);
+    }
+
+    #[test]
+    fn test_merge_code_lists_by_mode() {
+        let parent = create_test_schema("parent", vec![]).with_code_lists(vec![
+            CodeListDefinition::new("3035", vec!["BY", "SU", "DP", "IV"])
+                .with_description("Party qualifier"),
+            CodeListDefinition::new("6411", vec!["PCE", "KGM"]),
+            CodeListDefinition::new("1001", vec!["220"]),
+            CodeListDefinition::new("4343", vec!["AC"]),
+        ]);
+        let child = create_test_schema("child", vec![]).with_code_lists(vec![
+            CodeListDefinition::new("3035", vec!["BY", "SU", "DP"]).mode(CodeListMode::Restrict),
+            CodeListDefinition::new("6411", vec!["LTR"]).mode(CodeListMode::Extend),
+            CodeListDefinition::new("1001", vec!["221"]),
+        ]);
+
+        let merged = merge_schemas(&parent, &child);
+
+        let party = merged.find_code_list("3035").unwrap();
+        assert_eq!(party.codes, vec!["BY", "SU", "DP"]);
+        assert_eq!(party.description.as_deref(), Some("Party qualifier"));
+        assert_eq!(party.mode, CodeListMode::Replace);
+        assert_eq!(
+            merged.find_code_list("6411").unwrap().codes,
+            vec!["PCE", "KGM", "LTR"]
+        );
+        assert_eq!(merged.find_code_list("1001").unwrap().codes, vec!["221"]);
+        assert!(merged.find_code_list("4343").is_some());
+    }
+
+    #[test]
+    fn test_merge_inherits_element_code_list_binding() {
+        let parent = create_test_schema(
+            "parent",
+            vec![create_segment(
+                "NAD",
+                vec![ElementDefinition::new("e1", "Party qualifier", "an").code_list("3035")],
+                true,
+            )],
+        );
+        let child = create_test_schema(
+            "child",
+            vec![create_segment(
+                "NAD",
+                vec![ElementDefinition::new("e1", "Party qualifier", "an").length(1, 3)],
+                true,
+            )],
+        );
+
+        let merged = merge_schemas(&parent, &child);
+        let e1 = merged
+            .find_segment("NAD")
+            .unwrap()
+            .find_element("e1")
+            .unwrap();
+
+        assert_eq!(e1.code_list.as_deref(), Some("3035"));
+        assert_eq!(e1.max_length, 3);
+    }
+
     #[test]
     fn test_merge_preserves_child_inheritance() {
         let parent = Schema::new("PARENT", "1.0");
@@ -12,7 +12,9 @@ pub mod registry;
 
 pub use loader::SchemaLoader;
 pub use model::{
-    Constraint, ElementDefinition, InheritanceMetadata, Schema, SchemaRef, SegmentDefinition,
+    CodeListDefinition, CodeListMode, ComponentDefinition, Constraint, DependencyKind,
+    DependencyNote, ElementDefinition, InheritanceMetadata, Schema, SchemaEntry, SchemaRef,
+    SegmentDefinition, SegmentGroupDefinition,
 };
 pub use registry::{ConcurrentSchemaRegistry, SchemaRegistry};
 
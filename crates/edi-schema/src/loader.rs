@@ -1,7 +1,10 @@
 //! Schema loader with inheritance support
 
 use crate::inheritance::{detect_circular_dependency, merge_schemas};
-use crate::model::{ElementDefinition, Schema, SchemaRef, SegmentDefinition};
+use crate::model::{
+    CodeListDefinition, CodeListMode, ComponentDefinition, DependencyKind, DependencyNote,
+    ElementDefinition, Schema, SchemaRef, SegmentDefinition, SegmentGroupDefinition,
+};
 use crate::registry::ConcurrentSchemaRegistry;
 use crate::{Error, Result};
 use serde::Deserialize;
@@ -19,6 +22,10 @@ struct SchemaFile {
     parent: Option<SchemaRefFile>,
     #[serde(default)]
     segments: Vec<SegmentFile>,
+    #[serde(default)]
+    groups: Vec<GroupFile>,
+    #[serde(default)]
+    code_lists: Vec<CodeListFile>,
 }
 
 /// Serializable schema reference for inheritance
@@ -43,6 +50,28 @@ struct SegmentFile {
     is_mandatory: bool,
     #[serde(default)]
     max_repetitions: Option<usize>,
+    #[serde(default)]
+    position: Option<u32>,
+    #[serde(default)]
+    dependencies: Vec<DependencyFile>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GroupFile {
+    id: String,
+    /// Defaults to the tag of the first segment
+    #[serde(default)]
+    trigger: Option<String>,
+    #[serde(default)]
+    position: Option<u32>,
+    #[serde(default)]
+    min_occurs: usize,
+    #[serde(default)]
+    max_occurs: Option<usize>,
+    #[serde(default)]
+    segments: Vec<SegmentFile>,
+    #[serde(default)]
+    groups: Vec<GroupFile>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -56,12 +85,201 @@ struct ElementFile {
     max_length: usize,
     #[serde(default)]
     is_mandatory: bool,
+    #[serde(default)]
+    code_list: Option<String>,
+    #[serde(default)]
+    components: Vec<ComponentFile>,
+    #[serde(default)]
+    dependencies: Vec<DependencyFile>,
+}
+
+/// Dependency note, e.g. `{ rule: D3, ids: [e2, e4] }`
+#[derive(Debug, Deserialize)]
+struct DependencyFile {
+    rule: String,
+    ids: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ComponentFile {
+    id: String,
+    name: String,
+    data_type: String,
+    #[serde(default)]
+    min_length: usize,
+    #[serde(default = "default_max_length")]
+    max_length: usize,
+    #[serde(default)]
+    is_mandatory: bool,
+    #[serde(default)]
+    code_list: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CodeListFile {
+    id: String,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    codes: Vec<String>,
+    #[serde(default)]
+    mode: CodeListModeFile,
+}
+
+#[derive(Debug, Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum CodeListModeFile {
+    #[default]
+    Replace,
+    Extend,
+    Restrict,
+}
+
+impl From<CodeListModeFile> for CodeListMode {
+    fn from(mode: CodeListModeFile) -> Self {
+        match mode {
+            CodeListModeFile::Replace => Self::Replace,
+            CodeListModeFile::Extend => Self::Extend,
+            CodeListModeFile::Restrict => Self::Restrict,
+        }
+    }
 }
 
 fn default_max_length() -> usize {
     35
 }
 
+fn convert_segment_file(segment: SegmentFile) -> Result<SegmentDefinition> {
+    Ok(SegmentDefinition {
+        tag: segment.tag,
+        elements: segment
+            .elements
+            .into_iter()
+            .map(convert_element_file)
+            .collect::<Result<Vec<_>>>()?,
+        is_mandatory: segment.is_mandatory,
+        max_repetitions: segment.max_repetitions,
+        position: segment.position,
+        dependencies: convert_dependency_files(segment.dependencies)?,
+    })
+}
+
+fn convert_element_file(element: ElementFile) -> Result<ElementDefinition> {
+    Ok(ElementDefinition {
+        id: element.id,
+        name: element.name,
+        data_type: element.data_type,
+        min_length: element.min_length,
+        max_length: element.max_length,
+        is_mandatory: element.is_mandatory,
+        code_list: element.code_list,
+        components: element
+            .components
+            .into_iter()
+            .map(|c| ComponentDefinition {
+                id: c.id,
+                name: c.name,
+                data_type: c.data_type,
+                min_length: c.min_length,
+                max_length: c.max_length,
+                is_mandatory: c.is_mandatory,
+                code_list: c.code_list,
+            })
+            .collect(),
+        dependencies: convert_dependency_files(element.dependencies)?,
+    })
+}
+
+fn convert_dependency_files(dependencies: Vec<DependencyFile>) -> Result<Vec<DependencyNote>> {
+    dependencies
+        .into_iter()
+        .map(|d| {
+            let kind = DependencyKind::from_code(&d.rule).ok_or_else(|| {
+                Error::InvalidFormat(format!("Unknown dependency note rule: {}", d.rule))
+            })?;
+            Ok(DependencyNote::new(kind, d.ids))
+        })
+        .collect()
+}
+
+/// Reject dependency notes listing IDs their segment or composite does not
+/// declare
+fn check_dependency_ids(schema: &Schema) -> Result<()> {
+    fn check_segments(
+        segments: &[SegmentDefinition],
+        groups: &[SegmentGroupDefinition],
+    ) -> Result<()> {
+        for segment in segments {
+            check_notes(&segment.dependencies, &segment.tag, |id| {
+                segment.find_element(id).is_some()
+            })?;
+            for element in &segment.elements {
+                let owner = format!("{}/{}", segment.tag, element.id);
+                check_notes(&element.dependencies, &owner, |id| {
+                    element.find_component(id).is_some()
+                })?;
+            }
+        }
+        for group in groups {
+            check_segments(&group.segments, &group.groups)?;
+        }
+        Ok(())
+    }
+
+    fn check_notes(
+        notes: &[DependencyNote],
+        owner: &str,
+        declared: impl Fn(&str) -> bool,
+    ) -> Result<()> {
+        for note in notes {
+            if let Some(id) = note.ids.iter().find(|id| !declared(id)) {
+                return Err(Error::InvalidFormat(format!(
+                    "Dependency note {} on {} refers to unknown ID: {}",
+                    note.kind.code(),
+                    owner,
+                    id
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    check_segments(&schema.segments, &schema.groups)
+}
+
+fn convert_group_file(group: GroupFile) -> Result<SegmentGroupDefinition> {
+    let trigger = group
+        .trigger
+        .or_else(|| group.segments.first().map(|s| s.tag.clone()))
+        .ok_or_else(|| {
+            Error::InvalidFormat(format!(
+                "Segment group {} has neither a trigger nor segments",
+                group.id
+            ))
+        })?;
+
+    let mut definition = SegmentGroupDefinition::new(group.id, trigger)
+        .min_occurs(group.min_occurs)
+        .with_segments(
+            group
+                .segments
+                .into_iter()
+                .map(convert_segment_file)
+                .collect::<Result<Vec<_>>>()?,
+        )
+        .with_groups(
+            group
+                .groups
+                .into_iter()
+                .map(convert_group_file)
+                .collect::<Result<Vec<_>>>()?,
+        );
+    definition.position = group.position;
+    definition.max_occurs = group.max_occurs;
+
+    Ok(definition)
+}
+
 /// Enhanced schema loader with inheritance support
 pub struct SchemaLoader {
     registry: Arc<ConcurrentSchemaRegistry>,
@@ -165,7 +383,7 @@ impl SchemaLoader {
         let schema_file: SchemaFile = serde_json::from_str(json)
             .map_err(|e| Error::InvalidFormat(format!("JSON parse error: {}", e)))?;
 
-        Ok(self.convert_schema_file(schema_file))
+        self.convert_schema_file(schema_file)
     }
 
     /// Load a schema from YAML string
@@ -173,42 +391,48 @@ impl SchemaLoader {
         let schema_file: SchemaFile = serde_yaml::from_str(yaml)
             .map_err(|e| Error::InvalidFormat(format!("YAML parse error: {}", e)))?;
 
-        Ok(self.convert_schema_file(schema_file))
+        self.convert_schema_file(schema_file)
     }
 
     /// Convert a SchemaFile to a Schema
-    fn convert_schema_file(&self, schema_file: SchemaFile) -> Schema {
+    fn convert_schema_file(&self, schema_file: SchemaFile) -> Result<Schema> {
         let parent = schema_file.parent.map(|p| p.into());
 
-        let segments: Vec<SegmentDefinition> = schema_file
+        let segments = schema_file
             .segments
             .into_iter()
-            .map(|s| SegmentDefinition {
-                tag: s.tag,
-                elements: s
-                    .elements
-                    .into_iter()
-                    .map(|e| ElementDefinition {
-                        id: e.id,
-                        name: e.name,
-                        data_type: e.data_type,
-                        min_length: e.min_length,
-                        max_length: e.max_length,
-                        is_mandatory: e.is_mandatory,
-                    })
-                    .collect(),
-                is_mandatory: s.is_mandatory,
-                max_repetitions: s.max_repetitions,
+            .map(convert_segment_file)
+            .collect::<Result<Vec<_>>>()?;
+        let groups = schema_file
+            .groups
+            .into_iter()
+            .map(convert_group_file)
+            .collect::<Result<Vec<_>>>()?;
+
+        let code_lists = schema_file
+            .code_lists
+            .into_iter()
+            .map(|c| CodeListDefinition {
+                id: c.id,
+                description: c.description,
+                codes: c.codes,
+                mode: c.mode.into(),
             })
             .collect();
 
-        let mut schema = Schema::new(schema_file.name, schema_file.version).with_segments(segments);
+        let mut schema = Schema::new(schema_file.name, schema_file.version)
+            .with_segments(segments)
+            .with_groups(groups)
+            .with_code_lists(code_lists);
 
-        if let Some(parent_ref) = parent {
-            schema.inheritance.parent = Some(parent_ref);
+        // Notes may refer to elements a parent schema declares; those are
+        // checked once the inheritance chain has been merged
+        match parent {
+            Some(parent_ref) => schema.inheritance.parent = Some(parent_ref),
+            None => check_dependency_ids(&schema)?,
         }
 
-        schema
+        Ok(schema)
     }
 
     /// Load a schema from disk by name and version
@@ -345,6 +569,8 @@ impl SchemaLoader {
             .collect();
         result.inheritance.is_merged = true;
 
+        check_dependency_ids(&result)?;
+
         info!("Merged inheritance chain into schema: {}", leaf_name);
 
         Ok(result)
@@ -586,6 +812,224 @@ segments: []
         assert!(schema.inheritance.parent.is_some());
     }
 
+    #[test]
+    fn test_load_from_yaml_with_groups() {
+        let loader = SchemaLoader::default();
+        let yaml = r#"
+name: ORDERS
+version: D96A
+segments:
+  - tag: UNH
+    position: 10
+  - tag: UNS
+    position: 2000
+groups:
+  - id: SG2
+    position: 80
+    max_occurs: 99
+    segments:
+      - tag: NAD
+        is_mandatory: true
+    groups:
+      - id: SG5
+        trigger: CTA
+        min_occurs: 1
+        max_occurs: 5
+        segments:
+          - tag: CTA
+          - tag: COM
+"#;
+        let schema = loader.load_from_yaml(yaml).unwrap();
+
+        let sg2 = schema.find_group("SG2").unwrap();
+        assert_eq!(sg2.trigger, "NAD");
+        assert_eq!(sg2.position, Some(80));
+        assert_eq!(sg2.max_occurs, Some(99));
+        assert!(!sg2.is_mandatory());
+
+        let sg5 = schema.find_group("SG5").unwrap();
+        assert_eq!(sg5.min_occurs, 1);
+        assert_eq!(sg5.segments.len(), 2);
+        assert_eq!(schema.entries().len(), 3);
+    }
+
+    #[test]
+    fn test_load_group_without_trigger_or_segments() {
+        let loader = SchemaLoader::default();
+        let yaml = "name: TEST\nversion: '1.0'\ngroups:\n  - id: SG1";
+        let result = loader.load_from_yaml(yaml);
+        assert!(matches!(result, Err(Error::InvalidFormat(_))));
+    }
+
+    #[test]
+    fn test_load_from_yaml_with_composite_element() {
+        let loader = SchemaLoader::default();
+        let yaml = r#"
+name: TEST
+version: '1.0'
+segments:
+  - tag: QTY
+    elements:
+      - id: C186
+        name: Quantity details
+        data_type: c
+        is_mandatory: true
+        components:
+          - id: '6063'
+            name: Quantity qualifier
+            data_type: an
+            max_length: 3
+            is_mandatory: true
+            code_list: '6063'
+          - id: '6060'
+            name: Quantity
+            data_type: n
+            max_length: 15
+"#;
+        let schema = loader.load_from_yaml(yaml).unwrap();
+        let c186 = schema
+            .find_segment("QTY")
+            .unwrap()
+            .find_element("C186")
+            .unwrap();
+
+        assert!(c186.is_composite());
+        assert_eq!(c186.components[0].max_length, 3);
+        assert_eq!(c186.components[0].code_list.as_deref(), Some("6063"));
+        assert_eq!(c186.components[1].data_type, "n");
+    }
+
+    #[test]
+    fn test_load_from_yaml_with_code_lists() {
+        let loader = SchemaLoader::default();
+        let yaml = r#"
+name: TEST
+version: '1.0'
+code_lists:
+  - id: '3035'
+    description: Party qualifier
+    mode: restrict
+    codes: [BY, SU, DP]
+segments:
+  - tag: NAD
+    elements:
+      - id: e1
+        name: party_qualifier
+        data_type: an
+        code_list: '3035'
+"#;
+        let schema = loader.load_from_yaml(yaml).unwrap();
+
+        let list = schema.find_code_list("3035").unwrap();
+        assert_eq!(list.mode, CodeListMode::Restrict);
+        assert!(list.contains("DP"));
+        assert_eq!(
+            schema.find_segment("NAD").unwrap().elements[0]
+                .code_list
+                .as_deref(),
+            Some("3035")
+        );
+    }
+
+    #[test]
+    fn test_load_from_yaml_with_dependency_notes() {
+        let loader = SchemaLoader::default();
+        let yaml = r#"
+name: TEST
+version: '1.0'
+segments:
+  - tag: NAD
+    dependencies:
+      - rule: D3
+        ids: [e2, e4]
+    elements:
+      - id: e2
+        name: party_identification
+        data_type: c
+        dependencies:
+          - rule: D5
+            ids: ['3039', '3055']
+        components:
+          - id: '3039'
+            name: party_id
+            data_type: an
+          - id: '3055'
+            name: agency
+            data_type: an
+      - id: e4
+        name: party_name
+        data_type: an
+"#;
+        let schema = loader.load_from_yaml(yaml).unwrap();
+        let nad = schema.find_segment("NAD").unwrap();
+
+        assert_eq!(
+            nad.dependencies,
+            vec![DependencyNote::new(
+                DependencyKind::OneOrMore,
+                vec!["e2", "e4"]
+            )]
+        );
+        assert_eq!(
+            nad.elements[0].dependencies[0].kind,
+            DependencyKind::IfFirstThenAll
+        );
+    }
+
+    #[test]
+    fn test_load_unknown_dependency_rule() {
+        let loader = SchemaLoader::default();
+        let yaml = "name: TEST\nversion: '1.0'\nsegments:\n  - tag: NAD\n    dependencies:\n      - rule: D9\n        ids: [e1]";
+        let result = loader.load_from_yaml(yaml);
+        assert!(matches!(result, Err(Error::InvalidFormat(_))));
+    }
+
+    #[test]
+    fn test_load_dependency_note_with_unknown_id() {
+        let loader = SchemaLoader::default();
+        let yaml = r#"
+name: TEST
+version: '1.0'
+groups:
+  - id: SG2
+    segments:
+      - tag: NAD
+        dependencies:
+          - rule: D3
+            ids: [e2, e9]
+        elements:
+          - id: e2
+            name: party_identification
+            data_type: an
+"#;
+        let err = loader.load_from_yaml(yaml).unwrap_err();
+        assert!(
+            matches!(&err, Error::InvalidFormat(msg) if msg.contains("NAD") && msg.contains("e9"))
+        );
+
+        let yaml = r#"
+name: TEST
+version: '1.0'
+segments:
+  - tag: NAD
+    elements:
+      - id: e2
+        name: party_identification
+        data_type: c
+        dependencies:
+          - rule: D5
+            ids: ['3039', '1131']
+        components:
+          - id: '3039'
+            name: party_id
+            data_type: an
+"#;
+        let err = loader.load_from_yaml(yaml).unwrap_err();
+        assert!(
+            matches!(&err, Error::InvalidFormat(msg) if msg.contains("NAD/e2") && msg.contains("1131"))
+        );
+    }
+
     #[test]
     fn test_load_from_yaml_invalid() {
         let loader = SchemaLoader::default();
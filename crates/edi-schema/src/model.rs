@@ -38,7 +38,12 @@ pub struct InheritanceMetadata {
 pub struct Schema {
     pub name: String,
     pub version: String,
+    /// Message-level segments
     pub segments: Vec<SegmentDefinition>,
+    /// Message-level segment groups
+    pub groups: Vec<SegmentGroupDefinition>,
+    /// Code lists referenced by elements and components
+    pub code_lists: Vec<CodeListDefinition>,
     /// Inheritance metadata
     pub inheritance: InheritanceMetadata,
 }
@@ -50,6 +55,8 @@ impl Schema {
             name: name.into(),
             version: version.into(),
             segments: Vec::new(),
+            groups: Vec::new(),
+            code_lists: Vec::new(),
             inheritance: InheritanceMetadata::default(),
         }
     }
@@ -71,14 +78,224 @@ impl Schema {
         self
     }
 
-    /// Find a segment by tag
+    /// Add segment groups to the schema
+    pub fn with_groups(mut self, groups: Vec<SegmentGroupDefinition>) -> Self {
+        self.groups = groups;
+        self
+    }
+
+    /// Add code lists to the schema
+    pub fn with_code_lists(mut self, code_lists: Vec<CodeListDefinition>) -> Self {
+        self.code_lists = code_lists;
+        self
+    }
+
+    /// Find a code list by ID
+    pub fn find_code_list(&self, id: &str) -> Option<&CodeListDefinition> {
+        self.code_lists.iter().find(|c| c.id == id)
+    }
+
+    /// Find a segment by tag at any nesting level.
+    ///
+    /// Message-level segments are searched first, then each segment group in
+    /// declaration order, depth first; the first definition found wins.
+    pub fn find_segment(&self, tag: &str) -> Option<&SegmentDefinition> {
+        self.segments
+            .iter()
+            .find(|s| s.tag == tag)
+            .or_else(|| self.groups.iter().find_map(|g| g.find_segment(tag)))
+    }
+
+    /// Find the definition of a segment occurring in the segment group with
+    /// ID `group`, or outside any group when `group` is `None`.
+    ///
+    /// Grouped segments resolve within their group only, so a tag defined
+    /// differently in several groups gets the right definition. Ungrouped
+    /// segments, and segments of groups the schema does not declare, fall
+    /// back to [`Self::find_segment`].
+    pub fn find_segment_in(&self, group: Option<&str>, tag: &str) -> Option<&SegmentDefinition> {
+        match group.and_then(|id| self.find_group(id)) {
+            Some(group) => group.find_segment(tag),
+            None => self.find_segment(tag),
+        }
+    }
+
+    /// Find a segment by tag at any nesting level (mutable); the same
+    /// definition as [`Self::find_segment`] is returned
+    pub fn find_segment_mut(&mut self, tag: &str) -> Option<&mut SegmentDefinition> {
+        if let Some(index) = self.segments.iter().position(|s| s.tag == tag) {
+            return self.segments.get_mut(index);
+        }
+        self.groups.iter_mut().find_map(|g| g.find_segment_mut(tag))
+    }
+
+    /// Find a segment group by ID at any nesting level
+    pub fn find_group(&self, id: &str) -> Option<&SegmentGroupDefinition> {
+        self.groups.iter().find_map(|g| g.find_group(id))
+    }
+
+    /// Find a segment group by ID at any nesting level (mutable)
+    pub fn find_group_mut(&mut self, id: &str) -> Option<&mut SegmentGroupDefinition> {
+        self.groups.iter_mut().find_map(|g| g.find_group_mut(id))
+    }
+
+    /// Find the first segment group opened by the given trigger segment
+    pub fn find_group_by_trigger(&self, trigger: &str) -> Option<&SegmentGroupDefinition> {
+        self.groups
+            .iter()
+            .find_map(|g| g.find_group_by_trigger(trigger))
+    }
+
+    /// Message-level segments and groups in message order
+    pub fn entries(&self) -> Vec<SchemaEntry<'_>> {
+        ordered_entries(&self.segments, &self.groups)
+    }
+}
+
+/// A segment or segment group at a position in a message or group
+#[derive(Debug, Clone, Copy)]
+pub enum SchemaEntry<'a> {
+    Segment(&'a SegmentDefinition),
+    Group(&'a SegmentGroupDefinition),
+}
+
+impl SchemaEntry<'_> {
+    /// Position of this entry in the branching diagram, if declared
+    pub fn position(&self) -> Option<u32> {
+        match self {
+            SchemaEntry::Segment(segment) => segment.position,
+            SchemaEntry::Group(group) => group.position,
+        }
+    }
+}
+
+/// Order segments and groups by position
+/// Entries without a position keep their declared order (segments before
+/// groups) after all positioned entries
+fn ordered_entries<'a>(
+    segments: &'a [SegmentDefinition],
+    groups: &'a [SegmentGroupDefinition],
+) -> Vec<SchemaEntry<'a>> {
+    let mut entries: Vec<SchemaEntry<'a>> = segments
+        .iter()
+        .map(SchemaEntry::Segment)
+        .chain(groups.iter().map(SchemaEntry::Group))
+        .collect();
+    entries.sort_by_key(|entry| entry.position().unwrap_or(u32::MAX));
+    entries
+}
+
+/// Definition of a segment group (e.g. SG2 triggered by NAD)
+#[derive(Debug, Clone)]
+pub struct SegmentGroupDefinition {
+    pub id: String,
+    /// Tag of the segment that opens each group instance
+    pub trigger: String,
+    /// Position of the group in its parent's branching diagram
+    pub position: Option<u32>,
+    pub min_occurs: usize,
+    pub max_occurs: Option<usize>,
+    /// Segments of the group, starting with the trigger segment
+    pub segments: Vec<SegmentDefinition>,
+    /// Nested segment groups
+    pub groups: Vec<SegmentGroupDefinition>,
+}
+
+impl SegmentGroupDefinition {
+    /// Create a new segment group definition
+    pub fn new(id: impl Into<String>, trigger: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            trigger: trigger.into(),
+            position: None,
+            min_occurs: 0,
+            max_occurs: None,
+            segments: Vec::new(),
+            groups: Vec::new(),
+        }
+    }
+
+    /// Set the position in the parent's branching diagram
+    pub fn position(mut self, value: u32) -> Self {
+        self.position = Some(value);
+        self
+    }
+
+    /// Set minimum occurrences
+    pub fn min_occurs(mut self, value: usize) -> Self {
+        self.min_occurs = value;
+        self
+    }
+
+    /// Set maximum occurrences
+    pub fn max_occurs(mut self, value: usize) -> Self {
+        self.max_occurs = Some(value);
+        self
+    }
+
+    /// Add segments
+    pub fn with_segments(mut self, segments: Vec<SegmentDefinition>) -> Self {
+        self.segments = segments;
+        self
+    }
+
+    /// Add nested groups
+    pub fn with_groups(mut self, groups: Vec<SegmentGroupDefinition>) -> Self {
+        self.groups = groups;
+        self
+    }
+
+    /// Whether at least one occurrence of the group is required
+    pub fn is_mandatory(&self) -> bool {
+        self.min_occurs > 0
+    }
+
+    /// Find a segment by tag in this group or its nested groups; the
+    /// group's own segments are searched first
     pub fn find_segment(&self, tag: &str) -> Option<&SegmentDefinition> {
-        self.segments.iter().find(|s| s.tag == tag)
+        self.segments
+            .iter()
+            .find(|s| s.tag == tag)
+            .or_else(|| self.groups.iter().find_map(|g| g.find_segment(tag)))
     }
 
-    /// Find a segment by tag (mutable)
+    /// Find a segment by tag in this group or its nested groups (mutable)
     pub fn find_segment_mut(&mut self, tag: &str) -> Option<&mut SegmentDefinition> {
-        self.segments.iter_mut().find(|s| s.tag == tag)
+        if let Some(index) = self.segments.iter().position(|s| s.tag == tag) {
+            return self.segments.get_mut(index);
+        }
+        self.groups.iter_mut().find_map(|g| g.find_segment_mut(tag))
+    }
+
+    /// Find this group or a nested group by ID
+    pub fn find_group(&self, id: &str) -> Option<&SegmentGroupDefinition> {
+        if self.id == id {
+            return Some(self);
+        }
+        self.groups.iter().find_map(|g| g.find_group(id))
+    }
+
+    /// Find this group or a nested group by ID (mutable)
+    pub fn find_group_mut(&mut self, id: &str) -> Option<&mut SegmentGroupDefinition> {
+        if self.id == id {
+            return Some(self);
+        }
+        self.groups.iter_mut().find_map(|g| g.find_group_mut(id))
+    }
+
+    /// Find this group or a nested group opened by the given trigger segment
+    pub fn find_group_by_trigger(&self, trigger: &str) -> Option<&SegmentGroupDefinition> {
+        if self.trigger == trigger {
+            return Some(self);
+        }
+        self.groups
+            .iter()
+            .find_map(|g| g.find_group_by_trigger(trigger))
+    }
+
+    /// Segments and nested groups in branching diagram order
+    pub fn entries(&self) -> Vec<SchemaEntry<'_>> {
+        ordered_entries(&self.segments, &self.groups)
     }
 }
 
@@ -89,6 +306,10 @@ pub struct SegmentDefinition {
     pub elements: Vec<ElementDefinition>,
     pub is_mandatory: bool,
     pub max_repetitions: Option<usize>,
+    /// Position in the branching diagram (e.g. 10 for pos 0010)
+    pub position: Option<u32>,
+    /// Dependency notes between the segment's elements
+    pub dependencies: Vec<DependencyNote>,
 }
 
 impl SegmentDefinition {
@@ -99,9 +320,17 @@ impl SegmentDefinition {
             elements: Vec::new(),
             is_mandatory: false,
             max_repetitions: None,
+            position: None,
+            dependencies: Vec::new(),
         }
     }
 
+    /// Set the position in the branching diagram
+    pub fn position(mut self, value: u32) -> Self {
+        self.position = Some(value);
+        self
+    }
+
     /// Set mandatory flag
     pub fn mandatory(mut self, value: bool) -> Self {
         self.is_mandatory = value;
@@ -120,6 +349,12 @@ impl SegmentDefinition {
         self
     }
 
+    /// Add dependency notes between elements
+    pub fn with_dependencies(mut self, dependencies: Vec<DependencyNote>) -> Self {
+        self.dependencies = dependencies;
+        self
+    }
+
     /// Find an element by ID
     pub fn find_element(&self, id: &str) -> Option<&ElementDefinition> {
         self.elements.iter().find(|e| e.id == id)
@@ -139,11 +374,17 @@ impl SegmentDefinition {
 
         // Add parent elements that child doesn't have
         for parent_element in &parent.elements {
-            if !child_ids.contains(&parent_element.id) {
+            if child_ids.contains(&parent_element.id) {
+                if let Some(child_element) = self.find_element_mut(&parent_element.id) {
+                    child_element.merge(parent_element);
+                }
+            } else {
                 self.elements.push(parent_element.clone());
             }
         }
 
+        merge_dependencies(&mut self.dependencies, &parent.dependencies);
+
         // Child mandatory overrides parent optional
         if self.is_mandatory || parent.is_mandatory {
             self.is_mandatory = true;
@@ -155,6 +396,7 @@ impl SegmentDefinition {
 }
 
 /// Definition of a data element
+/// An element with components is a composite (e.g. C507 in DTM)
 #[derive(Debug, Clone)]
 pub struct ElementDefinition {
     pub id: String,
@@ -163,6 +405,12 @@ pub struct ElementDefinition {
     pub min_length: usize,
     pub max_length: usize,
     pub is_mandatory: bool,
+    /// Name of the code list the element value must belong to
+    pub code_list: Option<String>,
+    /// Component definitions, in position order, for composite elements
+    pub components: Vec<ComponentDefinition>,
+    /// Dependency notes between the composite's components
+    pub dependencies: Vec<DependencyNote>,
 }
 
 impl ElementDefinition {
@@ -179,6 +427,123 @@ impl ElementDefinition {
             min_length: 1,
             max_length: 35,
             is_mandatory: false,
+            code_list: None,
+            components: Vec::new(),
+            dependencies: Vec::new(),
+        }
+    }
+
+    /// Set length constraints
+    pub fn length(mut self, min: usize, max: usize) -> Self {
+        self.min_length = min;
+        self.max_length = max;
+        self
+    }
+
+    /// Set mandatory flag
+    pub fn mandatory(mut self, value: bool) -> Self {
+        self.is_mandatory = value;
+        self
+    }
+
+    /// Set the code list the value must belong to
+    pub fn code_list(mut self, name: impl Into<String>) -> Self {
+        self.code_list = Some(name.into());
+        self
+    }
+
+    /// Add component definitions, making this a composite element
+    pub fn with_components(mut self, components: Vec<ComponentDefinition>) -> Self {
+        self.components = components;
+        self
+    }
+
+    /// Add dependency notes between components
+    pub fn with_dependencies(mut self, dependencies: Vec<DependencyNote>) -> Self {
+        self.dependencies = dependencies;
+        self
+    }
+
+    /// Whether this element is a composite
+    pub fn is_composite(&self) -> bool {
+        !self.components.is_empty()
+    }
+
+    /// Find a component by ID
+    pub fn find_component(&self, id: &str) -> Option<&ComponentDefinition> {
+        self.components.iter().find(|c| c.id == id)
+    }
+
+    /// Merge another element definition into this one
+    /// Child (self) properties take precedence; parent components the child
+    /// does not define are inherited in the parent's order, and code list
+    /// bindings are inherited when the child does not declare one
+    pub fn merge(&mut self, parent: &ElementDefinition) {
+        if self.code_list.is_none() {
+            self.code_list.clone_from(&parent.code_list);
+        }
+
+        merge_dependencies(&mut self.dependencies, &parent.dependencies);
+
+        if parent.components.is_empty() {
+            return;
+        }
+
+        let mut merged: Vec<ComponentDefinition> = parent
+            .components
+            .iter()
+            .map(
+                |parent_component| match self.find_component(&parent_component.id) {
+                    Some(child_component) => {
+                        let mut component = child_component.clone();
+                        if component.code_list.is_none() {
+                            component.code_list.clone_from(&parent_component.code_list);
+                        }
+                        component
+                    }
+                    None => parent_component.clone(),
+                },
+            )
+            .collect();
+
+        for child_component in &self.components {
+            if parent.find_component(&child_component.id).is_none() {
+                merged.push(child_component.clone());
+            }
+        }
+
+        self.components = merged;
+    }
+}
+
+/// Definition of a component within a composite element
+#[derive(Debug, Clone)]
+pub struct ComponentDefinition {
+    pub id: String,
+    pub name: String,
+    pub data_type: String,
+    pub min_length: usize,
+    pub max_length: usize,
+    pub is_mandatory: bool,
+    /// Name of the code list the component value must belong to
+    pub code_list: Option<String>,
+}
+
+impl ComponentDefinition {
+    /// Create a new component definition
+    pub fn new(
+        id: impl Into<String>,
+        name: impl Into<String>,
+        data_type: impl Into<String>,
+    ) -> Self {
+        Self {
+            id: id.into(),
+            name: name.into(),
+            data_type: data_type.into(),
+            min_length: 1,
+            max_length: 35,
+            is_mandatory: false,
+            code_list: None,
         }
     }
 
@@ -194,6 +559,156 @@ impl ElementDefinition {
         self.is_mandatory = value;
         self
     }
+
+    /// Set the code list the value must belong to
+    pub fn code_list(mut self, name: impl Into<String>) -> Self {
+        self.code_list = Some(name.into());
+        self
+    }
+}
+
+/// EDIFACT dependency note relations (ISO 9735)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DependencyKind {
+    /// D1: one and only one
+    OneAndOnlyOne,
+    /// D2: all or none
+    AllOrNone,
+    /// D3: one or more
+    OneOrMore,
+    /// D4: one or none
+    OneOrNone,
+    /// D5: if first, then all
+    IfFirstThenAll,
+    /// D6: if first, then at least one more
+    IfFirstThenAtLeastOneMore,
+    /// D7: if first, then none of the others
+    IfFirstThenNone,
+}
+
+impl DependencyKind {
+    /// Directory notation (D1..D7)
+    pub fn code(&self) -> &'static str {
+        match self {
+            DependencyKind::OneAndOnlyOne => "D1",
+            DependencyKind::AllOrNone => "D2",
+            DependencyKind::OneOrMore => "D3",
+            DependencyKind::OneOrNone => "D4",
+            DependencyKind::IfFirstThenAll => "D5",
+            DependencyKind::IfFirstThenAtLeastOneMore => "D6",
+            DependencyKind::IfFirstThenNone => "D7",
+        }
+    }
+
+    /// Parse directory notation (D1..D7).
+    ///
+    /// The relational condition letters used by X12 style guides are
+    /// accepted as aliases: P (paired), R (required), E (exclusion),
+    /// C (conditional) and L (list conditional).
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "D1" => Some(DependencyKind::OneAndOnlyOne),
+            "D2" | "P" => Some(DependencyKind::AllOrNone),
+            "D3" | "R" => Some(DependencyKind::OneOrMore),
+            "D4" | "E" => Some(DependencyKind::OneOrNone),
+            "D5" | "C" => Some(DependencyKind::IfFirstThenAll),
+            "D6" | "L" => Some(DependencyKind::IfFirstThenAtLeastOneMore),
+            "D7" => Some(DependencyKind::IfFirstThenNone),
+            _ => None,
+        }
+    }
+
+    /// Human-readable description of the relation
+    pub fn description(&self) -> &'static str {
+        match self {
+            DependencyKind::OneAndOnlyOne => "one and only one",
+            DependencyKind::AllOrNone => "all or none",
+            DependencyKind::OneOrMore => "one or more",
+            DependencyKind::OneOrNone => "one or none",
+            DependencyKind::IfFirstThenAll => "if first, then all",
+            DependencyKind::IfFirstThenAtLeastOneMore => "if first, then at least one more",
+            DependencyKind::IfFirstThenNone => "if first, then none of the others",
+        }
+    }
+}
+
+/// A dependency note over elements of a segment or components of a
+/// composite, e.g. D3(e2, e4) on NAD
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DependencyNote {
+    pub kind: DependencyKind,
+    /// Element or component IDs, in the order the note lists them
+    pub ids: Vec<String>,
+}
+
+impl DependencyNote {
+    /// Create a new dependency note
+    pub fn new(kind: DependencyKind, ids: Vec<impl Into<String>>) -> Self {
+        Self {
+            kind,
+            ids: ids.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+/// Add parent dependency notes the child does not already declare
+pub(crate) fn merge_dependencies(child: &mut Vec<DependencyNote>, parent: &[DependencyNote]) {
+    for note in parent {
+        if !child.contains(note) {
+            child.push(note.clone());
+        }
+    }
+}
+
+/// How a code list declared in a child schema combines with the parent's
+/// list of the same ID
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CodeListMode {
+    /// Child codes replace the parent's codes
+    #[default]
+    Replace,
+    /// Child codes are added to the parent's codes
+    Extend,
+    /// Only parent codes also listed by the child remain allowed
+    Restrict,
+}
+
+/// A code list declared in a schema (e.g. 3035 party qualifiers)
+#[derive(Debug, Clone)]
+pub struct CodeListDefinition {
+    pub id: String,
+    pub description: Option<String>,
+    pub codes: Vec<String>,
+    pub mode: CodeListMode,
+}
+
+impl CodeListDefinition {
+    /// Create a new code list definition
+    pub fn new(id: impl Into<String>, codes: Vec<impl Into<String>>) -> Self {
+        Self {
+            id: id.into(),
+            description: None,
+            codes: codes.into_iter().map(Into::into).collect(),
+            mode: CodeListMode::Replace,
+        }
+    }
+
+    /// Set the inheritance mode
+    pub fn mode(mut self, mode: CodeListMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Set description
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Check whether a code is part of the list
+    pub fn contains(&self, code: &str) -> bool {
+        self.codes.iter().any(|c| c == code)
+    }
 }
 
 /// Constraint rules for validation
@@ -357,6 +872,64 @@ mod tests {
         assert!(element.is_mandatory);
     }
 
+    #[test]
+    fn test_composite_element_builder() {
+        let element =
+            ElementDefinition::new("C507", "Date/time/period", "c").with_components(vec![
+                ComponentDefinition::new("2005", "Qualifier", "an")
+                    .length(1, 3)
+                    .mandatory(true)
+                    .code_list("2005"),
+                ComponentDefinition::new("2380", "Value", "an").length(1, 35),
+            ]);
+
+        assert!(element.is_composite());
+        assert_eq!(element.components.len(), 2);
+        let qualifier = element.find_component("2005").unwrap();
+        assert!(qualifier.is_mandatory);
+        assert_eq!(qualifier.code_list.as_deref(), Some("2005"));
+        assert!(!ElementDefinition::new("1004", "Number", "an").is_composite());
+    }
+
+    #[test]
+    fn test_element_merge_components() {
+        let parent = ElementDefinition::new("C186", "Quantity details", "c").with_components(vec![
+            ComponentDefinition::new("6063", "Qualifier", "an").length(1, 3),
+            ComponentDefinition::new("6060", "Quantity", "n").length(1, 15),
+            ComponentDefinition::new("6411", "Unit", "an").length(1, 3),
+        ]);
+        let mut child =
+            ElementDefinition::new("C186", "Quantity details", "c").with_components(vec![
+                ComponentDefinition::new("6060", "Quantity", "n").mandatory(true),
+            ]);
+
+        child.merge(&parent);
+
+        let ids: Vec<&str> = child.components.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, vec!["6063", "6060", "6411"]);
+        assert!(child.find_component("6060").unwrap().is_mandatory);
+    }
+
+    #[test]
+    fn test_dependency_kind_codes() {
+        for code in ["D1", "D2", "D3", "D4", "D5", "D6", "D7"] {
+            assert_eq!(DependencyKind::from_code(code).unwrap().code(), code);
+        }
+        assert!(DependencyKind::from_code("D8").is_none());
+        assert_eq!(
+            DependencyKind::from_code("P"),
+            Some(DependencyKind::AllOrNone)
+        );
+        assert_eq!(
+            DependencyKind::from_code("L"),
+            Some(DependencyKind::IfFirstThenAtLeastOneMore)
+        );
+        assert_eq!(
+            DependencyKind::IfFirstThenAll.description(),
+            "if first, then all"
+        );
+    }
+
     #[test]
     fn test_segment_merge() {
         let parent = SegmentDefinition::new("BGM")
@@ -392,6 +965,82 @@ mod tests {
         assert!(child.is_mandatory);
     }
 
+    #[test]
+    fn test_segment_group_lookup() {
+        let mut schema = Schema::new("ORDERS", "D96A")
+            .with_segments(vec![SegmentDefinition::new("BGM").mandatory(true)])
+            .with_groups(vec![
+                SegmentGroupDefinition::new("SG2", "NAD")
+                    .max_occurs(99)
+                    .with_segments(vec![SegmentDefinition::new("NAD").mandatory(true)])
+                    .with_groups(vec![
+                        SegmentGroupDefinition::new("SG5", "CTA")
+                            .max_occurs(5)
+                            .with_segments(vec![SegmentDefinition::new("CTA")]),
+                    ]),
+            ]);
+
+        assert!(schema.find_segment("CTA").is_some());
+        schema.find_segment_mut("CTA").unwrap().is_mandatory = true;
+        assert!(schema.find_segment("CTA").unwrap().is_mandatory);
+        assert!(schema.find_segment_mut("XXX").is_none());
+        assert_eq!(schema.find_group("SG5").unwrap().max_occurs, Some(5));
+        assert_eq!(schema.find_group_by_trigger("NAD").unwrap().id, "SG2");
+        assert!(!schema.find_group("SG2").unwrap().is_mandatory());
+        assert!(schema.find_group("SG9").is_none());
+    }
+
+    #[test]
+    fn test_find_segment_in_group() {
+        let schema = Schema::new("ORDERS", "D96A")
+            .with_segments(vec![SegmentDefinition::new("DTM").max_repetitions(35)])
+            .with_groups(vec![
+                SegmentGroupDefinition::new("SG1", "RFF").with_segments(vec![
+                    SegmentDefinition::new("RFF"),
+                    SegmentDefinition::new("DTM").max_repetitions(5),
+                ]),
+                SegmentGroupDefinition::new("SG25", "LIN").with_segments(vec![
+                    SegmentDefinition::new("LIN"),
+                    SegmentDefinition::new("DTM").max_repetitions(1),
+                ]),
+            ]);
+
+        let max = |group: Option<&str>| {
+            schema
+                .find_segment_in(group, "DTM")
+                .unwrap()
+                .max_repetitions
+        };
+        assert_eq!(max(None), Some(35));
+        assert_eq!(max(Some("SG1")), Some(5));
+        assert_eq!(max(Some("SG25")), Some(1));
+        assert_eq!(max(Some("LINE_ITEM")), Some(35));
+        assert!(schema.find_segment_in(Some("SG1"), "LIN").is_none());
+    }
+
+    #[test]
+    fn test_entries_ordered_by_position() {
+        let schema = Schema::new("ORDERS", "D96A")
+            .with_segments(vec![
+                SegmentDefinition::new("UNH").position(10),
+                SegmentDefinition::new("UNS").position(1000),
+                SegmentDefinition::new("BGM").position(20),
+            ])
+            .with_groups(vec![
+                SegmentGroupDefinition::new("SG25", "LIN").position(500),
+            ]);
+
+        let order: Vec<String> = schema
+            .entries()
+            .iter()
+            .map(|entry| match entry {
+                SchemaEntry::Segment(segment) => segment.tag.clone(),
+                SchemaEntry::Group(group) => group.id.clone(),
+            })
+            .collect();
+        assert_eq!(order, vec!["UNH", "BGM", "SG25", "UNS"]);
+    }
+
     #[test]
     fn test_schema_ref() {
         let schema_ref = SchemaRef::new("EANCOM", "D96A");
@@ -132,6 +132,8 @@ mod tests {
             name: name.to_string(),
             version: "1.0".to_string(),
             segments: vec![],
+            groups: vec![],
+            code_lists: vec![],
             inheritance: Default::default(),
         }
     }
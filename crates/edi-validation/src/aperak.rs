@@ -0,0 +1,75 @@
+//! APERAK issues from validation results
+//!
+//! Converts the errors business validation found into the
+//! [`ApplicationIssue`]s [`AperakGenerator`](edi_adapter_edifact::AperakGenerator)
+//! reports, keeping each issue's code so it maps to an application error code.
+
+use edi_adapter_edifact::ApplicationIssue;
+
+use crate::engine::{ValidationError, ValidationResult};
+use crate::reporter::ValidationIssue;
+
+impl From<&ValidationIssue> for ApplicationIssue {
+    fn from(issue: &ValidationIssue) -> Self {
+        application_issue(issue.code.as_ref(), &issue.message)
+    }
+}
+
+impl From<&ValidationError> for ApplicationIssue {
+    fn from(error: &ValidationError) -> Self {
+        application_issue(error.code.as_ref(), &error.message)
+    }
+}
+
+impl ValidationResult {
+    /// APERAK issues for every error of this result; warnings are not
+    /// reported
+    #[must_use]
+    pub fn application_issues(&self) -> Vec<ApplicationIssue> {
+        self.errors
+            .iter()
+            .map(ApplicationIssue::from)
+            .chain(self.report.errors().into_iter().map(ApplicationIssue::from))
+            .collect()
+    }
+}
+
+fn application_issue(code: Option<&String>, message: &str) -> ApplicationIssue {
+    let issue = ApplicationIssue::new(message);
+    match code {
+        Some(code) => issue.with_code(code.clone()),
+        None => issue,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reporter::Severity;
+
+    #[test]
+    fn test_application_issues_report_errors_with_their_codes() {
+        let mut result = ValidationResult::valid();
+        result.add_error(ValidationError {
+            message: "Missing BGM".to_string(),
+            path: String::new(),
+            line: None,
+            severity: Severity::Error,
+            code: Some("MISSING_MANDATORY_SEGMENT".to_string()),
+        });
+        result.add_issue(
+            ValidationIssue::new(Severity::Error, "Unknown code").with_code("CODELIST_VIOLATION"),
+        );
+        result.add_issue(ValidationIssue::new(Severity::Warning, "Unknown segment"));
+        result.add_issue(ValidationIssue::new(Severity::Error, "Bad value"));
+
+        assert_eq!(
+            result.application_issues(),
+            [
+                ApplicationIssue::new("Missing BGM").with_code("MISSING_MANDATORY_SEGMENT"),
+                ApplicationIssue::new("Unknown code").with_code("CODELIST_VIOLATION"),
+                ApplicationIssue::new("Bad value"),
+            ]
+        );
+    }
+}
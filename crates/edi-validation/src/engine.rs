@@ -1,12 +1,16 @@
 //! Validation engine
 
-use crate::codelist::CodeListRegistry;
+use crate::codelist::{CodeList, CodeListRegistry};
 use crate::reporter::{Severity, ValidationIssue, ValidationReport};
 use crate::rules::{
-    ConditionalRule, SegmentOrderRule, validate_conditional, validate_segment_order,
+    ConditionalRule, SegmentOrderRule, evaluate_dependency, validate_conditional,
+    validate_segment_order,
+};
+use edi_ir::{Document, Node, NodeType, SourceSpan, parse_edi_decimal};
+use edi_schema::{
+    ComponentDefinition, DependencyNote, ElementDefinition, Schema, SegmentDefinition,
+    SegmentGroupDefinition,
 };
-use edi_ir::{Document, Node, NodeType};
-use edi_schema::{ElementDefinition, Schema, SegmentDefinition};
 use std::collections::HashMap;
 
 /// Strictness level for validation
@@ -175,6 +179,8 @@ pub struct ValidationContext {
     pub component_pos: Option<usize>,
     /// Line number (if available)
     pub line: Option<usize>,
+    /// Source span of the innermost node being validated (if available)
+    pub span: Option<SourceSpan>,
 }
 
 impl ValidationContext {
@@ -187,6 +193,7 @@ impl ValidationContext {
             element_pos: None,
             component_pos: None,
             line: None,
+            span: None,
         }
     }
 
@@ -204,6 +211,7 @@ impl ValidationContext {
             element_pos: self.element_pos,
             component_pos: self.component_pos,
             line: self.line,
+            span: self.span,
         }
     }
 
@@ -221,6 +229,7 @@ impl ValidationContext {
             element_pos: self.element_pos,
             component_pos: self.component_pos,
             line: self.line,
+            span: self.span,
         }
     }
 
@@ -251,6 +260,71 @@ impl ValidationContext {
         self.line = Some(line);
         self
     }
+
+    /// With the source span of the node being validated; keeps the enclosing
+    /// node's span when `span` is `None`
+    #[must_use]
+    pub fn with_span(mut self, span: Option<SourceSpan>) -> Self {
+        if span.is_some() {
+            self.span = span;
+        }
+        self
+    }
+}
+
+/// Length and data type constraints shared by elements and components
+struct ValueSpec<'a> {
+    kind: &'static str,
+    id: &'a str,
+    data_type: &'a str,
+    min_length: usize,
+    max_length: usize,
+}
+
+impl<'a> ValueSpec<'a> {
+    fn element(def: &'a ElementDefinition) -> Self {
+        Self {
+            kind: "Element",
+            id: &def.id,
+            data_type: &def.data_type,
+            min_length: def.min_length,
+            max_length: def.max_length,
+        }
+    }
+
+    fn component(def: &'a ComponentDefinition) -> Self {
+        Self {
+            kind: "Component",
+            id: &def.id,
+            data_type: &def.data_type,
+            min_length: def.min_length,
+            max_length: def.max_length,
+        }
+    }
+}
+
+/// Code lists visible while validating: lists declared by the schema being
+/// validated against shadow those registered on the engine, which are
+/// borrowed rather than copied
+struct CodeLists<'a> {
+    registered: &'a CodeListRegistry,
+    declared: CodeListRegistry,
+}
+
+impl<'a> CodeLists<'a> {
+    /// Only the lists registered on the engine
+    fn registered(registered: &'a CodeListRegistry) -> Self {
+        Self {
+            registered,
+            declared: CodeListRegistry::new(),
+        }
+    }
+
+    fn get(&self, name: &str) -> Option<&CodeList> {
+        self.declared
+            .get(name)
+            .or_else(|| self.registered.get(name))
+    }
 }
 
 /// Main validation engine
@@ -380,7 +454,7 @@ impl ValidationEngine {
         let context = ValidationContext::root();
 
         // Validate document structure against schema
-        self.validate_document_against_schema(doc, schema, &mut result, &context)?;
+        self.validate_document_against_schema(doc, schema, &mut result, &context);
 
         // Apply strictness rules
         Self::apply_strictness(&mut result);
@@ -398,8 +472,24 @@ impl ValidationEngine {
         segment: &Node,
         segment_def: &SegmentDefinition,
     ) -> crate::Result<ValidationResult> {
+        Ok(self.validate_segment_internal(
+            segment,
+            segment_def,
+            &CodeLists::registered(&self.codelist_registry),
+        ))
+    }
+
+    /// Validate a segment, resolving code lists through `codelists`
+    fn validate_segment_internal(
+        &self,
+        segment: &Node,
+        segment_def: &SegmentDefinition,
+        codelists: &CodeLists<'_>,
+    ) -> ValidationResult {
         let mut result = ValidationResult::valid();
-        let context = ValidationContext::root().child(&segment.name);
+        let context = ValidationContext::root()
+            .child(&segment.name)
+            .with_span(segment.span);
 
         if segment.node_type != NodeType::Segment {
             self.add_error(
@@ -408,7 +498,7 @@ impl ValidationEngine {
                 "TYPE_MISMATCH",
                 format!("Expected Segment, found {:?}", segment.node_type),
             );
-            return Ok(result);
+            return result;
         }
 
         // Check segment tag matches definition
@@ -423,7 +513,7 @@ impl ValidationEngine {
                 ),
             );
             if self.should_stop(&result) {
-                return Ok(result);
+                return result;
             }
         }
 
@@ -436,7 +526,7 @@ impl ValidationEngine {
                 format!("Mandatory segment '{}' has no elements", segment_def.tag),
             );
             if self.should_stop(&result) {
-                return Ok(result);
+                return result;
             }
         }
 
@@ -447,37 +537,28 @@ impl ValidationEngine {
             .zip(segment_def.elements.iter())
             .enumerate()
         {
-            let element_context = context.child(&element_def.id).with_element_pos(idx);
+            let element_context = context
+                .child(&element_def.id)
+                .with_element_pos(idx)
+                .with_span(child.span);
             let element_result =
-                self.validate_element_internal(child, element_def, &element_context);
+                self.validate_element_internal(child, element_def, &element_context, codelists);
             result.merge(element_result);
             if self.should_stop(&result) {
-                return Ok(result);
+                return result;
             }
         }
 
+        // Check dependency notes between elements
+        self.validate_segment_dependencies(segment, segment_def, &mut result, &context);
+        if self.should_stop(&result) {
+            return result;
+        }
+
         // Check for extra elements not in definition
-        if segment.children.len() > segment_def.elements.len() {
-            for (idx, extra) in segment
-                .children
-                .iter()
-                .enumerate()
-                .skip(segment_def.elements.len())
-            {
-                let extra_context = context.child(&extra.name).with_element_pos(idx);
-                self.add_warning(
-                    &mut result,
-                    &extra_context,
-                    "EXTRA_ELEMENT",
-                    format!(
-                        "Element '{}' at position {} is not defined in schema",
-                        extra.name, idx
-                    ),
-                );
-                if self.should_stop(&result) {
-                    return Ok(result);
-                }
-            }
+        self.validate_extra_elements(segment, segment_def, &mut result, &context);
+        if self.should_stop(&result) {
+            return result;
         }
 
         // Check for missing mandatory elements
@@ -494,12 +575,45 @@ impl ValidationEngine {
                     ),
                 );
                 if self.should_stop(&result) {
-                    return Ok(result);
+                    return result;
                 }
             }
         }
 
-        Ok(result)
+        result
+    }
+
+    /// Warn about elements beyond those defined for the segment
+    fn validate_extra_elements(
+        &self,
+        segment: &Node,
+        segment_def: &SegmentDefinition,
+        result: &mut ValidationResult,
+        context: &ValidationContext,
+    ) {
+        for (idx, extra) in segment
+            .children
+            .iter()
+            .enumerate()
+            .skip(segment_def.elements.len())
+        {
+            let extra_context = context
+                .child(&extra.name)
+                .with_element_pos(idx)
+                .with_span(extra.span);
+            self.add_warning(
+                result,
+                &extra_context,
+                "EXTRA_ELEMENT",
+                format!(
+                    "Element '{}' at position {} is not defined in schema",
+                    extra.name, idx
+                ),
+            );
+            if self.should_stop(result) {
+                return;
+            }
+        }
     }
 
     /// Validate a single element against its definition
@@ -512,8 +626,15 @@ impl ValidationEngine {
         element: &Node,
         element_def: &ElementDefinition,
     ) -> crate::Result<ValidationResult> {
-        let context = ValidationContext::root().child(&element_def.id);
-        Ok(self.validate_element_internal(element, element_def, &context))
+        let context = ValidationContext::root()
+            .child(&element_def.id)
+            .with_span(element.span);
+        Ok(self.validate_element_internal(
+            element,
+            element_def,
+            &context,
+            &CodeLists::registered(&self.codelist_registry),
+        ))
     }
 
     /// Internal method to validate an element with context
@@ -522,6 +643,7 @@ impl ValidationEngine {
         element: &Node,
         element_def: &ElementDefinition,
         context: &ValidationContext,
+        codelists: &CodeLists<'_>,
     ) -> ValidationResult {
         let mut result = ValidationResult::valid();
 
@@ -541,6 +663,54 @@ impl ValidationEngine {
             }
         }
 
+        // Repeated element (syntax version 4): each occurrence is validated
+        // against the same definition
+        if Self::is_repeated(element) {
+            for occurrence in &element.children {
+                let occurrence_context = context.child(&occurrence.name).with_span(occurrence.span);
+                let occurrence_result = self.validate_element_occurrence(
+                    occurrence,
+                    element_def,
+                    &occurrence_context,
+                    codelists,
+                );
+                result.merge(occurrence_result);
+                if self.should_stop(&result) {
+                    return result;
+                }
+            }
+            return result;
+        }
+
+        result.merge(self.validate_element_occurrence(element, element_def, context, codelists));
+        result
+    }
+
+    /// Whether an element node holds repeated occurrences rather than
+    /// components
+    fn is_repeated(element: &Node) -> bool {
+        !element.children.is_empty()
+            && element
+                .children
+                .iter()
+                .all(|child| child.node_type == NodeType::Element)
+    }
+
+    /// Validate a single (non-repeated) element value or composite
+    fn validate_element_occurrence(
+        &self,
+        element: &Node,
+        element_def: &ElementDefinition,
+        context: &ValidationContext,
+        codelists: &CodeLists<'_>,
+    ) -> ValidationResult {
+        let mut result = ValidationResult::valid();
+
+        if element_def.is_composite() {
+            self.validate_composite(element, element_def, codelists, &mut result, context);
+            return result;
+        }
+
         // Get the value to validate
         let value_str = element.value.as_ref().and_then(edi_ir::Value::as_string);
 
@@ -560,75 +730,370 @@ impl ValidationEngine {
             }
         }
 
-        // Validate length constraints if value exists
         if let Some(ref value) = value_str {
-            let len = value.len();
-            if len < element_def.min_length {
-                self.add_error(
+            if !self.validate_value(
+                value,
+                &ValueSpec::element(element_def),
+                &mut result,
+                context,
+            ) {
+                return result;
+            }
+
+            // Validate against codelist if configured: the schema binding
+            // wins, otherwise fall back to the `{data_type}_{id}` convention
+            if self.config.validate_codelists {
+                let codelist_name = element_def
+                    .code_list
+                    .clone()
+                    .unwrap_or_else(|| format!("{}_{}", element_def.data_type, element_def.id));
+                if !self.validate_codelist_value(
+                    value,
+                    codelists.get(&codelist_name),
                     &mut result,
                     context,
-                    "MIN_LENGTH_VIOLATION",
-                    format!(
-                        "Element '{}' length {} is less than minimum {} (data type: {})",
-                        element_def.id, len, element_def.min_length, element_def.data_type
-                    ),
-                );
-                if self.should_stop(&result) {
+                ) {
                     return result;
                 }
             }
-            if len > element_def.max_length {
+        }
+
+        // Validate component children if this is a composite element
+        for (idx, child) in element.children.iter().enumerate() {
+            let component_context = context
+                .child(&child.name)
+                .with_component_pos(idx)
+                .with_span(child.span);
+            self.validate_component(child, &mut result, &component_context);
+            if self.should_stop(&result) {
+                return result;
+            }
+        }
+
+        result
+    }
+
+    /// Validate length and data type of a simple element or component value.
+    /// Returns `false` when validation should stop.
+    fn validate_value(
+        &self,
+        value: &str,
+        spec: &ValueSpec<'_>,
+        result: &mut ValidationResult,
+        context: &ValidationContext,
+    ) -> bool {
+        // Lengths count characters, not UTF-8 bytes
+        let len = value.chars().count();
+        if len < spec.min_length {
+            self.add_error(
+                result,
+                context,
+                "MIN_LENGTH_VIOLATION",
+                format!(
+                    "{} '{}' length {} is less than minimum {} (data type: {})",
+                    spec.kind, spec.id, len, spec.min_length, spec.data_type
+                ),
+            );
+            if self.should_stop(result) {
+                return false;
+            }
+        }
+        if len > spec.max_length {
+            self.add_error(
+                result,
+                context,
+                "MAX_LENGTH_VIOLATION",
+                format!(
+                    "{} '{}' length {} exceeds maximum {} (data type: {})",
+                    spec.kind, spec.id, len, spec.max_length, spec.data_type
+                ),
+            );
+            if self.should_stop(result) {
+                return false;
+            }
+        }
+
+        // Validate data type
+        if let Err(msg) = Self::validate_data_type(value, spec.kind, spec.id, spec.data_type) {
+            self.add_error(result, context, "DATA_TYPE_VIOLATION", msg);
+            if self.should_stop(result) {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Check a value against a code list, if the list exists.
+    /// Returns `false` when validation should stop.
+    fn validate_codelist_value(
+        &self,
+        value: &str,
+        codelist: Option<&CodeList>,
+        result: &mut ValidationResult,
+        context: &ValidationContext,
+    ) -> bool {
+        if let Some(codelist) = codelist {
+            let validation_result = crate::codelist::validate_code(value, codelist);
+            if !validation_result.is_valid() {
+                if let Some(msg) = validation_result.error_message() {
+                    self.add_error(result, context, "CODELIST_VIOLATION", msg);
+                    if self.should_stop(result) {
+                        return false;
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    /// Validate a composite element against its component definitions.
+    /// A composite parsed as a simple element is treated as its first component.
+    fn validate_composite(
+        &self,
+        element: &Node,
+        element_def: &ElementDefinition,
+        codelists: &CodeLists<'_>,
+        result: &mut ValidationResult,
+        context: &ValidationContext,
+    ) {
+        let values: Vec<Option<String>> = if element.children.is_empty() {
+            vec![element.value.as_ref().and_then(edi_ir::Value::as_string)]
+        } else {
+            element
+                .children
+                .iter()
+                .map(|child| child.value.as_ref().and_then(edi_ir::Value::as_string))
+                .collect()
+        };
+        let is_present = values
+            .iter()
+            .any(|value| value.as_deref().is_some_and(|value| !value.is_empty()));
+
+        if !is_present {
+            if element_def.is_mandatory {
                 self.add_error(
-                    &mut result,
+                    result,
                     context,
-                    "MAX_LENGTH_VIOLATION",
+                    "MANDATORY_ELEMENT_EMPTY",
                     format!(
-                        "Element '{}' length {} exceeds maximum {} (data type: {})",
-                        element_def.id, len, element_def.max_length, element_def.data_type
+                        "Mandatory element '{}' ({}) has no value",
+                        element_def.id, element_def.name
                     ),
                 );
-                if self.should_stop(&result) {
-                    return result;
-                }
             }
+            return;
+        }
 
-            // Validate data type
-            if let Err(msg) = Self::validate_data_type_for_element(value, element_def) {
-                self.add_error(&mut result, context, "DATA_TYPE_VIOLATION", msg);
-                if self.should_stop(&result) {
-                    return result;
+        for (idx, component_def) in element_def.components.iter().enumerate() {
+            let component_context = context
+                .child(&component_def.id)
+                .with_component_pos(idx)
+                .with_span(element.children.get(idx).and_then(|child| child.span));
+            let value = values
+                .get(idx)
+                .and_then(Option::as_deref)
+                .filter(|value| !value.is_empty());
+
+            let Some(value) = value else {
+                if component_def.is_mandatory {
+                    self.add_error(
+                        result,
+                        &component_context,
+                        "MISSING_MANDATORY_COMPONENT",
+                        format!(
+                            "Mandatory component '{}' ({}) of composite '{}' has no value",
+                            component_def.id, component_def.name, element_def.id
+                        ),
+                    );
+                    if self.should_stop(result) {
+                        return;
+                    }
                 }
+                continue;
+            };
+
+            if !self.validate_value(
+                value,
+                &ValueSpec::component(component_def),
+                result,
+                &component_context,
+            ) {
+                return;
             }
 
-            // Validate against codelist if configured
             if self.config.validate_codelists {
-                // Check if this element should be validated against a codelist
-                // This could be determined from schema annotations
-                let codelist_name = format!("{}_{}", element_def.data_type, element_def.id);
-                if let Some(codelist) = self.codelist_registry.get(&codelist_name) {
-                    let validation_result = crate::codelist::validate_code(value, codelist);
-                    if !validation_result.is_valid() {
-                        if let Some(msg) = validation_result.error_message() {
-                            self.add_error(&mut result, context, "CODELIST_VIOLATION", msg);
-                            if self.should_stop(&result) {
-                                return result;
-                            }
-                        }
+                if let Some(codelist_name) = &component_def.code_list {
+                    if !self.validate_codelist_value(
+                        value,
+                        codelists.get(codelist_name),
+                        result,
+                        &component_context,
+                    ) {
+                        return;
                     }
                 }
             }
         }
 
-        // Validate component children if this is a composite element
+        self.validate_composite_dependencies(element_def, &values, result, context);
+        if self.should_stop(result) {
+            return;
+        }
+
+        self.validate_composite_children(element, element_def, result, context);
+    }
+
+    /// Check every component node of a composite, warning about those
+    /// beyond the composite definition
+    fn validate_composite_children(
+        &self,
+        element: &Node,
+        element_def: &ElementDefinition,
+        result: &mut ValidationResult,
+        context: &ValidationContext,
+    ) {
         for (idx, child) in element.children.iter().enumerate() {
-            let component_context = context.child(&child.name).with_component_pos(idx);
-            self.validate_component(child, &mut result, &component_context);
-            if self.should_stop(&result) {
-                return result;
+            let component_context = context
+                .child(&child.name)
+                .with_component_pos(idx)
+                .with_span(child.span);
+            if idx >= element_def.components.len() {
+                self.add_warning(
+                    result,
+                    &component_context,
+                    "EXTRA_COMPONENT",
+                    format!(
+                        "Component '{}' at position {} is not defined in composite '{}'",
+                        child.name, idx, element_def.id
+                    ),
+                );
+                if self.should_stop(result) {
+                    return;
+                }
+            }
+            self.validate_component(child, result, &component_context);
+            if self.should_stop(result) {
+                return;
             }
         }
+    }
 
-        result
+    /// Whether an element or component node carries a non-empty value
+    fn has_value(node: &Node) -> bool {
+        node.value
+            .as_ref()
+            .and_then(edi_ir::Value::as_string)
+            .is_some_and(|value| !value.is_empty())
+            || node.children.iter().any(Self::has_value)
+    }
+
+    /// Evaluate the dependency notes declared between a segment's elements
+    fn validate_segment_dependencies(
+        &self,
+        segment: &Node,
+        segment_def: &SegmentDefinition,
+        result: &mut ValidationResult,
+        context: &ValidationContext,
+    ) {
+        self.validate_dependency_notes(
+            &segment_def.dependencies,
+            &segment_def.tag,
+            |id| {
+                let idx = segment_def.elements.iter().position(|e| e.id == id);
+                let present = idx
+                    .and_then(|idx| segment.children.get(idx))
+                    .is_some_and(Self::has_value);
+                (idx, present)
+            },
+            false,
+            result,
+            context,
+        );
+    }
+
+    /// Evaluate the dependency notes declared between a composite's components
+    fn validate_composite_dependencies(
+        &self,
+        element_def: &ElementDefinition,
+        values: &[Option<String>],
+        result: &mut ValidationResult,
+        context: &ValidationContext,
+    ) {
+        self.validate_dependency_notes(
+            &element_def.dependencies,
+            &element_def.id,
+            |id| {
+                let idx = element_def.components.iter().position(|c| c.id == id);
+                let present = idx
+                    .and_then(|idx| values.get(idx))
+                    .and_then(Option::as_deref)
+                    .is_some_and(|value| !value.is_empty());
+                (idx, present)
+            },
+            true,
+            result,
+            context,
+        );
+    }
+
+    /// Evaluate dependency notes over the elements or components of `owner`.
+    ///
+    /// `locate` maps a listed id to its position in the owner definition and
+    /// whether a value is present there; violations are reported at the
+    /// offending element (or component, when `components` is set).
+    fn validate_dependency_notes<F>(
+        &self,
+        notes: &[DependencyNote],
+        owner: &str,
+        locate: F,
+        components: bool,
+        result: &mut ValidationResult,
+        context: &ValidationContext,
+    ) where
+        F: Fn(&str) -> (Option<usize>, bool),
+    {
+        for note in notes {
+            let targets: Vec<(Option<usize>, bool)> =
+                note.ids.iter().map(|id| locate(id)).collect();
+            let present: Vec<bool> = targets.iter().map(|(_, present)| *present).collect();
+
+            for offending in evaluate_dependency(note.kind, &present) {
+                let mut note_context = context.child(&note.ids[offending]);
+                if let Some(idx) = targets[offending].0 {
+                    note_context = if components {
+                        note_context.with_component_pos(idx)
+                    } else {
+                        note_context.with_element_pos(idx)
+                    };
+                }
+                self.add_dependency_error(result, &note_context, note, owner);
+                if self.should_stop(result) {
+                    return;
+                }
+            }
+        }
+    }
+
+    fn add_dependency_error(
+        &self,
+        result: &mut ValidationResult,
+        context: &ValidationContext,
+        note: &DependencyNote,
+        owner: &str,
+    ) {
+        self.add_error(
+            result,
+            context,
+            "DEPENDENCY_VIOLATION",
+            format!(
+                "Dependency note {}({}) in '{}' violated: {}",
+                note.kind.code(),
+                note.ids.join(", "),
+                owner,
+                note.kind.description()
+            ),
+        );
     }
 
     /// Validate a component element
@@ -674,10 +1139,13 @@ impl ValidationEngine {
         schema: &Schema,
         result: &mut ValidationResult,
         context: &ValidationContext,
-    ) -> crate::Result<()> {
-        // Collect all segments from the document
-        let mut segments = Vec::new();
-        Self::collect_segments(&doc.root, &mut segments);
+    ) {
+        // Collect all segments from the document, with their enclosing group
+        let mut grouped = Vec::new();
+        Self::collect_grouped_segments(&doc.root, schema, None, &mut grouped);
+        let segments: Vec<&Node> = grouped.iter().map(|(_, segment)| *segment).collect();
+
+        let codelists = self.schema_codelists(schema);
 
         // Validate segment order if rules are defined
         if let Some(rules) = self.segment_order_rules.get("") {
@@ -686,20 +1154,22 @@ impl ValidationEngine {
                 if let Some(msg) = order_result.message {
                     self.add_error(result, context, "SEGMENT_ORDER_VIOLATION", msg);
                     if self.should_stop(result) {
-                        return Ok(());
+                        return;
                     }
                 }
             }
         }
 
         // Validate each segment against its schema definition
-        for (idx, segment) in segments.iter().enumerate() {
+        for (idx, &(group, segment)) in grouped.iter().enumerate() {
             let segment_context = context
                 .indexed_child(&segment.name, idx)
-                .with_segment_pos(idx);
+                .with_segment_pos(idx)
+                .with_span(segment.span);
 
-            if let Some(segment_def) = schema.find_segment(&segment.name) {
-                let segment_result = self.validate_segment(segment, segment_def)?;
+            if let Some(segment_def) = schema.find_segment_in(group, &segment.name) {
+                let segment_result =
+                    self.validate_segment_internal(segment, segment_def, &codelists);
 
                 // Merge segment results
                 for issue in segment_result.report.all_issues() {
@@ -719,7 +1189,7 @@ impl ValidationEngine {
                     result.add_issue(issue);
                 }
                 if self.should_stop(result) {
-                    return Ok(());
+                    return;
                 }
             } else {
                 // Segment not found in schema
@@ -734,7 +1204,7 @@ impl ValidationEngine {
                     ),
                 );
                 if self.should_stop(result) {
-                    return Ok(());
+                    return;
                 }
             }
         }
@@ -742,7 +1212,7 @@ impl ValidationEngine {
         // Check mandatory child segments inside parser-produced segment groups.
         self.validate_segment_groups_against_schema(&doc.root, schema, result, context);
         if self.should_stop(result) {
-            return Ok(());
+            return;
         }
 
         // Check for mandatory segments
@@ -760,7 +1230,7 @@ impl ValidationEngine {
                         ),
                     );
                     if self.should_stop(result) {
-                        return Ok(());
+                        return;
                     }
                 }
             }
@@ -773,15 +1243,27 @@ impl ValidationEngine {
                 if !conditional_result.is_valid {
                     if let Some(msg) = conditional_result.message {
                         self.add_error(result, context, "CONDITIONAL_RULE_VIOLATION", msg);
-                        if self.should_stop(result) {
-                            return Ok(());
-                        }
+                        let _ = self.should_stop(result);
                     }
                 }
             }
         }
+    }
 
-        Ok(())
+    /// Code lists declared by `schema`, over those registered on the engine
+    fn schema_codelists(&self, schema: &Schema) -> CodeLists<'_> {
+        let mut declared = CodeListRegistry::new();
+        for definition in &schema.code_lists {
+            let mut list = CodeList::with_codes(&definition.id, definition.codes.clone());
+            if let Some(description) = &definition.description {
+                list = list.with_description(description);
+            }
+            declared.register(list);
+        }
+        CodeLists {
+            registered: &self.codelist_registry,
+            declared,
+        }
     }
 
     fn validate_segment_groups_against_schema(
@@ -791,6 +1273,16 @@ impl ValidationEngine {
         result: &mut ValidationResult,
         context: &ValidationContext,
     ) {
+        if !schema.groups.is_empty() {
+            // Group presence is only checked once the document carries the
+            // schema's group hierarchy; flat documents have no SG nodes.
+            let has_schema_groups = Self::contains_schema_group(node, schema);
+            self.validate_schema_groups(node, &schema.groups, has_schema_groups, result, context);
+            if self.should_stop(result) {
+                return;
+            }
+        }
+
         let mut line_item_groups = Vec::new();
         Self::collect_line_item_groups(node, &mut line_item_groups);
         if line_item_groups.is_empty() {
@@ -799,26 +1291,123 @@ impl ValidationEngine {
 
         let mandatory_tags = Self::line_item_mandatory_tags(schema);
 
-        for (group_idx, group) in line_item_groups.into_iter().enumerate() {
-            let group_context = context.indexed_child("LINE_ITEM", group_idx);
-            self.validate_line_item_group(group, &mandatory_tags, result, &group_context);
-            if self.should_stop(result) {
-                return;
+        for (group_idx, group) in line_item_groups.into_iter().enumerate() {
+            let group_context = context.indexed_child("LINE_ITEM", group_idx);
+            self.validate_group_mandatory_segments(group, &mandatory_tags, result, &group_context);
+            if self.should_stop(result) {
+                return;
+            }
+        }
+    }
+
+    fn collect_line_item_groups<'a>(node: &'a Node, groups: &mut Vec<&'a Node>) {
+        if node.node_type == NodeType::SegmentGroup && node.name == "LINE_ITEM" {
+            groups.push(node);
+        }
+
+        for child in &node.children {
+            Self::collect_line_item_groups(child, groups);
+        }
+    }
+
+    fn contains_schema_group(node: &Node, schema: &Schema) -> bool {
+        (node.node_type == NodeType::SegmentGroup && schema.find_group(&node.name).is_some())
+            || node
+                .children
+                .iter()
+                .any(|child| Self::contains_schema_group(child, schema))
+    }
+
+    fn validate_schema_groups(
+        &self,
+        container: &Node,
+        group_defs: &[SegmentGroupDefinition],
+        check_min_occurs: bool,
+        result: &mut ValidationResult,
+        context: &ValidationContext,
+    ) {
+        for group_def in group_defs {
+            let instances: Vec<&Node> = container
+                .children
+                .iter()
+                .filter(|child| {
+                    child.node_type == NodeType::SegmentGroup && child.name == group_def.id
+                })
+                .collect();
+
+            if check_min_occurs && instances.len() < group_def.min_occurs {
+                self.add_error(
+                    result,
+                    context,
+                    "MISSING_MANDATORY_GROUP",
+                    format!(
+                        "Segment group '{}' occurs {} time(s), at least {} required",
+                        group_def.id,
+                        instances.len(),
+                        group_def.min_occurs
+                    ),
+                );
+                if self.should_stop(result) {
+                    return;
+                }
+            }
+
+            if let Some(max) = group_def.max_occurs {
+                if instances.len() > max {
+                    self.add_error(
+                        result,
+                        context,
+                        "GROUP_REPETITION_EXCEEDED",
+                        format!(
+                            "Segment group '{}' occurs {} time(s), at most {max} allowed",
+                            group_def.id,
+                            instances.len()
+                        ),
+                    );
+                    if self.should_stop(result) {
+                        return;
+                    }
+                }
             }
-        }
-    }
 
-    fn collect_line_item_groups<'a>(node: &'a Node, groups: &mut Vec<&'a Node>) {
-        if node.node_type == NodeType::SegmentGroup && node.name == "LINE_ITEM" {
-            groups.push(node);
-        }
+            for (group_idx, group) in instances.into_iter().enumerate() {
+                let group_context = context.indexed_child(&group_def.id, group_idx);
+                let mandatory_tags: Vec<&str> = group_def
+                    .segments
+                    .iter()
+                    .filter(|segment| segment.is_mandatory)
+                    .map(|segment| segment.tag.as_str())
+                    .collect();
+                self.validate_group_mandatory_segments(
+                    group,
+                    &mandatory_tags,
+                    result,
+                    &group_context,
+                );
+                if self.should_stop(result) {
+                    return;
+                }
 
-        for child in &node.children {
-            Self::collect_line_item_groups(child, groups);
+                self.validate_schema_groups(group, &group_def.groups, true, result, &group_context);
+                if self.should_stop(result) {
+                    return;
+                }
+            }
         }
     }
 
     fn line_item_mandatory_tags(schema: &Schema) -> Vec<&str> {
+        if let Some(group) = schema.find_group_by_trigger("LIN") {
+            return group
+                .segments
+                .iter()
+                .filter(|segment| segment.is_mandatory)
+                .map(|segment| segment.tag.as_str())
+                .collect();
+        }
+
+        // Flat schemas: treat everything from LIN up to the summary section
+        // as the line-item group.
         let mut in_line_item_section = false;
         let mut tags = Vec::new();
 
@@ -838,7 +1427,7 @@ impl ValidationEngine {
         tags
     }
 
-    fn validate_line_item_group(
+    fn validate_group_mandatory_segments(
         &self,
         group: &Node,
         mandatory_tags: &[&str],
@@ -885,7 +1474,8 @@ impl ValidationEngine {
                             .with_segment_pos(idx)
                     } else {
                         context.child(&child.name)
-                    };
+                    }
+                    .with_span(child.span);
                     if !self.validate_node(child, result, &child_context) {
                         return false;
                     }
@@ -908,6 +1498,33 @@ impl ValidationEngine {
         }
     }
 
+    /// Collect segments in document order, each with the ID of its innermost
+    /// enclosing segment group declared by `schema`
+    fn collect_grouped_segments<'a>(
+        node: &'a Node,
+        schema: &Schema,
+        group: Option<&'a str>,
+        segments: &mut Vec<(Option<&'a str>, &'a Node)>,
+    ) {
+        if matches!(
+            node.node_type,
+            NodeType::Segment | NodeType::Interchange | NodeType::Message
+        ) {
+            segments.push((group, node));
+        }
+
+        let group = if node.node_type == NodeType::SegmentGroup
+            && schema.find_group(&node.name).is_some()
+        {
+            Some(node.name.as_str())
+        } else {
+            group
+        };
+        for child in &node.children {
+            Self::collect_grouped_segments(child, schema, group, segments);
+        }
+    }
+
     /// Validate a segment node without schema
     fn validate_segment_node(
         &self,
@@ -930,7 +1547,10 @@ impl ValidationEngine {
 
         // Validate each element
         for (idx, child) in segment.children.iter().enumerate() {
-            let element_context = context.child(&child.name).with_element_pos(idx);
+            let element_context = context
+                .child(&child.name)
+                .with_element_pos(idx)
+                .with_span(child.span);
             if !self.validate_element_node(child, result, &element_context) {
                 return false;
             }
@@ -963,7 +1583,10 @@ impl ValidationEngine {
 
         // Validate component children
         for (idx, child) in element.children.iter().enumerate() {
-            let component_context = context.child(&child.name).with_component_pos(idx);
+            let component_context = context
+                .child(&child.name)
+                .with_component_pos(idx)
+                .with_span(child.span);
             if !self.validate_element_node(child, result, &component_context) {
                 return false;
             }
@@ -973,23 +1596,25 @@ impl ValidationEngine {
     }
 
     /// Validate data type for an element
-    fn validate_data_type_for_element(
+    fn validate_data_type(
         value: &str,
-        element_def: &ElementDefinition,
+        kind: &str,
+        id: &str,
+        data_type: &str,
     ) -> Result<(), String> {
-        match element_def.data_type.as_str() {
+        match data_type {
             "an" | "a" | "n" => {
                 // alphanumeric, alphabetic, numeric strings - basic validation
-                if element_def.data_type == "a" && !value.chars().all(char::is_alphabetic) {
+                if data_type == "a" && !value.chars().all(char::is_alphabetic) {
                     return Err(format!(
-                        "Element '{}' should be alphabetic only, got '{}'",
-                        element_def.id, value
+                        "{kind} '{id}' should be alphabetic only, got '{value}'"
                     ));
                 }
-                if element_def.data_type == "n" && !value.chars().all(|ch| ch.is_ascii_digit()) {
+                // Numeric values may carry a sign and a decimal mark, which
+                // is a full stop or a comma
+                if data_type == "n" && parse_edi_decimal(value).is_none() {
                     return Err(format!(
-                        "Element '{}' should be numeric only, got '{}'",
-                        element_def.id, value
+                        "{kind} '{id}' should be numeric only, got '{value}'"
                     ));
                 }
                 Ok(())
@@ -998,8 +1623,7 @@ impl ValidationEngine {
                 // Date format
                 if value.len() != 8 || !value.chars().all(|ch| ch.is_ascii_digit()) {
                     return Err(format!(
-                        "Element '{}' should be date format (YYYYMMDD), got '{}'",
-                        element_def.id, value
+                        "{kind} '{id}' should be date format (YYYYMMDD), got '{value}'"
                     ));
                 }
                 Ok(())
@@ -1010,8 +1634,7 @@ impl ValidationEngine {
                     || !value.chars().all(|ch| ch.is_ascii_digit())
                 {
                     return Err(format!(
-                        "Element '{}' should be time format (HHMM or HHMMSS), got '{}'",
-                        element_def.id, value
+                        "{kind} '{id}' should be time format (HHMM or HHMMSS), got '{value}'"
                     ));
                 }
                 Ok(())
@@ -1038,7 +1661,9 @@ impl ValidationEngine {
             issue = issue.with_positions(segment_pos, context.element_pos, context.component_pos);
         }
 
-        if let Some(line) = context.line {
+        if let Some(span) = context.span {
+            issue = issue.with_span(span);
+        } else if let Some(line) = context.line {
             issue = issue.with_position(line, 1);
         }
 
@@ -1067,7 +1692,9 @@ impl ValidationEngine {
             issue = issue.with_positions(segment_pos, context.element_pos, context.component_pos);
         }
 
-        if let Some(line) = context.line {
+        if let Some(span) = context.span {
+            issue = issue.with_span(span);
+        } else if let Some(line) = context.line {
             issue = issue.with_position(line, 1);
         }
 
@@ -1109,7 +1736,10 @@ impl Default for ValidationEngine {
 mod tests {
     use super::*;
     use edi_ir::Value;
-    use edi_schema::{ElementDefinition, Schema, SegmentDefinition};
+    use edi_schema::{
+        ComponentDefinition, DependencyKind, ElementDefinition, Schema, SegmentDefinition,
+        SegmentGroupDefinition,
+    };
 
     // Helper function to create a test document
     fn create_test_document() -> Document {
@@ -1255,6 +1885,252 @@ mod tests {
         assert!(result.is_valid || !result.has_errors());
     }
 
+    fn create_qty_definition() -> SegmentDefinition {
+        SegmentDefinition::new("QTY").with_elements(vec![
+            ElementDefinition::new("e1", "Quantity details", "c")
+                .mandatory(true)
+                .with_components(vec![
+                    ComponentDefinition::new("6063", "Quantity qualifier", "an")
+                        .length(1, 3)
+                        .mandatory(true)
+                        .code_list("6063"),
+                    ComponentDefinition::new("6060", "Quantity", "n")
+                        .length(1, 15)
+                        .mandatory(true),
+                    ComponentDefinition::new("6411", "Unit", "an").length(1, 3),
+                ]),
+        ])
+    }
+
+    fn create_qty_segment(components: &[&str]) -> Node {
+        let mut segment = Node::new("QTY", NodeType::Segment);
+        let mut element = Node::new("e1", NodeType::Element);
+        for (idx, value) in components.iter().enumerate() {
+            element.add_child(Node::with_value(
+                format!("c{}", idx + 1),
+                NodeType::Component,
+                Value::String((*value).to_string()),
+            ));
+        }
+        segment.add_child(element);
+        segment
+    }
+
+    #[test]
+    fn test_issues_report_source_span_of_offending_component() {
+        let mut segment = create_qty_segment(&["21", "1O"]);
+        segment.set_span(SourceSpan::new(40, 12, 3, 1).with_segment_index(4));
+        segment.children[0].set_span(SourceSpan::new(44, 7, 3, 5));
+        segment.children[0].children[1].set_span(SourceSpan::new(47, 2, 3, 8));
+        let engine = ValidationEngine::new();
+
+        let result = engine
+            .validate_segment(&segment, &create_qty_definition())
+            .unwrap();
+        let issue = result
+            .report
+            .all_issues()
+            .iter()
+            .find(|issue| issue.path == "QTY/e1/6060")
+            .unwrap();
+
+        assert_eq!(issue.span, Some(SourceSpan::new(47, 2, 3, 8)));
+        assert_eq!((issue.line, issue.column), (Some(3), Some(8)));
+    }
+
+    #[test]
+    fn test_validate_composite_components() {
+        let mut engine = ValidationEngine::new();
+        engine.register_codelist(crate::codelist::CodeList::with_codes(
+            "6063",
+            vec!["21", "47"],
+        ));
+        let segment_def = create_qty_definition();
+
+        let valid = engine
+            .validate_segment(&create_qty_segment(&["21", "10", "PCE"]), &segment_def)
+            .unwrap();
+        assert!(valid.is_valid, "unexpected issues: {:?}", valid.report);
+
+        let invalid = engine
+            .validate_segment(
+                &create_qty_segment(&["99", "1O", "PIECE", "X"]),
+                &segment_def,
+            )
+            .unwrap();
+        let codes: Vec<(&str, &str)> = invalid
+            .report
+            .all_issues()
+            .iter()
+            .map(|issue| {
+                (
+                    issue.code.as_deref().unwrap_or_default(),
+                    issue.path.as_str(),
+                )
+            })
+            .collect();
+
+        assert!(codes.contains(&("CODELIST_VIOLATION", "QTY/e1/6063")));
+        assert!(codes.contains(&("DATA_TYPE_VIOLATION", "QTY/e1/6060")));
+        assert!(codes.contains(&("MAX_LENGTH_VIOLATION", "QTY/e1/6411")));
+        assert!(codes.contains(&("EXTRA_COMPONENT", "QTY/e1/c4")));
+    }
+
+    #[test]
+    fn test_validate_with_schema_applies_schema_code_lists() {
+        let schema = Schema::new("ORDERS", "ACME")
+            .with_segments(vec![SegmentDefinition::new("NAD").with_elements(vec![
+                ElementDefinition::new("e1", "Party qualifier", "an").code_list("3035"),
+            ])])
+            .with_code_lists(vec![edi_schema::CodeListDefinition::new(
+                "3035",
+                vec!["BY", "SU", "DP"],
+            )]);
+
+        let mut root = Node::new("ROOT", NodeType::Root);
+        for qualifier in ["BY", "IV"] {
+            let mut nad = Node::new("NAD", NodeType::Segment);
+            nad.add_child(Node::with_value(
+                "e1",
+                NodeType::Element,
+                Value::String(qualifier.to_string()),
+            ));
+            root.add_child(nad);
+        }
+        let doc = Document::new(root);
+
+        let engine = ValidationEngine::new();
+        let result = engine.validate_with_schema(&doc, &schema).unwrap();
+        let violations: Vec<_> = result
+            .report
+            .all_issues()
+            .iter()
+            .filter(|issue| issue.code.as_deref() == Some("CODELIST_VIOLATION"))
+            .collect();
+
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].path, "NAD[1]/e1");
+        assert!(violations[0].message.contains("'IV'"));
+
+        // Without the schema the binding has no list to resolve against.
+        let segment_def = schema.find_segment("NAD").unwrap();
+        let standalone = engine
+            .validate_segment(&doc.root.children[1], segment_def)
+            .unwrap();
+        assert!(standalone.is_valid);
+    }
+
+    #[test]
+    fn test_validate_composite_missing_mandatory_component() {
+        let engine = ValidationEngine::new();
+        let segment_def = create_qty_definition();
+
+        let mut segment = Node::new("QTY", NodeType::Segment);
+        segment.add_child(Node::with_value(
+            "e1",
+            NodeType::Element,
+            Value::String("21".to_string()),
+        ));
+
+        let result = engine.validate_segment(&segment, &segment_def).unwrap();
+        let issue = result
+            .report
+            .all_issues()
+            .iter()
+            .find(|issue| issue.code.as_deref() == Some("MISSING_MANDATORY_COMPONENT"))
+            .expect("expected missing quantity component");
+
+        assert_eq!(issue.path, "QTY/e1/6060");
+        assert!(
+            !result
+                .report
+                .all_issues()
+                .iter()
+                .any(|issue| issue.code.as_deref() == Some("MANDATORY_ELEMENT_EMPTY"))
+        );
+    }
+
+    #[test]
+    fn test_dependency_notes_report_offending_element_and_component_paths() {
+        let engine = ValidationEngine::new();
+        let segment_def = SegmentDefinition::new("NAD")
+            .with_elements(vec![
+                ElementDefinition::new("e1", "Party qualifier", "an").mandatory(true),
+                ElementDefinition::new("e2", "Party identification", "c")
+                    .with_components(vec![
+                        ComponentDefinition::new("3039", "Party id", "an"),
+                        ComponentDefinition::new("1131", "Code list qualifier", "an"),
+                        ComponentDefinition::new("3055", "Responsible agency", "an"),
+                    ])
+                    .with_dependencies(vec![DependencyNote::new(
+                        DependencyKind::IfFirstThenAll,
+                        vec!["3039".to_string(), "3055".to_string()],
+                    )]),
+                ElementDefinition::new("e3", "Name and address", "an"),
+                ElementDefinition::new("e4", "Party name", "an"),
+            ])
+            .with_dependencies(vec![
+                DependencyNote::new(
+                    DependencyKind::OneOrMore,
+                    vec!["e2".to_string(), "e4".to_string()],
+                ),
+                DependencyNote::new(
+                    DependencyKind::OneOrNone,
+                    vec!["e3".to_string(), "e4".to_string()],
+                ),
+            ]);
+
+        let mut segment = Node::new("NAD", NodeType::Segment);
+        segment.add_child(Node::with_value(
+            "e1",
+            NodeType::Element,
+            Value::String("BY".to_string()),
+        ));
+        let mut party = Node::new("e2", NodeType::Element);
+        party.add_child(Node::with_value(
+            "c1",
+            NodeType::Component,
+            Value::String("5412345000013".to_string()),
+        ));
+        segment.add_child(party);
+        segment.add_child(Node::with_value(
+            "e3",
+            NodeType::Element,
+            Value::String("MAIN STREET 1".to_string()),
+        ));
+        segment.add_child(Node::with_value(
+            "e4",
+            NodeType::Element,
+            Value::String("ACME".to_string()),
+        ));
+
+        let result = engine.validate_segment(&segment, &segment_def).unwrap();
+        let paths: Vec<&str> = result
+            .report
+            .all_issues()
+            .iter()
+            .filter(|issue| issue.code.as_deref() == Some("DEPENDENCY_VIOLATION"))
+            .map(|issue| issue.path.as_str())
+            .collect();
+        assert_eq!(paths, vec!["NAD/e2/3055", "NAD/e4"]);
+
+        let mut minimal = Node::new("NAD", NodeType::Segment);
+        minimal.add_child(Node::with_value(
+            "e1",
+            NodeType::Element,
+            Value::String("BY".to_string()),
+        ));
+        let result = engine.validate_segment(&minimal, &segment_def).unwrap();
+        let issue = result
+            .report
+            .all_issues()
+            .iter()
+            .find(|issue| issue.code.as_deref() == Some("DEPENDENCY_VIOLATION"))
+            .expect("expected D3 violation");
+        assert_eq!(issue.path, "NAD/e2");
+        assert!(issue.message.contains("D3(e2, e4)"));
+    }
+
     #[test]
     fn test_validate_segment_with_wrong_type() {
         let wrong_node = Node::new("NOT_SEGMENT", NodeType::Element);
@@ -1439,16 +2315,64 @@ mod tests {
     #[test]
     fn test_numeric_date_and_time_validation_require_ascii_digits() {
         let numeric_def = ElementDefinition::new("6060", "Quantity", "n");
-        assert!(ValidationEngine::validate_data_type_for_element("12345", &numeric_def).is_ok());
-        assert!(ValidationEngine::validate_data_type_for_element("١٢٣٤٥", &numeric_def).is_err());
+        assert!(
+            ValidationEngine::validate_data_type(
+                "12345",
+                "Element",
+                &numeric_def.id,
+                &numeric_def.data_type
+            )
+            .is_ok()
+        );
+        assert!(
+            ValidationEngine::validate_data_type(
+                "١٢٣٤٥",
+                "Element",
+                &numeric_def.id,
+                &numeric_def.data_type
+            )
+            .is_err()
+        );
 
         let date_def = ElementDefinition::new("2380", "Date", "dt");
-        assert!(ValidationEngine::validate_data_type_for_element("20260112", &date_def).is_ok());
-        assert!(ValidationEngine::validate_data_type_for_element("٢٠٢٦٠١١٢", &date_def).is_err());
+        assert!(
+            ValidationEngine::validate_data_type(
+                "20260112",
+                "Element",
+                &date_def.id,
+                &date_def.data_type
+            )
+            .is_ok()
+        );
+        assert!(
+            ValidationEngine::validate_data_type(
+                "٢٠٢٦٠١١٢",
+                "Element",
+                &date_def.id,
+                &date_def.data_type
+            )
+            .is_err()
+        );
 
         let time_def = ElementDefinition::new("2379", "Time", "tm");
-        assert!(ValidationEngine::validate_data_type_for_element("1215", &time_def).is_ok());
-        assert!(ValidationEngine::validate_data_type_for_element("١٢١٥", &time_def).is_err());
+        assert!(
+            ValidationEngine::validate_data_type(
+                "1215",
+                "Element",
+                &time_def.id,
+                &time_def.data_type
+            )
+            .is_ok()
+        );
+        assert!(
+            ValidationEngine::validate_data_type(
+                "١٢١٥",
+                "Element",
+                &time_def.id,
+                &time_def.data_type
+            )
+            .is_err()
+        );
     }
 
     #[test]
@@ -1577,6 +2501,146 @@ mod tests {
         assert_eq!(issue.path, "LINE_ITEM[0]");
     }
 
+    fn create_grouped_schema() -> Schema {
+        Schema::new("GROUP_TEST", "1.0")
+            .with_segments(vec![SegmentDefinition::new("BGM")])
+            .with_groups(vec![
+                SegmentGroupDefinition::new("SG2", "NAD")
+                    .min_occurs(1)
+                    .max_occurs(2)
+                    .with_segments(vec![SegmentDefinition::new("NAD").mandatory(true)])
+                    .with_groups(vec![
+                        SegmentGroupDefinition::new("SG5", "CTA")
+                            .min_occurs(1)
+                            .with_segments(vec![
+                                SegmentDefinition::new("CTA"),
+                                SegmentDefinition::new("COM").mandatory(true),
+                            ]),
+                    ]),
+                SegmentGroupDefinition::new("SG25", "LIN").with_segments(vec![
+                    SegmentDefinition::new("LIN").mandatory(true),
+                    SegmentDefinition::new("QTY").mandatory(true),
+                ]),
+            ])
+    }
+
+    fn create_group_node(id: &str, segments: &[&str]) -> Node {
+        let mut group = Node::new(id, NodeType::SegmentGroup);
+        for tag in segments {
+            group.add_child(Node::new(*tag, NodeType::Segment));
+        }
+        group
+    }
+
+    #[test]
+    fn test_schema_groups_report_cardinality_and_nested_mandatory_segments() {
+        let mut root = Node::new("ROOT", NodeType::Root);
+        for _ in 0..3 {
+            let mut sg2 = create_group_node("SG2", &["NAD"]);
+            sg2.add_child(create_group_node("SG5", &["CTA"]));
+            root.add_child(sg2);
+        }
+        let doc = Document::new(root);
+
+        let engine = ValidationEngine::new();
+        let result = engine
+            .validate_with_schema(&doc, &create_grouped_schema())
+            .unwrap();
+        let issues = result.report.all_issues();
+
+        assert!(issues.iter().any(|issue| {
+            issue.code.as_deref() == Some("GROUP_REPETITION_EXCEEDED")
+                && issue.message.contains("SG2")
+        }));
+        assert!(issues.iter().any(|issue| {
+            issue.code.as_deref() == Some("MISSING_MANDATORY_SEGMENT")
+                && issue.path == "SG2[1]/SG5[0]"
+                && issue.message.contains("COM")
+        }));
+    }
+
+    #[test]
+    fn test_schema_groups_skip_presence_check_for_flat_documents() {
+        let mut root = Node::new("ROOT", NodeType::Root);
+        root.add_child(Node::new("BGM", NodeType::Segment));
+        let doc = Document::new(root);
+
+        let engine = ValidationEngine::new();
+        let result = engine
+            .validate_with_schema(&doc, &create_grouped_schema())
+            .unwrap();
+
+        assert!(
+            !result
+                .report
+                .all_issues()
+                .iter()
+                .any(|issue| issue.code.as_deref() == Some("MISSING_MANDATORY_GROUP"))
+        );
+    }
+
+    #[test]
+    fn test_line_item_group_uses_schema_group_definition() {
+        let mut root = Node::new("ROOT", NodeType::Root);
+        let mut group = create_group_node("LINE_ITEM", &["LIN"]);
+        group.add_child(Node::new("PIA", NodeType::Segment));
+        root.add_child(group);
+        let doc = Document::new(root);
+
+        let engine = ValidationEngine::new();
+        let result = engine
+            .validate_with_schema(&doc, &create_grouped_schema())
+            .unwrap();
+
+        let issue = result
+            .report
+            .all_issues()
+            .iter()
+            .find(|issue| issue.code.as_deref() == Some("MISSING_MANDATORY_SEGMENT"))
+            .expect("expected missing QTY issue");
+        assert_eq!(issue.path, "LINE_ITEM[0]");
+        assert!(issue.message.contains("QTY"));
+    }
+
+    #[test]
+    fn test_grouped_segments_use_their_group_definition() {
+        let dtm = |max_length| {
+            SegmentDefinition::new("DTM").with_elements(vec![
+                ElementDefinition::new("C507", "Date/time/period", "c").with_components(vec![
+                    ComponentDefinition::new("2005", "Qualifier", "an").length(1, 3),
+                    ComponentDefinition::new("2380", "Value", "an").length(1, max_length),
+                ]),
+            ])
+        };
+        let schema = Schema::new("GROUP_TEST", "1.0").with_groups(vec![
+            SegmentGroupDefinition::new("SG2", "NAD")
+                .with_segments(vec![SegmentDefinition::new("NAD"), dtm(8)]),
+            SegmentGroupDefinition::new("SG25", "LIN")
+                .with_segments(vec![SegmentDefinition::new("LIN"), dtm(12)]),
+        ]);
+
+        let mut root = Node::new("ROOT", NodeType::Root);
+        for (id, trigger) in [("SG2", "NAD"), ("SG25", "LIN")] {
+            let mut group = create_group_node(id, &[trigger]);
+            group.add_child(edi_ir::testing::segment("DTM", &["2", "202603120930"]));
+            root.add_child(group);
+        }
+        let doc = Document::new(root);
+
+        let result = ValidationEngine::new()
+            .validate_with_schema(&doc, &schema)
+            .unwrap();
+        let violations: Vec<&str> = result
+            .report
+            .all_issues()
+            .iter()
+            .filter(|issue| issue.code.as_deref() == Some("MAX_LENGTH_VIOLATION"))
+            .map(|issue| issue.path.as_str())
+            .collect();
+
+        assert_eq!(violations, ["DTM[1]/C507/2380"]);
+    }
+
     #[test]
     fn test_validate_mandatory_segment_missing() {
         let root = Node::new("ROOT", NodeType::Root);
@@ -1606,6 +2670,25 @@ mod tests {
         assert!(!result.is_valid || result.has_errors());
     }
 
+    #[test]
+    fn test_length_counts_characters_not_bytes() {
+        // 10 characters, 13 bytes in UTF-8
+        let element = Node::with_value(
+            "3036",
+            NodeType::Element,
+            Value::String("Müller Öl€".to_string()),
+        );
+        let engine = ValidationEngine::new();
+
+        let fits = ElementDefinition::new("3036", "Party name", "an").length(1, 10);
+        let result = engine.validate_element(&element, &fits).unwrap();
+        assert!(result.is_valid, "unexpected errors: {:?}", result.errors);
+
+        let too_long = ElementDefinition::new("3036", "Party name", "an").length(1, 9);
+        let result = engine.validate_element(&element, &too_long).unwrap();
+        assert!(!result.is_valid);
+    }
+
     #[test]
     fn test_validate_element_data_type() {
         // Test numeric type
@@ -1621,6 +2704,15 @@ mod tests {
             Node::with_value("NUM", NodeType::Element, Value::String("12345".to_string()));
         let result = engine.validate_element(&element, &element_def).unwrap();
         assert!(result.is_valid);
+
+        // Amounts with a decimal comma are numeric too
+        let element = Node::with_value(
+            "NUM",
+            NodeType::Element,
+            Value::String("-1234,50".to_string()),
+        );
+        let result = engine.validate_element(&element, &element_def).unwrap();
+        assert!(result.is_valid);
     }
 
     #[test]
@@ -1759,4 +2851,28 @@ mod tests {
             Severity::Warning
         );
     }
+
+    #[test]
+    fn test_repeated_element_occurrences_are_validated_individually() {
+        let engine = ValidationEngine::new();
+        let element_def = ElementDefinition::new("e4", "Free text", "an").length(0, 5);
+
+        let mut element = Node::new("e4", NodeType::Element);
+        for (index, value) in ["LINE1", "TOO LONG"].into_iter().enumerate() {
+            element.add_child(Node::with_value(
+                format!("r{}", index + 1),
+                NodeType::Element,
+                Value::String(value.to_string()),
+            ));
+        }
+
+        let result = engine.validate_element(&element, &element_def).unwrap();
+        let paths: Vec<&str> = result
+            .report
+            .all_issues()
+            .iter()
+            .map(|issue| issue.path.as_str())
+            .collect();
+        assert_eq!(paths, vec!["e4/r2"]);
+    }
 }
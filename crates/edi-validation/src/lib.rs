@@ -32,10 +32,12 @@
 //! assert!(result.is_valid);
 //! ```
 
+pub mod aperak;
 pub mod codelist;
 pub mod engine;
 pub mod reporter;
 pub mod rules;
+pub mod typing;
 
 // Re-export main types
 pub use engine::{
@@ -48,6 +50,7 @@ pub use rules::{
     validate_conditional, validate_data_type, validate_length, validate_pattern, validate_required,
     validate_segment_order,
 };
+pub use typing::apply_schema_types;
 
 use thiserror::Error;
 
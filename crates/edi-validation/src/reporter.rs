@@ -1,5 +1,6 @@
 //! Validation reporter
 
+use edi_ir::SourceSpan;
 use std::collections::HashMap;
 use std::fmt::Write as _;
 
@@ -47,6 +48,9 @@ pub struct ValidationIssue {
     pub component_pos: Option<usize>,
     /// Additional context
     pub context: Option<String>,
+    /// Source span of the offending segment, element or component (if
+    /// available)
+    pub span: Option<SourceSpan>,
 }
 
 impl ValidationIssue {
@@ -63,6 +67,7 @@ impl ValidationIssue {
             element_pos: None,
             component_pos: None,
             context: None,
+            span: None,
         }
     }
 
@@ -81,6 +86,13 @@ impl ValidationIssue {
         self
     }
 
+    /// Set the source span, and the line and column from it
+    #[must_use]
+    pub fn with_span(mut self, span: SourceSpan) -> Self {
+        self.span = Some(span);
+        self.with_position(span.line, span.column)
+    }
+
     /// Set error code
     #[must_use]
     pub fn with_code(mut self, code: impl Into<String>) -> Self {
@@ -1,6 +1,7 @@
 //! Validation rules
 
-use edi_ir::{Node, NodeType};
+use edi_ir::{Node, NodeType, parse_edi_decimal};
+use edi_schema::DependencyKind;
 
 /// Data types for validation
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -112,7 +113,7 @@ pub fn validate_required(node: &Node) -> RuleResult {
 /// Validate length constraints
 #[must_use]
 pub fn validate_length(value: &str, constraint: &Constraint) -> RuleResult {
-    let len = value.len();
+    let len = value.chars().count();
 
     if let Some(min) = constraint.min_length {
         if len < min {
@@ -166,7 +167,7 @@ pub fn validate_data_type(value: &str, data_type: DataType) -> RuleResult {
             }
         }
         DataType::Decimal => {
-            if value.parse::<f64>().is_ok() {
+            if value.parse::<f64>().is_ok() || parse_edi_decimal(value).is_some() {
                 RuleResult::valid()
             } else {
                 RuleResult::invalid(format!("Value '{value}' is not a valid decimal"))
@@ -325,6 +326,40 @@ pub fn validate_conditional(nodes: &[&Node], rules: &[ConditionalRule]) -> RuleR
     RuleResult::valid()
 }
 
+/// Evaluate an EDIFACT dependency note over the presence of its listed
+/// elements or components, in note order.
+///
+/// Returns the indices of the entries a violation should be reported
+/// against; an empty result means the note is satisfied.
+#[must_use]
+pub fn evaluate_dependency(kind: DependencyKind, present: &[bool]) -> Vec<usize> {
+    let present_indices: Vec<usize> = (0..present.len()).filter(|&i| present[i]).collect();
+    let absent_indices: Vec<usize> = (0..present.len()).filter(|&i| !present[i]).collect();
+    let first_present = present.first().copied().unwrap_or(false);
+
+    match kind {
+        DependencyKind::OneAndOnlyOne | DependencyKind::OneOrMore
+            if present_indices.is_empty() && !present.is_empty() =>
+        {
+            vec![0]
+        }
+        DependencyKind::OneAndOnlyOne | DependencyKind::OneOrNone => {
+            present_indices.into_iter().skip(1).collect()
+        }
+        DependencyKind::AllOrNone if !present_indices.is_empty() => absent_indices,
+        DependencyKind::IfFirstThenAll if first_present => absent_indices,
+        DependencyKind::IfFirstThenAtLeastOneMore
+            if first_present && present.len() > 1 && present_indices.len() == 1 =>
+        {
+            vec![1]
+        }
+        DependencyKind::IfFirstThenNone if first_present => {
+            present_indices.into_iter().skip(1).collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
 /// Validate a value against a code list
 #[must_use]
 pub fn validate_code_list(value: &str, codes: &[String]) -> RuleResult {
@@ -708,6 +743,7 @@ mod tests {
         assert!(validate_data_type("0.0", DataType::Decimal).is_valid);
         assert!(validate_data_type(".5", DataType::Decimal).is_valid);
         assert!(validate_data_type("1e10", DataType::Decimal).is_valid);
+        assert!(validate_data_type("12,50", DataType::Decimal).is_valid);
         assert!(!validate_data_type("1.2.3", DataType::Decimal).is_valid);
 
         // Date edge cases
@@ -720,4 +756,54 @@ mod tests {
         assert!(validate_data_type("23:59:59", DataType::Time).is_valid);
         assert!(validate_data_type("00:00", DataType::Time).is_valid);
     }
+
+    #[test]
+    fn test_evaluate_dependency_notes() {
+        use DependencyKind::{
+            AllOrNone, IfFirstThenAll, IfFirstThenAtLeastOneMore, IfFirstThenNone, OneAndOnlyOne,
+            OneOrMore, OneOrNone,
+        };
+
+        assert_eq!(evaluate_dependency(OneAndOnlyOne, &[false, false]), vec![0]);
+        assert_eq!(
+            evaluate_dependency(OneAndOnlyOne, &[true, true, true]),
+            vec![1, 2]
+        );
+        assert!(evaluate_dependency(OneAndOnlyOne, &[false, true]).is_empty());
+
+        assert!(evaluate_dependency(AllOrNone, &[false, false]).is_empty());
+        assert!(evaluate_dependency(AllOrNone, &[true, true]).is_empty());
+        assert_eq!(
+            evaluate_dependency(AllOrNone, &[false, true, false]),
+            vec![0, 2]
+        );
+
+        assert_eq!(evaluate_dependency(OneOrMore, &[false, false]), vec![0]);
+        assert!(evaluate_dependency(OneOrMore, &[false, true]).is_empty());
+
+        assert!(evaluate_dependency(OneOrNone, &[false, false]).is_empty());
+        assert_eq!(
+            evaluate_dependency(OneOrNone, &[true, false, true]),
+            vec![2]
+        );
+
+        assert!(evaluate_dependency(IfFirstThenAll, &[false, true, false]).is_empty());
+        assert_eq!(
+            evaluate_dependency(IfFirstThenAll, &[true, true, false]),
+            vec![2]
+        );
+
+        assert_eq!(
+            evaluate_dependency(IfFirstThenAtLeastOneMore, &[true, false, false]),
+            vec![1]
+        );
+        assert!(evaluate_dependency(IfFirstThenAtLeastOneMore, &[true, false, true]).is_empty());
+        assert!(evaluate_dependency(IfFirstThenAtLeastOneMore, &[false, false, false]).is_empty());
+
+        assert_eq!(
+            evaluate_dependency(IfFirstThenNone, &[true, true, false]),
+            vec![1]
+        );
+        assert!(evaluate_dependency(IfFirstThenNone, &[false, true, true]).is_empty());
+    }
 }
@@ -0,0 +1,465 @@
+//! Schema-aware typing of parsed documents
+//!
+//! Parsers store every element and component value as `Value::String`.
+//! [`apply_schema_types`] walks a parsed document against its schema and
+//! converts the values of typed definitions:
+//!
+//! - `n` values become exact `Value::Decimal`s; a decimal comma is accepted
+//! - `dt` (CCYYMMDD) and `tm` (HHMM or HHMMSS) values become ISO 8601
+//!   `Value::Date`s and `Value::Time`s
+//! - the date/time/period value (2380) of a composite with a format
+//!   qualifier (2379), as in DTM, becomes a `Date` for 102 (CCYYMMDD), a
+//!   `DateTime` for 203 (CCYYMMDDHHMM) and a `Period` (an ISO 8601 interval
+//!   such as "2026-03-01/2026-03-31") for 718 (CCYYMMDD-CCYYMMDD)
+//!
+//! The EDIFACT serializer writes ISO 8601 dates, times and periods back in
+//! their EDIFACT form, so a typed document serializes to the same text.
+//!
+//! Every element and component node matched to a definition gets its id as
+//! `schema_type`. A value that cannot be converted keeps its text and is
+//! reported as a positioned `TYPE_CONVERSION_FAILED` error.
+
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use edi_ir::{Document, Node, NodeType, Value, parse_edi_decimal};
+use edi_schema::{ElementDefinition, Schema, SegmentDefinition};
+
+use crate::engine::{ValidationContext, ValidationResult};
+use crate::reporter::{Severity, ValidationIssue};
+
+/// Date/time/period value component (data element 2380)
+const DATE_VALUE_ID: &str = "2380";
+/// Date/time/period format qualifier component (data element 2379)
+const DATE_FORMAT_ID: &str = "2379";
+
+/// Convert the string values of `doc` to the typed values its `schema`
+/// defines, reporting values that cannot be converted.
+///
+/// Segments are numbered like [`ValidationEngine::validate_with_schema`]
+/// numbers them, so conversion issues line up with validation issues.
+///
+/// [`ValidationEngine::validate_with_schema`]: crate::ValidationEngine::validate_with_schema
+pub fn apply_schema_types(doc: &mut Document, schema: &Schema) -> ValidationResult {
+    let mut result = ValidationResult::valid();
+    let mut segment_pos = 0;
+    type_node(&mut doc.root, schema, None, &mut segment_pos, &mut result);
+    result
+}
+
+/// Type `node` and its descendants; `group` is the ID of the innermost
+/// enclosing segment group declared by `schema`
+fn type_node(
+    node: &mut Node,
+    schema: &Schema,
+    group: Option<&str>,
+    segment_pos: &mut usize,
+    result: &mut ValidationResult,
+) {
+    if matches!(
+        node.node_type,
+        NodeType::Segment | NodeType::Interchange | NodeType::Message
+    ) {
+        let index = *segment_pos;
+        *segment_pos += 1;
+        if node.node_type == NodeType::Segment {
+            if let Some(segment_def) = schema.find_segment_in(group, &node.name) {
+                let context = ValidationContext::root()
+                    .indexed_child(&node.name, index)
+                    .with_segment_pos(index)
+                    .with_span(node.span);
+                type_segment(node, segment_def, &context, result);
+            }
+            return;
+        }
+    }
+
+    let group =
+        if node.node_type == NodeType::SegmentGroup && schema.find_group(&node.name).is_some() {
+            Some(node.name.as_str())
+        } else {
+            group
+        };
+    for child in &mut node.children {
+        type_node(child, schema, group, segment_pos, result);
+    }
+}
+
+fn type_segment(
+    segment: &mut Node,
+    segment_def: &SegmentDefinition,
+    context: &ValidationContext,
+    result: &mut ValidationResult,
+) {
+    for (idx, (element, element_def)) in segment
+        .children
+        .iter_mut()
+        .zip(&segment_def.elements)
+        .enumerate()
+    {
+        let element_context = context
+            .child(&element_def.id)
+            .with_element_pos(idx)
+            .with_span(element.span);
+        type_element(element, element_def, &element_context, result);
+    }
+}
+
+fn type_element(
+    element: &mut Node,
+    element_def: &ElementDefinition,
+    context: &ValidationContext,
+    result: &mut ValidationResult,
+) {
+    element.set_schema_type(&element_def.id);
+
+    // A repeated element has one Element child per occurrence
+    if element
+        .children
+        .iter()
+        .any(|child| child.node_type == NodeType::Element)
+    {
+        for occurrence in &mut element.children {
+            let occurrence_context = context.clone().with_span(occurrence.span);
+            type_element(occurrence, element_def, &occurrence_context, result);
+        }
+        return;
+    }
+
+    if !element_def.is_composite() {
+        convert(element, &element_def.data_type, None, context, result);
+        return;
+    }
+
+    let format = element_def
+        .components
+        .iter()
+        .position(|component| component.id == DATE_FORMAT_ID)
+        .and_then(|idx| element.children.get(idx))
+        .and_then(|component| component.value.as_ref())
+        .and_then(Value::as_string);
+
+    for (idx, (component, component_def)) in element
+        .children
+        .iter_mut()
+        .zip(&element_def.components)
+        .enumerate()
+    {
+        component.set_schema_type(&component_def.id);
+        let component_context = context
+            .child(&component_def.id)
+            .with_component_pos(idx)
+            .with_span(component.span);
+        let format = format
+            .as_deref()
+            .filter(|_| component_def.id == DATE_VALUE_ID);
+        convert(
+            component,
+            &component_def.data_type,
+            format,
+            &component_context,
+            result,
+        );
+    }
+}
+
+/// Replace the string value of `node` with its typed value, or report why it
+/// cannot be converted
+fn convert(
+    node: &mut Node,
+    data_type: &str,
+    format: Option<&str>,
+    context: &ValidationContext,
+    result: &mut ValidationResult,
+) {
+    let Some(Value::String(text)) = &node.value else {
+        return;
+    };
+    if text.is_empty() {
+        return;
+    }
+
+    match typed_value(text, data_type, format) {
+        Some(Ok(value)) => node.value = Some(value),
+        Some(Err(expected)) => {
+            let mut issue = ValidationIssue::new(
+                Severity::Error,
+                format!(
+                    "Value '{text}' of '{}' is not {expected}",
+                    node.schema_type.as_deref().unwrap_or(&node.name)
+                ),
+            )
+            .with_path(&context.path)
+            .with_code("TYPE_CONVERSION_FAILED");
+            if let Some(segment_pos) = context.segment_pos {
+                issue =
+                    issue.with_positions(segment_pos, context.element_pos, context.component_pos);
+            }
+            if let Some(span) = context.span {
+                issue = issue.with_span(span);
+            }
+            result.add_issue(issue);
+        }
+        None => {}
+    }
+}
+
+/// Typed value of `text` for `data_type`, or for a date/time/period value
+/// with format qualifier `format`; `None` leaves the text as it is
+fn typed_value(
+    text: &str,
+    data_type: &str,
+    format: Option<&str>,
+) -> Option<Result<Value, &'static str>> {
+    match (format, data_type) {
+        (Some("102"), _) | (None, "dt") => Some(parse_date(text).map(Value::Date)),
+        (Some("203"), _) => Some(parse_date_time(text).map(Value::DateTime)),
+        (Some("718"), _) => Some(parse_period(text).map(Value::Period)),
+        (None, "n") => Some(
+            parse_edi_decimal(text)
+                .map(Value::Decimal)
+                .ok_or("a numeric value"),
+        ),
+        (None, "tm") => Some(parse_time(text).map(Value::Time)),
+        _ => None,
+    }
+}
+
+fn is_digits(text: &str, lengths: &[usize]) -> bool {
+    lengths.contains(&text.len()) && text.bytes().all(|byte| byte.is_ascii_digit())
+}
+
+/// CCYYMMDD as an ISO 8601 date
+fn parse_date(text: &str) -> Result<String, &'static str> {
+    is_digits(text, &[8])
+        .then(|| NaiveDate::parse_from_str(text, "%Y%m%d").ok())
+        .flatten()
+        .map(|date| date.format("%Y-%m-%d").to_string())
+        .ok_or("a valid date (CCYYMMDD)")
+}
+
+/// CCYYMMDDHHMM as an ISO 8601 date and time
+fn parse_date_time(text: &str) -> Result<String, &'static str> {
+    is_digits(text, &[12])
+        .then(|| NaiveDateTime::parse_from_str(text, "%Y%m%d%H%M").ok())
+        .flatten()
+        .map(|date_time| date_time.format("%Y-%m-%dT%H:%M").to_string())
+        .ok_or("a valid date and time (CCYYMMDDHHMM)")
+}
+
+/// CCYYMMDD-CCYYMMDD as an ISO 8601 interval of dates
+fn parse_period(text: &str) -> Result<String, &'static str> {
+    let invalid = "a valid period (CCYYMMDD-CCYYMMDD)";
+    let (start, end) = text.split_once('-').ok_or(invalid)?;
+    let start = parse_date(start).map_err(|_| invalid)?;
+    let end = parse_date(end).map_err(|_| invalid)?;
+    if start > end {
+        return Err("a period that ends after it starts");
+    }
+    Ok(format!("{start}/{end}"))
+}
+
+/// HHMM or HHMMSS as an ISO 8601 time
+fn parse_time(text: &str) -> Result<String, &'static str> {
+    let (input, output) = if text.len() == 4 {
+        ("%H%M", "%H:%M")
+    } else {
+        ("%H%M%S", "%H:%M:%S")
+    };
+    is_digits(text, &[4, 6])
+        .then(|| NaiveTime::parse_from_str(text, input).ok())
+        .flatten()
+        .map(|time| time.format(output).to_string())
+        .ok_or("a valid time (HHMM or HHMMSS)")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use edi_adapter_edifact::{EdifactParser, EdifactSerializer, SegmentSuffix};
+    use edi_ir::testing::segment;
+    use edi_schema::{ComponentDefinition, SegmentGroupDefinition};
+
+    fn schema() -> Schema {
+        Schema::new("INVOIC", "D96A").with_segments(vec![
+            SegmentDefinition::new("DTM").with_elements(vec![
+                ElementDefinition::new("C507", "Date/time/period", "c").with_components(vec![
+                    ComponentDefinition::new("2005", "Qualifier", "an"),
+                    ComponentDefinition::new("2380", "Value", "an"),
+                    ComponentDefinition::new("2379", "Format qualifier", "an"),
+                ]),
+            ]),
+            SegmentDefinition::new("QTY").with_elements(vec![
+                ElementDefinition::new("C186", "Quantity details", "c").with_components(vec![
+                    ComponentDefinition::new("6063", "Qualifier", "an"),
+                    ComponentDefinition::new("6060", "Quantity", "n"),
+                ]),
+            ]),
+        ])
+    }
+
+    fn document(segments: Vec<Node>) -> Document {
+        let mut root = Node::new("INVOIC", NodeType::Root);
+        for segment in segments {
+            root.add_child(segment);
+        }
+        Document::new(root)
+    }
+
+    fn value(doc: &Document, segment: usize, component: usize) -> &Value {
+        doc.root.children[segment].children[0].children[component]
+            .value
+            .as_ref()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_types_dates_and_numerics() {
+        let mut doc = document(vec![
+            segment("DTM", &["137", "20260312", "102"]),
+            segment("DTM", &["2", "202603120930", "203"]),
+            segment("DTM", &["263", "20260301-20260331", "718"]),
+            segment("DTM", &["3", "2026W11", "616"]),
+            segment("QTY", &["47", "12,50"]),
+        ]);
+
+        let result = apply_schema_types(&mut doc, &schema());
+
+        assert!(result.is_valid, "{:?}", result.report.all_issues());
+        assert_eq!(value(&doc, 0, 1), &Value::Date("2026-03-12".to_string()));
+        assert_eq!(
+            value(&doc, 1, 1),
+            &Value::DateTime("2026-03-12T09:30".to_string())
+        );
+        assert_eq!(
+            value(&doc, 2, 1),
+            &Value::Period("2026-03-01/2026-03-31".to_string())
+        );
+        assert_eq!(value(&doc, 3, 1), &Value::String("2026W11".to_string()));
+        assert_eq!(value(&doc, 4, 1).as_string(), Some("12.50".to_string()));
+        assert_eq!(value(&doc, 4, 0), &Value::String("47".to_string()));
+        let quantity = &doc.root.children[4].children[0];
+        assert_eq!(quantity.schema_type.as_deref(), Some("C186"));
+        assert_eq!(quantity.children[1].schema_type.as_deref(), Some("6060"));
+    }
+
+    #[test]
+    fn test_typed_document_serializes_to_source_text() {
+        let source = "UNH+1+INVOIC:D:96A:UN'DTM+137:20260312:102'DTM+2:202603120930:203'\
+                      DTM+263:20260301-20260331:718'QTY+47:12.50'UNT+6+1'";
+        let mut doc = EdifactParser::new()
+            .parse(
+                format!("UNB+UNOC:3+S+R+260312:0930+1'{source}UNZ+1+1'").as_bytes(),
+                "invoic.edi",
+            )
+            .unwrap()
+            .remove(0);
+
+        let result = apply_schema_types(&mut doc, &schema());
+
+        assert!(result.is_valid, "{:?}", result.report.all_issues());
+        let rendered = EdifactSerializer::new()
+            .with_segment_suffix(SegmentSuffix::None)
+            .serialize_document(&doc)
+            .unwrap();
+        assert_eq!(rendered, source);
+    }
+
+    #[test]
+    fn test_reports_failed_conversions_with_position() {
+        let mut doc = document(vec![
+            segment("DTM", &["137", "20261332", "102"]),
+            segment("QTY", &["47", "12x"]),
+        ]);
+
+        let result = apply_schema_types(&mut doc, &schema());
+
+        assert!(!result.is_valid);
+        let issues = result.report.all_issues();
+        assert_eq!(issues.len(), 2);
+        assert_eq!(issues[0].code.as_deref(), Some("TYPE_CONVERSION_FAILED"));
+        assert_eq!(issues[0].path, "DTM[0]/C507/2380");
+        assert_eq!(
+            (
+                issues[0].segment_pos,
+                issues[0].element_pos,
+                issues[0].component_pos
+            ),
+            (Some(0), Some(0), Some(1))
+        );
+        assert!(
+            issues[0].message.contains("CCYYMMDD"),
+            "{}",
+            issues[0].message
+        );
+        assert_eq!(issues[1].path, "QTY[1]/C186/6060");
+        assert_eq!(value(&doc, 0, 1), &Value::String("20261332".to_string()));
+    }
+
+    #[test]
+    fn test_reports_invalid_periods() {
+        let mut doc = document(vec![
+            segment("DTM", &["263", "20260332-20260301", "718"]),
+            segment("DTM", &["263", "20260331-20260301", "718"]),
+            segment("DTM", &["263", "20260301", "718"]),
+        ]);
+
+        let result = apply_schema_types(&mut doc, &schema());
+
+        let messages: Vec<(&str, &str)> = result
+            .report
+            .all_issues()
+            .iter()
+            .map(|issue| {
+                assert_eq!(issue.code.as_deref(), Some("TYPE_CONVERSION_FAILED"));
+                (issue.path.as_str(), issue.message.as_str())
+            })
+            .collect();
+        assert_eq!(messages.len(), 3);
+        assert!(messages[0].1.contains("CCYYMMDD-CCYYMMDD"), "{messages:?}");
+        assert!(
+            messages[1].1.contains("ends after it starts"),
+            "{messages:?}"
+        );
+        assert_eq!(messages[2].0, "DTM[2]/C507/2380");
+        assert_eq!(
+            value(&doc, 0, 1),
+            &Value::String("20260332-20260301".to_string())
+        );
+    }
+
+    #[test]
+    fn test_types_grouped_segments_by_their_group_definition() {
+        let dtm = |value_type| {
+            SegmentDefinition::new("DTM").with_elements(vec![
+                ElementDefinition::new("C507", "Date/time/period", "c").with_components(vec![
+                    ComponentDefinition::new("2005", "Qualifier", "an"),
+                    ComponentDefinition::new("2380", "Value", value_type),
+                ]),
+            ])
+        };
+        let schema = Schema::new("ORDERS", "D96A").with_groups(vec![
+            SegmentGroupDefinition::new("SG1", "RFF")
+                .with_segments(vec![SegmentDefinition::new("RFF"), dtm("an")]),
+            SegmentGroupDefinition::new("SG25", "LIN")
+                .with_segments(vec![SegmentDefinition::new("LIN"), dtm("dt")]),
+        ]);
+        let mut root = Node::new("ORDERS", NodeType::Root);
+        for (id, trigger) in [("SG1", "RFF"), ("SG25", "LIN")] {
+            let mut group = Node::new(id, NodeType::SegmentGroup);
+            group.add_child(segment(trigger, &["1"]));
+            group.add_child(segment("DTM", &["2", "20260312"]));
+            root.add_child(group);
+        }
+        let mut doc = Document::new(root);
+
+        let result = apply_schema_types(&mut doc, &schema);
+
+        assert!(result.is_valid, "{:?}", result.report.all_issues());
+        let date = |group: usize| {
+            doc.root.children[group].children[1].children[0].children[1]
+                .value
+                .clone()
+        };
+        assert_eq!(date(0), Some(Value::String("20260312".to_string())));
+        assert_eq!(date(1), Some(Value::Date("2026-03-12".to_string())));
+    }
+}
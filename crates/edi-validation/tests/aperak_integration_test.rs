@@ -0,0 +1,63 @@
+use std::fs;
+use std::path::PathBuf;
+
+use edi_adapter_edifact::{
+    AperakGenerator, ApplicationErrorCode, EdifactParser, EdifactSerializer,
+};
+use edi_ir::{Document, NodeType};
+use edi_schema::SchemaLoader;
+use edi_validation::ValidationEngine;
+
+fn repo_root() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../..")
+}
+
+fn normalize_document_for_validation(document: &Document) -> Document {
+    let mut normalized = document.clone();
+    if normalized.root.node_type == NodeType::Message {
+        normalized.root.node_type = NodeType::Root;
+        normalized.root.name = "ROOT".to_string();
+    }
+    normalized
+}
+
+#[test]
+fn aperak_rejects_desadv_failing_schema_validation() {
+    let root = repo_root();
+    let schema = SchemaLoader::new(Vec::new())
+        .load_from_file(&root.join("testdata/schemas/eancom_desadv_d96a.yaml"))
+        .expect("schema should load");
+    let edi_path = root.join("testdata/edi/invalid_desadv_d96a_missing_bgm.edi");
+    let data = fs::read(&edi_path).expect("edi fixture should load");
+    let source = EdifactParser::new()
+        .parse(&data, edi_path.to_string_lossy().as_ref())
+        .expect("edi should parse")
+        .remove(0);
+    let result = ValidationEngine::new()
+        .validate_with_schema(&normalize_document_for_validation(&source), &schema)
+        .expect("validation should run");
+    assert!(result.has_errors(), "fixture should fail validation");
+
+    let aperak = AperakGenerator::new("APE1")
+        .with_error_code(
+            "MISSING_MANDATORY_SEGMENT",
+            ApplicationErrorCode::new("13").with_free_text("Document header missing"),
+        )
+        .generate(&source, &result.application_issues());
+    let text = EdifactSerializer::new()
+        .serialize_document(&aperak)
+        .expect("APERAK should serialize");
+
+    assert!(
+        text.starts_with("UNH+APE1+APERAK:D:96A:UN:EAN002'"),
+        "{text}"
+    );
+    assert!(text.contains("BGM+305+APE1+27'"), "{text}");
+    assert!(text.contains("RFF+ACW:1'"), "{text}");
+    assert!(text.contains("NAD+MS+RECEIVER'\nNAD+MR+SENDER'"), "{text}");
+    assert!(
+        text.contains("ERC+13'\nFTX+AAO+++Document header missing'"),
+        "{text}"
+    );
+    assert!(text.ends_with("UNT+9+APE1'"), "{text}");
+}
@@ -126,4 +126,14 @@ fn invoic_invalid_fixture_reports_structured_diagnostics() {
         issue.message.contains("Mandatory element"),
         "expected actionable missing element message"
     );
+
+    let data = fs::read(repo_root().join("testdata/edi/invalid_invoic_d96a_missing_element.edi"))
+        .expect("edi fixture should load");
+    let span = issue.span.expect("expected source span of the BGM segment");
+    assert!(
+        span.slice(&data)
+            .is_some_and(|text| text.starts_with(b"BGM")),
+        "expected span to cover the segment missing the element"
+    );
+    assert_eq!(issue.line, Some(span.line));
 }